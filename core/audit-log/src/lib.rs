@@ -0,0 +1,253 @@
+//! Decision Audit Log
+//!
+//! Append-only JSON Lines record of what a deployed reflex saw and did, for
+//! post-incident forensics: each line holds the telemetry, the normalized
+//! features, the model's raw outputs, the decision applied after clamping,
+//! and which policy and which exact model made it. Files rotate by size, so
+//! a long-running policy keeps a bounded window of recent decisions.
+
+use reflex_format::Reflex;
+use serde::Serialize;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One audited decision, borrowed from the policy that made it
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord<'a, T, D> {
+    pub timestamp_us: u64, // wall clock, µs since the Unix epoch
+    pub policy_id: &'a str,
+    pub reflex_hash: &'a str, // the model file's CRC32, as `reflex_hash` writes it
+    pub telemetry: &'a T,
+    pub features: &'a [f32], // normalized, as fed to the model
+    pub raw_outputs: &'a [f32], // before bounds and calibration; empty when the model wasn't asked
+    pub decision: &'a D,
+    pub fallback: bool, // decided by a fallback policy rather than the model
+}
+
+/// Wall-clock µs since the Unix epoch, for `AuditRecord::timestamp_us`
+pub fn unix_time_us() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_micros() as u64)
+}
+
+/// `reflex`'s CRC32 as written to the log, identifying the exact model file
+pub fn reflex_hash(reflex: &Reflex) -> String {
+    match reflex.checksum() {
+        Ok(checksum) => format!("{:08x}", checksum),
+        Err(e) => format!("unknown ({})", e),
+    }
+}
+
+/// JSON Lines writer rotating to `<path>.1`, `<path>.2`, ... by size
+///
+/// Every record is written with a single call and no buffering, so a
+/// crash loses at most the record being written.
+#[derive(Debug)]
+pub struct AuditLogger {
+    path: PathBuf,
+    file: File,
+    written: u64, // bytes in the current file
+    max_bytes: u64,
+    keep: usize, // rotated files kept besides the current one
+    records: usize,
+    rotations: usize,
+    failed: usize,
+}
+
+impl AuditLogger {
+    pub const DEFAULT_MAX_BYTES: u64 = 64 << 20;
+    pub const DEFAULT_KEEP: usize = 4;
+
+    /// Append to `path`, creating it if needed; rotates at 64 MiB keeping 4 old files until configured
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            written,
+            max_bytes: Self::DEFAULT_MAX_BYTES,
+            keep: Self::DEFAULT_KEEP,
+            records: 0,
+            rotations: 0,
+            failed: 0,
+        })
+    }
+
+    /// Start a new file before one would grow past `max_bytes`, keeping the `keep` most recent old ones
+    ///
+    /// A single record larger than `max_bytes` still gets a file of its own.
+    /// With `keep` zero, rotation discards the old file.
+    pub fn with_rotation(mut self, max_bytes: u64, keep: usize) -> Self {
+        assert!(max_bytes > 0, "rotation size must be positive");
+        self.max_bytes = max_bytes;
+        self.keep = keep;
+        self
+    }
+
+    /// Append `record` as one line, rotating first if it wouldn't fit
+    pub fn log<T: Serialize, D: Serialize>(&mut self, record: &AuditRecord<T, D>) -> io::Result<()> {
+        let result = self.write(record);
+        if result.is_err() {
+            self.failed += 1;
+        }
+        result
+    }
+
+    fn write<T: Serialize, D: Serialize>(&mut self, record: &AuditRecord<T, D>) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        if self.written > 0 && self.written + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(&line)?;
+        self.written += line.len() as u64;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Shift `<path>.N` to `<path>.N+1`, dropping the oldest, and start `path` afresh
+    fn rotate(&mut self) -> io::Result<()> {
+        for n in (1..self.keep).rev() {
+            let from = self.rotated(n);
+            if from.exists() {
+                fs::rename(&from, self.rotated(n + 1))?;
+            }
+        }
+        if self.keep > 0 {
+            fs::rename(&self.path, self.rotated(1))?;
+        }
+        self.file = File::create(&self.path)?;
+        self.written = 0;
+        self.rotations += 1;
+        Ok(())
+    }
+
+    /// Path of the `n`th most recent rotated file
+    pub fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = OsString::from(self.path.as_os_str());
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records written since creation
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Files rotated out since creation
+    pub fn rotations(&self) -> usize {
+        self.rotations
+    }
+
+    /// Records that failed to write
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+}
+
+/// An [`AuditLogger`] bound to one policy and the model it runs
+#[derive(Debug)]
+pub struct PolicyAudit {
+    logger: AuditLogger,
+    policy_id: String,
+    reflex_hash: String,
+}
+
+impl PolicyAudit {
+    pub fn new(logger: AuditLogger, policy_id: impl Into<String>, reflex: &Reflex) -> Self {
+        Self {
+            logger,
+            policy_id: policy_id.into(),
+            reflex_hash: reflex_hash(reflex),
+        }
+    }
+
+    /// Attribute later records to `reflex`, after a model swap
+    pub fn set_reflex(&mut self, reflex: &Reflex) {
+        self.reflex_hash = reflex_hash(reflex);
+    }
+
+    /// Log one decision, stamped now
+    pub fn log<T: Serialize, D: Serialize>(
+        &mut self,
+        telemetry: &T,
+        features: &[f32],
+        raw_outputs: &[f32],
+        decision: &D,
+        fallback: bool,
+    ) -> io::Result<()> {
+        self.logger.log(&AuditRecord {
+            timestamp_us: unix_time_us(),
+            policy_id: &self.policy_id,
+            reflex_hash: &self.reflex_hash,
+            telemetry,
+            features,
+            raw_outputs,
+            decision,
+            fallback,
+        })
+    }
+
+    pub fn logger(&self) -> &AuditLogger {
+        &self.logger
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(logger: &mut AuditLogger, decision: u32) -> io::Result<()> {
+        logger.log(&AuditRecord {
+            timestamp_us: 1_700_000_000_000_000,
+            policy_id: "reflex",
+            reflex_hash: "0000beef",
+            telemetry: &[1.0f32, 2.0],
+            features: &[0.1, 0.2],
+            raw_outputs: &[7.6],
+            decision: &decision,
+            fallback: false,
+        })
+    }
+
+    #[test]
+    fn test_logger_rotates_by_size_and_keeps_recent_files() {
+        let path = std::env::temp_dir().join(format!("nematode-audit-{}.jsonl", std::process::id()));
+        let mut logger = AuditLogger::create(&path).unwrap();
+        log(&mut logger, 0).unwrap();
+        // Records are the same size: room for two per file
+        let line_bytes = fs::metadata(&path).unwrap().len();
+        let mut logger = logger.with_rotation(2 * line_bytes, 2);
+        for decision in 1..7 {
+            log(&mut logger, decision).unwrap();
+        }
+        assert_eq!((logger.records(), logger.rotations(), logger.failed()), (7, 3, 0));
+
+        // The oldest file, holding decisions 0 and 1, was dropped
+        let decisions = |path: &Path| -> Vec<u64> {
+            fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["decision"].as_u64().unwrap())
+                .collect()
+        };
+        assert_eq!(decisions(&path), [6]);
+        assert_eq!(decisions(&logger.rotated(1)), [4, 5]);
+        assert_eq!(decisions(&logger.rotated(2)), [2, 3]);
+        assert!(!logger.rotated(3).exists());
+
+        for file in [path.clone(), logger.rotated(1), logger.rotated(2)] {
+            fs::remove_file(file).unwrap();
+        }
+    }
+}
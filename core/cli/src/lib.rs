@@ -0,0 +1,61 @@
+//! Command-line flag helpers shared by the sim, sim-compute and trainer binaries
+//!
+//! Binaries take their flags out of `std::env::args()` one at a time, so
+//! whatever is left afterwards is positional. A flag missing its value or
+//! holding an unparsable one ends the process with a message on stderr.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Remove `--name <value>` from args and return the value
+pub fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let idx = args.iter().position(|a| a == name)?;
+    if idx + 1 >= args.len() {
+        eprintln!("Missing value for {}", name);
+        std::process::exit(1);
+    }
+    let value = args.remove(idx + 1);
+    args.remove(idx);
+    Some(value)
+}
+
+/// Remove `--name <value>` from args and parse the value, exiting if it doesn't parse
+pub fn take_parsed<T>(args: &mut Vec<String>, name: &str) -> Option<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    take_flag(args, name).map(|value| {
+        value.parse().unwrap_or_else(|e| {
+            eprintln!("Invalid value for {}: {}", name, e);
+            std::process::exit(1);
+        })
+    })
+}
+
+/// Remove every `--name` switch from args; returns whether there was one
+pub fn take_switch(args: &mut Vec<String>, name: &str) -> bool {
+    let present = args.iter().any(|a| a == name);
+    args.retain(|a| a != name);
+    present
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_taken_flags_leave_positionals_behind() {
+        let mut argv = args(&["bin", "--seed", "7", "input.csv", "--paired", "--runs", "3"]);
+        assert_eq!(take_flag(&mut argv, "--seed").as_deref(), Some("7"));
+        assert_eq!(take_parsed::<usize>(&mut argv, "--runs"), Some(3));
+        assert!(take_switch(&mut argv, "--paired"));
+        assert!(!take_switch(&mut argv, "--paired"));
+        assert_eq!(take_flag(&mut argv, "--output"), None);
+        assert_eq!(argv, args(&["bin", "input.csv"]));
+    }
+}
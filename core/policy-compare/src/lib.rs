@@ -0,0 +1,305 @@
+//! Policy Comparison Report
+//!
+//! Compares two policies' per-run metrics, as produced by either simulator:
+//! for each metric, an effect size, a bootstrap confidence interval on the
+//! difference of means, and a verdict on whether the candidate is better,
+//! worse or indistinguishable from the baseline.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+mod stats;
+
+pub use stats::{normal_cdf, MeanCi};
+
+/// Which direction of a metric is an improvement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    Lower,
+    Higher,
+}
+
+/// Outcome for the candidate relative to the baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Better,
+    Worse,
+    Inconclusive,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Verdict::Better => "better",
+            Verdict::Worse => "worse",
+            Verdict::Inconclusive => "inconclusive",
+        })
+    }
+}
+
+/// One metric compared between baseline (A) and candidate (B) runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricComparison {
+    pub metric: String,
+    pub goal: Goal,
+    pub runs_a: usize,
+    pub runs_b: usize,
+    pub mean_a: f64,
+    pub mean_b: f64,
+    /// Difference of means, B - A
+    pub diff: f64,
+    /// Bootstrap percentile interval on `diff`
+    pub diff_ci_low: f64,
+    pub diff_ci_high: f64,
+    /// Standardized difference of means; `None` when neither side varies
+    pub cohens_d: Option<f64>,
+    /// P(B > A) - P(B < A) over all run pairs, in [-1, 1]
+    pub cliffs_delta: f64,
+    pub verdict: Verdict,
+}
+
+/// Per-metric comparison of a candidate policy against a baseline
+///
+/// A metric's verdict is decided only when its bootstrap interval excludes
+/// zero and Cliff's delta is at least [`min_effect`](Self::with_min_effect)
+/// in size, so a tiny but consistent shift between deterministic runs
+/// doesn't count as a win.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparisonReport {
+    pub baseline: String,
+    pub candidate: String,
+    pub confidence: f64,
+    pub resamples: usize,
+    pub min_effect: f64,
+    pub seed: u64, // bootstrap RNG seed
+    pub metrics: Vec<MetricComparison>,
+}
+
+impl ComparisonReport {
+    /// Cliff's delta below this is conventionally negligible (Romano et al.)
+    pub const NEGLIGIBLE_EFFECT: f64 = 0.147;
+
+    pub fn new(baseline: &str, candidate: &str) -> Self {
+        Self {
+            baseline: baseline.to_string(),
+            candidate: candidate.to_string(),
+            confidence: 0.95,
+            resamples: 2000,
+            min_effect: Self::NEGLIGIBLE_EFFECT,
+            seed: 0,
+            metrics: Vec::new(),
+        }
+    }
+
+    /// Bootstrap resample count and RNG seed (default: 2000, seed 0)
+    pub fn with_bootstrap(mut self, resamples: usize, seed: u64) -> Self {
+        assert!(resamples > 0, "at least one bootstrap resample required");
+        self.resamples = resamples;
+        self.seed = seed;
+        self
+    }
+
+    /// Confidence level of the intervals (default 0.95)
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        assert!(confidence > 0.0 && confidence < 1.0, "confidence must be in (0, 1)");
+        self.confidence = confidence;
+        self
+    }
+
+    /// Smallest |Cliff's delta| that can decide a verdict (default 0.147)
+    pub fn with_min_effect(mut self, min_effect: f64) -> Self {
+        self.min_effect = min_effect;
+        self
+    }
+
+    /// Compare one metric's per-run values, `a` from the baseline and `b` from the candidate
+    pub fn add_metric(&mut self, metric: &str, goal: Goal, a: &[f64], b: &[f64]) -> &MetricComparison {
+        let (mean_a, mean_b) = (mean(a), mean(b));
+        let diff = mean_b - mean_a;
+        // Each metric gets its own stream so adding metrics doesn't shift earlier intervals
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(self.metrics.len() as u64));
+        let (diff_ci_low, diff_ci_high) = bootstrap_diff_ci(a, b, self.resamples, self.confidence, &mut rng);
+        let cliffs_delta = cliffs_delta(a, b);
+
+        let decided = !a.is_empty()
+            && !b.is_empty()
+            && (diff_ci_low > 0.0 || diff_ci_high < 0.0)
+            && cliffs_delta.abs() >= self.min_effect;
+        let verdict = match (decided, goal, diff > 0.0) {
+            (false, _, _) => Verdict::Inconclusive,
+            (true, Goal::Lower, false) | (true, Goal::Higher, true) => Verdict::Better,
+            (true, _, _) => Verdict::Worse,
+        };
+
+        self.metrics.push(MetricComparison {
+            metric: metric.to_string(),
+            goal,
+            runs_a: a.len(),
+            runs_b: b.len(),
+            mean_a,
+            mean_b,
+            diff,
+            diff_ci_low,
+            diff_ci_high,
+            cohens_d: cohens_d(a, b),
+            cliffs_delta,
+            verdict,
+        });
+        self.metrics.last().unwrap()
+    }
+
+    /// Worse if any metric is worse, better if any is better and none worse
+    pub fn verdict(&self) -> Verdict {
+        let any = |v: Verdict| self.metrics.iter().any(|m| m.verdict == v);
+        if any(Verdict::Worse) {
+            Verdict::Worse
+        } else if any(Verdict::Better) {
+            Verdict::Better
+        } else {
+            Verdict::Inconclusive
+        }
+    }
+
+    /// Write the report as JSON
+    pub fn write_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self).map_err(io::Error::from)
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} vs {} ({:.0}% bootstrap CI on B - A, {} resamples)",
+            self.candidate,
+            self.baseline,
+            self.confidence * 100.0,
+            self.resamples
+        )?;
+        for m in &self.metrics {
+            let d = m.cohens_d.map_or_else(|| "n/a".to_string(), |d| format!("{:+.2}", d));
+            writeln!(
+                f,
+                "  {:<18} {:+.2} [{:+.2}, {:+.2}]  d={} delta={:+.2}  {}",
+                m.metric, m.diff, m.diff_ci_low, m.diff_ci_high, d, m.cliffs_delta, m.verdict
+            )?;
+        }
+        write!(f, "Verdict: {} is {} than {}", self.candidate, self.verdict(), self.baseline)
+    }
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+fn sample_variance(samples: &[f64]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(samples);
+    samples.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (samples.len() - 1) as f64
+}
+
+/// Difference of means over pooled standard deviation
+fn cohens_d(a: &[f64], b: &[f64]) -> Option<f64> {
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    if n1 + n2 < 3.0 || a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let pooled = ((n1 - 1.0) * sample_variance(a) + (n2 - 1.0) * sample_variance(b)) / (n1 + n2 - 2.0);
+    if pooled == 0.0 {
+        return None;
+    }
+    Some((mean(b) - mean(a)) / pooled.sqrt())
+}
+
+/// Cliff's delta of `b` over `a`
+fn cliffs_delta(a: &[f64], b: &[f64]) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let mut dominance = 0i64;
+    for &y in b {
+        for &x in a {
+            dominance += (y > x) as i64 - (y < x) as i64;
+        }
+    }
+    dominance as f64 / (a.len() * b.len()) as f64
+}
+
+/// Percentile bootstrap interval on mean(b) - mean(a), resampling each side independently
+fn bootstrap_diff_ci(a: &[f64], b: &[f64], resamples: usize, confidence: f64, rng: &mut StdRng) -> (f64, f64) {
+    if a.is_empty() || b.is_empty() {
+        return (f64::NEG_INFINITY, f64::INFINITY);
+    }
+    let resample_mean = |samples: &[f64], rng: &mut StdRng| {
+        (0..samples.len()).map(|_| samples[rng.gen_range(0..samples.len())]).sum::<f64>() / samples.len() as f64
+    };
+    let mut diffs: Vec<f64> = (0..resamples)
+        .map(|_| resample_mean(b, rng) - resample_mean(a, rng))
+        .collect();
+    diffs.sort_by(|x, y| x.total_cmp(y));
+
+    let tail = (1.0 - confidence) / 2.0;
+    let at = |q: f64| diffs[((q * resamples as f64) as usize).min(resamples - 1)];
+    (at(tail), at(1.0 - tail))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clear_improvement_is_better() {
+        let baseline = [100.0, 104.0, 98.0, 101.0, 103.0, 99.0, 102.0, 100.0];
+        let candidate = [80.0, 83.0, 79.0, 81.0, 82.0, 78.0, 80.0, 84.0];
+        let mut report = ComparisonReport::new("static", "reflex");
+        let m = report.add_metric("p95_task_time_us", Goal::Lower, &baseline, &candidate);
+        assert_eq!(m.verdict, Verdict::Better);
+        assert!(m.diff_ci_high < 0.0);
+        assert_eq!(m.cliffs_delta, -1.0);
+        assert!(m.cohens_d.unwrap() < -5.0);
+
+        report.add_metric("mean_throughput", Goal::Higher, &baseline, &candidate);
+        assert_eq!(report.metrics[1].verdict, Verdict::Worse);
+        assert_eq!(report.verdict(), Verdict::Worse);
+    }
+
+    #[test]
+    fn test_overlapping_runs_are_inconclusive() {
+        let a = [10.0, 12.0, 9.0, 11.0, 13.0, 8.0];
+        let b = [11.0, 9.0, 12.0, 10.0, 8.0, 13.0];
+        let mut report = ComparisonReport::new("a", "b");
+        report.add_metric("p50", Goal::Lower, &a, &b);
+        assert_eq!(report.metrics[0].verdict, Verdict::Inconclusive);
+        assert!(report.metrics[0].diff_ci_low < 0.0 && report.metrics[0].diff_ci_high > 0.0);
+
+        // Identical deterministic runs: no spread, no verdict
+        report.add_metric("flips", Goal::Lower, &[3.0; 5], &[3.0; 5]);
+        assert_eq!(report.metrics[1].cohens_d, None);
+        assert_eq!(report.verdict(), Verdict::Inconclusive);
+    }
+
+    #[test]
+    fn test_bootstrap_is_reproducible() {
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let b = [2.0, 3.0, 4.0, 5.0, 6.0];
+        let ci = |seed| {
+            let mut report = ComparisonReport::new("a", "b").with_bootstrap(500, seed);
+            let m = report.add_metric("x", Goal::Lower, &a, &b);
+            (m.diff_ci_low, m.diff_ci_high)
+        };
+        assert_eq!(ci(7), ci(7));
+    }
+}
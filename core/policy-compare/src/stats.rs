@@ -0,0 +1,104 @@
+//! Summary statistics shared by both simulators' run comparisons
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Mean with a 95% confidence interval (Student t)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MeanCi {
+    pub mean: f64,
+    pub half_width: Option<f64>, // None with fewer than two samples
+}
+
+impl MeanCi {
+    pub fn from_samples(samples: &[f64]) -> Self {
+        let n = samples.len();
+        if n == 0 {
+            return Self {
+                mean: 0.0,
+                half_width: None,
+            };
+        }
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        if n == 1 {
+            return Self { mean, half_width: None };
+        }
+        let var = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        Self {
+            mean,
+            half_width: Some(t_critical_95(n - 1) * (var / n as f64).sqrt()),
+        }
+    }
+
+    pub fn low(&self) -> Option<f64> {
+        self.half_width.map(|half_width| self.mean - half_width)
+    }
+
+    pub fn high(&self) -> Option<f64> {
+        self.half_width.map(|half_width| self.mean + half_width)
+    }
+}
+
+/// `mean ± half_width`, both at the requested precision; `n/a` without an interval
+impl fmt::Display for MeanCi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.mean, f)?;
+        match (self.half_width, f.precision()) {
+            (Some(half_width), Some(precision)) => write!(f, " ± {:.*}", precision, half_width),
+            (Some(half_width), None) => write!(f, " ± {}", half_width),
+            (None, _) => write!(f, " ± n/a"),
+        }
+    }
+}
+
+/// Two-sided 95% critical value of Student's t
+fn t_critical_95(df: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+        2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+        2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+    ];
+    match df {
+        0 => f64::INFINITY,
+        1..=30 => TABLE[df - 1],
+        _ => 1.96,
+    }
+}
+
+/// Standard normal CDF (Abramowitz & Stegun 7.1.26 erf approximation)
+pub fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mean_ci() {
+        let ci = MeanCi::from_samples(&[10.0, 12.0, 14.0]);
+        assert_eq!(ci.mean, 12.0);
+        // s = 2, t(2) = 4.303, half width = 4.303 * 2 / sqrt(3)
+        assert!((ci.half_width.unwrap() - 4.969).abs() < 1e-3);
+        assert_eq!(format!("{:.1}", ci), "12.0 ± 5.0");
+    }
+
+    #[test]
+    fn test_mean_ci_without_interval_round_trips_through_json() {
+        for ci in [MeanCi::from_samples(&[7.0]), MeanCi::from_samples(&[10.0, 12.0, 14.0])] {
+            let json = serde_json::to_string(&ci).unwrap();
+            assert_eq!(serde_json::from_str::<MeanCi>(&json).unwrap(), ci);
+        }
+        let single = MeanCi::from_samples(&[7.0]);
+        assert_eq!(single.half_width, None);
+        assert_eq!(format!("{:.2}", single), "7.00 ± n/a");
+    }
+}
@@ -28,6 +28,26 @@ fn main() {
     for (i, tree) in reflex.trees.iter().enumerate() {
         println!("Tree {}: {} nodes", i, tree.len());
     }
+    for (i, ensemble) in reflex.ensembles.iter().enumerate() {
+        let nodes: usize = ensemble.trees.iter().map(Vec::len).sum();
+        println!(
+            "Ensemble {}: {} trees, {} nodes (base {}, scale {})",
+            i,
+            ensemble.trees.len(),
+            nodes,
+            ensemble.base,
+            ensemble.scale
+        );
+    }
+    for (i, model) in reflex.linear.iter().enumerate() {
+        println!("Linear {}: bias {}, weights {:?}", i, model.bias, model.weights);
+    }
+    for (i, table) in reflex.tables.iter().enumerate() {
+        println!("Table {}:\n{}", i, table);
+    }
+    if let Some(tree) = &reflex.multi_output {
+        println!("Multi-output tree: {} nodes, {} leaves", tree.nodes.len(), tree.leaves.len());
+    }
 
     println!("\n=== Bounds ===");
     println!("Min: {:?}", reflex.bounds.min);
@@ -36,6 +56,12 @@ fn main() {
     println!("\n=== Metadata ===");
     println!("{:#?}", reflex.metadata);
 
+    println!("\n=== Training Report ===");
+    match &reflex.report {
+        Some(report) => print!("{}", report),
+        None => println!("(none)"),
+    }
+
     println!("\n=== Test Inference ===");
     let test_features = vec![20.0, 1000.0, 1000.0, 300.0, 600.0, 1e6, 1e6, 1024.0, 100.0, 50.0];
     let norm_features: Vec<f32> = test_features.iter().map(|&x| x / 10000.0).collect(); // dummy normalization
@@ -1,11 +1,20 @@
 //! Reflex Binary Format
 //!
 //! Binary container for trained reflex models.
-//! Layout: [Header][Model][Bounds][Metadata][Checksum]
+//! Layout: [Header][Model][Bounds][Metadata][Report][Checksum]
+//!
+//! The report section is optional and fills whatever lies between the
+//! metadata and the checksum, so files without one still parse and readers
+//! that predate it skip it.
 
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::io::{self, Write};
 
+mod watch;
+
+pub use watch::{check_shape, FileWatch};
+
 /// Magic bytes: "NEM1"
 pub const MAGIC: [u8; 4] = *b"NEM1";
 
@@ -18,7 +27,25 @@ pub const VERSION: u16 = 1;
 pub enum ModelType {
     DecisionTree = 0,
     Linear = 1,
-    // Future: MLP = 2,
+    Ensemble = 2,
+    MultiOutputTree = 3,
+    LookupTable = 4,
+    // Future: MLP = 5,
+}
+
+impl TryFrom<u8> for ModelType {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ModelType::DecisionTree),
+            1 => Ok(ModelType::Linear),
+            2 => Ok(ModelType::Ensemble),
+            3 => Ok(ModelType::MultiOutputTree),
+            4 => Ok(ModelType::LookupTable),
+            other => Err(format!("Unknown model type: {}", other)),
+        }
+    }
 }
 
 /// Reflex file header (fixed size)
@@ -165,7 +192,7 @@ impl ReflexHeader {
 
 /// Decision tree node (for ModelType::DecisionTree)
 #[repr(C)]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct TreeNode {
     /// Feature index to split on (0xFF = leaf)
     pub feature_idx: u8,
@@ -201,6 +228,175 @@ impl TreeNode {
     }
 }
 
+/// Tree ensemble for one output (for ModelType::Ensemble)
+///
+/// Predicts `base + scale * Σ tree(x)`: a bagged forest is `base = 0`,
+/// `scale = 1/n`; a boosted model is the initial prediction plus
+/// learning-rate-scaled trees.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeEnsemble {
+    pub base: f32,
+    pub scale: f32,
+    pub trees: Vec<Vec<TreeNode>>,
+}
+
+impl TreeEnsemble {
+    /// Unclamped ensemble prediction
+    pub fn eval(&self, features: &[f32]) -> f32 {
+        let sum: f32 = self
+            .trees
+            .iter()
+            .map(|tree| eval_tree(tree, features))
+            .sum();
+        self.base + self.scale * sum
+    }
+}
+
+/// One tree predicting every output (for ModelType::MultiOutputTree)
+///
+/// Splits are ordinary `TreeNode`s; a leaf's `left` indexes its row of
+/// `leaves`, which holds one value per output, so the outputs always come
+/// from the same region of feature space.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultiOutputTree {
+    pub nodes: Vec<TreeNode>,
+    pub leaves: Vec<Vec<f32>>,
+}
+
+impl MultiOutputTree {
+    /// Unclamped outputs of the leaf `features` lands in
+    pub fn eval(&self, features: &[f32]) -> &[f32] {
+        let mut node_idx = 0;
+        loop {
+            let node = &self.nodes[node_idx];
+            if node.is_leaf() {
+                return &self.leaves[node.left as usize];
+            }
+            node_idx = if features[node.feature_idx as usize] <= node.threshold {
+                node.left as usize
+            } else {
+                node.right as usize
+            };
+        }
+    }
+
+    /// Every leaf points at a value row with `outputs` entries
+    fn validate(&self, outputs: usize) -> Result<(), String> {
+        for node in self.nodes.iter().filter(|node| node.is_leaf()) {
+            match self.leaves.get(node.left as usize) {
+                Some(values) if values.len() == outputs => {}
+                Some(values) => {
+                    return Err(format!(
+                        "leaf has {} outputs, expected {}",
+                        values.len(),
+                        outputs
+                    ))
+                }
+                None => return Err(format!("leaf points at missing value row {}", node.left)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Two-feature lookup table for one output (for ModelType::LookupTable)
+///
+/// The row is how many of `row_edges` the `row_feature` value exceeds, the
+/// column likewise for `col_feature`; `values` is row-major. Small enough
+/// to review by reading it, which `Display` prints it for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LookupTable {
+    pub row_feature: u8,
+    pub col_feature: u8,
+    pub row_edges: Vec<f32>, // ascending
+    pub col_edges: Vec<f32>, // ascending
+    pub values: Vec<Vec<f32>>,
+}
+
+impl LookupTable {
+    /// Unclamped value of the cell `features` falls in
+    pub fn eval(&self, features: &[f32]) -> f32 {
+        let row = bin(&self.row_edges, features[self.row_feature as usize]);
+        let col = bin(&self.col_edges, features[self.col_feature as usize]);
+        self.values[row][col]
+    }
+
+    /// One row per row bin, one value per column bin
+    fn validate(&self) -> Result<(), String> {
+        if self.values.len() != self.row_edges.len() + 1
+            || self
+                .values
+                .iter()
+                .any(|row| row.len() != self.col_edges.len() + 1)
+        {
+            return Err(format!(
+                "table needs {}x{} values",
+                self.row_edges.len() + 1,
+                self.col_edges.len() + 1
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Index of the bin `value` falls in: values at an edge go below it, as in tree splits
+fn bin(edges: &[f32], value: f32) -> usize {
+    edges.partition_point(|&edge| value > edge)
+}
+
+/// `<= e0`, `<= e1`, ..., `> e_last`
+fn bin_labels(edges: &[f32]) -> Vec<String> {
+    let mut labels: Vec<String> = edges.iter().map(|edge| format!("<= {:.3}", edge)).collect();
+    labels.push(
+        edges
+            .last()
+            .map_or("any".to_string(), |edge| format!("> {:.3}", edge)),
+    );
+    labels
+}
+
+impl fmt::Display for LookupTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>12}",
+            format!("f{} \\ f{}", self.row_feature, self.col_feature)
+        )?;
+        for label in bin_labels(&self.col_edges) {
+            write!(f, " {:>10}", label)?;
+        }
+        writeln!(f)?;
+        for (label, row) in bin_labels(&self.row_edges).iter().zip(&self.values) {
+            write!(f, "{:>12}", label)?;
+            for value in row {
+                write!(f, " {:>10.2}", value)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Linear model for one output (for ModelType::Linear)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinearModel {
+    pub weights: Vec<f32>, // one per feature
+    pub bias: f32,
+}
+
+impl LinearModel {
+    /// Unclamped `bias + weights · features`
+    pub fn eval(&self, features: &[f32]) -> f32 {
+        self.bias
+            + self
+                .weights
+                .iter()
+                .zip(features)
+                .map(|(w, x)| w * x)
+                .sum::<f32>()
+    }
+}
+
 /// Output bounds for clamping
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputBounds {
@@ -209,7 +405,7 @@ pub struct OutputBounds {
 }
 
 /// Metadata (YAML-encoded)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ReflexMetadata {
     pub created_at: String,
     pub trainer_commit: String,
@@ -217,25 +413,277 @@ pub struct ReflexMetadata {
     pub telemetry_hash: String,
     pub lambda: f32,
     pub notes: String,
+    #[serde(default)]
+    pub schema_hash: String, // of the feature names, in order
+    #[serde(default)]
+    pub config_hash: String, // of the training config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>, // the one RNG seed training drew from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalizer: Option<Normalization>, // embedded, instead of a sidecar file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<Lineage>, // set when warm-started from another reflex
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calibration: Vec<Calibration>, // one output transform per output, or none
+}
+
+/// Monotone piecewise-linear output correction
+///
+/// Maps a clamped output through the knots `(x[i], y[i])`, interpolating
+/// between them and holding the end values beyond them. Fit by isotonic
+/// regression of measured optima on predictions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    pub x: Vec<f32>, // ascending
+    pub y: Vec<f32>, // non-decreasing
+}
+
+impl Calibration {
+    pub fn apply(&self, value: f32) -> f32 {
+        let (Some(&first), Some(&last)) = (self.x.first(), self.x.last()) else {
+            return value;
+        };
+        if value <= first {
+            return self.y[0];
+        }
+        if value >= last {
+            return self.y[self.y.len() - 1];
+        }
+        let i = self.x.partition_point(|&x| x <= value);
+        let t = (value - self.x[i - 1]) / (self.x[i] - self.x[i - 1]);
+        self.y[i - 1] + t * (self.y[i] - self.y[i - 1])
+    }
+
+    /// Knots pair up, with x strictly ascending and y non-decreasing
+    fn validate(&self) -> Result<(), String> {
+        if self.x.len() != self.y.len() {
+            return Err(format!(
+                "calibration has {} x knots but {} y knots",
+                self.x.len(),
+                self.y.len()
+            ));
+        }
+        if !self.x.windows(2).all(|w| w[0] < w[1]) || !self.y.windows(2).all(|w| w[0] <= w[1]) {
+            return Err("calibration knots are not monotone".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The reflex a warm-started model was retrained from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lineage {
+    pub parent_checksum: u32, // the parent file's CRC32 trailer
+    pub parent_created_at: String,
+    pub generation: u32, // 1 for a child of a cold-trained reflex
+    pub method: String,  // e.g. `refit_leaves`, `append_rounds`
+}
+
+/// Per-feature min-max scaling the model's inputs were normalized with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Normalization {
+    pub min: Vec<f32>,
+    pub max: Vec<f32>,
+}
+
+/// Evaluation of the model, written by the trainer (JSON)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrainingReport {
+    pub train_rows: usize,
+    pub validation_rows: usize,
+    #[serde(default)]
+    pub cv_scores: Vec<CvEntry>, // best first
+    #[serde(default)]
+    pub feature_importance: Vec<FeatureImportance>, // most important first
+    #[serde(default)]
+    pub residuals: Option<ResidualSummary>, // on the validation rows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_budget: Option<SizeBudget>, // set when pruned to fit a size budget
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clamping: Vec<OutputClamping>, // one per output, on the validation rows
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub regimes: Vec<RegimeMetrics>, // per workload regime, in curriculum order
+}
+
+/// Validation of one workload regime on its own
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegimeMetrics {
+    pub regime: String,       // e.g. `steady`, `bursty`, `adversarial`
+    pub stage: Option<usize>, // curriculum stage that added it; None when pooled
+    pub train_rows: usize,
+    pub validation_rows: usize,
+    pub mae: f64, // on its validation rows
+}
+
+/// How often an output's bounds clamped the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputClamping {
+    pub min: f32,
+    pub max: f32,
+    pub learned: bool, // bounds derived from the label distribution, not configured
+    pub rows: usize,
+    pub below: usize, // rows whose raw output fell under `min`
+    pub above: usize, // and over `max`
+}
+
+/// Accuracy given up to fit the serialized reflex in a size budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeBudget {
+    pub max_bytes: usize,
+    pub unpruned_bytes: usize,
+    pub unpruned_mae: f64, // validation MAE before pruning
+    pub mae: f64,          // and after
+}
+
+/// Cross-validated score of one hyperparameter candidate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CvEntry {
+    pub params: String,
+    pub mean_mae: f64,
+    pub std_mae: f64,
+}
+
+/// Validation MAE increase when the feature is shuffled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureImportance {
+    pub feature: String,
+    pub mae_increase: f64,
+}
+
+/// Distribution of target - prediction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResidualSummary {
+    pub count: usize,
+    pub mean: f32,
+    pub mae: f32,
+    pub p5: f32,
+    pub p25: f32,
+    pub p50: f32,
+    pub p75: f32,
+    pub p95: f32,
+}
+
+impl fmt::Display for TrainingReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Rows: {} train / {} validation",
+            self.train_rows, self.validation_rows
+        )?;
+        if let Some(r) = &self.residuals {
+            writeln!(
+                f,
+                "Residuals (n={}): mean {:.3}, MAE {:.3}",
+                r.count, r.mean, r.mae
+            )?;
+            writeln!(
+                f,
+                "  p5 {:.3} | p25 {:.3} | p50 {:.3} | p75 {:.3} | p95 {:.3}",
+                r.p5, r.p25, r.p50, r.p75, r.p95
+            )?;
+        }
+        if !self.cv_scores.is_empty() {
+            writeln!(f, "CV scores (MAE, best first):")?;
+            for entry in &self.cv_scores {
+                writeln!(
+                    f,
+                    "  {:.3} ± {:.3}  {}",
+                    entry.mean_mae, entry.std_mae, entry.params
+                )?;
+            }
+        }
+        if !self.feature_importance.is_empty() {
+            writeln!(f, "Feature importance (MAE increase when shuffled):")?;
+            for entry in &self.feature_importance {
+                writeln!(f, "  {:>10.3}  {}", entry.mae_increase, entry.feature)?;
+            }
+        }
+        if !self.regimes.is_empty() {
+            writeln!(f, "Per-regime validation:")?;
+            for r in &self.regimes {
+                let stage = r
+                    .stage
+                    .map_or("pooled".to_string(), |stage| format!("stage {}", stage));
+                writeln!(
+                    f,
+                    "  {:<12} MAE {:.3} on {} rows ({} train, {})",
+                    r.regime, r.mae, r.validation_rows, r.train_rows, stage
+                )?;
+            }
+        }
+        for (i, c) in self.clamping.iter().enumerate() {
+            writeln!(
+                f,
+                "Output {} bounds [{}, {}] ({}): clamped {} below, {} above of {} rows",
+                i,
+                c.min,
+                c.max,
+                if c.learned {
+                    "learned from labels"
+                } else {
+                    "configured"
+                },
+                c.below,
+                c.above,
+                c.rows
+            )?;
+        }
+        if let Some(budget) = &self.size_budget {
+            writeln!(
+                f,
+                "Size budget: {} bytes (unpruned {}), validation MAE {:.3} -> {:.3}",
+                budget.max_bytes, budget.unpruned_bytes, budget.unpruned_mae, budget.mae
+            )?;
+        }
+        Ok(())
+    }
 }
 
 /// Complete reflex model
 #[derive(Debug, Clone)]
 pub struct Reflex {
     pub header: ReflexHeader,
-    pub trees: Vec<Vec<TreeNode>>, // one tree per output
+    pub trees: Vec<Vec<TreeNode>>, // one tree per output (DecisionTree)
+    pub ensembles: Vec<TreeEnsemble>, // one ensemble per output (Ensemble)
+    pub linear: Vec<LinearModel>,  // one model per output (Linear)
+    pub multi_output: Option<MultiOutputTree>, // one tree for all outputs (MultiOutputTree)
+    pub tables: Vec<LookupTable>,  // one table per output (LookupTable)
     pub bounds: OutputBounds,
     pub metadata: ReflexMetadata,
+    pub report: Option<TrainingReport>,
 }
 
 impl Reflex {
+    /// Attach the trainer's evaluation, written as the report section
+    pub fn with_report(mut self, report: TrainingReport) -> Self {
+        self.report = Some(report);
+        self
+    }
+
+    /// CRC32 trailer of the serialized file, identifying this exact model
+    pub fn checksum(&self) -> io::Result<u32> {
+        let bytes = self.to_bytes()?;
+        let trailer = &bytes[bytes.len() - 4..];
+        Ok(u32::from_le_bytes([
+            trailer[0], trailer[1], trailer[2], trailer[3],
+        ]))
+    }
+
     /// Serialize to binary format
     pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
         let mut buf = Vec::new();
 
-        // Serialize model (trees)
-        let model_bytes = serde_json::to_vec(&self.trees)
+        // Serialize model (trees or ensembles, by model type)
+        let model_type = ModelType::try_from(self.header.model_type)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let model_bytes = match model_type {
+            ModelType::DecisionTree => serde_json::to_vec(&self.trees),
+            ModelType::Ensemble => serde_json::to_vec(&self.ensembles),
+            ModelType::Linear => serde_json::to_vec(&self.linear),
+            ModelType::MultiOutputTree => serde_json::to_vec(&self.multi_output),
+            ModelType::LookupTable => serde_json::to_vec(&self.tables),
+        }
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
         // Serialize bounds
         let bounds_bytes = serde_json::to_vec(&self.bounds)
@@ -247,7 +695,7 @@ impl Reflex {
 
         // Build header
         let header = ReflexHeader::new(
-            ModelType::DecisionTree,
+            model_type,
             self.header.feature_count,
             self.header.output_count,
             self.header.created_at_unix,
@@ -268,6 +716,13 @@ impl Reflex {
         // Write metadata
         buf.write_all(&metadata_bytes)?;
 
+        // Write report, if any
+        if let Some(report) = &self.report {
+            let report_bytes = serde_json::to_vec(report)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            buf.write_all(&report_bytes)?;
+        }
+
         // Compute and write CRC32
         let crc = crc32fast::hash(&buf);
         buf.write_all(&crc.to_le_bytes())?;
@@ -278,10 +733,7 @@ impl Reflex {
     /// Deserialize from binary format
     pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
         if data.len() < ReflexHeader::SIZE + 4 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Data too short",
-            ));
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Data too short"));
         }
 
         // Extract and validate checksum
@@ -297,20 +749,61 @@ impl Reflex {
         if actual_crc != expected_crc {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
-                format!("CRC mismatch: expected {}, got {}", expected_crc, actual_crc),
+                format!(
+                    "CRC mismatch: expected {}, got {}",
+                    expected_crc, actual_crc
+                ),
             ));
         }
 
         // Parse header
         let header = ReflexHeader::from_bytes(&payload[..ReflexHeader::SIZE])?;
-        header.validate().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        header
+            .validate()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let model_type = ModelType::try_from(header.model_type)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
         let mut offset = ReflexHeader::SIZE;
 
         // Parse model
         let model_end = offset + header.model_size_bytes as usize;
-        let trees: Vec<Vec<TreeNode>> = serde_json::from_slice(&payload[offset..model_end])
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let model_slice = &payload[offset..model_end];
+        let mut trees: Vec<Vec<TreeNode>> = Vec::new();
+        let mut ensembles: Vec<TreeEnsemble> = Vec::new();
+        let mut linear: Vec<LinearModel> = Vec::new();
+        let mut multi_output: Option<MultiOutputTree> = None;
+        let mut tables: Vec<LookupTable> = Vec::new();
+        match model_type {
+            ModelType::DecisionTree => {
+                trees = serde_json::from_slice(model_slice)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            }
+            ModelType::Ensemble => {
+                ensembles = serde_json::from_slice(model_slice)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            }
+            ModelType::Linear => {
+                linear = serde_json::from_slice(model_slice)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            }
+            ModelType::MultiOutputTree => {
+                let tree: MultiOutputTree = serde_json::from_slice(model_slice)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                tree.validate(header.output_count as usize)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                multi_output = Some(tree);
+            }
+            ModelType::LookupTable => {
+                tables = serde_json::from_slice(model_slice)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                for table in &tables {
+                    table
+                        .validate()
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                }
+            }
+        }
         offset = model_end;
 
         // Parse bounds
@@ -323,52 +816,103 @@ impl Reflex {
         let metadata_end = offset + header.metadata_size_bytes as usize;
         let metadata: ReflexMetadata = serde_json::from_slice(&payload[offset..metadata_end])
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        for calibration in &metadata.calibration {
+            calibration
+                .validate()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+
+        // Parse report: the rest of the payload, if any
+        let report = if metadata_end < payload_len {
+            Some(
+                serde_json::from_slice(&payload[metadata_end..])
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            )
+        } else {
+            None
+        };
 
         Ok(Reflex {
             header,
             trees,
+            ensembles,
+            linear,
+            multi_output,
+            tables,
             bounds,
             metadata,
+            report,
         })
     }
 
     /// Run inference on a single sample
     pub fn infer(&self, features: &[f32]) -> Vec<f32> {
+        let mut outputs = self.raw_outputs(features);
+
+        // Clamp to bounds, calibrate, and clamp again
+        for (i, output) in outputs.iter_mut().enumerate() {
+            *output = output.clamp(self.bounds.min[i], self.bounds.max[i]);
+            if let Some(calibration) = self.metadata.calibration.get(i) {
+                *output = calibration
+                    .apply(*output)
+                    .clamp(self.bounds.min[i], self.bounds.max[i]);
+            }
+        }
+
+        outputs
+    }
+
+    /// The model's outputs before bounds and calibration
+    pub fn raw_outputs(&self, features: &[f32]) -> Vec<f32> {
         assert_eq!(
             features.len(),
             self.header.feature_count as usize,
             "Feature count mismatch"
         );
 
-        let mut outputs = Vec::with_capacity(self.trees.len());
-
-        for tree in &self.trees {
-            let value = self.eval_tree(tree, features);
-            outputs.push(value);
+        match ModelType::try_from(self.header.model_type) {
+            Ok(ModelType::Ensemble) => self
+                .ensembles
+                .iter()
+                .map(|ensemble| ensemble.eval(features))
+                .collect(),
+            Ok(ModelType::Linear) => self
+                .linear
+                .iter()
+                .map(|model| model.eval(features))
+                .collect(),
+            Ok(ModelType::LookupTable) => self
+                .tables
+                .iter()
+                .map(|table| table.eval(features))
+                .collect(),
+            Ok(ModelType::MultiOutputTree) => self
+                .multi_output
+                .as_ref()
+                .map_or(Vec::new(), |tree| tree.eval(features).to_vec()),
+            _ => self
+                .trees
+                .iter()
+                .map(|tree| eval_tree(tree, features))
+                .collect(),
         }
-
-        // Clamp to bounds
-        for (i, output) in outputs.iter_mut().enumerate() {
-            *output = output.clamp(self.bounds.min[i], self.bounds.max[i]);
-        }
-
-        outputs
     }
+}
 
-    fn eval_tree(&self, tree: &[TreeNode], features: &[f32]) -> f32 {
-        let mut node_idx = 0;
-        loop {
-            let node = &tree[node_idx];
-            if node.is_leaf() {
-                return node.threshold;
-            }
-            let feature_val = features[node.feature_idx as usize];
-            node_idx = if feature_val <= node.threshold {
-                node.left as usize
-            } else {
-                node.right as usize
-            };
+/// Walk one tree to its leaf value
+pub fn eval_tree(tree: &[TreeNode], features: &[f32]) -> f32 {
+    let mut node_idx = 0;
+    loop {
+        let node = &tree[node_idx];
+        if node.is_leaf() {
+            return node.threshold;
         }
+        let feature_val = features[node.feature_idx as usize];
+        node_idx = if feature_val <= node.threshold {
+            node.left as usize
+        } else {
+            node.right as usize
+        };
     }
 }
 
@@ -380,7 +924,7 @@ mod tests {
     fn test_header_roundtrip() {
         let h = ReflexHeader::new(ModelType::DecisionTree, 10, 2, 1728000000, 100, 50, 200);
         let bytes = h.to_bytes();
-        let h2 = ReflexHeader::from_bytes(bytes);
+        let h2 = ReflexHeader::from_bytes(&bytes).unwrap();
         assert_eq!(h.magic, h2.magic);
         assert_eq!(h.version, h2.version);
         assert_eq!(h.model_type, h2.model_type);
@@ -399,6 +943,10 @@ mod tests {
         let reflex = Reflex {
             header: ReflexHeader::new(ModelType::DecisionTree, 1, 1, 1728000000, 0, 0, 0),
             trees: vec![tree],
+            ensembles: Vec::new(),
+            linear: Vec::new(),
+            multi_output: None,
+            tables: Vec::new(),
             bounds: OutputBounds {
                 min: vec![0.0],
                 max: vec![100.0],
@@ -410,7 +958,9 @@ mod tests {
                 telemetry_hash: "abcd".to_string(),
                 lambda: 0.1,
                 notes: "test reflex".to_string(),
+                ..ReflexMetadata::default()
             },
+            report: None,
         };
 
         // Serialize and deserialize
@@ -423,5 +973,220 @@ mod tests {
 
         let out2 = reflex2.infer(&[0.7]);
         assert_eq!(out2[0], 20.0);
+        assert_eq!(reflex2.report, None);
+        assert_eq!(
+            reflex2.checksum().unwrap().to_le_bytes(),
+            bytes[bytes.len() - 4..]
+        );
+
+        // With a report section
+        let report = TrainingReport {
+            train_rows: 80,
+            validation_rows: 20,
+            cv_scores: vec![CvEntry {
+                params: "max_depth=3".to_string(),
+                mean_mae: 1.5,
+                std_mae: 0.25,
+            }],
+            ..TrainingReport::default()
+        };
+        let with_report = reflex2.with_report(report.clone());
+        let reflex3 = Reflex::from_bytes(&with_report.to_bytes().unwrap()).unwrap();
+        assert_eq!(reflex3.report, Some(report));
+        assert_eq!(reflex3.infer(&[0.7])[0], 20.0);
+    }
+
+    #[test]
+    fn test_ensemble_roundtrip() {
+        let stump = |low, high| {
+            vec![
+                TreeNode::split(0, 0.5, 1, 2),
+                TreeNode::leaf(low),
+                TreeNode::leaf(high),
+            ]
+        };
+        let reflex = Reflex {
+            header: ReflexHeader::new(ModelType::Ensemble, 1, 1, 1728000000, 0, 0, 0),
+            trees: Vec::new(),
+            ensembles: vec![TreeEnsemble {
+                base: 0.0,
+                scale: 0.5,
+                trees: vec![stump(10.0, 20.0), stump(30.0, 40.0)],
+            }],
+            linear: Vec::new(),
+            multi_output: None,
+            tables: Vec::new(),
+            bounds: OutputBounds {
+                min: vec![0.0],
+                max: vec![25.0],
+            },
+            metadata: ReflexMetadata {
+                created_at: "2025-10-06T12:00:00Z".to_string(),
+                trainer_commit: "test".to_string(),
+                feature_schema: "v1".to_string(),
+                telemetry_hash: "abcd".to_string(),
+                lambda: 0.0,
+                notes: "forest".to_string(),
+                ..ReflexMetadata::default()
+            },
+            report: None,
+        };
+
+        let reflex2 = Reflex::from_bytes(&reflex.to_bytes().unwrap()).unwrap();
+        assert_eq!(reflex2.header.model_type, ModelType::Ensemble as u8);
+        assert_eq!(reflex2.infer(&[0.3])[0], 20.0); // mean of 10 and 30
+        assert_eq!(reflex2.infer(&[0.7])[0], 25.0); // mean of 20 and 40, clamped
+    }
+
+    #[test]
+    fn test_linear_roundtrip() {
+        let reflex = Reflex {
+            header: ReflexHeader::new(ModelType::Linear, 2, 1, 1728000000, 0, 0, 0),
+            trees: Vec::new(),
+            ensembles: Vec::new(),
+            linear: vec![LinearModel {
+                weights: vec![10.0, -4.0],
+                bias: 2.0,
+            }],
+            multi_output: None,
+            tables: Vec::new(),
+            bounds: OutputBounds {
+                min: vec![1.0],
+                max: vec![64.0],
+            },
+            metadata: ReflexMetadata {
+                created_at: "2025-10-06T12:00:00Z".to_string(),
+                trainer_commit: "test".to_string(),
+                feature_schema: "v1".to_string(),
+                telemetry_hash: "abcd".to_string(),
+                lambda: 0.0,
+                notes: "ridge".to_string(),
+                ..ReflexMetadata::default()
+            },
+            report: None,
+        };
+
+        let reflex2 = Reflex::from_bytes(&reflex.to_bytes().unwrap()).unwrap();
+        assert_eq!(reflex2.linear[0].weights, vec![10.0, -4.0]);
+        assert_eq!(reflex2.infer(&[0.5, 0.5])[0], 5.0);
+        assert_eq!(reflex2.infer(&[0.0, 1.0])[0], 1.0); // -2, clamped
+    }
+
+    #[test]
+    fn test_calibration_maps_clamped_outputs() {
+        let calibration = Calibration {
+            x: vec![4.0, 8.0, 16.0],
+            y: vec![6.0, 6.0, 20.0],
+        };
+        assert_eq!(calibration.apply(2.0), 6.0);
+        assert_eq!(calibration.apply(12.0), 13.0);
+        assert_eq!(calibration.apply(30.0), 20.0);
+
+        let reflex = Reflex {
+            header: ReflexHeader::new(ModelType::Linear, 1, 1, 1728000000, 0, 0, 0),
+            trees: Vec::new(),
+            ensembles: Vec::new(),
+            linear: vec![LinearModel {
+                weights: vec![100.0],
+                bias: 0.0,
+            }],
+            multi_output: None,
+            tables: Vec::new(),
+            bounds: OutputBounds {
+                min: vec![1.0],
+                max: vec![16.0],
+            },
+            metadata: ReflexMetadata {
+                calibration: vec![calibration],
+                ..ReflexMetadata::default()
+            },
+            report: None,
+        };
+        let reflex2 = Reflex::from_bytes(&reflex.to_bytes().unwrap()).unwrap();
+        assert_eq!(reflex2.metadata.calibration, reflex.metadata.calibration);
+        assert_eq!(reflex2.infer(&[0.12])[0], 13.0);
+        assert_eq!(reflex2.infer(&[1.0])[0], 16.0); // 100 clamped to 16, maps to 20, clamped
+    }
+
+    #[test]
+    fn test_lookup_table_roundtrip() {
+        let table = LookupTable {
+            row_feature: 0,
+            col_feature: 1,
+            row_edges: vec![0.5],
+            col_edges: vec![0.25, 0.75],
+            values: vec![vec![2.0, 4.0, 8.0], vec![8.0, 16.0, 80.0]],
+        };
+        let reflex = Reflex {
+            header: ReflexHeader::new(ModelType::LookupTable, 2, 1, 1728000000, 0, 0, 0),
+            trees: Vec::new(),
+            ensembles: Vec::new(),
+            linear: Vec::new(),
+            multi_output: None,
+            tables: vec![table.clone()],
+            bounds: OutputBounds {
+                min: vec![1.0],
+                max: vec![64.0],
+            },
+            metadata: ReflexMetadata::default(),
+            report: None,
+        };
+
+        let reflex2 = Reflex::from_bytes(&reflex.to_bytes().unwrap()).unwrap();
+        assert_eq!(reflex2.tables[0], table);
+        assert_eq!(reflex2.infer(&[0.5, 0.25])[0], 2.0); // edges go below
+        assert_eq!(reflex2.infer(&[0.6, 0.5])[0], 16.0);
+        assert_eq!(reflex2.infer(&[0.9, 0.9])[0], 64.0); // 80, clamped
+
+        let printed = table.to_string();
+        assert!(
+            printed.starts_with("     f0 \\ f1   <= 0.250"),
+            "{}",
+            printed
+        );
+        assert!(
+            printed.lines().nth(2).unwrap().starts_with("     > 0.500"),
+            "{}",
+            printed
+        );
+
+        let mut ragged = reflex;
+        ragged.tables[0].values[1].pop();
+        assert!(Reflex::from_bytes(&ragged.to_bytes().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_multi_output_roundtrip() {
+        let leaf = |row| TreeNode {
+            left: row,
+            ..TreeNode::leaf(0.0)
+        };
+        let reflex = Reflex {
+            header: ReflexHeader::new(ModelType::MultiOutputTree, 1, 2, 1728000000, 0, 0, 0),
+            trees: Vec::new(),
+            ensembles: Vec::new(),
+            linear: Vec::new(),
+            multi_output: Some(MultiOutputTree {
+                nodes: vec![TreeNode::split(0, 0.5, 1, 2), leaf(0), leaf(1)],
+                leaves: vec![vec![4.0, 50.0], vec![16.0, 4000.0]],
+            }),
+            tables: Vec::new(),
+            bounds: OutputBounds {
+                min: vec![1.0, 50.0],
+                max: vec![64.0, 1000.0],
+            },
+            metadata: ReflexMetadata::default(),
+            report: None,
+        };
+
+        let reflex2 = Reflex::from_bytes(&reflex.to_bytes().unwrap()).unwrap();
+        assert_eq!(reflex2.multi_output, reflex.multi_output);
+        assert_eq!(reflex2.infer(&[0.3]), vec![4.0, 50.0]);
+        assert_eq!(reflex2.infer(&[0.7]), vec![16.0, 1000.0]); // second output clamped
+
+        // A leaf row with the wrong number of outputs doesn't load
+        let mut short = reflex;
+        short.multi_output.as_mut().unwrap().leaves[1].pop();
+        assert!(Reflex::from_bytes(&short.to_bytes().unwrap()).is_err());
     }
 }
@@ -0,0 +1,88 @@
+//! Polling a `.reflex` file for rewrites
+//!
+//! Shared by the simulators' reloadable reflex policies: [`FileWatch`]
+//! reports a new version of the file whenever its modification time or
+//! size changes, and [`check_shape`] refuses a version that wouldn't drop
+//! in for the running model.
+
+use crate::Reflex;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Modification time and size, enough to tell a rewritten file apart
+type FileStamp = (SystemTime, u64);
+
+/// Watches one file, rate-limiting how often it's looked at
+#[derive(Debug)]
+pub struct FileWatch {
+    path: PathBuf,
+    stamp: Option<FileStamp>, // of the last version read
+    poll_interval: Duration,
+    last_poll: Instant,
+}
+
+impl FileWatch {
+    pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Watch `path`; the file counts as changed until it's first read
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            stamp: None,
+            poll_interval: Self::DEFAULT_POLL_INTERVAL,
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// How often `poll_due` lets a check through (default 1s, wall clock); zero lets every one through
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether a poll interval has passed since the last time this returned true
+    pub fn poll_due(&mut self) -> bool {
+        if self.last_poll.elapsed() < self.poll_interval {
+            return false;
+        }
+        self.last_poll = Instant::now();
+        true
+    }
+
+    /// Read the file and remember this version as seen
+    pub fn read(&mut self) -> io::Result<Vec<u8>> {
+        let metadata = fs::metadata(&self.path)?;
+        self.stamp = Some((metadata.modified()?, metadata.len()));
+        fs::read(&self.path)
+    }
+
+    /// Read the file if it changed since it was last read
+    pub fn changed(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let metadata = fs::metadata(&self.path)?;
+        if self.stamp == Some((metadata.modified()?, metadata.len())) {
+            return Ok(None);
+        }
+        self.read().map(Some)
+    }
+}
+
+/// Check `next` takes and returns as many values as `running`
+pub fn check_shape(running: &Reflex, next: &Reflex) -> io::Result<()> {
+    let (running, next) = (&running.header, &next.header);
+    if (next.feature_count, next.output_count) != (running.feature_count, running.output_count) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} features and {} outputs, the running model has {} and {}",
+                next.feature_count, next.output_count, running.feature_count, running.output_count
+            ),
+        ));
+    }
+    Ok(())
+}
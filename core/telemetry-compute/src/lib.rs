@@ -7,6 +7,10 @@ use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
 /// Telemetry sample (raw, unnormalized)
+///
+/// Fields after `idle_worker_count` are extended telemetry: visible to
+/// policies but not part of the v1 feature vector, so existing reflexes and
+/// normalizers keep working.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct ComputeTelemetry {
     pub timestamp_us: u64,
@@ -20,6 +24,26 @@ pub struct ComputeTelemetry {
     pub task_size_mean: f32,            // mean task execution time (µs)
     pub task_size_var: f32,             // variance of task execution time (µs²)
     pub idle_worker_count: u32,         // number of idle workers
+    #[serde(default)]
+    pub memory_headroom_mb: f32,        // memory budget left after worker reservations
+    #[serde(default)]
+    pub pool_size: u32,                 // workers currently in the pool
+    #[serde(default)]
+    pub service_time_mean_us: f32,      // mean work of tasks completed in the window
+    #[serde(default)]
+    pub shed_rate: f32,                 // tasks/s rejected by admission control
+    #[serde(default)]
+    pub batch_size_mean: f32,           // tasks per arrival event
+    #[serde(default)]
+    pub batch_size_var: f32,            // variance of tasks per arrival event
+    #[serde(default)]
+    pub queue_discipline: u32,          // 0 = FIFO, 1 = LIFO, 2 = shortest-job-first
+    #[serde(default)]
+    pub oldest_wait_us: f32,            // age of the longest-waiting queued task
+    #[serde(default)]
+    pub wait_time_p50_us: f32,          // median queue wait (arrival → first start)
+    #[serde(default)]
+    pub wait_time_p95_us: f32,          // 95th percentile queue wait
 }
 
 impl ComputeTelemetry {
@@ -75,9 +99,9 @@ impl Normalizer {
 
     /// Update bounds from a sample
     pub fn observe(&mut self, features: &[f32; ComputeTelemetry::FEATURE_COUNT]) {
-        for i in 0..ComputeTelemetry::FEATURE_COUNT {
-            self.min[i] = self.min[i].min(features[i]);
-            self.max[i] = self.max[i].max(features[i]);
+        for (i, &value) in features.iter().enumerate() {
+            self.min[i] = self.min[i].min(value);
+            self.max[i] = self.max[i].max(value);
         }
     }
 
@@ -195,6 +219,16 @@ mod tests {
             task_size_mean: 450.0,
             task_size_var: 2500.0,
             idle_worker_count: 1,
+            memory_headroom_mb: 0.0,
+            pool_size: 8,
+            service_time_mean_us: 450.0,
+            shed_rate: 0.0,
+            batch_size_mean: 0.0,
+            batch_size_var: 0.0,
+            queue_discipline: 0,
+            oldest_wait_us: 0.0,
+            wait_time_p50_us: 50.0,
+            wait_time_p95_us: 750.0,
         };
 
         let features = telem.to_features();
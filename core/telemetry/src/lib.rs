@@ -7,7 +7,11 @@ use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
 /// Telemetry sample (raw, unnormalized)
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+///
+/// Fields after `rtt_ewma_us` are extended telemetry: visible to policies but
+/// not part of the v1 feature vector, so existing reflexes and normalizers
+/// keep working.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct TelemetrySample {
     pub timestamp_us: u64,
     pub queue_depth: u32,
@@ -20,6 +24,14 @@ pub struct TelemetrySample {
     pub packet_size_mean: f32,
     pub packet_size_var: f32,
     pub rtt_ewma_us: f32,
+    #[serde(default)]
+    pub loss_rate: f32,         // fraction of transmissions lost (last second)
+    #[serde(default, alias = "drop_rate")]
+    pub drops_per_sec: f32,     // packets/s dropped at a full queue
+    #[serde(default)]
+    pub link_idle_us: f32,      // time since the link last finished transmitting
+    #[serde(default)]
+    pub latency_p99_us: f32,
 }
 
 impl TelemetrySample {
@@ -75,9 +87,9 @@ impl Normalizer {
 
     /// Update bounds from a sample
     pub fn observe(&mut self, features: &[f32; TelemetrySample::FEATURE_COUNT]) {
-        for i in 0..TelemetrySample::FEATURE_COUNT {
-            self.min[i] = self.min[i].min(features[i]);
-            self.max[i] = self.max[i].max(features[i]);
+        for (i, &value) in features.iter().enumerate() {
+            self.min[i] = self.min[i].min(value);
+            self.max[i] = self.max[i].max(value);
         }
     }
 
@@ -207,6 +219,10 @@ mod tests {
             packet_size_mean: 1024.0,
             packet_size_var: 100.0,
             rtt_ewma_us: 50.0,
+            loss_rate: 0.0,
+            drops_per_sec: 0.0,
+            link_idle_us: 0.0,
+            latency_p99_us: 250.0,
         };
 
         wc.push(sample);
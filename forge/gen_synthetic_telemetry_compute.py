@@ -9,6 +9,25 @@ import pandas as pd
 import numpy as np
 import sys
 
+# Mirrors sim-compute's CoreModel defaults
+CORES = 8
+TIMESLICE_US = 3000.0
+
+
+def estimate_ctx_switches(completion_rate, exec_time_us, n_workers, worker_util):
+    """
+    Context switches per second, counted the way the simulator does: one per
+    task start, plus one per extra timeslice while busy workers outnumber cores.
+
+    Sampled as a one-second Poisson count, so the feature carries the noise
+    of a real window rather than a fixed per-worker rate.
+    """
+    busy_workers = n_workers * worker_util
+    switches_per_task = 1.0
+    if busy_workers > CORES:
+        switches_per_task += max(0.0, np.ceil(exec_time_us / TIMESLICE_US) - 1.0)
+    return float(np.random.poisson(max(0.0, completion_rate) * switches_per_task))
+
 
 def generate_steady_workload(n_samples=1000):
     """
@@ -39,8 +58,8 @@ def generate_steady_workload(n_samples=1000):
         # Worker utilization
         worker_util = min(1.0, arrival_rate / (n_workers * tasks_per_worker))
 
-        # Context switches increase with worker count
-        ctx_switches_per_sec = n_workers * 10.0 + np.random.normal(0, 20)
+        # Task starts, plus time slicing if busy workers outnumber cores
+        ctx_switches_per_sec = estimate_ctx_switches(completion_rate, exec_time, n_workers, worker_util)
 
         # Task size stats
         task_size_mean = exec_time
@@ -90,7 +109,7 @@ def generate_bursty_workload(n_samples=1000):
         task_time_p95 = task_time_p50 * 1.5 + np.random.normal(0, 100)
 
         worker_util = min(1.0, arrival_rate / (n_workers * tasks_per_worker))
-        ctx_switches_per_sec = n_workers * 10.0 + np.random.normal(0, 20)
+        ctx_switches_per_sec = estimate_ctx_switches(completion_rate, exec_time, n_workers, worker_util)
 
         task_size_mean = exec_time
         task_size_var = 2500.0 + np.random.normal(0, 500)
@@ -136,7 +155,7 @@ def generate_adversarial_workload(n_samples=1000):
         task_time_p95 = task_time_p50 * np.random.uniform(1.2, 2.5)
 
         worker_util = min(1.0, arrival_rate / (n_workers * tasks_per_worker))
-        ctx_switches_per_sec = n_workers * np.random.uniform(5, 20)
+        ctx_switches_per_sec = estimate_ctx_switches(completion_rate, exec_time, n_workers, worker_util)
 
         task_size_mean = exec_time
         task_size_var = exec_time * exec_time * np.random.uniform(0.1, 0.5)
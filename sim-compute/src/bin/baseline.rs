@@ -1,42 +1,69 @@
 //! Baseline thread pool simulator
 //!
-//! Runs thread pool with static sizing (N=8)
+//! Runs thread pool with static sizing (N=8), or with the Erlang C
+//! queueing-theory baseline (`erlang`), the online hill-climbing tuner
+//! (`hillclimb`) or a PID controller holding the run queue near 4 (`pid`)
 
-use sim_compute::{BaselinePolicy, SteadyWorkload, ThreadPoolSim, WorkloadGenerator};
-use std::thread;
+use cli::take_flag;
+use sim_compute::{BaselinePolicy, EnergyModel, ErlangCPolicy, HillClimbPolicy, PidPolicy, PoolSizePolicy, SteadyWorkload, ThreadPoolSim, TraceWorkload, write_decision_log, write_state_series};
 use std::time::Duration;
 
 fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let trace_path = take_flag(&mut args, "--trace");
+    let decision_log_path = take_flag(&mut args, "--decision-log");
+    let state_series_path = take_flag(&mut args, "--state-series");
+    let state_interval = take_flag(&mut args, "--state-interval-ms")
+        .map(|s| Duration::from_millis(s.parse().expect("--state-interval-ms must be a whole number")))
+        .unwrap_or(Duration::from_millis(10));
+    let policy_name = args.get(1).cloned().unwrap_or_else(|| "static".to_string());
+    let policy: Box<dyn PoolSizePolicy> = match policy_name.as_str() {
+        "static" => Box::new(BaselinePolicy::new()),
+        "erlang" => Box::new(ErlangCPolicy::default()),
+        "hillclimb" => Box::new(HillClimbPolicy::new(8)),
+        "pid" => Box::new(PidPolicy::queue_length(4.0)),
+        _ => {
+            eprintln!("Usage: baseline-compute [static|erlang|hillclimb|pid] [--trace tasks.csv] [--decision-log log.jsonl] [--state-series series.csv]");
+            std::process::exit(1);
+        }
+    };
+
     println!("=== Thread Pool Simulator: Baseline ===");
-    println!("Policy: Static N=8 workers\n");
+    match policy_name.as_str() {
+        "erlang" => println!("Policy: Erlang C, P(wait) <= 0.2\n"),
+        "hillclimb" => println!("Policy: Hill-climbing tuner from N=8\n"),
+        "pid" => println!("Policy: PID on run queue length, setpoint 4\n"),
+        _ => println!("Policy: Static N=8 workers\n"),
+    }
 
-    let policy = BaselinePolicy::new();
     let mut sim = ThreadPoolSim::new(policy, 8);
+    if decision_log_path.is_some() {
+        sim = sim.with_decision_log();
+    }
+    if state_series_path.is_some() {
+        sim = sim.with_state_series(state_interval);
+    }
 
-    // Steady workload: 100 tasks/sec, 500µs per task, for 10 seconds
-    let mut workload = SteadyWorkload::new(100.0, 500, Duration::from_secs(10));
-
-    println!("Starting simulation...");
-    println!("Workload: Steady 100 tasks/sec, 500µs/task, 10s duration\n");
-
-    let start = std::time::Instant::now();
-
-    // Run simulation
-    loop {
-        // Generate tasks
-        if let Some((wait, work_us)) = workload.next_task() {
-            thread::sleep(wait.min(Duration::from_micros(100))); // Speed up sim
-            sim.enqueue(work_us);
-        }
+    if let Some(path) = trace_path {
+        // Replay the trace on the virtual clock, with a second to drain
+        let mut workload = TraceWorkload::from_csv(&path).expect("Failed to load trace");
+        println!(
+            "Workload: trace {} ({} tasks over {:.1}s, virtual time)\n",
+            path,
+            workload.len(),
+            workload.span().as_secs_f64()
+        );
+        let duration = workload.span() + Duration::from_secs(1);
+        sim.run_virtual(&mut workload, duration);
+    } else {
+        // Steady workload: 100 tasks/sec, 500µs per task, for 10 seconds
+        let mut workload = SteadyWorkload::new(100.0, 500, Duration::from_secs(10));
 
-        // Tick simulator every 10ms
-        sim.tick();
-        thread::sleep(Duration::from_millis(10));
+        println!("Starting simulation...");
+        println!("Workload: Steady 100 tasks/sec, 500µs/task, 10s duration\n");
 
-        // Check if done
-        if start.elapsed() >= Duration::from_secs(11) {
-            break;
-        }
+        // Wall clock, retiring each task the moment it finishes; one extra second to drain
+        sim.run_real_time(&mut workload, Duration::from_secs(11));
     }
 
     // Print metrics
@@ -46,6 +73,33 @@ fn main() {
     println!("p50 task time: {:.2} µs", metrics.p50_task_time());
     println!("p95 task time: {:.2} µs", metrics.p95_task_time());
     println!("p99 task time: {:.2} µs", metrics.p99_task_time());
+    println!(
+        "p95 wait / service: {:.2} / {:.2} µs",
+        metrics.wait_percentile(0.95),
+        metrics.service_percentile(0.95)
+    );
     println!("Mean throughput: {:.2} tasks/s", metrics.mean_throughput());
     println!("Decision changes: {}", metrics.decision_changes);
+    println!("Changes per minute: {:.2}", metrics.changes_per_minute());
+    println!("Flip-flops: {}", metrics.flip_flops);
+    println!(
+        "Workers retired: {} (p95 shrink latency {:.2} µs)",
+        metrics.shrink_latencies_us.len(),
+        metrics.shrink_latency_percentile(0.95)
+    );
+    println!(
+        "Energy: {:.2} J ({:.1} busy / {:.1} idle worker-s)",
+        metrics.energy_joules(EnergyModel::default()),
+        metrics.busy_worker_us as f64 / 1e6,
+        metrics.idle_worker_us as f64 / 1e6
+    );
+
+    if let (Some(path), Some(log)) = (decision_log_path, sim.decision_log()) {
+        write_decision_log(log, &path).expect("Failed to write decision log");
+        println!("Decision log: {} records -> {}", log.len(), path);
+    }
+    if let (Some(path), Some(series)) = (state_series_path, sim.state_series()) {
+        write_state_series(series, &path).expect("Failed to write state series");
+        println!("State series: {} samples -> {}", series.len(), path);
+    }
 }
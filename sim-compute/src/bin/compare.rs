@@ -0,0 +1,113 @@
+//! Paired-seed policy comparison
+//!
+//! Runs two pool sizing policies over the same seeds on the virtual clock,
+//! both seeing the identical task arrivals per seed, and reports the paired
+//! difference of each metric (mean ± 95% CI) with a Wilcoxon signed-rank test,
+//! then a report of effect sizes, bootstrap CIs and a better / worse /
+//! inconclusive verdict for B against A (`--report` writes it as JSON).
+
+use cli::take_flag;
+use sim_compute::{
+    paired_differences, policy_from_spec, run_paired, AdversarialWorkload, BurstyWorkload, PoolSizePolicy, RunResult,
+    SteadyWorkload, ThreadPoolSim, WorkloadGenerator,
+};
+use policy_compare::{ComparisonReport, Goal};
+use std::time::Duration;
+
+type Metric = fn(&RunResult) -> f64;
+
+/// Build a policy from a baseline name or `reflex:<model>[,<normalizer>]`, exiting on error
+fn make_policy(spec: &str) -> Box<dyn PoolSizePolicy + Send> {
+    policy_from_spec(spec).unwrap_or_else(|e| {
+        eprintln!("Failed to build policy {}: {}", spec, e);
+        eprintln!("  policy: static | erlang | hillclimb | pid | reflex:<model>[,<normalizer>]");
+        std::process::exit(1);
+    })
+}
+
+/// Preset workloads, around 2000 tasks/s of 500µs tasks on average
+fn workload_preset(name: &str, duration: Duration, seed: u64) -> Option<Box<dyn WorkloadGenerator>> {
+    let workload: Box<dyn WorkloadGenerator> = match name {
+        "steady" => Box::new(SteadyWorkload::new(2000.0, 500, duration).with_seed(seed)),
+        "bursty" => Box::new(
+            BurstyWorkload::new(3200.0, 800.0, 500, Duration::from_secs(1), duration).with_seed(seed),
+        ),
+        "adversarial" => Box::new(AdversarialWorkload::new(1600.0, (50, 950), duration).with_seed(seed)),
+        _ => return None,
+    };
+    Some(workload)
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let runs: u64 = take_flag(&mut args, "--runs")
+        .map(|s| s.parse().expect("--runs must be an integer"))
+        .unwrap_or(10);
+    let duration = take_flag(&mut args, "--duration-secs")
+        .map(|s| Duration::from_secs_f64(s.parse().expect("--duration-secs must be a number")))
+        .unwrap_or(Duration::from_secs(10));
+    let output_path = take_flag(&mut args, "--output");
+    let report_path = take_flag(&mut args, "--report");
+    if args.len() < 4 {
+        eprintln!("Usage: compare-compute <workload_type> <policy_a> <policy_b> [--runs N] [--duration-secs S] [--output results.json] [--report report.json]");
+        eprintln!("  workload_type: steady | bursty | adversarial");
+        eprintln!("  policy: static | erlang | hillclimb | pid | reflex:<model>[,<normalizer>]");
+        std::process::exit(1);
+    }
+    if workload_preset(&args[1], duration, 0).is_none() {
+        eprintln!("Unknown workload type: {}", args[1]);
+        std::process::exit(1);
+    }
+    // Fail on a bad policy spec before any runs
+    make_policy(&args[2]);
+    make_policy(&args[3]);
+
+    let seeds: Vec<u64> = (0..runs).collect();
+    println!("Running {} vs {} on paired {} arrivals x{}", args[2], args[3], args[1], seeds.len());
+    let pairs = run_paired(
+        (&args[2], &args[3]),
+        || ThreadPoolSim::new(make_policy(&args[2]), 8),
+        || ThreadPoolSim::new(make_policy(&args[3]), 8),
+        |seed| workload_preset(&args[1], duration, seed).unwrap(),
+        duration,
+        &seeds,
+    );
+
+    println!("\n=== Paired differences ({} - {}, mean ± 95% CI) ===", args[3], args[2]);
+    for d in paired_differences(&pairs) {
+        let marker = if d.test.p_value < 0.05 { " *" } else { "" };
+        println!(
+            "  {:<18} {:+.2}  (Wilcoxon z={:.2} p={:.4}){}",
+            d.metric, d.diff, d.test.z, d.test.p_value, marker
+        );
+    }
+    println!("  (* p < 0.05)");
+
+    let (results_a, results_b): (Vec<RunResult>, Vec<RunResult>) = pairs.into_iter().unzip();
+    let metrics: [(&str, Metric, Goal); 7] = [
+        ("p50_task_time_us", |r| r.p50_task_time_us, Goal::Lower),
+        ("p95_task_time_us", |r| r.p95_task_time_us, Goal::Lower),
+        ("p99_task_time_us", |r| r.p99_task_time_us, Goal::Lower),
+        ("p95_wait_time_us", |r| r.p95_wait_time_us, Goal::Lower),
+        ("mean_throughput", |r| r.mean_throughput, Goal::Higher),
+        ("energy_j", |r| r.energy_j, Goal::Lower),
+        ("flip_flops", |r| r.flip_flops as f64, Goal::Lower),
+    ];
+    let mut report = ComparisonReport::new(&args[2], &args[3]);
+    for (name, metric, goal) in metrics {
+        let a: Vec<f64> = results_a.iter().map(metric).collect();
+        let b: Vec<f64> = results_b.iter().map(metric).collect();
+        report.add_metric(name, goal, &a, &b);
+    }
+    println!("\n=== Report ===\n{}", report);
+    if let Some(path) = report_path {
+        report.write_json(&path).expect("Failed to write report");
+        println!("Report written to {}", path);
+    }
+
+    if let Some(path) = output_path {
+        let all: Vec<RunResult> = results_a.into_iter().chain(results_b).collect();
+        RunResult::write_all(&all, &path).expect("Failed to write results");
+        println!("\nResults written to {}", path);
+    }
+}
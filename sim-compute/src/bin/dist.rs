@@ -0,0 +1,207 @@
+//! Distributed sweeps and cross-validation over a shared job queue
+//!
+//! The coordinator and its workers share nothing but a directory
+//! (`JobQueue`), so any machine that can mount it can help:
+//!
+//! - `dist-compute sweep <queue> ...` queues one `sweep grid` job per
+//!   workload cell, seeded as a single-box grid would seed it, waits, and
+//!   merges the shards in grid order, so the dataset matches what
+//!   `sweep grid` would have written. Arguments after `--` go to every job.
+//! - `dist-compute folds <queue> <dataset> <config.yaml>` queues one
+//!   blocked cross-validation fold per job and prints each fold's MAE.
+//! - `dist-compute worker <queue>` claims and runs jobs, sweep jobs via the
+//!   `sweep` binary next to it.
+//!
+//! Coordinators with --requeue-after put jobs whose claim hasn't been
+//! touched for that long back in the queue, for workers that died mid-job.
+//! Live workers touch their claim every 5s, so allow several of those.
+
+use cli::{take_flag, take_switch};
+use sim_compute::{merge_datasets, Job, JobQueue, QueueStatus};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+fn usage() -> ! {
+    eprintln!("Usage: dist-compute sweep <queue_dir> <duration_secs> --rates R1,R2,.. --task-us T1,T2,.. --output dataset.csv|dataset.json [--burstiness B1,B2,..] [--seed N] [--requeue-after SECS] [-- sweep grid flags]");
+    eprintln!("       dist-compute folds <queue_dir> <dataset.csv|dataset.json> <config.yaml> [--folds K] [--gap-us N] [--requeue-after SECS]");
+    eprintln!("       dist-compute worker <queue_dir> [--name NAME] [--sweep PATH] [--exit-when-empty]");
+    eprintln!("Example: dist-compute sweep /mnt/shared/queue 5 --rates 100,1000,5000 --task-us 200,500,2000 --output data/telemetry/compute-sweep.csv -- --workload bursty --burstiness 4");
+    std::process::exit(1);
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    let passthrough: Vec<String> = match args.iter().position(|a| a == "--") {
+        Some(idx) => args.split_off(idx).split_off(1),
+        None => Vec::new(),
+    };
+    let requeue_after = take_flag(&mut args, "--requeue-after")
+        .map(|s| Duration::from_secs(s.parse().expect("--requeue-after must be seconds")));
+    let exit_when_empty = take_switch(&mut args, "--exit-when-empty");
+
+    let (Some(command), Some(queue_dir)) = (args.get(1).cloned(), args.get(2).cloned()) else {
+        usage();
+    };
+    let queue = JobQueue::open(&queue_dir).unwrap_or_else(|e| {
+        eprintln!("Failed to open queue {}: {}", queue_dir, e);
+        std::process::exit(1);
+    });
+
+    match command.as_str() {
+        "sweep" => coordinate_sweep(&queue, &mut args, passthrough, requeue_after),
+        "folds" => coordinate_folds(&queue, &mut args, requeue_after),
+        "worker" => {
+            let name = take_flag(&mut args, "--name").unwrap_or_else(|| {
+                let host = env::var("HOSTNAME").unwrap_or_else(|_| "worker".to_string());
+                format!("{}-{}", host, std::process::id())
+            });
+            let sweep = take_flag(&mut args, "--sweep").map_or_else(
+                || env::current_exe().expect("own path").with_file_name("sweep"),
+                PathBuf::from,
+            );
+            println!("=== Worker {} on {} ===", name, queue_dir);
+            let ran = queue.work(&name, &sweep, exit_when_empty).unwrap_or_else(|e| {
+                eprintln!("Worker failed: {}", e);
+                std::process::exit(1);
+            });
+            println!("Queue empty after {} jobs", ran);
+        }
+        _ => usage(),
+    }
+}
+
+/// Queue one single-cell `sweep grid` job per (rate, task size, burstiness) and merge the shards
+fn coordinate_sweep(queue: &JobQueue, args: &mut Vec<String>, passthrough: Vec<String>, requeue_after: Option<Duration>) {
+    let rates = take_flag(args, "--rates");
+    let task_sizes = take_flag(args, "--task-us");
+    let output = take_flag(args, "--output");
+    let burstiness = take_flag(args, "--burstiness");
+    let seed: u64 = take_flag(args, "--seed").map_or(0, |s| s.parse().expect("--seed must be u64"));
+    let (Some(rates), Some(task_sizes), Some(output), Some(duration)) = (rates, task_sizes, output, args.get(3)) else {
+        usage();
+    };
+    if passthrough.iter().any(|a| ["--rates", "--task-us", "--burstiness", "--seed", "--output"].contains(&a.as_str())) {
+        eprintln!("Pass --rates, --task-us, --burstiness, --seed and --output before --, not after");
+        std::process::exit(1);
+    }
+    let extension = if output.ends_with(".json") { "json" } else { "csv" };
+    let burstiness: Vec<Option<&str>> = match &burstiness {
+        Some(list) => list.split(',').map(Some).collect(),
+        None => vec![None],
+    };
+
+    // Same cell order and seeds as `sweep grid`: rate, then task size, then burstiness
+    let mut ids = Vec::new();
+    let mut shards = Vec::new();
+    for rate in rates.split(',') {
+        for task_us in task_sizes.split(',') {
+            for b in &burstiness {
+                let cell = ids.len();
+                let id = format!("sweep-{:05}", cell);
+                let shard = queue.results_dir().join(format!("{}.{}", id, extension));
+                let mut job_args = vec![
+                    "grid".to_string(),
+                    duration.clone(),
+                    "--rates".to_string(),
+                    rate.to_string(),
+                    "--task-us".to_string(),
+                    task_us.to_string(),
+                    "--seed".to_string(),
+                    (seed + cell as u64).to_string(),
+                ];
+                if let Some(b) = b {
+                    job_args.extend(["--burstiness".to_string(), b.to_string()]);
+                }
+                job_args.extend(passthrough.iter().cloned());
+                submit(queue, &id, &Job::Sweep { args: job_args, output: shard.clone() });
+                ids.push(id);
+                shards.push(shard);
+            }
+        }
+    }
+
+    println!("=== Distributed Sweep ===");
+    println!("{} workload cells queued, {} sec each (base seed {})\n", ids.len(), duration, seed);
+    wait(queue, requeue_after);
+    let failed = failures(queue, &ids);
+    if failed > 0 {
+        eprintln!("{} of {} cells failed; see {}", failed, ids.len(), queue.results_dir().display());
+        std::process::exit(1);
+    }
+    let rows = merge_datasets(&shards, Path::new(&output)).expect("Failed to merge shards");
+    println!("\n{} samples from {} cells written to {}", rows, ids.len(), output);
+}
+
+/// Queue one job per blocked cross-validation fold and report their MAEs
+fn coordinate_folds(queue: &JobQueue, args: &mut Vec<String>, requeue_after: Option<Duration>) {
+    let folds: usize = take_flag(args, "--folds").map_or(5, |s| s.parse().expect("--folds must be a count"));
+    let gap_us: u64 = take_flag(args, "--gap-us").map_or(0, |s| s.parse().expect("--gap-us must be microseconds"));
+    let (Some(dataset), Some(config)) = (args.get(3), args.get(4)) else {
+        usage();
+    };
+    // Workers resolve paths themselves, so hand them absolute ones
+    let absolute = |path: &str| {
+        std::fs::canonicalize(path).unwrap_or_else(|e| {
+            eprintln!("Failed to resolve {}: {}", path, e);
+            std::process::exit(1);
+        })
+    };
+    let (dataset_path, config_path) = (absolute(dataset), absolute(config));
+
+    let ids: Vec<String> = (0..folds).map(|fold| format!("fold-{:03}", fold)).collect();
+    for (fold, id) in ids.iter().enumerate() {
+        let job = Job::Fold { dataset: dataset_path.clone(), config: config_path.clone(), fold, folds, gap_us };
+        submit(queue, id, &job);
+    }
+
+    println!("=== Distributed Cross-Validation ===");
+    println!("{} over {} blocked folds (gap {} µs)\n", config, folds, gap_us);
+    wait(queue, requeue_after);
+    println!("\n{:<10} {:>10} {:>16}", "Fold", "MAE", "Worker");
+    println!("{:-<38}", "");
+    let mut maes = Vec::new();
+    for id in &ids {
+        match queue.report(id).expect("Failed to read report") {
+            Some(report) => match (report.mae, &report.error) {
+                (Some(mae), None) => {
+                    println!("{:<10} {:>10.3} {:>16}", id, mae, report.worker);
+                    maes.push(mae);
+                }
+                (_, error) => println!("{:<10} {:>10} {:>16}  {}", id, "failed", report.worker, error.as_deref().unwrap_or("")),
+            },
+            None => println!("{:<10} {:>10}", id, "missing"),
+        }
+    }
+    if maes.is_empty() {
+        std::process::exit(1);
+    }
+    let mean = maes.iter().sum::<f64>() / maes.len() as f64;
+    let std = (maes.iter().map(|m| (m - mean).powi(2)).sum::<f64>() / maes.len() as f64).sqrt();
+    println!("\nCV MAE: {:.3} ± {:.3} over {} of {} folds", mean, std, maes.len(), folds);
+}
+
+fn submit(queue: &JobQueue, id: &str, job: &Job) {
+    queue.submit(id, job).unwrap_or_else(|e| {
+        eprintln!("Failed to queue {}: {}", id, e);
+        std::process::exit(1);
+    });
+}
+
+fn wait(queue: &JobQueue, requeue_after: Option<Duration>) -> QueueStatus {
+    queue
+        .wait(requeue_after, |s| {
+            println!("pending {:>5}  running {:>4}  done {:>5}  failed {:>4}", s.pending, s.running, s.done, s.failed)
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to poll queue: {}", e);
+            std::process::exit(1);
+        })
+}
+
+/// How many of `ids` ended in failure or never reported
+fn failures(queue: &JobQueue, ids: &[String]) -> usize {
+    ids.iter()
+        .filter(|id| !matches!(queue.report(id), Ok(Some(report)) if report.error.is_none()))
+        .count()
+}
@@ -0,0 +1,166 @@
+//! Teacher-student distillation
+//!
+//! Runs an online tuner (hill climbing or PID) on the simulator's virtual
+//! clock over the preset workloads, logging what it decided for the
+//! telemetry it saw, fits a decision tree to imitate those decisions, and
+//! writes the student as a `.reflex` with its normalizer embedded (and as
+//! a sidecar file), after checking the two fit each other. The student is
+//! then run against its teacher on held-out seeds.
+
+use cli::take_flag;
+use reflex_format::{Normalization, OutputBounds};
+use sim_compute::{
+    policy_from_spec, run_paired, AdversarialWorkload, BurstyWorkload, PoolSizePolicy, ReflexPolicy, SteadyWorkload,
+    ThreadPoolSim, WorkloadGenerator,
+};
+use std::time::Duration;
+use trainer::distill::{demonstration_normalizer, imitation_dataset, Agreement, Demonstration};
+use trainer::{
+    build_reflex, check_normalizer, training_metadata, training_report, write_reflex, CartTrainer, Dataset,
+};
+
+/// Preset workloads, around 2000 tasks/s of 500µs tasks on average
+fn workload_preset(name: &str, duration: Duration, seed: u64) -> Option<Box<dyn WorkloadGenerator>> {
+    let workload: Box<dyn WorkloadGenerator> = match name {
+        "steady" => Box::new(SteadyWorkload::new(2000.0, 500, duration).with_seed(seed)),
+        "bursty" => Box::new(
+            BurstyWorkload::new(3200.0, 800.0, 500, Duration::from_secs(1), duration).with_seed(seed),
+        ),
+        "adversarial" => Box::new(AdversarialWorkload::new(1600.0, (50, 950), duration).with_seed(seed)),
+        _ => return None,
+    };
+    Some(workload)
+}
+
+fn make_teacher(spec: &str) -> Box<dyn PoolSizePolicy + Send> {
+    policy_from_spec(spec).unwrap_or_else(|e| {
+        eprintln!("Failed to build teacher {}: {}", spec, e);
+        std::process::exit(1);
+    })
+}
+
+/// Teacher decisions from one run, one every `sample_interval` once the telemetry windows have filled
+fn demonstrate(teacher: &str, workload: &str, seed: u64, duration: Duration, sample_interval: Duration) -> Vec<Demonstration> {
+    let mut sim = ThreadPoolSim::new(make_teacher(teacher), 8).with_decision_log();
+    let mut arrivals = workload_preset(workload, duration, seed).unwrap();
+    sim.run_virtual(arrivals.as_mut(), duration);
+
+    let mut demos = Vec::new();
+    let mut next_sample_us = 1_000_000;
+    for record in sim.decision_log().unwrap_or_default() {
+        if record.timestamp_us >= next_sample_us {
+            demos.push(Demonstration {
+                telemetry: record.telemetry,
+                action: record.decision.n_workers,
+            });
+            next_sample_us += sample_interval.as_micros() as u64;
+        }
+    }
+    demos
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let output = take_flag(&mut args, "--output");
+    let normalizer_out = take_flag(&mut args, "--normalizer-out");
+    let workloads = take_flag(&mut args, "--workloads").unwrap_or_else(|| "steady,bursty,adversarial".to_string());
+    let runs: u64 = take_flag(&mut args, "--runs").map_or(5, |s| s.parse().expect("--runs must be an integer"));
+    let duration = take_flag(&mut args, "--duration-secs")
+        .map_or(Duration::from_secs(20), |s| Duration::from_secs_f64(s.parse().expect("--duration-secs must be a number")));
+    let sample_interval = take_flag(&mut args, "--sample-ms")
+        .map_or(Duration::from_millis(100), |s| Duration::from_millis(s.parse().expect("--sample-ms must be an integer")));
+    let max_depth: usize = take_flag(&mut args, "--max-depth").map_or(4, |s| s.parse().expect("--max-depth must be an integer"));
+    let min_leaf: usize = take_flag(&mut args, "--min-leaf").map_or(20, |s| s.parse().expect("--min-leaf must be an integer"));
+    let (Some(output), Some(teacher)) = (output, args.get(1).cloned()) else {
+        eprintln!("Usage: distill-compute <hillclimb|pid> --output student.reflex [--normalizer-out normalizer.json] [--workloads steady,bursty,adversarial] [--runs N] [--duration-secs S] [--sample-ms MS] [--max-depth D] [--min-leaf L]");
+        std::process::exit(1);
+    };
+    let normalizer_out = normalizer_out.unwrap_or_else(|| output.replace(".reflex", "") + "-normalizer.json");
+    let workloads: Vec<&str> = workloads.split(',').collect();
+    if let Some(unknown) = workloads.iter().find(|w| workload_preset(w, duration, 0).is_none()) {
+        eprintln!("Unknown workload: {} (expected steady | bursty | adversarial)", unknown);
+        std::process::exit(1);
+    }
+    make_teacher(&teacher);
+
+    println!("=== Distilling {} ===", teacher);
+    let mut demos = Vec::new();
+    for workload in &workloads {
+        for seed in 0..runs {
+            demos.extend(demonstrate(&teacher, workload, seed, duration, sample_interval));
+        }
+    }
+    println!(
+        "Demonstrations: {} ({} workloads x {} runs x {:.0}s, every {}ms)",
+        demos.len(),
+        workloads.len(),
+        runs,
+        duration.as_secs_f64(),
+        sample_interval.as_millis()
+    );
+
+    let normalizer = demonstration_normalizer(&demos);
+    let data = imitation_dataset(&demos, &normalizer).expect("Failed to build dataset");
+    let student = CartTrainer::new()
+        .with_max_depth(max_depth)
+        .with_min_samples_leaf(min_leaf)
+        .fit(&data);
+    let agreement = Agreement::measure(&data, |row| student.predict(row).clamp(1.0, 64.0));
+    println!(
+        "Student: {} leaves, depth {}; agreement {:.1}% exact, MAE {:.2} workers",
+        student.leaf_count(),
+        student.depth(),
+        agreement.exact_rate * 100.0,
+        agreement.mae_workers
+    );
+
+    // Imitation has no held-out rows; the report's residuals are on the demonstrations
+    let report = training_report(&data, &Dataset::default(), |row| student.predict(row));
+    let mut metadata = training_metadata("compute-v1", &data.fingerprint(), 0.0, &format!("distilled from {}", teacher));
+    metadata.normalizer = Some(Normalization {
+        min: normalizer.min.to_vec(),
+        max: normalizer.max.to_vec(),
+    });
+    let reflex = build_reflex(
+        vec![student],
+        data.feature_count(),
+        OutputBounds {
+            min: vec![1.0],
+            max: vec![64.0],
+        },
+        metadata,
+    )
+    .expect("Failed to build reflex")
+    .with_report(report);
+    check_normalizer(&reflex, &normalizer).expect("Student and normalizer don't fit each other");
+    write_reflex(&reflex, &output).expect("Failed to write reflex");
+    std::fs::write(&normalizer_out, serde_json::to_string_pretty(&normalizer).unwrap()).expect("Failed to write normalizer");
+    println!("Wrote {} and {}", output, normalizer_out);
+
+    println!("\n=== Student vs Teacher (held-out seeds) ===");
+    println!("{:<12} {:>16} {:>16}", "Workload", "Teacher p95 µs", "Student p95 µs");
+    let seeds: Vec<u64> = (1000..1000 + runs).collect();
+    for workload in &workloads {
+        let pairs = run_paired(
+            (&teacher, "student"),
+            || ThreadPoolSim::new(make_teacher(&teacher), 8),
+            || {
+                let student = ReflexPolicy::load(&output, normalizer.clone()).expect("Failed to load student");
+                // Decide every tick, as the teacher did
+                ThreadPoolSim::new(student.with_hold_time(Duration::ZERO), 8)
+            },
+            |seed| workload_preset(workload, duration, seed).unwrap(),
+            duration,
+            &seeds,
+        );
+        let mean = |pick: fn(&(sim_compute::RunResult, sim_compute::RunResult)) -> f64| {
+            pairs.iter().map(pick).sum::<f64>() / pairs.len() as f64
+        };
+        println!(
+            "{:<12} {:>16.0} {:>16.0}",
+            workload,
+            mean(|(t, _)| t.p95_task_time_us),
+            mean(|(_, s)| s.p95_task_time_us)
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! Real thread pool run
+//!
+//! Feeds a steady workload of CPU-spinning jobs to a `RealThreadPool` on OS
+//! threads, sized live by any policy `compare-compute` accepts, and reports
+//! the same metrics as the simulator.
+
+use cli::take_flag;
+use sim_compute::{policy_from_spec, EnergyModel, RealThreadPool, SteadyWorkload, WorkloadGenerator};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Busy-wait for `work_us`, standing in for CPU-bound work
+fn spin(work_us: u64) {
+    let until = Instant::now() + Duration::from_micros(work_us);
+    while Instant::now() < until {
+        std::hint::spin_loop();
+    }
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let policy_spec = take_flag(&mut args, "--policy").unwrap_or_else(|| "static".to_string());
+    let rate: f64 = take_flag(&mut args, "--rate")
+        .map_or(1000.0, |s| s.parse().expect("--rate must be tasks/s"));
+    let task_us: u64 = take_flag(&mut args, "--task-us")
+        .map_or(500, |s| s.parse().expect("--task-us must be a whole number"));
+    let duration = take_flag(&mut args, "--duration-secs")
+        .map_or(Duration::from_secs(5), |s| Duration::from_secs_f64(s.parse().expect("--duration-secs must be a number")));
+    let policy = policy_from_spec(&policy_spec).unwrap_or_else(|e| {
+        eprintln!("Failed to build policy {}: {}", policy_spec, e);
+        eprintln!("Usage: real-compute [--policy static|erlang|hillclimb|pid|reflex:<model>[,<normalizer>]|reload:<model>[,<normalizer>]] [--rate R] [--task-us US] [--duration-secs S]");
+        std::process::exit(1);
+    });
+
+    println!("=== Real Thread Pool ===");
+    println!("Policy: {}", policy_spec);
+    println!("Workload: Steady {} tasks/sec, {}µs/task, {:.1}s\n", rate, task_us, duration.as_secs_f64());
+
+    let pool = RealThreadPool::new(policy, 8);
+    let mut workload = SteadyWorkload::new(rate, task_us, duration);
+    let mut next_at = Instant::now();
+    while let Some((wait, work_us)) = workload.next_task() {
+        next_at += wait;
+        thread::sleep(next_at.saturating_duration_since(Instant::now()));
+        pool.execute(move || spin(work_us));
+    }
+    let final_size = pool.pool_size();
+    let metrics = pool.metrics();
+    pool.shutdown();
+
+    println!("=== Results ===");
+    println!("Total tasks completed: {}", metrics.task_times_us.len());
+    println!("p50 task time: {:.2} µs", metrics.p50_task_time());
+    println!("p95 task time: {:.2} µs", metrics.p95_task_time());
+    println!("p99 task time: {:.2} µs", metrics.p99_task_time());
+    println!(
+        "p95 wait / service: {:.2} / {:.2} µs",
+        metrics.wait_percentile(0.95),
+        metrics.service_percentile(0.95)
+    );
+    println!("Mean throughput: {:.2} tasks/s", metrics.mean_throughput());
+    println!("Decision changes: {}", metrics.decision_changes);
+    println!("Flip-flops: {}", metrics.flip_flops);
+    println!(
+        "Workers retired: {} (p95 shrink latency {:.2} µs)",
+        metrics.shrink_latencies_us.len(),
+        metrics.shrink_latency_percentile(0.95)
+    );
+    println!(
+        "Energy: {:.2} J ({:.1} busy / {:.1} idle worker-s)",
+        metrics.energy_joules(EnergyModel::default()),
+        metrics.busy_worker_us as f64 / 1e6,
+        metrics.idle_worker_us as f64 / 1e6
+    );
+    println!("Final pool size: {}", final_size);
+}
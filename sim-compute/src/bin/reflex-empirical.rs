@@ -2,14 +2,27 @@
 //!
 //! Runs thread pool with empirical-trained reflex
 
-use sim_compute::{ReflexPolicy, SteadyWorkload, ThreadPoolSim, WorkloadGenerator};
-use std::thread;
+use cli::take_flag;
+use sim_compute::{EnergyModel, ReflexPolicy, SteadyWorkload, ThreadPoolSim};
 use std::time::Duration;
 use telemetry_compute::Normalizer;
 
 fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let hold_ms: u64 = take_flag(&mut args, "--hold-ms")
+        .map_or(500, |s| s.parse().expect("--hold-ms must be a whole number"));
+    let min_workers: u32 = take_flag(&mut args, "--min-workers")
+        .map_or(1, |s| s.parse().expect("--min-workers must be u32"));
+    let max_workers: u32 = take_flag(&mut args, "--max-workers")
+        .map_or(64, |s| s.parse().expect("--max-workers must be u32"));
+    if min_workers == 0 || min_workers > max_workers {
+        eprintln!("Need 0 < --min-workers <= --max-workers");
+        std::process::exit(1);
+    }
+
     println!("=== Thread Pool Simulator: Empirical Reflex ===");
-    println!("Policy: Empirical-trained reflex (1429 bytes)\n");
+    println!("Policy: Empirical-trained reflex (1429 bytes)");
+    println!("Hold time: {}ms, bounds: [{}, {}]\n", hold_ms, min_workers, max_workers);
 
     // Load normalizer and reflex
     let normalizer_json = std::fs::read_to_string("data/models/normalizer-compute-empirical.json")
@@ -18,9 +31,11 @@ fn main() {
         .expect("Failed to parse normalizer");
 
     let policy = ReflexPolicy::load("data/models/thread-pool-empirical.reflex", normalizer)
-        .expect("Failed to load reflex");
+        .expect("Failed to load reflex")
+        .with_hold_time(Duration::from_millis(hold_ms))
+        .with_bounds(min_workers, max_workers);
 
-    let mut sim = ThreadPoolSim::new(policy, 8);
+    let mut sim = ThreadPoolSim::new(policy, 8u32.clamp(min_workers, max_workers));
 
     // Steady workload: 100 tasks/sec, 500µs per task, for 10 seconds
     let mut workload = SteadyWorkload::new(100.0, 500, Duration::from_secs(10));
@@ -28,25 +43,8 @@ fn main() {
     println!("Starting simulation...");
     println!("Workload: Steady 100 tasks/sec, 500µs/task, 10s duration\n");
 
-    let start = std::time::Instant::now();
-
-    // Run simulation
-    loop {
-        // Generate tasks
-        if let Some((wait, work_us)) = workload.next_task() {
-            thread::sleep(wait.min(Duration::from_micros(100))); // Speed up sim
-            sim.enqueue(work_us);
-        }
-
-        // Tick simulator every 10ms
-        sim.tick();
-        thread::sleep(Duration::from_millis(10));
-
-        // Check if done
-        if start.elapsed() >= Duration::from_secs(11) {
-            break;
-        }
-    }
+    // Wall clock, retiring each task the moment it finishes; one extra second to drain
+    sim.run_real_time(&mut workload, Duration::from_secs(11));
 
     // Print metrics
     let metrics = sim.metrics();
@@ -55,6 +53,24 @@ fn main() {
     println!("p50 task time: {:.2} µs", metrics.p50_task_time());
     println!("p95 task time: {:.2} µs", metrics.p95_task_time());
     println!("p99 task time: {:.2} µs", metrics.p99_task_time());
+    println!(
+        "p95 wait / service: {:.2} / {:.2} µs",
+        metrics.wait_percentile(0.95),
+        metrics.service_percentile(0.95)
+    );
     println!("Mean throughput: {:.2} tasks/s", metrics.mean_throughput());
     println!("Decision changes: {}", metrics.decision_changes);
+    println!("Changes per minute: {:.2}", metrics.changes_per_minute());
+    println!("Flip-flops: {}", metrics.flip_flops);
+    println!(
+        "Workers retired: {} (p95 shrink latency {:.2} µs)",
+        metrics.shrink_latencies_us.len(),
+        metrics.shrink_latency_percentile(0.95)
+    );
+    println!(
+        "Energy: {:.2} J ({:.1} busy / {:.1} idle worker-s)",
+        metrics.energy_joules(EnergyModel::default()),
+        metrics.busy_worker_us as f64 / 1e6,
+        metrics.idle_worker_us as f64 / 1e6
+    );
 }
@@ -1,12 +1,43 @@
 //! Reflex thread pool simulator
 //!
-//! Runs thread pool with adaptive sizing from .reflex model
+//! Runs thread pool with adaptive sizing from .reflex model. With
+//! `--candidate model.reflex` a second model shadows it: asked on every
+//! decision, never applied, and scored on how often and how far it would
+//! have disagreed. With `--ood-fallback <policy spec>` an out-of-distribution
+//! watchdog checks each normalized feature vector against the training
+//! range and hands sustained out-of-range input to that policy.
+//! `--audit-log decisions.jsonl` records every decision not held over with
+//! its inputs and raw outputs, rotating at `--audit-max-mb` (default 64).
 
-use sim_compute::{ReflexPolicy, SteadyWorkload, ThreadPoolSim, WorkloadGenerator};
-use std::thread;
+use cli::take_flag;
+use audit_log::AuditLogger;
+use sim_compute::{embedded_normalizer, policy_from_spec, EnergyModel, OodWatchdog, ReflexPolicy, SteadyWorkload, ThreadPoolSim, TraceWorkload, write_decision_log, write_state_series};
 use std::time::Duration;
 
 fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let trace_path = take_flag(&mut args, "--trace");
+    let decision_log_path = take_flag(&mut args, "--decision-log");
+    let state_series_path = take_flag(&mut args, "--state-series");
+    let state_interval = take_flag(&mut args, "--state-interval-ms")
+        .map(|s| Duration::from_millis(s.parse().expect("--state-interval-ms must be a whole number")))
+        .unwrap_or(Duration::from_millis(10));
+    let candidate_path = take_flag(&mut args, "--candidate");
+    let ood_fallback = take_flag(&mut args, "--ood-fallback");
+    let audit_path = take_flag(&mut args, "--audit-log");
+    let audit_max_mb: u64 = take_flag(&mut args, "--audit-max-mb")
+        .map_or(64, |s| s.parse().expect("--audit-max-mb must be a whole number"));
+    let hold_ms: u64 = take_flag(&mut args, "--hold-ms")
+        .map_or(500, |s| s.parse().expect("--hold-ms must be a whole number"));
+    let min_workers: u32 = take_flag(&mut args, "--min-workers")
+        .map_or(1, |s| s.parse().expect("--min-workers must be u32"));
+    let max_workers: u32 = take_flag(&mut args, "--max-workers")
+        .map_or(64, |s| s.parse().expect("--max-workers must be u32"));
+    if min_workers == 0 || min_workers > max_workers {
+        eprintln!("Need 0 < --min-workers <= --max-workers");
+        std::process::exit(1);
+    }
+
     println!("=== Thread Pool Simulator: Reflex ===");
 
     let reflex_path = "data/models/thread-pool.reflex";
@@ -18,36 +49,67 @@ fn main() {
     let normalizer: telemetry_compute::Normalizer = serde_json::from_str(&normalizer_json)
         .expect("Failed to parse normalizer");
 
-    // Load reflex
-    let policy = ReflexPolicy::load(reflex_path, normalizer)
-        .expect("Failed to load reflex");
-
-    let mut sim = ThreadPoolSim::new(policy, 8); // Start with 8 workers
+    // Load reflex, and the shadow candidate with its embedded normalizer or the incumbent's
+    let mut policy = ReflexPolicy::load(reflex_path, normalizer.clone())
+        .expect("Failed to load reflex")
+        .with_hold_time(Duration::from_millis(hold_ms))
+        .with_bounds(min_workers, max_workers);
+    if let Some(path) = &candidate_path {
+        let bytes = std::fs::read(path).expect("Failed to read candidate");
+        let candidate = reflex_format::Reflex::from_bytes(&bytes).expect("Failed to load candidate");
+        let candidate_normalizer = embedded_normalizer(&candidate).unwrap_or(normalizer);
+        policy = policy.with_candidate(candidate, candidate_normalizer);
+    }
+    if let Some(spec) = &ood_fallback {
+        let fallback = policy_from_spec(spec).expect("Failed to build --ood-fallback policy");
+        policy = policy.with_watchdog(OodWatchdog::new(), fallback);
+    }
+    if let Some(path) = &audit_path {
+        let logger = AuditLogger::create(path)
+            .expect("Failed to open audit log")
+            .with_rotation(audit_max_mb << 20, AuditLogger::DEFAULT_KEEP);
+        policy = policy.with_audit_log(logger, format!("reflex:{}", reflex_path));
+    }
 
-    // Steady workload: 100 tasks/sec, 500µs per task, for 10 seconds
-    let mut workload = SteadyWorkload::new(100.0, 500, Duration::from_secs(10));
+    // Start with 8 workers
+    let mut sim = ThreadPoolSim::new(policy, 8u32.clamp(min_workers, max_workers));
+    if decision_log_path.is_some() {
+        sim = sim.with_decision_log();
+    }
+    if state_series_path.is_some() {
+        sim = sim.with_state_series(state_interval);
+    }
 
     println!("Policy: Reflex from {}", reflex_path);
-    println!("Workload: Steady 100 tasks/sec, 500µs/task, 10s duration\n");
-
-    let start = std::time::Instant::now();
-
-    // Run simulation
-    loop {
-        // Generate tasks
-        if let Some((wait, work_us)) = workload.next_task() {
-            thread::sleep(wait.min(Duration::from_micros(100)));
-            sim.enqueue(work_us);
-        }
+    if let Some(path) = &candidate_path {
+        println!("Shadow candidate: {}", path);
+    }
+    if let Some(spec) = &ood_fallback {
+        println!("OOD watchdog: falls back to {}", spec);
+    }
+    if let Some(path) = &audit_path {
+        println!("Audit log: {}", path);
+    }
+    println!("Hold time: {}ms, bounds: [{}, {}]", hold_ms, min_workers, max_workers);
+    if let Some(path) = trace_path {
+        // Replay the trace on the virtual clock, with a second to drain
+        let mut workload = TraceWorkload::from_csv(&path).expect("Failed to load trace");
+        println!(
+            "Workload: trace {} ({} tasks over {:.1}s, virtual time)\n",
+            path,
+            workload.len(),
+            workload.span().as_secs_f64()
+        );
+        let duration = workload.span() + Duration::from_secs(1);
+        sim.run_virtual(&mut workload, duration);
+    } else {
+        // Steady workload: 100 tasks/sec, 500µs per task, for 10 seconds
+        let mut workload = SteadyWorkload::new(100.0, 500, Duration::from_secs(10));
 
-        // Tick simulator
-        sim.tick();
-        thread::sleep(Duration::from_millis(10));
+        println!("Workload: Steady 100 tasks/sec, 500µs/task, 10s duration\n");
 
-        // Check if done
-        if start.elapsed() >= Duration::from_secs(11) {
-            break;
-        }
+        // Wall clock, retiring each task the moment it finishes; one extra second to drain
+        sim.run_real_time(&mut workload, Duration::from_secs(11));
     }
 
     // Print metrics
@@ -57,6 +119,39 @@ fn main() {
     println!("p50 task time: {:.2} µs", metrics.p50_task_time());
     println!("p95 task time: {:.2} µs", metrics.p95_task_time());
     println!("p99 task time: {:.2} µs", metrics.p99_task_time());
+    println!(
+        "p95 wait / service: {:.2} / {:.2} µs",
+        metrics.wait_percentile(0.95),
+        metrics.service_percentile(0.95)
+    );
     println!("Mean throughput: {:.2} tasks/s", metrics.mean_throughput());
     println!("Decision changes: {}", metrics.decision_changes);
+    println!("Changes per minute: {:.2}", metrics.changes_per_minute());
+    println!("Flip-flops: {}", metrics.flip_flops);
+    println!(
+        "Workers retired: {} (p95 shrink latency {:.2} µs)",
+        metrics.shrink_latencies_us.len(),
+        metrics.shrink_latency_percentile(0.95)
+    );
+    println!(
+        "Energy: {:.2} J ({:.1} busy / {:.1} idle worker-s)",
+        metrics.energy_joules(EnergyModel::default()),
+        metrics.busy_worker_us as f64 / 1e6,
+        metrics.idle_worker_us as f64 / 1e6
+    );
+    if let Some(shadow) = &metrics.shadow {
+        print!("{}", shadow);
+    }
+    if ood_fallback.is_some() {
+        println!("OOD alerts: {}, fallback decisions: {}", metrics.ood_alerts, metrics.ood_decisions);
+    }
+
+    if let (Some(path), Some(log)) = (decision_log_path, sim.decision_log()) {
+        write_decision_log(log, &path).expect("Failed to write decision log");
+        println!("Decision log: {} records -> {}", log.len(), path);
+    }
+    if let (Some(path), Some(series)) = (state_series_path, sim.state_series()) {
+        write_state_series(series, &path).expect("Failed to write state series");
+        println!("State series: {} samples -> {}", series.len(), path);
+    }
 }
@@ -0,0 +1,64 @@
+//! Offline counterfactual evaluation of a candidate reflex
+//!
+//! Replays recorded telemetry (a decision log or bare telemetry JSON
+//! lines) through the simulator under the incumbent policy and under the
+//! candidate, paired per seed, and reports the expected p95 and throughput
+//! with a deployment confidence score (`--report` writes it as JSON).
+
+use cli::take_flag;
+use sim_compute::{embedded_normalizer, OfflineEval};
+use telemetry_compute::Normalizer;
+use trainer::dataset::read_telemetry_log;
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let incumbent = take_flag(&mut args, "--incumbent").unwrap_or_else(|| "static".to_string());
+    let normalizer_path = take_flag(&mut args, "--normalizer");
+    let runs: u64 = take_flag(&mut args, "--runs")
+        .map(|s| s.parse().expect("--runs must be an integer"))
+        .unwrap_or(10);
+    let report_path = take_flag(&mut args, "--report");
+    let [_, telemetry_path, model_path] = &args[..] else {
+        eprintln!("Usage: replay-compute <telemetry.jsonl> <candidate.reflex> [--normalizer normalizer.json] [--incumbent static] [--runs N] [--report report.json]");
+        eprintln!("  incumbent: static | erlang | hillclimb | pid | reflex:<model>[,<normalizer>]");
+        std::process::exit(1);
+    };
+
+    let windows = read_telemetry_log(telemetry_path).unwrap_or_else(|e| {
+        eprintln!("Failed to read telemetry {}: {}", telemetry_path, e);
+        std::process::exit(1);
+    });
+    let candidate = std::fs::read(model_path)
+        .and_then(|bytes| reflex_format::Reflex::from_bytes(&bytes))
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load candidate {}: {}", model_path, e);
+            std::process::exit(1);
+        });
+    let normalizer: Normalizer = match &normalizer_path {
+        Some(path) => std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_else(|| {
+                eprintln!("Failed to load normalizer {}", path);
+                std::process::exit(1);
+            }),
+        None => embedded_normalizer(&candidate).unwrap_or_else(|| {
+            eprintln!("{} embeds no normalizer; pass --normalizer", model_path);
+            std::process::exit(1);
+        }),
+    };
+
+    println!("Replaying {} through {} vs {}", telemetry_path, model_path, incumbent);
+    let report = OfflineEval::new()
+        .with_runs(runs)
+        .evaluate(&candidate, &normalizer, &incumbent, &windows)
+        .unwrap_or_else(|e| {
+            eprintln!("Evaluation failed: {}", e);
+            std::process::exit(1);
+        });
+    println!("\n=== Offline Evaluation ===\n{}", report);
+    if let Some(path) = report_path {
+        report.write_json(&path).expect("Failed to write report");
+        println!("Report written to {}", path);
+    }
+}
@@ -0,0 +1,43 @@
+//! Scenario runner
+//!
+//! Runs a TOML scenario file (see sim-compute/scenarios/) once per seed on
+//! the virtual clock and reports mean ± 95% CI across runs.
+
+use cli::take_flag;
+use sim_compute::{RunResult, RunSummary, Scenario};
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let output_path = take_flag(&mut args, "--output");
+    if args.len() < 2 {
+        eprintln!("Usage: scenario-compute <scenario.toml> [--output results.json]");
+        std::process::exit(1);
+    }
+
+    let scenario = Scenario::load(&args[1]).expect("Failed to load scenario");
+    println!(
+        "Running scenario '{}': {} policy from N={}, {} phases, {:.1}s x{} seeds",
+        scenario.name,
+        scenario.policy,
+        scenario.initial_workers,
+        scenario.phases.len(),
+        scenario.duration().as_secs_f64(),
+        scenario.seeds.len()
+    );
+
+    let results = scenario.run().expect("Scenario run failed");
+
+    let s = RunSummary::from_results(&results);
+    println!("\n=== Summary ({} runs, mean ± 95% CI) ===", s.runs);
+    println!("p50 task time: {:.2} µs", s.p50_task_time_us);
+    println!("p95 task time: {:.2} µs", s.p95_task_time_us);
+    println!("p99 task time: {:.2} µs", s.p99_task_time_us);
+    println!("p95 wait:      {:.2} µs", s.p95_wait_time_us);
+    println!("throughput:    {:.2} tasks/s", s.mean_throughput);
+    println!("energy:        {:.2} J", s.energy_j);
+
+    if let Some(path) = output_path {
+        RunResult::write_all(&results, &path).expect("Failed to write results");
+        println!("\nResults written to {}", path);
+    }
+}
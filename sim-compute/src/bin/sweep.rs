@@ -1,12 +1,52 @@
 //! Pool Size Sweep - Empirical Ground Truth Collector
 //!
 //! Runs simulations across N ∈ {1,2,4,8,16,32,64} for a given workload
-//! and measures actual p95 latency to find empirically optimal pool size.
+//! and scores each run to find the empirically optimal pool size. --reward
+//! loads the scoring spec (weighted p95, throughput, decision churn, SLO
+//! violations; see `Reward`) from TOML; the default is plain p95.
+//!
+//! --workload picks the arrival pattern: steady Poisson, bursty (high/low
+//! phases, --burstiness and --period-ms), or adversarial (per-arrival rate
+//! swings and task sizes drawn from --work-range).
+//!
+//! `sweep grid` repeats this over a matrix of (arrival rate, task size,
+//! burstiness) and writes a training dataset: telemetry windows observed
+//! under every N, labeled with the empirically optimal N for the workload
+//! and the version of the reward that picked it.
+//!
+//! `sweep active` starts from that grid and keeps going: each round trains
+//! a reflex with --config, scores every swept cell by model uncertainty
+//! and sparse coverage (`trainer::ActiveLearner`), and sweeps new workloads
+//! between the worst cells and their neighbours on the rate and task-size
+//! axes, until error on a fixed set of held-out random workloads stops
+//! improving or --rounds runs out.
+//!
+//! Runs on the simulator's virtual clock by default, so results are
+//! deterministic for a given --seed and long durations finish quickly.
+//! --real-time runs on the wall clock instead (`run_real_time`).
+//!
+//! Virtual-time runs execute in parallel across --jobs threads (default:
+//! all cores). Every grid point gets its own seed; the pool sizes compared
+//! within a point share it, so they see identical arrivals.
 
-use sim_compute::{PoolSizeDecision, PoolSizePolicy, SteadyWorkload, ThreadPoolSim, WorkloadGenerator};
+use cli::{take_flag, take_switch};
+use serde::Serialize;
+use sim_compute::{
+    AdversarialWorkload, BurstyWorkload, CoreModel, PoolSizeDecision, PoolSizePolicy, QueueDiscipline, Reward, SteadyWorkload,
+    ThreadPoolSim, WorkDistribution, WorkloadGenerator,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
-use std::env;
+use telemetry_compute::ComputeTelemetry;
+use trainer::dataset::{read_sweep_cells, read_sweep_dataset};
+use trainer::{mean_absolute_error, ActiveLearner, TrainConfig};
 
 /// Fixed pool size policy (for testing specific N values)
 struct FixedPolicy {
@@ -20,79 +60,729 @@ impl FixedPolicy {
 }
 
 impl PoolSizePolicy for FixedPolicy {
-    fn decide(&mut self, _telem: &telemetry_compute::ComputeTelemetry) -> PoolSizeDecision {
+    fn decide(&mut self, _telem: &ComputeTelemetry) -> PoolSizeDecision {
         PoolSizeDecision {
             n_workers: self.n_workers,
+            admit_rate: None,
         }
     }
 }
 
-fn run_simulation(n_workers: u32, arrival_rate: f64, task_us: u64, duration_secs: u64) -> (f64, f64, f64, f64) {
-    let policy = FixedPolicy::new(n_workers);
-    let mut sim = ThreadPoolSim::new(policy, n_workers);
+const POOL_SIZES: [u32; 7] = [1, 2, 4, 8, 16, 32, 64];
 
-    let mut workload = SteadyWorkload::new(arrival_rate, task_us, Duration::from_secs(duration_secs));
+/// Dataset telemetry cadence, matching the 2 Hz schema sampling
+const SAMPLE_INTERVAL_US: u64 = 500_000;
 
-    let start = std::time::Instant::now();
+/// Arrival pattern of the swept workload
+#[derive(Debug, Clone, Copy)]
+enum WorkloadKind {
+    Steady,
+    /// Alternating phases of `period`, rates a factor of `burstiness` apart
+    Bursty { period: Duration },
+    /// Rate swinging 0.1–5× per arrival; task sizes uniform over `work_range` × task_us
+    Adversarial { work_range: (f64, f64) },
+}
 
-    // Run simulation
-    loop {
-        if let Some((wait, work_us)) = workload.next_task() {
-            thread::sleep(wait.min(Duration::from_micros(100)));
-            sim.enqueue(work_us);
+impl WorkloadKind {
+    fn name(&self) -> &'static str {
+        match self {
+            WorkloadKind::Steady => "steady",
+            WorkloadKind::Bursty { .. } => "bursty",
+            WorkloadKind::Adversarial { .. } => "adversarial",
         }
+    }
+}
 
-        sim.tick();
-        thread::sleep(Duration::from_millis(10));
+/// Sweep parameters shared by every pool size
+#[derive(Clone)]
+struct SweepConfig {
+    workload: WorkloadKind,
+    arrival_rate: f64,
+    task_us: u64,
+    work: WorkDistribution,
+    burstiness: f64, // high/low rate ratio of bursty workloads
+    duration_secs: u64,
+    seed: u64,
+    real_time: bool,
+    cores: Option<u32>,
+    smt: Option<f64>,
+    discipline: QueueDiscipline,
+    reward: Reward,
+}
 
-        if start.elapsed() >= Duration::from_secs(duration_secs + 1) {
-            break;
+/// The configured workload, with a mean arrival rate of `arrival_rate`
+fn build_workload(config: &SweepConfig) -> Box<dyn WorkloadGenerator> {
+    let duration = Duration::from_secs(config.duration_secs);
+    match config.workload {
+        WorkloadKind::Steady => Box::new(
+            SteadyWorkload::new(config.arrival_rate, config.task_us, duration)
+                .with_work_distribution(config.work)
+                .with_seed(config.seed),
+        ),
+        WorkloadKind::Bursty { period } => {
+            let low_rate = 2.0 * config.arrival_rate / (config.burstiness + 1.0);
+            Box::new(
+                BurstyWorkload::new(low_rate * config.burstiness, low_rate, config.task_us, period, duration)
+                    .with_work_distribution(config.work)
+                    .with_seed(config.seed),
+            )
+        }
+        WorkloadKind::Adversarial { work_range: (low, high) } => {
+            // Mean wait is E[1/m]/base_rate for a rate multiplier m ~ U(0.1, 5)
+            let mean_inverse_multiplier = (5.0f64 / 0.1).ln() / (5.0 - 0.1);
+            let work_range_us = (
+                (config.task_us as f64 * low).round().max(1.0) as u64,
+                (config.task_us as f64 * high).round().max(1.0) as u64,
+            );
+            Box::new(
+                AdversarialWorkload::new(config.arrival_rate * mean_inverse_multiplier, work_range_us, duration)
+                    .with_seed(config.seed),
+            )
+        }
+    }
+}
+
+/// Measurements from one pool size
+struct RunOutcome {
+    p50: f64,
+    p95: f64,
+    p99: f64,
+    throughput: f64,
+    cost: f64, // under the config's reward
+    samples: Vec<ComputeTelemetry>, // telemetry every SAMPLE_INTERVAL_US
+}
+
+fn run_simulation(n_workers: u32, config: &SweepConfig) -> RunOutcome {
+    let policy = FixedPolicy::new(n_workers);
+    let mut sim = ThreadPoolSim::new(policy, n_workers)
+        .with_discipline(config.discipline)
+        .with_decision_log();
+    if let Some(cores) = config.cores {
+        let mut model = CoreModel::new(cores);
+        if let Some(sibling_throughput) = config.smt {
+            model = model.with_smt(sibling_throughput);
+        }
+        sim = sim.with_cores(model);
+    }
+
+    let mut workload = build_workload(config);
+    let duration_secs = config.duration_secs;
+
+    if config.real_time {
+        sim.run_real_time(workload.as_mut(), Duration::from_secs(duration_secs + 1));
+    } else {
+        // One extra second lets in-flight tasks drain
+        sim.run_virtual(workload.as_mut(), Duration::from_secs(duration_secs + 1));
+    }
+
+    // Sample once the one-second telemetry windows have filled
+    let mut samples = Vec::new();
+    let mut next_sample_us = 1_000_000;
+    for record in sim.decision_log().unwrap_or_default() {
+        if record.timestamp_us >= next_sample_us {
+            samples.push(record.telemetry);
+            next_sample_us += SAMPLE_INTERVAL_US;
         }
     }
 
     let metrics = sim.metrics();
-    (
-        metrics.p50_task_time(),
-        metrics.p95_task_time(),
-        metrics.p99_task_time(),
-        metrics.mean_throughput(),
-    )
+    RunOutcome {
+        p50: metrics.p50_task_time(),
+        p95: metrics.p95_task_time(),
+        p99: metrics.p99_task_time(),
+        throughput: metrics.mean_throughput(),
+        cost: config.reward.cost(metrics),
+        samples,
+    }
+}
+
+/// Run every pool size of every config on up to `jobs` threads
+///
+/// Returns, per config, each pool size's outcome in `POOL_SIZES` order.
+fn run_all(configs: &[SweepConfig], jobs: usize) -> Vec<Vec<(u32, RunOutcome)>> {
+    let runs: Vec<(usize, u32)> = (0..configs.len())
+        .flat_map(|point| POOL_SIZES.iter().map(move |&n| (point, n)))
+        .collect();
+    let next_run = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<RunOutcome>>> = Mutex::new(runs.iter().map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, runs.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next_run.fetch_add(1, Ordering::Relaxed);
+                let Some(&(point, n)) = runs.get(i) else {
+                    break;
+                };
+                let outcome = run_simulation(n, &configs[point]);
+                results.lock().unwrap()[i] = Some(outcome);
+            });
+        }
+    });
+
+    let mut outcomes = results.into_inner().unwrap().into_iter().map(Option::unwrap);
+    configs
+        .iter()
+        .map(|_| POOL_SIZES.iter().map(|&n| (n, outcomes.next().unwrap())).collect())
+        .collect()
+}
+
+/// The pool size with the lowest reward cost
+fn best_pool_size(outcomes: &[(u32, RunOutcome)]) -> u32 {
+    let mut best_n = 1;
+    let mut best_cost = f64::MAX;
+    for (n, outcome) in outcomes {
+        if outcome.cost < best_cost {
+            best_cost = outcome.cost;
+            best_n = *n;
+        }
+    }
+    best_n
+}
+
+/// One dataset row: a telemetry window observed under `n_workers`, labeled
+/// with the workload's empirically optimal N
+#[derive(Serialize)]
+struct DatasetRow {
+    workload: &'static str,
+    workload_rate: f64,
+    workload_task_us: u64,
+    workload_burstiness: f64,
+    n_workers: u32,
+    telemetry: ComputeTelemetry,
+    optimal_n_workers: u32,
+    reward: String, // version of the reward that picked the optimum
+}
+
+/// Write rows as a JSON array if `path` ends in `.json`, else as CSV with
+/// the compute-v1 feature columns
+fn write_dataset(rows: &[DatasetRow], path: &str) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    if path.ends_with(".json") {
+        serde_json::to_writer_pretty(&mut writer, rows)?;
+        return writer.flush();
+    }
+
+    writeln!(
+        writer,
+        "workload,workload_rate,workload_task_us,workload_burstiness,n_workers,{},optimal_n_workers,reward",
+        ComputeTelemetry::feature_names().join(",")
+    )?;
+    for row in rows {
+        let features: Vec<String> = row.telemetry.to_features().iter().map(|f| f.to_string()).collect();
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{}",
+            row.workload,
+            row.workload_rate,
+            row.workload_task_us,
+            row.workload_burstiness,
+            row.n_workers,
+            features.join(","),
+            row.optimal_n_workers,
+            row.reward
+        )?;
+    }
+    writer.flush()
+}
+
+/// Parse `lognormal[:SIGMA]` or `pareto[:SHAPE]` around a median/minimum of `task_us`
+fn parse_work_dist(spec: &str, task_us: u64) -> Option<WorkDistribution> {
+    let (name, param) = match spec.split_once(':') {
+        Some((name, param)) => (name, Some(param.parse::<f64>().ok()?)),
+        None => (spec, None),
+    };
+    match name {
+        "constant" => Some(WorkDistribution::Constant(task_us)),
+        "lognormal" => Some(WorkDistribution::LogNormal {
+            median: task_us as f64,
+            sigma: param.unwrap_or(1.0),
+        }),
+        "pareto" => Some(WorkDistribution::Pareto {
+            scale: task_us as f64,
+            shape: param.unwrap_or(1.5),
+            cap: task_us * 1000,
+        }),
+        _ => None,
+    }
+}
+
+/// Work distribution for `task_us` under an optional `--work-dist` spec
+fn work_for(work_dist: Option<&str>, task_us: u64) -> WorkDistribution {
+    work_dist.map_or(WorkDistribution::Constant(task_us), |spec| {
+        parse_work_dist(spec, task_us).unwrap_or_else(|| {
+            eprintln!("--work-dist expects constant | lognormal[:SIGMA] | pareto[:SHAPE]");
+            std::process::exit(1);
+        })
+    })
+}
+
+/// Parse a comma-separated list such as `100,500,1000`
+fn parse_list<T: std::str::FromStr>(list: &str, flag: &str) -> Vec<T> {
+    list.split(',')
+        .map(|item| {
+            item.trim().parse().unwrap_or_else(|_| {
+                eprintln!("{} expects a comma-separated list, got {:?}", flag, list);
+                std::process::exit(1);
+            })
+        })
+        .collect()
 }
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    let seed: u64 = take_flag(&mut args, "--seed")
+        .map(|s| s.parse().expect("--seed must be u64"))
+        .unwrap_or(0);
+    let cores: Option<u32> = take_flag(&mut args, "--cores").map(|s| s.parse().expect("--cores must be u32"));
+    let smt: Option<f64> = take_flag(&mut args, "--smt").map(|s| s.parse().expect("--smt must be a throughput factor"));
+    let discipline = take_flag(&mut args, "--discipline").map_or(QueueDiscipline::Fifo, |name| {
+        QueueDiscipline::from_name(&name).unwrap_or_else(|| {
+            eprintln!("--discipline expects fifo | lifo | sjf");
+            std::process::exit(1);
+        })
+    });
+    let work_dist = take_flag(&mut args, "--work-dist");
+    let reward = take_flag(&mut args, "--reward").map_or_else(Reward::p95, |path| {
+        Reward::load(&path).unwrap_or_else(|e| {
+            eprintln!("Failed to load reward {}: {}", path, e);
+            std::process::exit(1);
+        })
+    });
+    let burstiness: Option<Vec<f64>> = take_flag(&mut args, "--burstiness").map(|s| parse_list(&s, "--burstiness"));
+    let period = Duration::from_millis(
+        take_flag(&mut args, "--period-ms").map_or(1000, |s| s.parse().expect("--period-ms must be a whole number")),
+    );
+    let work_range = take_flag(&mut args, "--work-range").map_or((0.25, 4.0), |s| match parse_list(&s, "--work-range")[..] {
+        [low, high] if low <= high => (low, high),
+        _ => {
+            eprintln!("--work-range expects LOW,HIGH multiples of task_us");
+            std::process::exit(1);
+        }
+    });
+    // Bursty by default when a burstiness above 1 is asked for
+    let default_kind = if burstiness.iter().flatten().any(|&b| b > 1.0) { "bursty" } else { "steady" };
+    let workload = match take_flag(&mut args, "--workload").as_deref().unwrap_or(default_kind) {
+        "steady" => WorkloadKind::Steady,
+        "bursty" => WorkloadKind::Bursty { period },
+        "adversarial" => WorkloadKind::Adversarial { work_range },
+        other => {
+            eprintln!("Unknown workload: {} (expected steady | bursty | adversarial)", other);
+            std::process::exit(1);
+        }
+    };
+    let burstiness = match (workload, burstiness) {
+        (WorkloadKind::Bursty { .. }, burstiness) => burstiness.unwrap_or_else(|| vec![4.0]),
+        (_, None) => vec![1.0],
+        (_, Some(_)) => {
+            eprintln!("--burstiness only applies to bursty workloads");
+            std::process::exit(1);
+        }
+    };
+    if matches!(workload, WorkloadKind::Adversarial { .. }) && work_dist.is_some() {
+        eprintln!("Adversarial workloads draw task sizes from --work-range, not --work-dist");
+        std::process::exit(1);
+    }
+    let rates = take_flag(&mut args, "--rates");
+    let task_sizes = take_flag(&mut args, "--task-us");
+    let output_path = take_flag(&mut args, "--output");
+    let train_config = take_flag(&mut args, "--config");
+    let rounds: usize = take_flag(&mut args, "--rounds").map_or(5, |s| s.parse().expect("--rounds must be a count"));
+    let batch: usize = take_flag(&mut args, "--batch").map_or(4, |s| s.parse().expect("--batch must be a count"));
+    let holdout: usize = take_flag(&mut args, "--holdout").map_or(4, |s| s.parse().expect("--holdout must be a count"));
+    let real_time = take_switch(&mut args, "--real-time");
+    // Wall-clock runs stay sequential so they don't compete for cores
+    let jobs: usize = match take_flag(&mut args, "--jobs") {
+        Some(jobs) => jobs.parse().expect("--jobs must be a thread count"),
+        None if real_time => 1,
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+
+    if args.get(1).map(String::as_str) == Some("active") {
+        let (Some(rates), Some(task_sizes), Some(output_path), Some(config_path), Some(duration)) =
+            (rates, task_sizes, output_path, train_config, args.get(2))
+        else {
+            eprintln!("Usage: sweep active <duration_secs> --rates R1,R2,.. --task-us T1,T2,.. --config train.yaml --output dataset.csv|dataset.json [--rounds N] [--batch N] [--holdout N] [sweep grid options]");
+            eprintln!("Example: sweep active 5 --rates 100,5000 --task-us 200,2000 --config trainer/configs/tree.yaml --output data/telemetry/compute-active.csv");
+            std::process::exit(1);
+        };
+        if rounds == 0 || batch == 0 || holdout == 0 {
+            eprintln!("--rounds, --batch and --holdout must be at least 1");
+            std::process::exit(1);
+        }
+        let config = TrainConfig::load(&config_path).unwrap_or_else(|e| {
+            eprintln!("Failed to load config {}: {}", config_path, e);
+            std::process::exit(1);
+        });
+        let base = SweepConfig {
+            workload,
+            arrival_rate: 0.0,
+            task_us: 0,
+            work: WorkDistribution::Constant(0),
+            burstiness: 1.0,
+            duration_secs: duration.parse().expect("duration_secs must be u64"),
+            seed,
+            real_time,
+            cores,
+            smt,
+            discipline,
+            reward,
+        };
+        let grid = Grid {
+            rates: parse_list(&rates, "--rates"),
+            task_sizes: parse_list(&task_sizes, "--task-us"),
+            burstiness,
+            work_dist,
+        };
+        let learner = ActiveLearner::new().with_batch(batch).with_seed(config.seed);
+        run_active(&base, &grid, &config, &learner, rounds, holdout, &output_path, jobs);
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("grid") {
+        let (Some(rates), Some(task_sizes), Some(output_path), Some(duration)) =
+            (rates, task_sizes, output_path, args.get(2))
+        else {
+            eprintln!("Usage: sweep grid <duration_secs> --rates R1,R2,.. --task-us T1,T2,.. --output dataset.csv|dataset.json [--workload steady|bursty|adversarial] [--burstiness B1,B2,..] [--period-ms N] [--work-range LOW,HIGH] [--seed N] [--cores N [--smt FACTOR]] [--discipline fifo|lifo|sjf] [--work-dist lognormal[:SIGMA]|pareto[:SHAPE]] [--reward reward.toml] [--jobs N] [--real-time]");
+            eprintln!("Example: sweep grid 5 --rates 100,1000,5000 --task-us 200,500,2000 --burstiness 1,4 --output data/telemetry/compute-sweep.csv");
+            std::process::exit(1);
+        };
+        let base = SweepConfig {
+            workload,
+            arrival_rate: 0.0,
+            task_us: 0,
+            work: WorkDistribution::Constant(0),
+            burstiness: 1.0,
+            duration_secs: duration.parse().expect("duration_secs must be u64"),
+            seed,
+            real_time,
+            cores,
+            smt,
+            discipline,
+            reward,
+        };
+        let rates: Vec<f64> = parse_list(&rates, "--rates");
+        let task_sizes: Vec<u64> = parse_list(&task_sizes, "--task-us");
+        run_grid(&base, &rates, &task_sizes, &burstiness, work_dist.as_deref(), &output_path, jobs);
+        return;
+    }
 
     if args.len() < 4 {
-        eprintln!("Usage: sweep <arrival_rate> <task_us> <duration_secs>");
+        eprintln!("Usage: sweep <arrival_rate> <task_us> <duration_secs> [--seed N] [--cores N [--smt FACTOR]] [--discipline fifo|lifo|sjf] [--work-dist lognormal[:SIGMA]|pareto[:SHAPE]] [--workload steady|bursty|adversarial] [--burstiness B] [--period-ms N] [--work-range LOW,HIGH] [--reward reward.toml] [--jobs N] [--real-time]");
+        eprintln!("       sweep grid <duration_secs> --rates .. --task-us .. --output PATH (run `sweep grid` for details)");
         eprintln!("Example: sweep 100 500 5");
         std::process::exit(1);
     }
 
-    let arrival_rate: f64 = args[1].parse().expect("arrival_rate must be float");
     let task_us: u64 = args[2].parse().expect("task_us must be u64");
-    let duration_secs: u64 = args[3].parse().expect("duration_secs must be u64");
+    let config = SweepConfig {
+        workload,
+        arrival_rate: args[1].parse().expect("arrival_rate must be float"),
+        task_us,
+        work: work_for(work_dist.as_deref(), task_us),
+        burstiness: burstiness[0],
+        duration_secs: args[3].parse().expect("duration_secs must be u64"),
+        seed,
+        real_time,
+        cores,
+        smt,
+        discipline,
+        reward,
+    };
 
     println!("=== Pool Size Sweep ===");
-    println!("Workload: {} tasks/sec, {} µs/task, {} sec duration\n", arrival_rate, task_us, duration_secs);
-    println!("{:<10} {:>12} {:>12} {:>12} {:>15}", "N Workers", "p50 (µs)", "p95 (µs)", "p99 (µs)", "Throughput");
-    println!("{:-<65}", "");
+    println!(
+        "Workload: {:?}, {} tasks/sec, {:?} µs/task, burstiness {}, {} sec duration ({}, seed {}, {:?} queue)\n",
+        config.workload,
+        config.arrival_rate,
+        config.work,
+        config.burstiness,
+        config.duration_secs,
+        if config.real_time { "real time" } else { "virtual time" },
+        config.seed,
+        config.discipline
+    );
+    println!("Reward: {}\n", config.reward.version);
+    println!(
+        "{:<10} {:>12} {:>12} {:>12} {:>15} {:>12}",
+        "N Workers", "p50 (µs)", "p95 (µs)", "p99 (µs)", "Throughput", "Cost"
+    );
+    println!("{:-<78}", "");
 
-    let pool_sizes = [1, 2, 4, 8, 16, 32, 64];
-    let mut best_n = 1;
-    let mut best_p95 = f64::MAX;
+    let outcomes = run_all(std::slice::from_ref(&config), jobs).remove(0);
+    let best_n = best_pool_size(&outcomes);
+    for (n, outcome) in &outcomes {
+        println!("{:<10} {:>12.0} {:>12.0} {:>12.0} {:>15.2} {:>12.1}",
+                 n, outcome.p50, outcome.p95, outcome.p99, outcome.throughput, outcome.cost);
+    }
+    let best = outcomes.iter().find(|(n, _)| *n == best_n).map(|(_, o)| o);
+
+    println!("\n=== Empirical Optimum ===");
+    println!(
+        "Best N: {} (p95 = {:.0} µs, cost = {:.1})",
+        best_n,
+        best.map_or(0.0, |o| o.p95),
+        best.map_or(0.0, |o| o.cost)
+    );
+}
+
+/// Sweep every workload in the matrix and write the labeled dataset
+fn run_grid(
+    base: &SweepConfig,
+    rates: &[f64],
+    task_sizes: &[u64],
+    burstiness: &[f64],
+    work_dist: Option<&str>,
+    output_path: &str,
+    jobs: usize,
+) {
+    println!("=== Pool Size Sweep: Workload Grid ===");
+    println!(
+        "{:?}: {} rates × {} task sizes × {} burstiness levels × {} pool sizes, {} sec each ({}, base seed {}, {} jobs, reward {})\n",
+        base.workload,
+        rates.len(),
+        task_sizes.len(),
+        burstiness.len(),
+        POOL_SIZES.len(),
+        base.duration_secs,
+        if base.real_time { "real time" } else { "virtual time" },
+        base.seed,
+        jobs,
+        base.reward.version
+    );
+    println!("{:>12} {:>10} {:>11} {:>8}", "Rate (/s)", "Task (µs)", "Burstiness", "Best N");
+    println!("{:-<44}", "");
+
+    let configs = grid_configs(base, rates, task_sizes, burstiness, work_dist, 0);
+    let rows: Vec<DatasetRow> = sweep_rows(&configs, jobs).into_iter().map(|(_, row)| row).collect();
+    write_dataset(&rows, output_path).expect("Failed to write dataset");
+    println!("\n{} samples written to {}", rows.len(), output_path);
+}
 
-    for n in pool_sizes {
-        let (p50, p95, p99, throughput) = run_simulation(n, arrival_rate, task_us, duration_secs);
+/// One config per (rate, task size, burstiness), seeded from `base.seed + first_seed` up
+fn grid_configs(
+    base: &SweepConfig,
+    rates: &[f64],
+    task_sizes: &[u64],
+    burstiness: &[f64],
+    work_dist: Option<&str>,
+    first_seed: u64,
+) -> Vec<SweepConfig> {
+    let mut configs = Vec::new();
+    for &arrival_rate in rates {
+        for &task_us in task_sizes {
+            for &b in burstiness {
+                configs.push(SweepConfig {
+                    arrival_rate,
+                    task_us,
+                    work: work_for(work_dist, task_us),
+                    burstiness: b,
+                    seed: base.seed + first_seed + configs.len() as u64,
+                    ..base.clone()
+                });
+            }
+        }
+    }
+    configs
+}
 
-        println!("{:<10} {:>12.0} {:>12.0} {:>12.0} {:>15.2}",
-                 n, p50, p95, p99, throughput);
+/// Sweep every config, printing its best N, and label each window; rows carry their config's index
+fn sweep_rows(configs: &[SweepConfig], jobs: usize) -> Vec<(usize, DatasetRow)> {
+    let mut rows = Vec::new();
+    for (i, (config, outcomes)) in configs.iter().zip(run_all(configs, jobs)).enumerate() {
+        let best_n = best_pool_size(&outcomes);
+        println!(
+            "{:>12} {:>10} {:>11} {:>8}",
+            config.arrival_rate, config.task_us, config.burstiness, best_n
+        );
 
-        if p95 < best_p95 {
-            best_p95 = p95;
-            best_n = n;
+        for (n_workers, outcome) in outcomes {
+            rows.extend(outcome.samples.into_iter().map(|telemetry| {
+                (
+                    i,
+                    DatasetRow {
+                        workload: config.workload.name(),
+                        workload_rate: config.arrival_rate,
+                        workload_task_us: config.task_us,
+                        workload_burstiness: config.burstiness,
+                        n_workers,
+                        telemetry,
+                        optimal_n_workers: best_n,
+                        reward: config.reward.version.clone(),
+                    },
+                )
+            }));
         }
     }
+    rows
+}
 
-    println!("\n=== Empirical Optimum ===");
-    println!("Best N: {} (p95 = {:.0} µs)", best_n, best_p95);
+/// The workload matrix a sweep starts from
+struct Grid {
+    rates: Vec<f64>,
+    task_sizes: Vec<u64>,
+    burstiness: Vec<f64>,
+    work_dist: Option<String>,
+}
+
+/// Sweep the grid, then keep sweeping around the cells the model is least sure of
+///
+/// The training rows are rewritten to `output_path` every round; the
+/// held-out workloads go to a `-holdout` file next to it.
+#[allow(clippy::too_many_arguments)]
+fn run_active(
+    base: &SweepConfig,
+    grid: &Grid,
+    config: &TrainConfig,
+    learner: &ActiveLearner,
+    rounds: usize,
+    holdout: usize,
+    output_path: &str,
+    jobs: usize,
+) {
+    let work_dist = grid.work_dist.as_deref();
+    let holdout_path = match output_path.rsplit_once('.') {
+        Some((stem, ext)) => format!("{}-holdout.{}", stem, ext),
+        None => format!("{}-holdout.csv", output_path),
+    };
+    // Validation is the fixed holdout, so every training row is trained on
+    let config = TrainConfig {
+        validation_fraction: 0.0,
+        ..config.clone()
+    };
+    println!("=== Pool Size Sweep: Active Learning ===");
+    println!(
+        "{:?}: {} rates × {} task sizes × {} burstiness levels to start, {} cells a round, up to {} rounds, {} held-out workloads ({} sec each, base seed {}, model {:?})\n",
+        base.workload,
+        grid.rates.len(),
+        grid.task_sizes.len(),
+        grid.burstiness.len(),
+        learner.batch,
+        rounds,
+        holdout,
+        base.duration_secs,
+        base.seed,
+        config.model
+    );
+    println!("{:>12} {:>10} {:>11} {:>8}", "Rate (/s)", "Task (µs)", "Burstiness", "Best N");
+    println!("{:-<44}", "");
+
+    let mut configs = grid_configs(base, &grid.rates, &grid.task_sizes, &grid.burstiness, work_dist, 0);
+    let holdout_configs = holdout_configs(base, grid, holdout, configs.len() as u64);
+    let mut next_seed = (configs.len() + holdout_configs.len()) as u64;
+
+    println!("Held out:");
+    let holdout_rows: Vec<DatasetRow> = sweep_rows(&holdout_configs, jobs).into_iter().map(|(_, row)| row).collect();
+    write_dataset(&holdout_rows, &holdout_path).expect("Failed to write holdout dataset");
+    let holdout_raw = read_sweep_dataset(&holdout_path, &config.target).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", holdout_path, e);
+        std::process::exit(1);
+    });
+
+    println!("Round 0:");
+    let (mut row_config, mut rows): (Vec<usize>, Vec<DatasetRow>) = sweep_rows(&configs, jobs).into_iter().unzip();
+    let mut errors = Vec::new();
+    for round in 0.. {
+        write_dataset(&rows, output_path).expect("Failed to write dataset");
+        let (raw, cells) = match (read_sweep_dataset(output_path, &config.target), read_sweep_cells(output_path)) {
+            (Ok(raw), Ok(cells)) => (raw, cells),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("Failed to read {}: {}", output_path, e);
+                std::process::exit(1);
+            }
+        };
+        let trained = config.train(&raw).unwrap_or_else(|e| {
+            eprintln!("Training failed: {}", e);
+            std::process::exit(1);
+        });
+        let holdout_mae = mean_absolute_error(&trained.normalize(&holdout_raw), |row| trained.reflex.infer(row)[0]);
+        errors.push(holdout_mae);
+        println!("-> {} cells, {} samples, holdout MAE {:.3}\n", configs.len(), rows.len(), holdout_mae);
+        if learner.converged(&errors) {
+            println!("Holdout MAE stopped improving");
+            break;
+        }
+        if round + 1 == rounds {
+            break;
+        }
+
+        let mut added = Vec::new();
+        for cell in learner.select(&trained.normalize(&raw), &cells) {
+            let at = row_config[cells.iter().position(|c| *c == cell).expect("selected cell has rows")];
+            for (arrival_rate, task_us) in refinements(&configs, &configs[at]) {
+                let swept = |c: &SweepConfig| c.arrival_rate == arrival_rate && c.task_us == task_us && c.burstiness == configs[at].burstiness;
+                if configs.iter().chain(&added).any(swept) {
+                    continue;
+                }
+                added.push(SweepConfig {
+                    arrival_rate,
+                    task_us,
+                    work: work_for(work_dist, task_us),
+                    seed: base.seed + next_seed,
+                    ..configs[at].clone()
+                });
+                next_seed += 1;
+            }
+        }
+        if added.is_empty() {
+            println!("No unswept neighbours left to refine");
+            break;
+        }
+        println!("Round {}:", round + 1);
+        for (i, row) in sweep_rows(&added, jobs) {
+            row_config.push(configs.len() + i);
+            rows.push(row);
+        }
+        configs.extend(added);
+    }
+
+    let trace: Vec<String> = errors.iter().map(|e| format!("{:.3}", e)).collect();
+    println!("Holdout MAE by round: {}", trace.join(" -> "));
+    println!("{} samples written to {}, {} held-out samples to {}", rows.len(), output_path, holdout_rows.len(), holdout_path);
+}
+
+/// `count` random workloads inside the grid's rate and task-size range, log-uniform
+fn holdout_configs(base: &SweepConfig, grid: &Grid, count: usize, first_seed: u64) -> Vec<SweepConfig> {
+    let mut rng = StdRng::seed_from_u64(base.seed ^ 0x6e65_6d61);
+    let log_uniform = |rng: &mut StdRng, values: &[f64]| {
+        let (low, high) = values.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+        (low.ln() + rng.gen::<f64>() * (high.ln() - low.ln())).exp()
+    };
+    let task_sizes: Vec<f64> = grid.task_sizes.iter().map(|&t| t as f64).collect();
+    (0..count)
+        .map(|i| {
+            let task_us = log_uniform(&mut rng, &task_sizes).round().max(1.0) as u64;
+            SweepConfig {
+                arrival_rate: log_uniform(&mut rng, &grid.rates).round().max(1.0),
+                task_us,
+                work: work_for(grid.work_dist.as_deref(), task_us),
+                burstiness: grid.burstiness[rng.gen_range(0..grid.burstiness.len())],
+                seed: base.seed + first_seed + i as u64,
+                ..base.clone()
+            }
+        })
+        .collect()
+}
+
+/// Workloads halfway (geometrically) between `config` and its nearest swept neighbours on each axis
+fn refinements(configs: &[SweepConfig], config: &SweepConfig) -> Vec<(f64, u64)> {
+    let rates: Vec<f64> = configs.iter().map(|c| c.arrival_rate).collect();
+    let task_sizes: Vec<f64> = configs.iter().map(|c| c.task_us as f64).collect();
+    let rate = config.arrival_rate;
+    let task_us = config.task_us as f64;
+    let mut points = Vec::new();
+    for neighbour in neighbours(&rates, rate) {
+        points.push(((rate * neighbour).sqrt().round().max(1.0), config.task_us));
+    }
+    for neighbour in neighbours(&task_sizes, task_us) {
+        points.push((rate, (task_us * neighbour).sqrt().round().max(1.0) as u64));
+    }
+    points.retain(|&(r, t)| (r, t) != (rate, config.task_us));
+    points
+}
+
+/// The nearest value below and above `value`, where there is one
+fn neighbours(values: &[f64], value: f64) -> Vec<f64> {
+    let below = values.iter().copied().filter(|&v| v < value).fold(None, |best: Option<f64>, v| Some(best.map_or(v, |b| b.max(v))));
+    let above = values.iter().copied().filter(|&v| v > value).fold(None, |best: Option<f64>, v| Some(best.map_or(v, |b| b.min(v))));
+    below.into_iter().chain(above).collect()
 }
@@ -0,0 +1,236 @@
+//! Offline counterfactual evaluation of a candidate reflex
+//!
+//! Recorded telemetry says what load production actually saw, but not what
+//! a different pool size would have done with it. [`replay_workload`]
+//! rebuilds that load window by window (Poisson arrivals at the recorded
+//! rate, lognormal task sizes with the recorded mean and variance) and
+//! [`OfflineEval`] runs the candidate and the incumbent on it in the
+//! simulator, paired per seed, before the candidate is ever deployed. The
+//! report's deployment confidence is the share of recorded windows inside
+//! the candidate's training range times the share of seeds on which it
+//! held the incumbent's p95 and throughput.
+
+use crate::{policy_from_spec, run_paired, ReflexPolicy, RunResult, RunSummary, ThreadPoolSim, TraceWorkload, WorkDistribution};
+use policy_compare::{ComparisonReport, Goal};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::time::Duration;
+use telemetry_compute::{ComputeTelemetry, Normalizer};
+
+type Metric = fn(&RunResult) -> f64;
+
+/// Gaps longer than this many typical window steps are recording gaps, replayed as one step
+const MAX_GAP_STEPS: u64 = 4;
+
+/// Recorded windows as a task trace, one replay step per window
+///
+/// Each window's load runs from its timestamp to the next window's; the
+/// last runs one typical step. Stretches with no telemetry aren't
+/// replayed, so the trace can be shorter than the recording.
+pub fn replay_workload(windows: &[ComputeTelemetry], seed: u64) -> TraceWorkload {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut records = Vec::new();
+    let mut start_us = 0;
+    for (window, span_us) in window_spans(windows) {
+        let work = work_distribution(&window);
+        if window.arrival_rate > 0.0 {
+            let mut offset_us = 0.0;
+            loop {
+                let u: f64 = 1.0 - rng.gen::<f64>();
+                offset_us += -u.ln() / window.arrival_rate as f64 * 1e6;
+                if offset_us >= span_us as f64 {
+                    break;
+                }
+                records.push((start_us + offset_us as u64, work.sample(&mut rng)));
+            }
+        }
+        start_us += span_us;
+    }
+    TraceWorkload::new(records)
+}
+
+/// How long `replay_workload` takes to play `windows`
+pub fn replay_duration(windows: &[ComputeTelemetry]) -> Duration {
+    Duration::from_micros(window_spans(windows).iter().map(|&(_, span_us)| span_us).sum())
+}
+
+/// Windows in time order, each with how long its load is replayed
+fn window_spans(windows: &[ComputeTelemetry]) -> Vec<(ComputeTelemetry, u64)> {
+    let mut windows = windows.to_vec();
+    windows.sort_by_key(|window| window.timestamp_us);
+    let mut gaps: Vec<u64> = windows.windows(2).map(|w| w[1].timestamp_us - w[0].timestamp_us).filter(|&gap| gap > 0).collect();
+    gaps.sort_unstable();
+    let step_us = gaps.get(gaps.len() / 2).copied().unwrap_or(1_000_000);
+    (0..windows.len())
+        .map(|i| {
+            let gap = windows.get(i + 1).map_or(step_us, |next| next.timestamp_us - windows[i].timestamp_us);
+            let span_us = if gap > MAX_GAP_STEPS * step_us { step_us } else { gap };
+            (windows[i], span_us)
+        })
+        .collect()
+}
+
+/// Lognormal task sizes with the window's mean and variance
+///
+/// The mean is the completed tasks' service time, falling back to the
+/// queued tasks' mean size and then the median task time for telemetry
+/// without them; the variance is the queued tasks'.
+fn work_distribution(window: &ComputeTelemetry) -> WorkDistribution {
+    let Some(mean) = [window.service_time_mean_us, window.task_size_mean, window.task_time_p50_us]
+        .into_iter()
+        .find(|&mean| mean > 0.0)
+        .map(f64::from)
+    else {
+        return WorkDistribution::Constant(1);
+    };
+    let sigma_sq = (1.0 + window.task_size_var.max(0.0) as f64 / (mean * mean)).ln();
+    WorkDistribution::LogNormal {
+        median: mean / (sigma_sq / 2.0).exp(),
+        sigma: sigma_sq.sqrt(),
+    }
+}
+
+/// Paired simulator replay of a candidate reflex against the incumbent
+#[derive(Debug, Clone)]
+pub struct OfflineEval {
+    pub runs: u64, // paired seeds
+    pub initial_workers: u32,
+    pub tolerance: f64, // relative slack before a seed counts as a regression
+}
+
+impl OfflineEval {
+    /// 10 paired runs from 8 workers, allowing 5% slack
+    pub fn new() -> Self {
+        Self {
+            runs: 10,
+            initial_workers: 8,
+            tolerance: 0.05,
+        }
+    }
+
+    pub fn with_runs(mut self, runs: u64) -> Self {
+        assert!(runs > 0, "need at least one run");
+        self.runs = runs;
+        self
+    }
+
+    pub fn with_initial_workers(mut self, workers: u32) -> Self {
+        self.initial_workers = workers;
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Replay `windows` under the incumbent policy spec and under `candidate`
+    pub fn evaluate(
+        &self,
+        candidate: &reflex_format::Reflex,
+        normalizer: &Normalizer,
+        incumbent: &str,
+        windows: &[ComputeTelemetry],
+    ) -> io::Result<OfflineReport> {
+        if windows.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no telemetry to replay"));
+        }
+        // Fail on a bad incumbent spec before any runs
+        policy_from_spec(incumbent)?;
+        let duration = replay_duration(windows);
+        let seeds: Vec<u64> = (0..self.runs).collect();
+        let pairs = run_paired(
+            (incumbent, "candidate"),
+            || ThreadPoolSim::new(policy_from_spec(incumbent).expect("policy spec validated above"), self.initial_workers),
+            || ThreadPoolSim::new(ReflexPolicy::new(candidate.clone(), normalizer.clone()), self.initial_workers),
+            |seed| Box::new(replay_workload(windows, seed)),
+            duration,
+            &seeds,
+        );
+
+        let held = pairs
+            .iter()
+            .filter(|(a, b)| {
+                b.p95_task_time_us <= a.p95_task_time_us * (1.0 + self.tolerance)
+                    && b.mean_throughput >= a.mean_throughput * (1.0 - self.tolerance)
+            })
+            .count();
+        let coverage = windows.iter().filter(|window| in_range(normalizer, &window.to_features())).count() as f64
+            / windows.len() as f64;
+        let no_regression = held as f64 / pairs.len() as f64;
+
+        let (incumbent_runs, candidate_runs): (Vec<RunResult>, Vec<RunResult>) = pairs.into_iter().unzip();
+        let mut comparison = ComparisonReport::new(incumbent, "candidate");
+        let metrics: [(&str, Metric, Goal); 3] = [
+            ("p95_task_time_us", |r| r.p95_task_time_us, Goal::Lower),
+            ("mean_throughput", |r| r.mean_throughput, Goal::Higher),
+            ("p95_wait_time_us", |r| r.p95_wait_time_us, Goal::Lower),
+        ];
+        for (name, metric, goal) in metrics {
+            let a: Vec<f64> = incumbent_runs.iter().map(metric).collect();
+            let b: Vec<f64> = candidate_runs.iter().map(metric).collect();
+            comparison.add_metric(name, goal, &a, &b);
+        }
+        Ok(OfflineReport {
+            windows: windows.len(),
+            replay_secs: duration.as_secs_f64(),
+            coverage,
+            no_regression,
+            confidence: coverage * no_regression,
+            incumbent: RunSummary::from_results(&incumbent_runs),
+            candidate: RunSummary::from_results(&candidate_runs),
+            comparison,
+        })
+    }
+}
+
+impl Default for OfflineEval {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether every feature lies within the bounds the normalizer was fit on
+fn in_range(normalizer: &Normalizer, features: &[f32; ComputeTelemetry::FEATURE_COUNT]) -> bool {
+    features.iter().enumerate().all(|(i, &value)| value >= normalizer.min[i] && value <= normalizer.max[i])
+}
+
+/// Estimated effect of deploying the candidate on the recorded load
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OfflineReport {
+    pub windows: usize,
+    pub replay_secs: f64,
+    pub coverage: f64,      // share of windows inside the candidate's training range
+    pub no_regression: f64, // share of seeds where p95 and throughput held within tolerance
+    pub confidence: f64,    // coverage × no_regression
+    pub incumbent: RunSummary,
+    pub candidate: RunSummary,
+    pub comparison: ComparisonReport,
+}
+
+impl OfflineReport {
+    pub fn write_json(&self, path: impl AsRef<std::path::Path>) -> io::Result<()> {
+        let writer = io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(writer, self).map_err(io::Error::from)
+    }
+}
+
+impl fmt::Display for OfflineReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Replayed {} windows ({:.1}s) x{} seeds", self.windows, self.replay_secs, self.candidate.runs)?;
+        for summary in [&self.incumbent, &self.candidate] {
+            writeln!(
+                f,
+                "  {:<12} p95 {:.0} µs, throughput {:.1} tasks/s",
+                summary.policy, summary.p95_task_time_us, summary.mean_throughput
+            )?;
+        }
+        writeln!(f, "Coverage: {:.0}% of windows in the candidate's training range", 100.0 * self.coverage)?;
+        writeln!(f, "No regression: {:.0}% of seeds", 100.0 * self.no_regression)?;
+        writeln!(f, "Deployment confidence: {:.2}", self.confidence)?;
+        write!(f, "\n{}", self.comparison)
+    }
+}
@@ -0,0 +1,211 @@
+//! Paired policy comparison
+//!
+//! Runs two sizing policies per seed on the identical arrival sequence, so
+//! per-seed differences come from the policies rather than the workload,
+//! and tests each metric's differences for significance.
+
+use crate::stats::{wilcoxon_signed_rank, MeanCi, SignedRank};
+use crate::{
+    embedded_normalizer, BaselinePolicy, EnergyModel, ErlangCPolicy, HillClimbPolicy, Metrics, PidPolicy, PoolSizePolicy, ReflexPolicy, ReloadableReflexPolicy, ThreadPoolSim,
+    WorkloadGenerator,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+use std::time::Duration;
+
+/// Normalizer used by `reflex:` specs that don't name one, for models without one embedded
+const DEFAULT_NORMALIZER: &str = "data/models/normalizer-compute.json";
+
+/// Build a policy from a baseline name or `reflex:<model>[,<normalizer>]`
+///
+/// Baselines: `static` (N=8), `erlang`, `hillclimb`, `pid`. A reflex
+/// without a named normalizer uses the one embedded in it, if any; a
+/// normalizer that can't feed the model is an error. `reload:` instead of
+/// `reflex:` keeps watching the model file and swaps in rewrites
+/// (`ReloadableReflexPolicy`); its models must embed their normalizer or
+/// name one.
+pub fn policy_from_spec(spec: &str) -> io::Result<Box<dyn PoolSizePolicy + Send>> {
+    if let Some(paths) = spec.strip_prefix("reload:") {
+        let (model, normalizer) = match paths.split_once(',') {
+            Some((model, normalizer)) => (model, Some(read_normalizer(normalizer)?)),
+            None => (paths, None),
+        };
+        return Ok(Box::new(ReloadableReflexPolicy::load(model, normalizer)?));
+    }
+    if let Some(paths) = spec.strip_prefix("reflex:") {
+        let (model, normalizer_path) = match paths.split_once(',') {
+            Some((model, normalizer)) => (model, Some(normalizer)),
+            None => (paths, None),
+        };
+        let reflex = reflex_format::Reflex::from_bytes(&std::fs::read(model)?)?;
+        let normalizer = match (normalizer_path, embedded_normalizer(&reflex)) {
+            (None, Some(embedded)) => embedded,
+            (path, _) => read_normalizer(path.unwrap_or(DEFAULT_NORMALIZER))?,
+        };
+        trainer::check_normalizer(&reflex, &normalizer)?;
+        return Ok(Box::new(ReflexPolicy::new(reflex, normalizer)));
+    }
+    let policy: Box<dyn PoolSizePolicy + Send> = match spec {
+        "static" => Box::new(BaselinePolicy::new()),
+        "erlang" => Box::new(ErlangCPolicy::default()),
+        "hillclimb" => Box::new(HillClimbPolicy::new(8)),
+        "pid" => Box::new(PidPolicy::queue_length(4.0)),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("unknown policy: {}", spec))),
+    };
+    Ok(policy)
+}
+
+fn read_normalizer(path: &str) -> io::Result<telemetry_compute::Normalizer> {
+    serde_json::from_str(&std::fs::read_to_string(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Headline metrics of one run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunResult {
+    pub policy: String,
+    pub seed: u64,
+    pub p50_task_time_us: f64,
+    pub p95_task_time_us: f64,
+    pub p99_task_time_us: f64,
+    pub p95_wait_time_us: f64,
+    pub mean_throughput: f64,
+    pub energy_j: f64, // under the default EnergyModel
+    pub decision_changes: usize,
+    pub flip_flops: usize,
+}
+
+impl RunResult {
+    pub fn from_metrics(policy: &str, seed: u64, metrics: &Metrics) -> Self {
+        Self {
+            policy: policy.to_string(),
+            seed,
+            p50_task_time_us: metrics.p50_task_time(),
+            p95_task_time_us: metrics.p95_task_time(),
+            p99_task_time_us: metrics.p99_task_time(),
+            p95_wait_time_us: metrics.wait_percentile(0.95),
+            mean_throughput: metrics.mean_throughput(),
+            energy_j: metrics.energy_joules(EnergyModel::default()),
+            decision_changes: metrics.decision_changes,
+            flip_flops: metrics.flip_flops,
+        }
+    }
+
+    /// Write results as a JSON array
+    pub fn write_all(results: &[RunResult], path: impl AsRef<Path>) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, results).map_err(io::Error::from)
+    }
+}
+
+/// Run `make_workload(seed)` once per seed on a fresh simulator from
+/// `make_sim`, on the virtual clock
+pub fn run_repeated<P: PoolSizePolicy>(
+    policy_id: &str,
+    mut make_sim: impl FnMut() -> ThreadPoolSim<P>,
+    mut make_workload: impl FnMut(u64) -> io::Result<Box<dyn WorkloadGenerator>>,
+    duration: Duration,
+    seeds: &[u64],
+) -> io::Result<Vec<RunResult>> {
+    let mut results = Vec::with_capacity(seeds.len());
+    for &seed in seeds {
+        let mut sim = make_sim();
+        sim.run_virtual(make_workload(seed)?.as_mut(), duration);
+        results.push(RunResult::from_metrics(policy_id, seed, sim.metrics()));
+    }
+    Ok(results)
+}
+
+/// Mean and 95% CI of the headline metrics over repeated runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub policy: String,
+    pub runs: usize,
+    pub p50_task_time_us: MeanCi,
+    pub p95_task_time_us: MeanCi,
+    pub p99_task_time_us: MeanCi,
+    pub p95_wait_time_us: MeanCi,
+    pub mean_throughput: MeanCi,
+    pub energy_j: MeanCi,
+}
+
+impl RunSummary {
+    pub fn from_results(results: &[RunResult]) -> Self {
+        let ci = |metric: Metric| MeanCi::from_samples(&results.iter().map(metric).collect::<Vec<_>>());
+        Self {
+            policy: results.first().map(|r| r.policy.clone()).unwrap_or_default(),
+            runs: results.len(),
+            p50_task_time_us: ci(|r| r.p50_task_time_us),
+            p95_task_time_us: ci(|r| r.p95_task_time_us),
+            p99_task_time_us: ci(|r| r.p99_task_time_us),
+            p95_wait_time_us: ci(|r| r.p95_wait_time_us),
+            mean_throughput: ci(|r| r.mean_throughput),
+            energy_j: ci(|r| r.energy_j),
+        }
+    }
+}
+
+/// Run two policies per seed on the virtual clock with identical arrivals
+///
+/// `make_workload` is called once per policy with the run's seed. Workload
+/// generators are open-loop and seeded, so both simulators see the same
+/// task sequence.
+pub fn run_paired<A: PoolSizePolicy, B: PoolSizePolicy>(
+    policy_ids: (&str, &str),
+    mut make_a: impl FnMut() -> ThreadPoolSim<A>,
+    mut make_b: impl FnMut() -> ThreadPoolSim<B>,
+    mut make_workload: impl FnMut(u64) -> Box<dyn WorkloadGenerator>,
+    duration: Duration,
+    seeds: &[u64],
+) -> Vec<(RunResult, RunResult)> {
+    seeds
+        .iter()
+        .map(|&seed| {
+            let mut a = make_a();
+            a.run_virtual(make_workload(seed).as_mut(), duration);
+            let mut b = make_b();
+            b.run_virtual(make_workload(seed).as_mut(), duration);
+            (
+                RunResult::from_metrics(policy_ids.0, seed, a.metrics()),
+                RunResult::from_metrics(policy_ids.1, seed, b.metrics()),
+            )
+        })
+        .collect()
+}
+
+/// Per-seed differences (B - A) of one metric: mean with paired 95% CI and
+/// a Wilcoxon signed-rank test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedDifference {
+    pub metric: String,
+    pub diff: MeanCi,
+    pub test: SignedRank,
+}
+
+type Metric = fn(&RunResult) -> f64;
+
+/// Paired differences of the headline metrics over `run_paired` output
+pub fn paired_differences(pairs: &[(RunResult, RunResult)]) -> Vec<PairedDifference> {
+    let metrics: [(&str, Metric); 8] = [
+        ("p50_task_time_us", |r| r.p50_task_time_us),
+        ("p95_task_time_us", |r| r.p95_task_time_us),
+        ("p99_task_time_us", |r| r.p99_task_time_us),
+        ("p95_wait_time_us", |r| r.p95_wait_time_us),
+        ("mean_throughput", |r| r.mean_throughput),
+        ("energy_j", |r| r.energy_j),
+        ("decision_changes", |r| r.decision_changes as f64),
+        ("flip_flops", |r| r.flip_flops as f64),
+    ];
+    metrics
+        .iter()
+        .map(|(name, metric)| {
+            let diffs: Vec<f64> = pairs.iter().map(|(a, b)| metric(b) - metric(a)).collect();
+            PairedDifference {
+                metric: name.to_string(),
+                diff: MeanCi::from_samples(&diffs),
+                test: wilcoxon_signed_rank(&diffs),
+            }
+        })
+        .collect()
+}
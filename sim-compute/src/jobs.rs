@@ -0,0 +1,411 @@
+//! File-queue job runner for sweeps and training folds across machines
+//!
+//! A fine-grained workload-matrix sweep takes days on one box. A
+//! [`JobQueue`] is a directory every machine can see (NFS, a synced
+//! mount): the coordinator drops one JSON file per job into `pending/`,
+//! workers claim jobs by renaming them into `running/`, which only one
+//! rename can win, and leave a [`JobReport`] in `done/` or `failed/`.
+//! Outputs go to `results/`. A worker that dies leaves its job in
+//! `running/`; [`JobQueue::requeue_stale`] moves it back, so a job can run
+//! twice but never zero times. Live workers touch their claim every
+//! [`HEARTBEAT_INTERVAL`] while a job runs, so only a dead worker's claim
+//! goes stale. Sweep jobs are seeded per cell, so a rerun writes the same
+//! rows.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+use trainer::dataset::read_sweep_dataset;
+use trainer::{blocked_folds, mean_absolute_error, TrainConfig};
+
+const QUEUE_DIRS: [&str; 5] = ["pending", "running", "done", "failed", "results"];
+
+/// How often a worker touches the claim of the job it is running; requeue timeouts should be several of these
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// One unit of work
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Job {
+    /// Run the `sweep` binary with `args`, writing its dataset to `output`
+    Sweep { args: Vec<String>, output: PathBuf },
+    /// Train `config` on `dataset` without blocked fold `fold` of `folds`, and score it on that fold
+    Fold {
+        dataset: PathBuf,
+        config: PathBuf,
+        fold: usize,
+        folds: usize,
+        gap_us: u64,
+    },
+}
+
+impl Job {
+    /// Run the job here; `sweep` is the sweep binary for sweep jobs
+    ///
+    /// Returns the fold's validation MAE for fold jobs.
+    pub fn run(&self, sweep: &Path, log: &Path) -> io::Result<Option<f64>> {
+        match self {
+            Job::Sweep { args, output } => {
+                let result = Command::new(sweep).args(args).arg("--output").arg(output).output()?;
+                let mut file = fs::File::create(log)?;
+                file.write_all(&result.stdout)?;
+                file.write_all(&result.stderr)?;
+                if !result.status.success() {
+                    return Err(io::Error::other(format!("sweep exited with {} (see {})", result.status, log.display())));
+                }
+                Ok(None)
+            }
+            Job::Fold { dataset, config, fold, folds, gap_us } => {
+                let config = TrainConfig::load(config)?;
+                let raw = read_sweep_dataset(dataset, &config.target)?;
+                if *folds < 2 || raw.len() < *folds || fold >= folds {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("fold {} of {} over {} rows", fold, folds, raw.len())));
+                }
+                let (train_rows, valid_rows) = blocked_folds(&raw, *folds, *gap_us).swap_remove(*fold);
+                // The fold is the validation set; train on everything else
+                let config = TrainConfig { validation_fraction: 0.0, ..config };
+                let trained = config.train(&raw.subset(&train_rows))?;
+                let valid = trained.normalize(&raw.subset(&valid_rows));
+                Ok(Some(mean_absolute_error(&valid, |row| trained.reflex.infer(row)[0])))
+            }
+        }
+    }
+}
+
+/// What became of a job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobReport {
+    pub id: String,
+    pub worker: String,
+    pub elapsed_secs: f64,
+    pub mae: Option<f64>,      // fold jobs
+    pub error: Option<String>, // set when the job failed
+}
+
+/// Jobs in each state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStatus {
+    pub pending: usize,
+    pub running: usize,
+    pub done: usize,
+    pub failed: usize,
+}
+
+impl QueueStatus {
+    /// Nothing left to wait for
+    pub fn is_settled(&self) -> bool {
+        self.pending == 0 && self.running == 0
+    }
+}
+
+/// A claimed job and who claimed it, as stored in `running/`
+#[derive(Serialize, Deserialize)]
+struct Claim {
+    worker: String,
+    job: Job,
+}
+
+/// Job queue in a shared directory
+#[derive(Debug, Clone)]
+pub struct JobQueue {
+    root: PathBuf,
+}
+
+impl JobQueue {
+    /// Open the queue at `root`, creating its directories
+    pub fn open(root: impl AsRef<Path>) -> io::Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        for dir in QUEUE_DIRS {
+            fs::create_dir_all(root.join(dir))?;
+        }
+        Ok(Self { root })
+    }
+
+    /// Where jobs write their outputs
+    pub fn results_dir(&self) -> PathBuf {
+        self.root.join("results")
+    }
+
+    /// Queue `job` as `id`, replacing any earlier report for that id
+    pub fn submit(&self, id: &str, job: &Job) -> io::Result<()> {
+        for dir in ["done", "failed"] {
+            remove_if_present(&self.path(dir, id))?;
+        }
+        // Written aside and renamed in, so workers never see a partial file
+        let staging = self.root.join(format!(".{}.json", id));
+        fs::write(&staging, serde_json::to_vec_pretty(job)?)?;
+        fs::rename(staging, self.path("pending", id))
+    }
+
+    /// Claim the first pending job, in id order, or `None` if there is none
+    pub fn claim(&self, worker: &str) -> io::Result<Option<(String, Job)>> {
+        for id in self.ids("pending")? {
+            let running = self.path("running", &id);
+            match fs::rename(self.path("pending", &id), &running) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue, // another worker won
+                Err(e) => return Err(e),
+            }
+            let job: Job = serde_json::from_slice(&fs::read(&running)?).map_err(invalid)?;
+            // Rewriting also restarts the clock `requeue_stale` reads
+            let claim = Claim { worker: worker.to_string(), job: job.clone() };
+            fs::write(&running, serde_json::to_vec_pretty(&claim)?)?;
+            return Ok(Some((id, job)));
+        }
+        Ok(None)
+    }
+
+    /// Record how a claimed job ended and release its claim
+    ///
+    /// A claim another worker took after this one was requeued stays, so
+    /// the queue keeps waiting for that run.
+    pub fn finish(&self, report: &JobReport) -> io::Result<()> {
+        let dir = if report.error.is_some() { "failed" } else { "done" };
+        fs::write(self.path(dir, &report.id), serde_json::to_vec_pretty(report)?)?;
+        if self.claimed_by(&report.id)?.is_some_and(|worker| worker == report.worker) {
+            remove_if_present(&self.path("running", &report.id))?;
+        }
+        Ok(())
+    }
+
+    /// Restart the staleness clock of `worker`'s claim on `id`; returns whether `worker` still holds it
+    pub fn heartbeat(&self, id: &str, worker: &str) -> io::Result<bool> {
+        if self.claimed_by(id)?.as_deref() != Some(worker) {
+            return Ok(false);
+        }
+        match fs::File::options().write(true).open(self.path("running", id)) {
+            Ok(file) => file.set_modified(SystemTime::now())?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        Ok(true)
+    }
+
+    /// Worker holding the claim on `id`, if it is running and its claim is written
+    fn claimed_by(&self, id: &str) -> io::Result<Option<String>> {
+        match fs::read(self.path("running", id)) {
+            Ok(bytes) => Ok(serde_json::from_slice::<Claim>(&bytes).ok().map(|claim| claim.worker)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Move jobs claimed longer than `timeout` ago back to pending; returns how many moved
+    pub fn requeue_stale(&self, timeout: Duration) -> io::Result<usize> {
+        let mut moved = 0;
+        for id in self.ids("running")? {
+            let running = self.path("running", &id);
+            let Ok(modified) = fs::metadata(&running).and_then(|m| m.modified()) else {
+                continue; // finished meanwhile
+            };
+            if SystemTime::now().duration_since(modified).unwrap_or_default() < timeout {
+                continue;
+            }
+            let Ok(bytes) = fs::read(&running) else {
+                continue;
+            };
+            // A bare job is a claim still being written
+            let Ok(claim) = serde_json::from_slice::<Claim>(&bytes) else {
+                continue;
+            };
+            self.submit(&id, &claim.job)?;
+            remove_if_present(&running)?;
+            moved += 1;
+        }
+        Ok(moved)
+    }
+
+    pub fn status(&self) -> io::Result<QueueStatus> {
+        Ok(QueueStatus {
+            pending: self.ids("pending")?.len(),
+            running: self.ids("running")?.len(),
+            done: self.ids("done")?.len(),
+            failed: self.ids("failed")?.len(),
+        })
+    }
+
+    /// The report for `id`, if it has finished
+    pub fn report(&self, id: &str) -> io::Result<Option<JobReport>> {
+        for dir in ["done", "failed"] {
+            match fs::read(self.path(dir, id)) {
+                Ok(bytes) => return serde_json::from_slice(&bytes).map(Some).map_err(invalid),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Poll until no job is pending or running, requeueing claims older than `timeout`
+    ///
+    /// `progress` is called whenever the counts change.
+    pub fn wait(&self, timeout: Option<Duration>, mut progress: impl FnMut(QueueStatus)) -> io::Result<QueueStatus> {
+        let mut last = None;
+        loop {
+            if let Some(timeout) = timeout {
+                self.requeue_stale(timeout)?;
+            }
+            let status = self.status()?;
+            if last != Some(status) {
+                progress(status);
+                last = Some(status);
+            }
+            if status.is_settled() {
+                return Ok(status);
+            }
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    /// Claim and run jobs until none are pending (or forever, polling, unless `exit_when_empty`)
+    pub fn work(&self, worker: &str, sweep: &Path, exit_when_empty: bool) -> io::Result<usize> {
+        let mut ran = 0;
+        loop {
+            let Some((id, job)) = self.claim(worker)? else {
+                if exit_when_empty {
+                    return Ok(ran);
+                }
+                std::thread::sleep(Duration::from_secs(1));
+                continue;
+            };
+            let started = Instant::now();
+            let log = self.results_dir().join(format!("{}.log", id));
+            // Heartbeat the claim until the job returns, so it isn't requeued while running
+            let outcome = std::thread::scope(|scope| {
+                let (stop, stopped) = mpsc::channel::<()>();
+                let id = &id;
+                scope.spawn(move || {
+                    while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(HEARTBEAT_INTERVAL) {
+                        if !matches!(self.heartbeat(id, worker), Ok(true)) {
+                            break;
+                        }
+                    }
+                });
+                let outcome = job.run(sweep, &log);
+                drop(stop);
+                outcome
+            });
+            let report = JobReport {
+                id,
+                worker: worker.to_string(),
+                elapsed_secs: started.elapsed().as_secs_f64(),
+                mae: outcome.as_ref().ok().copied().flatten(),
+                error: outcome.err().map(|e| e.to_string()),
+            };
+            println!(
+                "{} {} in {:.1}s{}",
+                report.id,
+                if report.error.is_some() { "failed" } else { "done" },
+                report.elapsed_secs,
+                report.error.as_ref().map_or(String::new(), |e| format!(": {}", e))
+            );
+            self.finish(&report)?;
+            ran += 1;
+        }
+    }
+
+    /// Job ids in `dir`, sorted
+    fn ids(&self, dir: &str) -> io::Result<Vec<String>> {
+        let mut ids: Vec<String> = fs::read_dir(self.root.join(dir))?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str()?.strip_suffix(".json").map(str::to_string))
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+
+    fn path(&self, dir: &str, id: &str) -> PathBuf {
+        self.root.join(dir).join(format!("{}.json", id))
+    }
+}
+
+/// Concatenate sweep datasets, in order, into one file of the same format
+///
+/// CSV shards keep the first header only; JSON shards are merged into one
+/// array.
+pub fn merge_datasets(shards: &[PathBuf], output: &Path) -> io::Result<usize> {
+    let mut writer = BufWriter::new(fs::File::create(output)?);
+    let mut rows = 0;
+    if output.extension().is_some_and(|ext| ext == "json") {
+        let mut merged: Vec<serde_json::Value> = Vec::new();
+        for shard in shards {
+            let shard_rows: Vec<serde_json::Value> =
+                serde_json::from_reader(BufReader::new(fs::File::open(shard)?)).map_err(invalid)?;
+            merged.extend(shard_rows);
+        }
+        serde_json::to_writer_pretty(&mut writer, &merged)?;
+        rows = merged.len();
+    } else {
+        for (i, shard) in shards.iter().enumerate() {
+            for (line_no, line) in BufReader::new(fs::File::open(shard)?).lines().enumerate() {
+                let line = line?;
+                if line_no == 0 {
+                    if i == 0 {
+                        writeln!(writer, "{}", line)?;
+                    }
+                    continue;
+                }
+                writeln!(writer, "{}", line)?;
+                rows += 1;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(rows)
+}
+
+fn remove_if_present(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn invalid(error: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(id: &str, worker: &str) -> JobReport {
+        JobReport {
+            id: id.to_string(),
+            worker: worker.to_string(),
+            elapsed_secs: 1.0,
+            mae: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_stale_claims_requeue_and_only_their_holder_releases_them() {
+        let root = std::env::temp_dir().join(format!("nematode-jobs-{}", std::process::id()));
+        let queue = JobQueue::open(&root).unwrap();
+        let job = Job::Sweep { args: Vec::new(), output: PathBuf::from("out.csv") };
+        queue.submit("a", &job).unwrap();
+
+        let (id, _) = queue.claim("w1").unwrap().unwrap();
+        assert!(queue.heartbeat(&id, "w1").unwrap());
+        assert!(!queue.heartbeat(&id, "w2").unwrap());
+        assert_eq!(queue.requeue_stale(Duration::from_secs(60)).unwrap(), 0);
+
+        // w1 looks dead; w2 takes the job over
+        assert_eq!(queue.requeue_stale(Duration::ZERO).unwrap(), 1);
+        queue.claim("w2").unwrap().unwrap();
+        assert!(!queue.heartbeat(&id, "w1").unwrap());
+
+        // w1 turns out to finish after all: its report lands, w2's claim stays
+        queue.finish(&report(&id, "w1")).unwrap();
+        assert_eq!(queue.status().unwrap(), QueueStatus { pending: 0, running: 1, done: 1, failed: 0 });
+        queue.finish(&report(&id, "w2")).unwrap();
+        assert!(queue.status().unwrap().is_settled());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -2,29 +2,85 @@
 //!
 //! Simulates a task queue with configurable thread pool sizing policies.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
 use std::time::{Duration, Instant};
 use telemetry_compute::ComputeTelemetry;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+mod counterfactual;
+mod harness;
+mod jobs;
+mod multi_pool;
+mod rayon_pool;
+mod real_pool;
+mod reload;
+mod reward;
+mod scenario;
+mod shadow;
+pub mod stats;
+mod timeline;
+mod watchdog;
+
+pub use counterfactual::{replay_duration, replay_workload, OfflineEval, OfflineReport};
+pub use harness::{
+    paired_differences, policy_from_spec, run_paired, run_repeated, PairedDifference, RunResult, RunSummary,
+};
+pub use jobs::{merge_datasets, Job, JobQueue, JobReport, QueueStatus};
+pub use multi_pool::{MultiOutputReflexPolicy, MultiPoolPolicy, MultiPoolSim};
+pub use rayon_pool::RayonPoolAdapter;
+pub use real_pool::RealThreadPool;
+pub use reload::ReloadableReflexPolicy;
+pub use reward::Reward;
+pub use scenario::Scenario;
+pub use shadow::{ShadowMetrics, DIVERGENCE_BUCKETS};
+pub use timeline::{write_decision_log, write_state_series, DecisionRecord, StateSample};
+pub use watchdog::{DensityScore, OodWatchdog};
 
 /// Simulated task
 #[derive(Debug, Clone)]
 pub struct Task {
     pub id: u64,
     pub work_us: u64,              // microseconds of work
+    pub remaining_us: u64,         // work left; less than work_us after preemption
+    pub priority: usize,           // 0 = highest
+    pub task_type: u32,            // tasks of one type share warm caches
     pub arrival_time: Instant,
+    pub deadline: Option<Instant>, // completion target, if any
     pub start_time: Option<Instant>,
 }
 
 /// Thread pool sizing decision
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PoolSizeDecision {
     pub n_workers: u32,
+    /// Admission rate in tasks/s when admission control is enabled; None admits everything
+    #[serde(default)]
+    pub admit_rate: Option<u32>,
 }
 
 /// Thread pool sizing policy trait
 pub trait PoolSizePolicy {
     fn decide(&mut self, telem: &ComputeTelemetry) -> PoolSizeDecision;
+
+    /// Normalized features the last decision was inferred from, for model-backed policies
+    fn last_features(&self) -> Option<[f32; ComputeTelemetry::FEATURE_COUNT]> {
+        None
+    }
+
+    /// What a shadow candidate would have decided in the last decision's place, for policies carrying one
+    fn shadow_decision(&self) -> Option<PoolSizeDecision> {
+        None
+    }
+
+    /// Whether the last decision came from an out-of-distribution fallback, for policies with a watchdog
+    fn ood_fallback(&self) -> bool {
+        false
+    }
 }
 
 /// Baseline static policy
@@ -44,147 +100,1249 @@ impl Default for BaselinePolicy {
     }
 }
 
+impl<P: PoolSizePolicy + ?Sized> PoolSizePolicy for Box<P> {
+    fn decide(&mut self, telem: &ComputeTelemetry) -> PoolSizeDecision {
+        (**self).decide(telem)
+    }
+
+    fn last_features(&self) -> Option<[f32; ComputeTelemetry::FEATURE_COUNT]> {
+        (**self).last_features()
+    }
+
+    fn shadow_decision(&self) -> Option<PoolSizeDecision> {
+        (**self).shadow_decision()
+    }
+
+    fn ood_fallback(&self) -> bool {
+        (**self).ood_fallback()
+    }
+}
+
 impl PoolSizePolicy for BaselinePolicy {
     fn decide(&mut self, _telem: &ComputeTelemetry) -> PoolSizeDecision {
         PoolSizeDecision {
             n_workers: self.n_workers,
+            admit_rate: None,
         }
     }
 }
 
+/// How a reflex's output maps to a pool size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputEncoding {
+    /// Output is the target worker count
+    Absolute,
+    /// Output is a signed change to the current pool
+    Delta,
+}
+
+/// The normalizer stored in a reflex's metadata, if it has one of compute-v1's width
+pub fn embedded_normalizer(reflex: &reflex_format::Reflex) -> Option<telemetry_compute::Normalizer> {
+    let embedded = reflex.metadata.normalizer.as_ref()?;
+    Some(telemetry_compute::Normalizer {
+        min: embedded.min.as_slice().try_into().ok()?,
+        max: embedded.max.as_slice().try_into().ok()?,
+    })
+}
+
 /// Reflex policy (loaded from .reflex file)
 pub struct ReflexPolicy {
     reflex: reflex_format::Reflex,
     normalizer: telemetry_compute::Normalizer,
+    encoding: OutputEncoding,
     last_decision: Option<PoolSizeDecision>,
-    last_decision_time: Option<Instant>,
+    last_decision_us: Option<u64>, // telemetry timestamp of last decision
+    last_features: Option<[f32; ComputeTelemetry::FEATURE_COUNT]>, // None while holding
     hold_time: Duration,
+    min_workers: u32,
+    max_workers: u32,
+    candidate: Option<(reflex_format::Reflex, telemetry_compute::Normalizer)>, // asked every decision, never applied
+    shadow_decision: Option<PoolSizeDecision>, // None while holding
+    watchdog: Option<(OodWatchdog, Box<dyn PoolSizePolicy + Send>)>, // checked before inference; the fallback decides while tripped
+    ood_fallback: bool,
+    audit: Option<audit_log::PolicyAudit>,
 }
 
 impl ReflexPolicy {
     pub fn load(reflex_path: &str, normalizer: telemetry_compute::Normalizer) -> std::io::Result<Self> {
         let bytes = std::fs::read(reflex_path)?;
         let reflex = reflex_format::Reflex::from_bytes(&bytes)?;
+        Ok(Self::new(reflex, normalizer))
+    }
 
-        Ok(Self {
+    pub fn new(reflex: reflex_format::Reflex, normalizer: telemetry_compute::Normalizer) -> Self {
+        Self {
             reflex,
             normalizer,
+            encoding: OutputEncoding::Absolute,
             last_decision: None,
-            last_decision_time: None,
+            last_decision_us: None,
+            last_features: None,
             hold_time: Duration::from_millis(500),
-        })
+            min_workers: 1,
+            max_workers: 64,
+            candidate: None,
+            shadow_decision: None,
+            watchdog: None,
+            ood_fallback: false,
+            audit: None,
+        }
+    }
+
+    pub fn reflex(&self) -> &reflex_format::Reflex {
+        &self.reflex
+    }
+
+    pub fn normalizer(&self) -> &telemetry_compute::Normalizer {
+        &self.normalizer
+    }
+
+    /// Run `reflex` fed by `normalizer` from the next decision on, keeping the hold state
+    pub(crate) fn replace_model(&mut self, reflex: reflex_format::Reflex, normalizer: telemetry_compute::Normalizer) {
+        if let Some(audit) = self.audit.as_mut() {
+            audit.set_reflex(&reflex);
+        }
+        self.reflex = reflex;
+        self.normalizer = normalizer;
+    }
+
+    /// Minimum time between decisions (default 500ms); zero re-decides every tick
+    pub fn with_hold_time(mut self, hold_time: Duration) -> Self {
+        self.hold_time = hold_time;
+        self
+    }
+
+    /// Clamp decided pool sizes to [min_workers, max_workers] (default [1, 64])
+    pub fn with_bounds(mut self, min_workers: u32, max_workers: u32) -> Self {
+        assert!(0 < min_workers && min_workers <= max_workers, "need 0 < min_workers <= max_workers");
+        self.min_workers = min_workers;
+        self.max_workers = max_workers;
+        self
+    }
+
+    /// Shadow `candidate`, fed by its own `normalizer`: decode its output like this policy's on every decision, but never apply it
+    ///
+    /// The candidate's decisions are read with `shadow_decision` and add up
+    /// in the simulator's `Metrics::shadow`.
+    pub fn with_candidate(mut self, candidate: reflex_format::Reflex, normalizer: telemetry_compute::Normalizer) -> Self {
+        self.candidate = Some((candidate, normalizer));
+        self
+    }
+
+    /// Check each normalized feature vector with `watchdog` before inference, deciding with `fallback` while it's tripped
+    ///
+    /// The fallback is only asked while the watchdog is tripped. Decisions
+    /// it makes and the watchdog's trips add up in the simulator's
+    /// `Metrics::ood_decisions` and `Metrics::ood_alerts`.
+    pub fn with_watchdog(mut self, watchdog: OodWatchdog, fallback: Box<dyn PoolSizePolicy + Send>) -> Self {
+        self.watchdog = Some((watchdog, fallback));
+        self
+    }
+
+    pub fn watchdog(&self) -> Option<&OodWatchdog> {
+        self.watchdog.as_ref().map(|(watchdog, _)| watchdog)
+    }
+
+    /// Log every decision not held over to `logger` as `policy_id`, with the model's hash
+    ///
+    /// Decisions an out-of-distribution fallback made are marked and have no raw outputs.
+    pub fn with_audit_log(mut self, logger: audit_log::AuditLogger, policy_id: impl Into<String>) -> Self {
+        self.audit = Some(audit_log::PolicyAudit::new(logger, policy_id, &self.reflex));
+        self
+    }
+
+    pub fn audit_log(&self) -> Option<&audit_log::AuditLogger> {
+        self.audit.as_ref().map(|audit| audit.logger())
+    }
+
+    /// Decode a model's outputs: n_workers or its delta, then an optional admission rate
+    fn decode(&self, outputs: &[f32], telem: &ComputeTelemetry) -> PoolSizeDecision {
+        let target = match self.encoding {
+            OutputEncoding::Absolute => outputs[0].round(),
+            OutputEncoding::Delta => {
+                let current = self.last_decision.map_or(telem.pool_size, |d| d.n_workers);
+                current as f32 + outputs[0].round()
+            }
+        };
+        let n_workers = target.clamp(self.min_workers as f32, self.max_workers as f32) as u32;
+
+        let admit_rate = outputs.get(1).map(|rate| rate.round().max(0.0) as u32);
+        PoolSizeDecision { n_workers, admit_rate }
+    }
+
+    /// Treat the output as a signed worker delta, saturating at `min_workers` and `max_workers`
+    ///
+    /// The delta applies to the policy's previous decision, or to the
+    /// telemetry's pool size before the first one, so shrinks still waiting
+    /// on busy workers are not applied twice.
+    pub fn with_delta_output(mut self, min_workers: u32, max_workers: u32) -> Self {
+        self.encoding = OutputEncoding::Delta;
+        self.with_bounds(min_workers, max_workers)
     }
 }
 
 impl PoolSizePolicy for ReflexPolicy {
     fn decide(&mut self, telem: &ComputeTelemetry) -> PoolSizeDecision {
-        let now = Instant::now();
-
-        // Hold time enforcement
-        if let Some(last_time) = self.last_decision_time {
-            if now.duration_since(last_time) < self.hold_time {
+        // Hold time enforcement, on the simulator's clock so virtual runs stay deterministic
+        if let Some(last_us) = self.last_decision_us {
+            if telem.timestamp_us.saturating_sub(last_us) < self.hold_time.as_micros() as u64 {
+                self.last_features = None;
+                self.shadow_decision = None;
                 return self.last_decision.unwrap();
             }
         }
 
-        // Normalize features
-        let features = telem.to_features();
-        let norm_features = self.normalizer.normalize(&features);
+        // Normalize features
+        let features = telem.to_features();
+        let norm_features = self.normalizer.normalize(&features);
+
+        // Out-of-distribution input goes to the fallback rather than the model
+        self.ood_fallback = false;
+        if let Some((watchdog, fallback)) = self.watchdog.as_mut() {
+            if watchdog.check(&norm_features) {
+                let decision = fallback.decide(telem);
+                self.ood_fallback = true;
+                self.shadow_decision = None;
+                self.last_decision = Some(decision);
+                self.last_decision_us = Some(telem.timestamp_us);
+                self.last_features = Some(norm_features);
+                if let Some(audit) = self.audit.as_mut() {
+                    let _ = audit.log(telem, &norm_features, &[], &decision, true);
+                }
+                return decision;
+            }
+        }
+
+        // Infer and decode, the candidate from the same telemetry and state
+        let decision = self.decode(&self.reflex.infer(&norm_features), telem);
+        // A failed write is counted by the logger and never blocks the decision
+        if let Some(audit) = self.audit.as_mut() {
+            let raw_outputs = self.reflex.raw_outputs(&norm_features);
+            let _ = audit.log(telem, &norm_features, &raw_outputs, &decision, false);
+        }
+        self.shadow_decision = self
+            .candidate
+            .as_ref()
+            .map(|(candidate, normalizer)| self.decode(&candidate.infer(&normalizer.normalize(&features)), telem));
+
+        self.last_decision = Some(decision);
+        self.last_decision_us = Some(telem.timestamp_us);
+        self.last_features = Some(norm_features);
+
+        decision
+    }
+
+    fn last_features(&self) -> Option<[f32; ComputeTelemetry::FEATURE_COUNT]> {
+        self.last_features
+    }
+
+    fn shadow_decision(&self) -> Option<PoolSizeDecision> {
+        self.shadow_decision
+    }
+
+    fn ood_fallback(&self) -> bool {
+        self.ood_fallback
+    }
+}
+
+/// Queueing-theory baseline: smallest pool meeting an M/M/c wait target
+///
+/// Treats the pool as an M/M/c queue with the measured arrival rate and mean
+/// service time, and picks the fewest workers whose Erlang C probability of
+/// a task having to wait is at most `target_wait_probability`.
+pub struct ErlangCPolicy {
+    target_wait_probability: f64,
+    min_workers: u32,
+    max_workers: u32,
+}
+
+impl ErlangCPolicy {
+    pub fn new(target_wait_probability: f64) -> Self {
+        assert!(
+            target_wait_probability > 0.0 && target_wait_probability < 1.0,
+            "target wait probability must be in (0, 1)"
+        );
+        Self {
+            target_wait_probability,
+            min_workers: 1,
+            max_workers: 64,
+        }
+    }
+
+    pub fn with_bounds(mut self, min_workers: u32, max_workers: u32) -> Self {
+        assert!(0 < min_workers && min_workers <= max_workers, "need 0 < min_workers <= max_workers");
+        self.min_workers = min_workers;
+        self.max_workers = max_workers;
+        self
+    }
+}
+
+impl Default for ErlangCPolicy {
+    fn default() -> Self {
+        Self::new(0.2)
+    }
+}
+
+impl PoolSizePolicy for ErlangCPolicy {
+    fn decide(&mut self, telem: &ComputeTelemetry) -> PoolSizeDecision {
+        let offered_load = telem.arrival_rate as f64 * telem.service_time_mean_us as f64 / 1e6;
+        let n_workers = (self.min_workers..=self.max_workers)
+            .find(|&c| erlang_c(c, offered_load) <= self.target_wait_probability)
+            .unwrap_or(self.max_workers);
+        PoolSizeDecision { n_workers, admit_rate: None }
+    }
+}
+
+/// Probability an arrival waits in an M/M/c queue with `servers` and
+/// `offered_load` erlangs (1.0 when the queue is unstable)
+pub fn erlang_c(servers: u32, offered_load: f64) -> f64 {
+    let c = servers as f64;
+    if offered_load <= 0.0 {
+        return 0.0;
+    }
+    if offered_load >= c {
+        return 1.0;
+    }
+    // Erlang B by recursion, then convert to Erlang C
+    let mut blocking = 1.0;
+    for k in 1..=servers {
+        blocking = offered_load * blocking / (k as f64 + offered_load * blocking);
+    }
+    c * blocking / (c - offered_load * (1.0 - blocking))
+}
+
+/// PID controller gains, in workers per unit of error
+#[derive(Debug, Clone, Copy)]
+pub struct PidGains {
+    pub kp: f64,
+    pub ki: f64,
+    pub kd: f64,
+}
+
+/// What a [`PidPolicy`] regulates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PidSetpoint {
+    /// Run-queue length, in tasks
+    QueueLength(f64),
+    /// Fraction of workers busy, in [0, 1]
+    Utilization(f64),
+}
+
+/// Control-theory baseline: PID regulation of queue length or utilization
+///
+/// The control output is added to a nominal pool size and clamped to
+/// [min_workers, max_workers]. Anti-windup is by conditional integration:
+/// the integral stops accumulating while the output is saturated in the
+/// direction the error is pushing.
+pub struct PidPolicy {
+    setpoint: PidSetpoint,
+    gains: PidGains,
+    nominal_workers: f64,
+    min_workers: u32,
+    max_workers: u32,
+    integral: f64,
+    last_error: Option<f64>,
+    last_us: Option<u64>,
+}
+
+impl PidPolicy {
+    pub fn new(setpoint: PidSetpoint, gains: PidGains) -> Self {
+        Self {
+            setpoint,
+            gains,
+            nominal_workers: 8.0,
+            min_workers: 1,
+            max_workers: 64,
+            integral: 0.0,
+            last_error: None,
+            last_us: None,
+        }
+    }
+
+    /// Hold the run queue near `tasks` (gains: 0.5 / 1.0 / 0.0)
+    pub fn queue_length(tasks: f64) -> Self {
+        Self::new(PidSetpoint::QueueLength(tasks), PidGains { kp: 0.5, ki: 1.0, kd: 0.0 })
+    }
+
+    /// Hold worker utilization near `fraction` (gains: 8.0 / 4.0 / 0.0)
+    pub fn utilization(fraction: f64) -> Self {
+        assert!(fraction > 0.0 && fraction <= 1.0, "utilization setpoint must be in (0, 1]");
+        Self::new(PidSetpoint::Utilization(fraction), PidGains { kp: 8.0, ki: 4.0, kd: 0.0 })
+    }
+
+    pub fn with_gains(mut self, gains: PidGains) -> Self {
+        self.gains = gains;
+        self
+    }
+
+    /// Pool size at zero control output (default: 8)
+    pub fn with_nominal(mut self, workers: u32) -> Self {
+        self.nominal_workers = workers as f64;
+        self
+    }
+
+    pub fn with_bounds(mut self, min_workers: u32, max_workers: u32) -> Self {
+        assert!(0 < min_workers && min_workers <= max_workers, "need 0 < min_workers <= max_workers");
+        self.min_workers = min_workers;
+        self.max_workers = max_workers;
+        self
+    }
+
+    /// Positive when more workers are needed
+    fn error(&self, telem: &ComputeTelemetry) -> f64 {
+        match self.setpoint {
+            PidSetpoint::QueueLength(tasks) => telem.runq_len as f64 - tasks,
+            PidSetpoint::Utilization(fraction) => telem.worker_util as f64 - fraction,
+        }
+    }
+}
+
+impl PoolSizePolicy for PidPolicy {
+    fn decide(&mut self, telem: &ComputeTelemetry) -> PoolSizeDecision {
+        let error = self.error(telem);
+        let dt = self
+            .last_us
+            .map_or(0.0, |last| telem.timestamp_us.saturating_sub(last) as f64 / 1e6);
+        let derivative = match self.last_error {
+            Some(last) if dt > 0.0 => (error - last) / dt,
+            _ => 0.0,
+        };
+        self.last_error = Some(error);
+        self.last_us = Some(telem.timestamp_us);
+
+        let (min, max) = (self.min_workers as f64, self.max_workers as f64);
+        let output = |integral: f64| {
+            self.nominal_workers + self.gains.kp * error + self.gains.ki * integral + self.gains.kd * derivative
+        };
+        let integral = self.integral + error * dt;
+        let saturated = (output(integral) > max && error > 0.0) || (output(integral) < min && error < 0.0);
+        if !saturated {
+            self.integral = integral;
+        }
+
+        PoolSizeDecision {
+            n_workers: output(self.integral).round().clamp(min, max) as u32,
+            admit_rate: None,
+        }
+    }
+}
+
+/// Online search baseline: perturb the pool and keep changes that lower p95
+///
+/// Alternates measurement windows: one holding the current pool size, then
+/// one trying a neighbour (`step` up or down). The neighbour is kept if its
+/// mean p95 task time beats the current size's by `min_improvement`, or if
+/// the run queue was growing and grew less under the neighbour (in overload
+/// p95 rises with the backlog whatever the pool size). The first `settle`
+/// fraction of each window is ignored while the pool adjusts.
+///
+/// Every comparison also yields a training label: the telemetry seen while
+/// holding the current size, paired with whichever size won.
+pub struct HillClimbPolicy {
+    current: u32,
+    step: u32,
+    min_workers: u32,
+    max_workers: u32,
+    window: Duration,
+    settle: f64,
+    min_improvement: f64,
+    direction_up: bool,
+    trial: Option<u32>,             // candidate under test, None while measuring current
+    window_start_us: Option<u64>,
+    window_runq_start: u32,
+    window_p95: Vec<f32>,
+    current_score: Option<(WindowScore, ComputeTelemetry)>, // score and telemetry at current size
+    labels: Vec<(ComputeTelemetry, u32)>,
+}
+
+impl HillClimbPolicy {
+    pub fn new(initial_workers: u32) -> Self {
+        Self {
+            current: initial_workers.max(1),
+            step: 1,
+            min_workers: 1,
+            max_workers: 64,
+            window: Duration::from_secs(2),
+            settle: 0.25,
+            min_improvement: 0.02,
+            direction_up: true,
+            trial: None,
+            window_start_us: None,
+            window_runq_start: 0,
+            window_p95: Vec::new(),
+            current_score: None,
+            labels: Vec::new(),
+        }
+    }
+
+    pub fn with_step(mut self, step: u32) -> Self {
+        assert!(step > 0, "step must be positive");
+        self.step = step;
+        self
+    }
+
+    pub fn with_bounds(mut self, min_workers: u32, max_workers: u32) -> Self {
+        assert!(0 < min_workers && min_workers <= max_workers, "need 0 < min_workers <= max_workers");
+        self.min_workers = min_workers;
+        self.max_workers = max_workers;
+        self.current = self.current.clamp(min_workers, max_workers);
+        self
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// (telemetry, winning n_workers) pairs from every completed comparison
+    pub fn labels(&self) -> &[(ComputeTelemetry, u32)] {
+        &self.labels
+    }
+
+    /// Next candidate in the current direction, turning around at the bounds
+    fn neighbour(&mut self) -> Option<u32> {
+        for _ in 0..2 {
+            let candidate = if self.direction_up {
+                self.current.saturating_add(self.step).min(self.max_workers)
+            } else {
+                self.current.saturating_sub(self.step).max(self.min_workers)
+            };
+            if candidate != self.current {
+                return Some(candidate);
+            }
+            self.direction_up = !self.direction_up;
+        }
+        None
+    }
+
+    /// Close the current measurement window
+    fn finish_window(&mut self, telem: &ComputeTelemetry) {
+        let score = WindowScore {
+            mean_p95: if self.window_p95.is_empty() {
+                f64::MAX
+            } else {
+                self.window_p95.iter().map(|&p| p as f64).sum::<f64>() / self.window_p95.len() as f64
+            },
+            runq_growth: telem.runq_len as i64 - self.window_runq_start as i64,
+        };
+        self.window_p95.clear();
+        self.window_runq_start = telem.runq_len;
+
+        match self.trial.take() {
+            None => {
+                self.current_score = Some((score, *telem));
+                self.trial = self.neighbour();
+            }
+            Some(candidate) => {
+                let (current_score, current_telem) = self.current_score.take().unwrap();
+                let drains_better = current_score.runq_growth > 0 && score.runq_growth < current_score.runq_growth;
+                if drains_better || score.mean_p95 < current_score.mean_p95 * (1.0 - self.min_improvement) {
+                    self.current = candidate;
+                } else {
+                    self.direction_up = !self.direction_up;
+                }
+                self.labels.push((current_telem, self.current));
+            }
+        }
+    }
+}
+
+/// What one [`HillClimbPolicy`] measurement window observed
+#[derive(Debug, Clone, Copy)]
+struct WindowScore {
+    mean_p95: f64,
+    runq_growth: i64,
+}
+
+impl PoolSizePolicy for HillClimbPolicy {
+    fn decide(&mut self, telem: &ComputeTelemetry) -> PoolSizeDecision {
+        let start = *self.window_start_us.get_or_insert_with(|| {
+            self.window_runq_start = telem.runq_len;
+            telem.timestamp_us
+        });
+        let elapsed = Duration::from_micros(telem.timestamp_us.saturating_sub(start));
+
+        if elapsed >= self.window {
+            self.finish_window(telem);
+            self.window_start_us = Some(telem.timestamp_us);
+        } else if elapsed.as_secs_f64() >= self.window.as_secs_f64() * self.settle && telem.task_time_p95_us > 0.0 {
+            self.window_p95.push(telem.task_time_p95_us);
+        }
+
+        PoolSizeDecision {
+            n_workers: self.trial.unwrap_or(self.current),
+            admit_rate: None,
+        }
+    }
+}
+
+/// Write hill-climbing labels as CSV: the compute-v1 features plus `optimal_n_workers`
+pub fn write_labels(labels: &[(ComputeTelemetry, u32)], path: impl AsRef<Path>) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{},optimal_n_workers", ComputeTelemetry::feature_names().join(","))?;
+    for (telem, n_workers) in labels {
+        let features: Vec<String> = telem.to_features().iter().map(|f| f.to_string()).collect();
+        writeln!(writer, "{},{}", features.join(","), n_workers)?;
+    }
+    writer.flush()
+}
+
+/// Wrapper limiting how fast any policy can resize the pool
+///
+/// Scale-up and scale-down each have their own cooldown (time since the last
+/// change) and maximum step, so a pool can grow quickly under load but shrink
+/// slowly. Times come from telemetry timestamps.
+pub struct CooldownPolicy<P: PoolSizePolicy> {
+    inner: P,
+    up_cooldown: Duration,
+    up_max_step: u32,
+    down_cooldown: Duration,
+    down_max_step: u32,
+    current: Option<u32>,
+    last_change_us: Option<u64>,
+}
+
+impl<P: PoolSizePolicy> CooldownPolicy<P> {
+    /// Defaults: scale up by up to 8 workers after 100ms, down by 1 after 5s
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            up_cooldown: Duration::from_millis(100),
+            up_max_step: 8,
+            down_cooldown: Duration::from_secs(5),
+            down_max_step: 1,
+            current: None,
+            last_change_us: None,
+        }
+    }
+
+    pub fn with_scale_up(mut self, cooldown: Duration, max_step: u32) -> Self {
+        assert!(max_step > 0, "scale-up step must be positive");
+        self.up_cooldown = cooldown;
+        self.up_max_step = max_step;
+        self
+    }
+
+    pub fn with_scale_down(mut self, cooldown: Duration, max_step: u32) -> Self {
+        assert!(max_step > 0, "scale-down step must be positive");
+        self.down_cooldown = cooldown;
+        self.down_max_step = max_step;
+        self
+    }
+}
+
+impl<P: PoolSizePolicy> PoolSizePolicy for CooldownPolicy<P> {
+    fn decide(&mut self, telem: &ComputeTelemetry) -> PoolSizeDecision {
+        let inner = self.inner.decide(telem);
+        let wanted = inner.n_workers;
+        let current = *self.current.get_or_insert(telem.pool_size.max(1));
+        let since_change = self
+            .last_change_us
+            .map_or(Duration::MAX, |last| Duration::from_micros(telem.timestamp_us.saturating_sub(last)));
+
+        let n_workers = if wanted > current && since_change >= self.up_cooldown {
+            wanted.min(current + self.up_max_step)
+        } else if wanted < current && since_change >= self.down_cooldown {
+            wanted.max(current.saturating_sub(self.down_max_step))
+        } else {
+            current
+        };
+
+        if n_workers != current {
+            self.current = Some(n_workers);
+            self.last_change_us = Some(telem.timestamp_us);
+        }
+        PoolSizeDecision {
+            n_workers,
+            admit_rate: inner.admit_rate,
+        }
+    }
+
+    fn last_features(&self) -> Option<[f32; ComputeTelemetry::FEATURE_COUNT]> {
+        self.inner.last_features()
+    }
+
+    fn shadow_decision(&self) -> Option<PoolSizeDecision> {
+        self.inner.shadow_decision()
+    }
+
+    fn ood_fallback(&self) -> bool {
+        self.inner.ood_fallback()
+    }
+}
+
+/// Metrics collector
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    pub task_times_us: Vec<u64>,
+    pub priority_task_times_us: Vec<Vec<u64>>, // per priority level
+    pub wait_times_us: Vec<u64>,    // arrival to first start, per completed task
+    pub service_times_us: Vec<u64>, // first start to completion, per completed task
+    pub preemptions: usize,
+    pub cold_starts: usize, // tasks that paid the warm-up penalty
+    pub shrink_latencies_us: Vec<u64>, // per retired worker: shrink decision to retirement
+    pub busy_worker_us: u64, // worker-time spent running tasks
+    pub idle_worker_us: u64, // worker-time spent held idle in the pool
+    pub lateness_us: Vec<u64>, // per task with a deadline; 0 when met
+    pub arrivals: usize,
+    pub shed_tasks: usize, // arrivals rejected by admission control
+    pub over_budget_decisions: usize, // decisions asking for more workers than memory allows
+    pub denied_workers: u64,          // summed workers refused by the memory budget
+    pub throughput_samples: Vec<f64>, // tasks/s
+    pub decision_changes: usize,
+    pub decisions: usize,
+    pub output_delta_sum: f64, // summed relative worker-count change per decision
+    pub flip_flops: usize,     // changes reverting the previous one within FLIP_FLOP_WINDOW
+    pub shadow: Option<ShadowMetrics>, // candidate decisions against applied ones, once a policy shadows one
+    pub ood_decisions: usize, // decisions an out-of-distribution fallback made
+    pub ood_alerts: usize,    // times a fallback took over from the model
+    last_ood: bool,
+    last_decision: Option<PoolSizeDecision>,
+    last_change: Option<(PoolSizeDecision, Instant)>, // decision replaced, when
+    decision_span: Option<(Instant, Instant)>,         // first and last decision
+}
+
+impl Metrics {
+    /// A change back to the previous decision within this window is a flip-flop
+    pub const FLIP_FLOP_WINDOW: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        Self {
+            task_times_us: Vec::new(),
+            priority_task_times_us: Vec::new(),
+            wait_times_us: Vec::new(),
+            service_times_us: Vec::new(),
+            preemptions: 0,
+            cold_starts: 0,
+            shrink_latencies_us: Vec::new(),
+            busy_worker_us: 0,
+            idle_worker_us: 0,
+            lateness_us: Vec::new(),
+            arrivals: 0,
+            shed_tasks: 0,
+            over_budget_decisions: 0,
+            denied_workers: 0,
+            throughput_samples: Vec::new(),
+            decision_changes: 0,
+            decisions: 0,
+            output_delta_sum: 0.0,
+            flip_flops: 0,
+            shadow: None,
+            ood_decisions: 0,
+            ood_alerts: 0,
+            last_ood: false,
+            last_decision: None,
+            last_change: None,
+            decision_span: None,
+        }
+    }
+
+    pub fn record_task_time(&mut self, time_us: u64) {
+        self.task_times_us.push(time_us);
+    }
+
+    /// Split a completed task's time into queue wait and service
+    ///
+    /// Service runs from the task's first start, so it includes time spent
+    /// requeued after preemption and any core contention.
+    pub fn record_latency_split(&mut self, wait_us: u64, service_us: u64) {
+        self.wait_times_us.push(wait_us);
+        self.service_times_us.push(service_us);
+    }
+
+    pub fn record_priority_task_time(&mut self, priority: usize, time_us: u64) {
+        if self.priority_task_times_us.len() <= priority {
+            self.priority_task_times_us.resize(priority + 1, Vec::new());
+        }
+        self.priority_task_times_us[priority].push(time_us);
+    }
+
+    /// Record how long a removed worker took to retire (0 if it was idle)
+    pub fn record_shrink_latency(&mut self, latency_us: u64) {
+        self.shrink_latencies_us.push(latency_us);
+    }
+
+    /// Charge `elapsed` to `busy` running and `idle` waiting workers
+    pub fn record_worker_time(&mut self, busy: usize, idle: usize, elapsed: Duration) {
+        let elapsed_us = elapsed.as_micros() as u64;
+        self.busy_worker_us += busy as u64 * elapsed_us;
+        self.idle_worker_us += idle as u64 * elapsed_us;
+    }
+
+    /// Energy spent by the pool's workers over the run, in joules
+    pub fn energy_joules(&self, model: EnergyModel) -> f64 {
+        (model.busy_watts * self.busy_worker_us as f64 + model.idle_watts * self.idle_worker_us as f64) / 1e6
+    }
+
+    /// Record how far past its deadline a task completed (0 if on time)
+    pub fn record_lateness(&mut self, lateness_us: u64) {
+        self.lateness_us.push(lateness_us);
+    }
+
+    pub fn record_arrival(&mut self) {
+        self.arrivals += 1;
+    }
+
+    pub fn record_shed(&mut self) {
+        self.shed_tasks += 1;
+    }
+
+    /// Fraction of arrivals shed by admission control
+    pub fn shed_fraction(&self) -> f64 {
+        if self.arrivals == 0 {
+            return 0.0;
+        }
+        self.shed_tasks as f64 / self.arrivals as f64
+    }
+
+    /// Record a decision that asked for `denied` workers beyond the memory budget
+    pub fn record_over_budget(&mut self, denied: u32) {
+        self.over_budget_decisions += 1;
+        self.denied_workers += denied as u64;
+    }
+
+    /// Fraction of decisions that exceeded the memory budget
+    pub fn over_budget_rate(&self) -> f64 {
+        if self.decisions == 0 {
+            return 0.0;
+        }
+        self.over_budget_decisions as f64 / self.decisions as f64
+    }
+
+    pub fn record_preemption(&mut self) {
+        self.preemptions += 1;
+    }
+
+    pub fn record_cold_start(&mut self) {
+        self.cold_starts += 1;
+    }
+
+    pub fn record_throughput(&mut self, tasks_per_sec: f64) {
+        self.throughput_samples.push(tasks_per_sec);
+    }
+
+    pub fn record_decision_change(&mut self) {
+        self.decision_changes += 1;
+    }
+
+    /// Record one policy decision made at `now`, tracking changes and stability
+    pub fn record_decision(&mut self, decision: PoolSizeDecision, now: Instant) {
+        self.decisions += 1;
+        self.decision_span = Some((self.decision_span.map_or(now, |(first, _)| first), now));
+
+        let last = self.last_decision.replace(decision);
+        if let Some(last) = last.filter(|last| *last != decision) {
+            if self.last_change.is_some_and(|(replaced, at)| {
+                replaced == decision && now.duration_since(at) <= Self::FLIP_FLOP_WINDOW
+            }) {
+                self.flip_flops += 1;
+            }
+            self.last_change = Some((last, now));
+            self.output_delta_sum += (decision.n_workers as f64 - last.n_workers as f64).abs()
+                / (last.n_workers as f64).max(1.0);
+            self.record_decision_change();
+        }
+    }
+
+    /// Record a shadow candidate's decision next to the `applied` one, if the policy made one
+    pub fn record_shadow(&mut self, applied: PoolSizeDecision, candidate: Option<PoolSizeDecision>) {
+        if let Some(candidate) = candidate {
+            self.shadow.get_or_insert_with(ShadowMetrics::new).record(applied, candidate);
+        }
+    }
+
+    /// Record whether an out-of-distribution fallback made the last decision
+    pub fn record_ood(&mut self, fallback: bool) {
+        if fallback {
+            self.ood_decisions += 1;
+            if !self.last_ood {
+                self.ood_alerts += 1;
+            }
+        }
+        self.last_ood = fallback;
+    }
+
+    /// Decision changes per minute of recorded decisions
+    pub fn changes_per_minute(&self) -> f64 {
+        let minutes = self
+            .decision_span
+            .map_or(0.0, |(first, last)| last.duration_since(first).as_secs_f64() / 60.0);
+        if minutes == 0.0 {
+            return 0.0;
+        }
+        self.decision_changes as f64 / minutes
+    }
+
+    /// Mean relative worker-count change per decision (0 for a constant policy)
+    pub fn mean_output_delta(&self) -> f64 {
+        if self.decisions == 0 {
+            return 0.0;
+        }
+        self.output_delta_sum / self.decisions as f64
+    }
+
+    pub fn p50_task_time(&self) -> f64 {
+        self.percentile(0.50)
+    }
+
+    pub fn p95_task_time(&self) -> f64 {
+        self.percentile(0.95)
+    }
+
+    pub fn p99_task_time(&self) -> f64 {
+        self.percentile(0.99)
+    }
+
+    /// Task time percentile for one priority level
+    pub fn priority_percentile(&self, priority: usize, p: f64) -> f64 {
+        self.priority_task_times_us
+            .get(priority)
+            .map_or(0.0, |times| percentile(times, p))
+    }
+
+    fn percentile(&self, p: f64) -> f64 {
+        percentile(&self.task_times_us, p)
+    }
+
+    /// Queue wait percentile: the part of task time more workers can fix
+    pub fn wait_percentile(&self, p: f64) -> f64 {
+        percentile(&self.wait_times_us, p)
+    }
+
+    /// Service time percentile: the part of task time set by the tasks themselves
+    pub fn service_percentile(&self, p: f64) -> f64 {
+        percentile(&self.service_times_us, p)
+    }
+
+    /// Fraction of tasks with a deadline that completed after it
+    pub fn deadline_miss_rate(&self) -> f64 {
+        if self.lateness_us.is_empty() {
+            return 0.0;
+        }
+        self.lateness_us.iter().filter(|&&l| l > 0).count() as f64 / self.lateness_us.len() as f64
+    }
+
+    /// Mean lateness over tasks with a deadline, counting on-time tasks as 0
+    pub fn mean_lateness(&self) -> f64 {
+        if self.lateness_us.is_empty() {
+            return 0.0;
+        }
+        self.lateness_us.iter().sum::<u64>() as f64 / self.lateness_us.len() as f64
+    }
+
+    /// Shrink latency percentile over retired workers
+    pub fn shrink_latency_percentile(&self, p: f64) -> f64 {
+        percentile(&self.shrink_latencies_us, p)
+    }
+
+    /// Lateness percentile over tasks with a deadline
+    pub fn lateness_percentile(&self, p: f64) -> f64 {
+        percentile(&self.lateness_us, p)
+    }
+
+    pub fn mean_throughput(&self) -> f64 {
+        if self.throughput_samples.is_empty() {
+            return 0.0;
+        }
+        self.throughput_samples.iter().sum::<f64>() / self.throughput_samples.len() as f64
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Median and 95th percentile of a telemetry window's values
+fn window_p50_p95(window: &VecDeque<(Instant, u64)>) -> (f32, f32) {
+    if window.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mut sorted: Vec<u64> = window.iter().map(|(_, t)| *t).collect();
+    sorted.sort_unstable();
+    let p50 = sorted[sorted.len() / 2] as f32;
+    let p95_idx = ((sorted.len() as f32 * 0.95) as usize).min(sorted.len() - 1);
+    (p50, sorted[p95_idx] as f32)
+}
+
+/// Pop entries older than `cutoff` off the front of a window kept in time order
+pub(crate) fn prune_window<T>(window: &mut VecDeque<T>, cutoff: Instant, time: impl Fn(&T) -> Instant) {
+    while window.front().is_some_and(|entry| time(entry) < cutoff) {
+        window.pop_front();
+    }
+}
+
+fn percentile(values: &[u64], p: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let idx = ((sorted.len() as f64) * p).floor() as usize;
+    sorted[idx.min(sorted.len() - 1)] as f64
+}
+
+/// Order in which workers take tasks from a priority level's queue
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueueDiscipline {
+    #[default]
+    Fifo,
+    /// Newest first: fresh arrivals stay fast during bursts while the backlog waits
+    Lifo,
+    /// Least remaining work first
+    ShortestJobFirst,
+}
+
+impl QueueDiscipline {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fifo" => Some(Self::Fifo),
+            "lifo" => Some(Self::Lifo),
+            "sjf" => Some(Self::ShortestJobFirst),
+            _ => None,
+        }
+    }
+
+    /// Numeric code reported in telemetry (`queue_discipline`)
+    pub fn code(self) -> u32 {
+        match self {
+            Self::Fifo => 0,
+            Self::Lifo => 1,
+            Self::ShortestJobFirst => 2,
+        }
+    }
+
+    /// Remove the next task to run from `queue`
+    fn take(self, queue: &mut VecDeque<Task>) -> Option<Task> {
+        match self {
+            Self::Fifo => queue.pop_front(),
+            Self::Lifo => queue.pop_back(),
+            Self::ShortestJobFirst => {
+                let shortest = queue.iter().enumerate().min_by_key(|(_, t)| t.remaining_us)?.0;
+                queue.remove(shortest)
+            }
+        }
+    }
+
+    /// Put a preempted task back where it will be taken next among equals
+    fn requeue(self, queue: &mut VecDeque<Task>, task: Task) {
+        match self {
+            Self::Lifo => queue.push_back(task),
+            Self::Fifo | Self::ShortestJobFirst => queue.push_front(task),
+        }
+    }
+}
+
+/// Physical CPU model: workers time-slice once runnable workers exceed hardware threads
+///
+/// With SMT enabled each core runs two hardware threads; a worker whose
+/// sibling thread is also busy runs at `smt_throughput` of full speed.
+#[derive(Debug, Clone, Copy)]
+pub struct CoreModel {
+    pub cores: u32,
+    pub timeslice_us: u64,
+    pub switch_cost_us: u64,         // wall-clock cost of each context switch
+    pub smt_throughput: Option<f64>, // per-sibling speed when a core's two threads are busy
+}
+
+impl CoreModel {
+    pub fn new(cores: u32) -> Self {
+        assert!(cores > 0, "at least one core required");
+        Self {
+            cores,
+            timeslice_us: 3_000,
+            switch_cost_us: 5,
+            smt_throughput: None,
+        }
+    }
+
+    /// Two hardware threads per core, each at `sibling_throughput` when both are busy
+    ///
+    /// E.g. 0.6 means a fully busy core does 1.2x the work of one thread, so
+    /// adding workers past the core count helps only up to that plateau.
+    pub fn with_smt(mut self, sibling_throughput: f64) -> Self {
+        assert!(
+            sibling_throughput > 0.0 && sibling_throughput <= 1.0,
+            "SMT sibling throughput must be in (0, 1]"
+        );
+        self.smt_throughput = Some(sibling_throughput);
+        self
+    }
 
-        // Infer
-        let outputs = self.reflex.infer(&norm_features);
+    /// Hardware threads available to workers
+    pub fn hardware_threads(&self) -> usize {
+        let threads_per_core = if self.smt_throughput.is_some() { 2 } else { 1 };
+        self.cores as usize * threads_per_core
+    }
 
-        // Decode output (single output: n_workers)
-        let n_workers = outputs[0].round().max(1.0).min(64.0) as u32;
+    /// Work done per unit time, in single-thread equivalents, with `busy` hardware threads in use
+    fn capacity(&self, busy: usize) -> f64 {
+        let cores = self.cores as usize;
+        match self.smt_throughput {
+            Some(sibling) if busy > cores => {
+                let paired_cores = busy - cores;
+                (cores - paired_cores) as f64 + 2.0 * sibling * paired_cores as f64
+            }
+            _ => busy.min(cores) as f64,
+        }
+    }
 
-        let decision = PoolSizeDecision { n_workers };
+    pub fn with_timeslice(mut self, timeslice_us: u64) -> Self {
+        assert!(timeslice_us > 0, "timeslice must be positive");
+        self.timeslice_us = timeslice_us;
+        self
+    }
 
-        self.last_decision = Some(decision);
-        self.last_decision_time = Some(now);
+    pub fn with_switch_cost(mut self, switch_cost_us: u64) -> Self {
+        self.switch_cost_us = switch_cost_us;
+        self
+    }
 
-        decision
+    /// Wall-clock service time and context switches for `work_us` of work
+    /// started while `runnable` workers (including this one) compete for cores
+    ///
+    /// The share of CPU is fixed at start, so later changes in contention
+    /// do not stretch or shrink a task already running.
+    pub fn service(&self, work_us: u64, runnable: usize) -> (u64, u64) {
+        let runnable = runnable.max(1);
+        let threads = self.hardware_threads();
+        let speed = self.capacity(runnable.min(threads)) / runnable as f64;
+        let stretched_us = (work_us as f64 / speed).round() as u64;
+        if runnable <= threads {
+            return (stretched_us, 1);
+        }
+        // Descheduled at the end of every timeslice but the last
+        let switches = 1 + work_us.div_ceil(self.timeslice_us).saturating_sub(1);
+        (stretched_us + switches * self.switch_cost_us, switches)
     }
 }
 
-/// Metrics collector
-#[derive(Debug, Clone)]
-pub struct Metrics {
-    pub task_times_us: Vec<u64>,
-    pub throughput_samples: Vec<f64>, // tasks/s
-    pub decision_changes: usize,
+/// Memory model: each worker reserves a fixed amount out of a total budget
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryModel {
+    pub per_worker_mb: f64,
+    pub budget_mb: f64,
 }
 
-impl Metrics {
-    pub fn new() -> Self {
-        Self {
-            task_times_us: Vec::new(),
-            throughput_samples: Vec::new(),
-            decision_changes: 0,
-        }
+impl MemoryModel {
+    pub fn new(per_worker_mb: f64, budget_mb: f64) -> Self {
+        assert!(per_worker_mb > 0.0, "per-worker memory must be positive");
+        Self { per_worker_mb, budget_mb }
     }
 
-    pub fn record_task_time(&mut self, time_us: u64) {
-        self.task_times_us.push(time_us);
+    /// Largest pool that fits in the budget (at least one worker)
+    pub fn max_workers(&self) -> u32 {
+        ((self.budget_mb / self.per_worker_mb).floor() as u32).max(1)
     }
 
-    pub fn record_throughput(&mut self, tasks_per_sec: f64) {
-        self.throughput_samples.push(tasks_per_sec);
+    /// Budget left with `workers` workers allocated (negative if over)
+    pub fn headroom_mb(&self, workers: usize) -> f64 {
+        self.budget_mb - workers as f64 * self.per_worker_mb
     }
+}
 
-    pub fn record_decision_change(&mut self) {
-        self.decision_changes += 1;
-    }
+/// Warm-cache model: a worker running a task type it has not run within
+/// `warm_for` pays `penalty_us` of extra work first
+///
+/// New workers start cold, so shrinking the pool and growing it back costs
+/// warm-ups that keeping marginal workers alive would have avoided.
+#[derive(Debug, Clone, Copy)]
+pub struct WarmCacheModel {
+    pub penalty_us: u64,
+    pub warm_for: Duration,
+}
 
-    pub fn p50_task_time(&self) -> f64 {
-        self.percentile(0.50)
+impl WarmCacheModel {
+    pub fn new(penalty_us: u64, warm_for: Duration) -> Self {
+        Self { penalty_us, warm_for }
     }
+}
 
-    pub fn p95_task_time(&self) -> f64 {
-        self.percentile(0.95)
+/// Energy model: a worker draws `busy_watts` while running a task and
+/// `idle_watts` while held idle in the pool
+///
+/// The idle draw is the holding cost of over-provisioning, which latency
+/// metrics alone never charge a policy for.
+#[derive(Debug, Clone, Copy)]
+pub struct EnergyModel {
+    pub idle_watts: f64,
+    pub busy_watts: f64,
+}
+
+impl EnergyModel {
+    pub fn new(idle_watts: f64, busy_watts: f64) -> Self {
+        assert!(idle_watts >= 0.0 && busy_watts >= 0.0, "power draw must be non-negative");
+        Self { idle_watts, busy_watts }
     }
+}
 
-    pub fn p99_task_time(&self) -> f64 {
-        self.percentile(0.99)
+impl Default for EnergyModel {
+    /// Roughly one core of an edge-class ARM SoC
+    fn default() -> Self {
+        Self::new(0.3, 1.5)
     }
+}
 
-    fn percentile(&self, p: f64) -> f64 {
-        if self.task_times_us.is_empty() {
-            return 0.0;
+/// Token bucket behind [`ThreadPoolSim::with_admission`]
+#[derive(Debug)]
+struct AdmissionControl {
+    burst: f64,
+    tokens: f64,
+    rate: Option<u32>, // tasks/s; None admits everything
+    last_refill: Instant,
+}
+
+impl AdmissionControl {
+    fn refill(&mut self, now: Instant) {
+        if let Some(rate) = self.rate {
+            let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+            self.tokens = (self.tokens + rate as f64 * elapsed).min(self.burst);
         }
-        let mut sorted = self.task_times_us.clone();
-        sorted.sort_unstable();
-        let idx = ((sorted.len() as f64) * p).floor() as usize;
-        sorted[idx.min(sorted.len() - 1)] as f64
+        self.last_refill = now;
     }
 
-    pub fn mean_throughput(&self) -> f64 {
-        if self.throughput_samples.is_empty() {
-            return 0.0;
+    fn set_rate(&mut self, rate: Option<u32>, now: Instant) {
+        self.refill(now);
+        if self.rate.is_some() && rate.is_none() {
+            self.tokens = self.burst;
         }
-        self.throughput_samples.iter().sum::<f64>() / self.throughput_samples.len() as f64
+        self.rate = rate;
     }
-}
 
-impl Default for Metrics {
-    fn default() -> Self {
-        Self::new()
+    /// Take a token for one arrival, if available
+    fn admit(&mut self, now: Instant) -> bool {
+        if self.rate.is_none() {
+            return true;
+        }
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
     }
 }
 
 /// Worker state
 #[derive(Debug)]
 struct Worker {
-    id: usize,
     current_task: Option<Task>,
     task_finish_time: Option<Instant>,
+    slowdown: f64, // wall-clock time per unit of work for the current task
+    last_ran: HashMap<u32, Instant>, // task type -> when this worker last started one
+    draining_since: Option<Instant>, // marked for removal once its task finishes
 }
 
 impl Worker {
-    fn new(id: usize) -> Self {
+    fn new() -> Self {
         Self {
-            id,
             current_task: None,
             task_finish_time: None,
+            slowdown: 1.0,
+            last_ran: HashMap::new(),
+            draining_since: None,
         }
     }
 
@@ -192,11 +1350,39 @@ impl Worker {
         self.current_task.is_none()
     }
 
-    fn assign(&mut self, mut task: Task, now: Instant) {
-        task.start_time = Some(now);
-        let finish_time = now + Duration::from_micros(task.work_us);
+    /// Charge `task` the warm-up penalty if its type is cold on this worker;
+    /// returns whether it was
+    fn warm_up(&mut self, task: &mut Task, now: Instant, cache: WarmCacheModel) -> bool {
+        let last = self.last_ran.insert(task.task_type, now);
+        let cold = last.is_none_or(|at| now.duration_since(at) > cache.warm_for);
+        if cold {
+            task.remaining_us += cache.penalty_us;
+        }
+        cold
+    }
+
+    /// Start `task`, returning the context switches it will incur
+    fn assign(&mut self, mut task: Task, now: Instant, cores: Option<CoreModel>, runnable: usize) -> u64 {
+        task.start_time.get_or_insert(now);
+        let (service_us, switches) = cores.map_or((task.remaining_us, 1), |c| c.service(task.remaining_us, runnable));
+        self.slowdown = service_us as f64 / task.remaining_us.max(1) as f64;
+        let finish_time = now + Duration::from_micros(service_us);
         self.current_task = Some(task);
         self.task_finish_time = Some(finish_time);
+        switches
+    }
+
+    /// Take the running task off the worker, keeping the work it has left
+    fn preempt(&mut self, now: Instant) -> Option<Task> {
+        let mut task = self.current_task.take()?;
+        let finish_time = self.task_finish_time.take()?;
+        let wall_left_us = finish_time.saturating_duration_since(now).as_micros() as f64;
+        task.remaining_us = (wall_left_us / self.slowdown).round() as u64;
+        Some(task)
+    }
+
+    fn running_priority(&self) -> Option<usize> {
+        self.current_task.as_ref().map(|t| t.priority)
     }
 
     fn check_complete(&mut self, now: Instant) -> Option<Task> {
@@ -213,96 +1399,587 @@ impl Worker {
 
 /// Thread pool simulator
 pub struct ThreadPoolSim<P: PoolSizePolicy> {
-    queue: VecDeque<Task>,
+    queues: Vec<VecDeque<Task>>, // one per priority level, index 0 = highest
+    discipline: QueueDiscipline,
+    preemption: bool,
+    blocked: HashMap<u64, (Task, usize)>, // task waiting on parents, parents left
+    children: HashMap<u64, Vec<u64>>,     // parent task -> blocked children
+    unfinished: HashSet<u64>,             // enqueued tasks that have not completed
     workers: Vec<Worker>,
     policy: P,
     metrics: Metrics,
     next_task_id: u64,
-    last_decision: Option<PoolSizeDecision>,
     completed_tasks: usize,
     last_throughput_measurement: Instant,
     arrival_count_window: VecDeque<(Instant, usize)>,
     completion_count_window: VecDeque<(Instant, usize)>,
-    task_times_window: Vec<u64>,
+    completed_work_window: VecDeque<(Instant, u64)>, // (completed, work_us)
+    switch_window: VecDeque<(Instant, u64)>, // (task start, context switches)
+    cores: Option<CoreModel>,
+    deadline: Option<Duration>, // default relative deadline for new tasks
+    memory: Option<MemoryModel>,
+    warm_cache: Option<WarmCacheModel>,
+    admission: Option<AdmissionControl>,
+    shed_window: VecDeque<Instant>,
+    task_times_window: VecDeque<(Instant, u64)>, // (completed, task time)
+    wait_times_window: VecDeque<(Instant, u64)>, // (completed, queue wait)
+    worker_time_until: Instant, // worker time is charged to metrics up to here
+    created_at: Instant,
+    virtual_now: Option<Instant>, // set once run_virtual takes over the clock
+    external_busy: usize,         // busy workers of other pools sharing the cores
+    decision_log: Option<Vec<DecisionRecord>>,
+    state_series: Option<StateSeries>,
+}
+
+/// Fixed-cadence sampler behind [`ThreadPoolSim::with_state_series`]
+struct StateSeries {
+    interval: Duration,
+    next_at: Instant,
+    samples: Vec<StateSample>,
 }
 
 impl<P: PoolSizePolicy> ThreadPoolSim<P> {
+    /// Policy tick cadence used by [`run_virtual`](Self::run_virtual)
+    pub const TICK_INTERVAL: Duration = Duration::from_millis(10);
+
     pub fn new(policy: P, initial_workers: u32) -> Self {
         let workers = (0..initial_workers)
-            .map(|i| Worker::new(i as usize))
+            .map(|_| Worker::new())
             .collect();
+        // One instant for both, so virtual runs measure identical throughput windows
+        let created_at = Instant::now();
 
         Self {
-            queue: VecDeque::new(),
+            queues: vec![VecDeque::new()],
+            discipline: QueueDiscipline::Fifo,
+            preemption: false,
+            blocked: HashMap::new(),
+            children: HashMap::new(),
+            unfinished: HashSet::new(),
             workers,
             policy,
             metrics: Metrics::new(),
             next_task_id: 0,
-            last_decision: None,
             completed_tasks: 0,
-            last_throughput_measurement: Instant::now(),
+            last_throughput_measurement: created_at,
             arrival_count_window: VecDeque::new(),
             completion_count_window: VecDeque::new(),
-            task_times_window: Vec::new(),
+            completed_work_window: VecDeque::new(),
+            switch_window: VecDeque::new(),
+            cores: None,
+            deadline: None,
+            memory: None,
+            warm_cache: None,
+            admission: None,
+            shed_window: VecDeque::new(),
+            task_times_window: VecDeque::new(),
+            wait_times_window: VecDeque::new(),
+            worker_time_until: created_at,
+            created_at,
+            virtual_now: None,
+            external_busy: 0,
+            decision_log: None,
+            state_series: None,
         }
     }
 
-    /// Enqueue a task
+    /// Record telemetry, model features and decision for every policy invocation
+    pub fn with_decision_log(mut self) -> Self {
+        self.decision_log = Some(Vec::new());
+        self
+    }
+
+    /// Sample pool size, busy workers and run queue length every `interval`
+    pub fn with_state_series(mut self, interval: Duration) -> Self {
+        assert!(interval > Duration::ZERO, "state sampling interval must be positive");
+        self.state_series = Some(StateSeries {
+            interval,
+            next_at: self.virtual_now.unwrap_or(self.created_at),
+            samples: Vec::new(),
+        });
+        self
+    }
+
+    /// Limit the pool to a physical core count (default: one core per worker)
+    ///
+    /// Running more workers than cores stretches service times by time
+    /// slicing and adds per-switch overhead.
+    pub fn with_cores(mut self, cores: CoreModel) -> Self {
+        self.cores = Some(cores);
+        self
+    }
+
+    /// Cap the pool at what fits in a memory budget (default: unlimited)
+    ///
+    /// Decisions beyond the budget are clamped and counted as over-budget in
+    /// metrics, the penalty an evaluation or oracle can weigh.
+    pub fn with_memory(mut self, memory: MemoryModel) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Charge warm-up work to tasks whose type is cold on their worker (default: no warm-up)
+    pub fn with_warm_cache(mut self, cache: WarmCacheModel) -> Self {
+        self.warm_cache = Some(cache);
+        self
+    }
+
+    /// Put a token bucket holding up to `burst` tasks in front of the queue
+    ///
+    /// The policy's `admit_rate` sets the refill rate on every tick; arrivals
+    /// finding the bucket empty are shed. Shed tasks count as complete for
+    /// any dependants.
+    pub fn with_admission(mut self, burst: u32) -> Self {
+        assert!(burst > 0, "admission burst must be positive");
+        self.admission = Some(AdmissionControl {
+            burst: burst as f64,
+            tokens: burst as f64,
+            rate: None,
+            last_refill: self.created_at,
+        });
+        self
+    }
+
+    /// Give every task a deadline `deadline` after it is enqueued unless the
+    /// task sets its own (default: no deadlines)
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Split the run queue into `levels` priority levels (index 0 = highest)
+    ///
+    /// Idle workers always take the highest-priority queued task first.
+    /// Tasks asking for a level past the last run at the last.
+    pub fn with_priority_levels(mut self, levels: usize) -> Self {
+        assert!(levels > 0, "at least one priority level required");
+        self.queues = (0..levels).map(|_| VecDeque::new()).collect();
+        self
+    }
+
+    /// Choose which queued task of a priority level runs next (default: FIFO)
+    pub fn with_discipline(mut self, discipline: QueueDiscipline) -> Self {
+        self.discipline = discipline;
+        self
+    }
+
+    /// Let queued tasks preempt running tasks of lower priority when no worker is idle
+    ///
+    /// A preempted task goes back to its level with its remaining work, placed
+    /// to run next among equals under the queue discipline.
+    pub fn with_preemption(mut self) -> Self {
+        self.preemption = true;
+        self
+    }
+
+    /// Current simulator time: wall clock, or the virtual clock under [`run_virtual`](Self::run_virtual)
+    fn now(&self) -> Instant {
+        self.virtual_now.unwrap_or_else(Instant::now)
+    }
+
+    /// Enqueue a task at the lowest priority level
     pub fn enqueue(&mut self, work_us: u64) {
+        self.enqueue_priority(work_us, self.queues.len() - 1);
+    }
+
+    /// Enqueue a task at a given priority level
+    pub fn enqueue_priority(&mut self, work_us: u64, priority: usize) {
+        self.enqueue_dependent(work_us, priority, &[]);
+    }
+
+    /// Enqueue a task that becomes runnable only once every task in `parents` has completed
+    ///
+    /// Returns the new task's ID for use as a parent of later tasks. Parents
+    /// that have already completed are ignored. Task times still count from
+    /// enqueue, so they include any wait on parents.
+    pub fn enqueue_dependent(&mut self, work_us: u64, priority: usize, parents: &[u64]) -> u64 {
+        self.record_arrival_event(1);
+        self.enqueue_task(work_us, priority, parents, None, 0)
+    }
+
+    /// Enqueue a task at the lowest priority level that is due `deadline` from now
+    pub fn enqueue_with_deadline(&mut self, work_us: u64, deadline: Duration) {
+        self.record_arrival_event(1);
+        self.enqueue_task(work_us, self.queues.len() - 1, &[], Some(deadline), 0);
+    }
+
+    /// Enqueue a task of type `task_type` at the lowest priority level
+    ///
+    /// Types only matter under [`with_warm_cache`](Self::with_warm_cache);
+    /// untyped tasks are type 0.
+    pub fn enqueue_typed(&mut self, work_us: u64, task_type: u32) {
+        self.record_arrival_event(1);
+        self.enqueue_task(work_us, self.queues.len() - 1, &[], None, task_type);
+    }
+
+    /// Enqueue several tasks arriving together at the lowest priority level,
+    /// e.g. the sub-tasks of one multi-task request
+    ///
+    /// The whole batch counts as one arrival event in the batch-size telemetry.
+    pub fn enqueue_batch(&mut self, work_us: &[u64]) {
+        if work_us.is_empty() {
+            return;
+        }
+        self.record_arrival_event(work_us.len());
+        for &work in work_us {
+            self.enqueue_task(work, self.queues.len() - 1, &[], None, 0);
+        }
+    }
+
+    /// Count `tasks` arriving together toward the arrival-rate and batch-size windows
+    fn record_arrival_event(&mut self, tasks: usize) {
+        let now = self.now();
+        self.arrival_count_window.push_back((now, tasks));
+    }
+
+    /// Enqueue with every option; `deadline` falls back to [`with_deadline`](Self::with_deadline)
+    ///
+    /// Callers record the arrival event with [`record_arrival_event`](Self::record_arrival_event).
+    fn enqueue_task(
+        &mut self,
+        work_us: u64,
+        priority: usize,
+        parents: &[u64],
+        deadline: Option<Duration>,
+        task_type: u32,
+    ) -> u64 {
+        let now = self.now();
+        let priority = priority.min(self.queues.len() - 1);
         let task = Task {
             id: self.next_task_id,
             work_us,
-            arrival_time: Instant::now(),
+            remaining_us: work_us,
+            priority,
+            task_type,
+            arrival_time: now,
+            deadline: deadline.or(self.deadline).map(|d| now + d),
             start_time: None,
         };
         self.next_task_id += 1;
-        self.queue.push_back(task);
 
-        // Track arrivals
-        let now = Instant::now();
-        self.arrival_count_window.push_back((now, 1));
+        // Track arrivals, including work that admission control then sheds
+        self.metrics.record_arrival();
+        if self.admission.as_mut().is_some_and(|a| !a.admit(now)) {
+            self.metrics.record_shed();
+            self.shed_window.push_back(now);
+            return task.id;
+        }
+        self.unfinished.insert(task.id);
+
+        let id = task.id;
+        let waiting_on: Vec<u64> = parents.iter().copied().filter(|p| self.unfinished.contains(p)).collect();
+        if waiting_on.is_empty() {
+            self.queues[priority].push_back(task);
+        } else {
+            for parent in &waiting_on {
+                self.children.entry(*parent).or_default().push(id);
+            }
+            self.blocked.insert(id, (task, waiting_on.len()));
+        }
+        id
+    }
+
+    /// Tasks enqueued but still waiting on parents
+    pub fn blocked_tasks(&self) -> usize {
+        self.blocked.len()
     }
 
-    /// Tick the simulator
-    pub fn tick(&mut self) {
-        let now = Instant::now();
+    /// Mark `id` complete and make children with no remaining parents runnable
+    fn release_children(&mut self, id: u64) {
+        self.unfinished.remove(&id);
+        for child in self.children.remove(&id).unwrap_or_default() {
+            let Some((_, parents_left)) = self.blocked.get_mut(&child) else {
+                continue;
+            };
+            *parents_left -= 1;
+            if *parents_left == 0 {
+                let (task, _) = self.blocked.remove(&child).unwrap();
+                self.queues[task.priority].push_back(task);
+            }
+        }
+    }
+
+    /// Drive the simulator on a virtual clock from `workload` for `duration`
+    ///
+    /// Jumps straight from one event to the next (task arrival, task
+    /// completion, policy tick every [`TICK_INTERVAL`](Self::TICK_INTERVAL))
+    /// instead of sleeping, so long runs finish in a fraction of their
+    /// simulated time and a seeded workload gives identical results. The
+    /// per-second throughput and telemetry windows roll over on policy ticks.
+    ///
+    /// Workload task dependencies are resolved against tasks this call
+    /// enqueues, so the workload should be the only source of tasks.
+    pub fn run_virtual(&mut self, workload: &mut dyn WorkloadGenerator, duration: Duration) {
+        let start = *self.virtual_now.get_or_insert(self.created_at);
+        let end = start + duration;
+        let first_id = self.next_task_id;
+        let mut next_arrival = schedule_arrival(workload, start);
+        let mut next_tick = start;
+
+        loop {
+            let next_completion = self.workers.iter().filter_map(|w| w.task_finish_time).min();
+            let now = [next_arrival.as_ref().map(|a| a.at), next_completion, Some(next_tick)]
+                .into_iter()
+                .flatten()
+                .min()
+                .unwrap();
+            if now > end {
+                break;
+            }
+            self.virtual_now = Some(now);
+
+            let mut arrivals = Vec::new();
+            while let Some(arrival) = next_arrival.take_if(|a| a.at == now) {
+                next_arrival = schedule_arrival(workload, arrival.at);
+                arrivals.push(arrival);
+            }
+            self.enqueue_arrivals(&arrivals, first_id);
+
+            if now == next_tick {
+                self.tick();
+                next_tick += Self::TICK_INTERVAL;
+            } else {
+                self.dispatch(now);
+            }
+        }
+        self.charge_worker_time(end);
+        self.virtual_now = Some(end);
+    }
+
+    /// Drive the simulator on the wall clock from `workload` for `duration`
+    ///
+    /// Same events as [`run_virtual`](Self::run_virtual), but sleeps until
+    /// each one: arrivals are enqueued when due and completions are retired
+    /// the moment the next worker finishes, rather than on the next policy
+    /// tick, so task times aren't rounded up to the tick interval. Policy
+    /// ticks keep the [`TICK_INTERVAL`](Self::TICK_INTERVAL) cadence; ticks
+    /// missed after a stall are skipped rather than run back to back.
+    pub fn run_real_time(&mut self, workload: &mut dyn WorkloadGenerator, duration: Duration) {
+        assert!(self.virtual_now.is_none(), "simulator is on the virtual clock");
+        let start = Instant::now();
+        let end = start + duration;
+        let first_id = self.next_task_id;
+        let mut next_arrival = schedule_arrival(workload, start);
+        let mut next_tick = start;
+
+        loop {
+            let next_completion = self.workers.iter().filter_map(|w| w.task_finish_time).min();
+            let due = [next_arrival.as_ref().map(|a| a.at), next_completion, Some(next_tick)]
+                .into_iter()
+                .flatten()
+                .min()
+                .unwrap();
+            if due > end {
+                break;
+            }
+            std::thread::sleep(due.saturating_duration_since(Instant::now()));
+            let now = Instant::now();
+
+            // Arrivals sharing a scheduled instant form one batch, even if several are late
+            while let Some(at) = next_arrival.as_ref().map(|a| a.at).filter(|at| *at <= now) {
+                let mut arrivals = Vec::new();
+                while let Some(arrival) = next_arrival.take_if(|a| a.at == at) {
+                    next_arrival = schedule_arrival(workload, arrival.at);
+                    arrivals.push(arrival);
+                }
+                self.enqueue_arrivals(&arrivals, first_id);
+            }
+
+            if now >= next_tick {
+                self.tick();
+                while next_tick <= now {
+                    next_tick += Self::TICK_INTERVAL;
+                }
+            } else {
+                self.dispatch(now);
+            }
+        }
+        std::thread::sleep(end.saturating_duration_since(Instant::now()));
+        self.charge_worker_time(Instant::now());
+    }
+
+    /// Enqueue workload arrivals due at the same instant as one batch, with
+    /// parents numbered from `first_id`
+    fn enqueue_arrivals(&mut self, arrivals: &[Arrival], first_id: u64) {
+        if arrivals.is_empty() {
+            return;
+        }
+        self.record_arrival_event(arrivals.len());
+        for arrival in arrivals {
+            let priority = arrival.priority.unwrap_or(self.queues.len() - 1);
+            let parents: Vec<u64> = arrival.parents.iter().map(|seq| first_id + seq).collect();
+            self.enqueue_task(arrival.work_us, priority, &parents, arrival.deadline, arrival.task_type);
+        }
+    }
+
+    /// Retire completed tasks and hand queued tasks to idle workers
+    fn dispatch(&mut self, now: Instant) {
+        self.charge_worker_time(now);
 
         // Check for completed tasks
+        let mut completed = Vec::new();
         for worker in &mut self.workers {
             if let Some(task) = worker.check_complete(now) {
                 let total_time = now.duration_since(task.arrival_time).as_micros() as u64;
+                let started = task.start_time.unwrap_or(task.arrival_time);
+                let wait_time = started.duration_since(task.arrival_time).as_micros() as u64;
                 self.metrics.record_task_time(total_time);
-                self.task_times_window.push(total_time);
+                self.metrics.record_latency_split(wait_time, total_time - wait_time);
+                self.metrics.record_priority_task_time(task.priority, total_time);
+                if let Some(deadline) = task.deadline {
+                    self.metrics.record_lateness(now.saturating_duration_since(deadline).as_micros() as u64);
+                }
+                self.task_times_window.push_back((now, total_time));
+                self.wait_times_window.push_back((now, wait_time));
                 self.completed_tasks += 1;
                 self.completion_count_window.push_back((now, 1));
+                self.completed_work_window.push_back((now, task.work_us));
+                completed.push(task.id);
             }
         }
+        for id in completed {
+            self.release_children(id);
+        }
+
+        // Draining workers retire as soon as their task is done
+        let metrics = &mut self.metrics;
+        self.workers.retain(|w| match w.draining_since {
+            Some(since) if w.is_idle() => {
+                metrics.record_shrink_latency(now.duration_since(since).as_micros() as u64);
+                false
+            }
+            _ => true,
+        });
 
-        // Assign tasks to idle workers
+        // Assign tasks to idle workers, highest priority first
+        let mut runnable = self.external_busy + self.busy_workers();
+        let discipline = self.discipline;
         for worker in &mut self.workers {
             if worker.is_idle() {
-                if let Some(task) = self.queue.pop_front() {
-                    worker.assign(task, now);
+                if let Some(mut task) = self.queues.iter_mut().find_map(|q| discipline.take(q)) {
+                    if self.warm_cache.is_some_and(|cache| worker.warm_up(&mut task, now, cache)) {
+                        self.metrics.record_cold_start();
+                    }
+                    runnable += 1;
+                    let switches = worker.assign(task, now, self.cores, runnable);
+                    self.switch_window.push_back((now, switches));
                 }
             }
         }
 
-        // Collect telemetry
-        let telem = self.collect_telemetry();
+        if self.preemption {
+            self.preempt(now);
+        }
+        self.sample_state(now);
+    }
+
+    /// Append a state sample if the sampling interval has elapsed
+    fn sample_state(&mut self, now: Instant) {
+        if self.state_series.as_ref().is_none_or(|series| now < series.next_at) {
+            return;
+        }
+
+        let sample = StateSample {
+            timestamp_us: now.duration_since(self.created_at).as_micros() as u64,
+            n_workers: self.active_workers(),
+            draining_workers: self.workers.len() - self.active_workers(),
+            busy_workers: self.busy_workers(),
+            runq_len: self.queues.iter().map(VecDeque::len).sum(),
+        };
+        let series = self.state_series.as_mut().unwrap();
+        series.samples.push(sample);
+        // Skip missed slots rather than bursting to catch up after a stall
+        while series.next_at <= now {
+            series.next_at += series.interval;
+        }
+    }
+
+    /// Displace the lowest-priority running tasks while higher-priority work waits
+    fn preempt(&mut self, now: Instant) {
+        while let Some(waiting) = self.queues.iter().position(|q| !q.is_empty()) {
+            let victim = self
+                .workers
+                .iter()
+                .enumerate()
+                .filter(|(_, w)| w.draining_since.is_none())
+                .filter_map(|(i, w)| w.running_priority().map(|p| (i, p)))
+                .filter(|&(_, p)| p > waiting)
+                .max_by_key(|&(_, p)| p);
+            let Some((victim, _)) = victim else {
+                return;
+            };
+
+            let runnable = self.external_busy + self.busy_workers();
+            let preempted = self.workers[victim].preempt(now).unwrap();
+            let mut task = self.discipline.take(&mut self.queues[waiting]).unwrap();
+            if self.warm_cache.is_some_and(|cache| self.workers[victim].warm_up(&mut task, now, cache)) {
+                self.metrics.record_cold_start();
+            }
+            let switches = self.workers[victim].assign(task, now, self.cores, runnable);
+            self.switch_window.push_back((now, switches));
+            self.discipline.requeue(&mut self.queues[preempted.priority], preempted);
+            self.metrics.record_preemption();
+        }
+    }
+
+    fn busy_workers(&self) -> usize {
+        self.workers.iter().filter(|w| !w.is_idle()).count()
+    }
+
+    /// Charge the time since the last charge to workers in their state since then
+    ///
+    /// Workers only change state in `dispatch` and on resize, which follows a
+    /// dispatch at the same instant, so charging at the top of `dispatch` is exact.
+    fn charge_worker_time(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.worker_time_until);
+        let busy = self.busy_workers();
+        self.metrics.record_worker_time(busy, self.workers.len() - busy, elapsed);
+        self.worker_time_until = self.worker_time_until.max(now);
+    }
 
-        // Get policy decision
+    /// Tick the simulator
+    pub fn tick(&mut self) {
+        let now = self.now();
+        let telem = self.observe(now);
         let decision = self.policy.decide(&telem);
+        self.apply_decision(now, telem, decision);
+    }
+
+    /// First half of a tick: dispatch, then collect telemetry for the policy
+    fn observe(&mut self, now: Instant) -> ComputeTelemetry {
+        self.dispatch(now);
+        self.collect_telemetry()
+    }
+
+    /// Second half of a tick: act on `decision` and roll the measurement windows
+    fn apply_decision(&mut self, now: Instant, telem: ComputeTelemetry, decision: PoolSizeDecision) {
+        if let Some(log) = self.decision_log.as_mut() {
+            log.push(DecisionRecord {
+                timestamp_us: now.duration_since(self.created_at).as_micros() as u64,
+                telemetry: telem,
+                features: self.policy.last_features(),
+                decision,
+            });
+        }
 
         // Track decision changes
-        if let Some(last) = self.last_decision {
-            if last.n_workers != decision.n_workers {
-                self.metrics.record_decision_change();
-            }
+        self.metrics.record_decision(decision, now);
+        self.metrics.record_shadow(decision, self.policy.shadow_decision());
+        self.metrics.record_ood(self.policy.ood_fallback());
+
+        if let Some(admission) = self.admission.as_mut() {
+            admission.set_rate(decision.admit_rate, now);
         }
-        self.last_decision = Some(decision);
 
         // Resize worker pool
-        self.resize_workers(decision.n_workers);
+        let mut target = decision.n_workers;
+        if let Some(max_workers) = self.memory.map(|m| m.max_workers()) {
+            if target > max_workers {
+                self.metrics.record_over_budget(target - max_workers);
+                target = max_workers;
+            }
+        }
+        self.resize_workers(target, now);
 
         // Measure throughput every second
         if now.duration_since(self.last_throughput_measurement) >= Duration::from_secs(1) {
@@ -315,56 +1992,86 @@ impl<P: PoolSizePolicy> ThreadPoolSim<P> {
 
         // Cleanup old window data
         let cutoff = now - Duration::from_secs(1);
-        self.arrival_count_window.retain(|(t, _)| *t >= cutoff);
-        self.completion_count_window.retain(|(t, _)| *t >= cutoff);
+        prune_window(&mut self.arrival_count_window, cutoff, |(t, _)| *t);
+        prune_window(&mut self.completion_count_window, cutoff, |(t, _)| *t);
+        prune_window(&mut self.completed_work_window, cutoff, |(t, _)| *t);
+        prune_window(&mut self.shed_window, cutoff, |t| *t);
+        prune_window(&mut self.switch_window, cutoff, |(t, _)| *t);
+        prune_window(&mut self.task_times_window, cutoff, |(t, _)| *t);
+        prune_window(&mut self.wait_times_window, cutoff, |(t, _)| *t);
     }
 
-    fn resize_workers(&mut self, target: u32) {
-        let current = self.workers.len();
+    fn resize_workers(&mut self, target: u32, now: Instant) {
+        let mut active = self.active_workers();
         let target = target as usize;
 
-        if target > current {
+        if target > active {
+            // Cancel pending drains first: those workers are already running
+            for worker in &mut self.workers {
+                if active == target {
+                    break;
+                }
+                if worker.draining_since.take().is_some() {
+                    active += 1;
+                }
+            }
             // Add workers
-            for i in current..target {
-                self.workers.push(Worker::new(i));
+            for _ in active..target {
+                self.workers.push(Worker::new());
             }
-        } else if target < current {
-            // Remove idle workers until we reach target
-            let mut to_remove = current - target;
+        } else if target < active {
+            // Remove idle workers at once
+            let mut to_remove = active - target;
+            let metrics = &mut self.metrics;
             self.workers.retain(|w| {
                 if to_remove > 0 && w.is_idle() {
                     to_remove -= 1;
+                    metrics.record_shrink_latency(0);
                     false
                 } else {
                     true
                 }
             });
+
+            // Busy workers drain instead, soonest-finishing first
+            let mut busy: Vec<&mut Worker> = self.workers.iter_mut().filter(|w| w.draining_since.is_none()).collect();
+            busy.sort_by_key(|w| w.task_finish_time);
+            for worker in busy.into_iter().take(to_remove) {
+                worker.draining_since = Some(now);
+            }
         }
     }
 
+    /// Workers not draining: the pool size in force
+    fn active_workers(&self) -> usize {
+        self.workers.iter().filter(|w| w.draining_since.is_none()).count()
+    }
+
     fn collect_telemetry(&self) -> ComputeTelemetry {
-        let now = Instant::now();
+        let now = self.now();
 
-        let runq_len = self.queue.len() as u32;
+        let runq_len = self.queues.iter().map(VecDeque::len).sum::<usize>() as u32;
 
         // Arrival rate
         let arrival_rate = self.arrival_count_window.iter().map(|(_, c)| *c).sum::<usize>() as f32;
 
-        // Completion rate
-        let completion_rate = self.completion_count_window.iter().map(|(_, c)| *c).sum::<usize>() as f32;
-
-        // Task time percentiles
-        let (task_time_p50, task_time_p95) = if self.task_times_window.is_empty() {
+        // Batch size stats (tasks per arrival event)
+        let events = self.arrival_count_window.len() as f32;
+        let (batch_size_mean, batch_size_var) = if events == 0.0 {
             (0.0, 0.0)
         } else {
-            let mut sorted = self.task_times_window.clone();
-            sorted.sort_unstable();
-            let p50 = sorted[sorted.len() / 2] as f32;
-            let p95_idx = ((sorted.len() as f32 * 0.95) as usize).min(sorted.len() - 1);
-            let p95 = sorted[p95_idx] as f32;
-            (p50, p95)
+            let mean = arrival_rate / events;
+            let var = self.arrival_count_window.iter().map(|(_, c)| (*c as f32 - mean).powi(2)).sum::<f32>() / events;
+            (mean, var)
         };
 
+        // Completion rate
+        let completion_rate = self.completion_count_window.iter().map(|(_, c)| *c).sum::<usize>() as f32;
+
+        // Task time and queue wait percentiles
+        let (task_time_p50, task_time_p95) = window_p50_p95(&self.task_times_window);
+        let (wait_time_p50, wait_time_p95) = window_p50_p95(&self.wait_times_window);
+
         // Worker utilization
         let busy_workers = self.workers.iter().filter(|w| !w.is_idle()).count();
         let worker_util = if self.workers.is_empty() {
@@ -377,67 +2084,308 @@ impl<P: PoolSizePolicy> ThreadPoolSim<P> {
         let idle_worker_count = (self.workers.len() - busy_workers) as u32;
 
         // Task size stats (from queue)
-        let task_sizes: Vec<f32> = self.queue.iter().map(|t| t.work_us as f32).collect();
+        let task_sizes: Vec<f32> = self.queues.iter().flatten().map(|t| t.work_us as f32).collect();
         let task_size_mean = if task_sizes.is_empty() {
             0.0
         } else {
             task_sizes.iter().sum::<f32>() / task_sizes.len() as f32
         };
 
-        let task_size_var = if task_sizes.is_empty() {
-            0.0
+        let task_size_var = if task_sizes.is_empty() {
+            0.0
+        } else {
+            let mean = task_size_mean;
+            task_sizes.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / task_sizes.len() as f32
+        };
+
+        // Context switches: one per task start plus time-slice switches
+        let ctx_switches_per_sec = self.switch_window.iter().map(|(_, n)| *n).sum::<u64>() as f32;
+
+        ComputeTelemetry {
+            timestamp_us: now.duration_since(self.created_at).as_micros() as u64,
+            runq_len,
+            arrival_rate,
+            completion_rate,
+            task_time_p50_us: task_time_p50,
+            task_time_p95_us: task_time_p95,
+            worker_util,
+            ctx_switches_per_sec,
+            task_size_mean,
+            task_size_var,
+            idle_worker_count,
+            memory_headroom_mb: self.memory.map_or(0.0, |m| m.headroom_mb(self.workers.len()) as f32),
+            pool_size: self.active_workers() as u32,
+            shed_rate: self.shed_window.len() as f32,
+            service_time_mean_us: if self.completed_work_window.is_empty() {
+                0.0
+            } else {
+                self.completed_work_window.iter().map(|(_, w)| *w).sum::<u64>() as f32
+                    / self.completed_work_window.len() as f32
+            },
+            batch_size_mean,
+            batch_size_var,
+            queue_discipline: self.discipline.code(),
+            oldest_wait_us: self
+                .queues
+                .iter()
+                .flatten()
+                .map(|t| now.duration_since(t.arrival_time).as_micros() as f32)
+                .fold(0.0, f32::max),
+            wait_time_p50_us: wait_time_p50,
+            wait_time_p95_us: wait_time_p95,
+        }
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// The sizing policy, e.g. to read back state it accumulated during a run
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Recorded decisions, if enabled with [`with_decision_log`](Self::with_decision_log)
+    pub fn decision_log(&self) -> Option<&[DecisionRecord]> {
+        self.decision_log.as_deref()
+    }
+
+    /// Sampled pool state, if enabled with [`with_state_series`](Self::with_state_series)
+    pub fn state_series(&self) -> Option<&[StateSample]> {
+        self.state_series.as_ref().map(|series| series.samples.as_slice())
+    }
+}
+
+/// A task from the workload, pinned to an absolute virtual time
+struct Arrival {
+    at: Instant,
+    work_us: u64,
+    priority: Option<usize>,
+    parents: Vec<u64>, // workload sequence numbers
+    deadline: Option<Duration>,
+    task_type: u32,
+}
+
+/// Next arrival from `workload`, timed from `after`
+fn schedule_arrival(workload: &mut dyn WorkloadGenerator, after: Instant) -> Option<Arrival> {
+    let (wait, work_us) = workload.next_task()?;
+    Some(Arrival {
+        at: after + wait,
+        work_us,
+        priority: workload.priority(),
+        parents: workload.parents().to_vec(),
+        deadline: workload.deadline(),
+        task_type: workload.task_type(),
+    })
+}
+
+/// Workload generator
+pub trait WorkloadGenerator {
+    fn next_task(&mut self) -> Option<(Duration, u64)>; // (wait_time, work_us)
+
+    /// Priority level of the task last returned by `next_task` (default: lowest level)
+    fn priority(&self) -> Option<usize> {
+        None
+    }
+
+    /// Tasks the last returned task depends on, as 0-based positions in
+    /// this workload's output sequence (default: independent)
+    fn parents(&self) -> &[u64] {
+        &[]
+    }
+
+    /// Deadline of the last returned task, relative to its arrival
+    /// (default: the simulator's [`with_deadline`](ThreadPoolSim::with_deadline))
+    fn deadline(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Type of the task last returned by `next_task` (default: 0)
+    fn task_type(&self) -> u32 {
+        0
+    }
+}
+
+/// Latency-critical tasks (priority 0) sharing the pool with batch work (priority 1)
+///
+/// Merges two generators by arrival time; use with
+/// [`ThreadPoolSim::with_priority_levels(2)`](ThreadPoolSim::with_priority_levels),
+/// or both share the one level.
+pub struct PriorityMixWorkload {
+    critical: Box<dyn WorkloadGenerator>,
+    batch: Box<dyn WorkloadGenerator>,
+    next_critical: Option<(Duration, u64)>, // (arrival since start, work_us)
+    next_batch: Option<(Duration, u64)>,
+    elapsed: Duration,
+    last_priority: Option<usize>,
+}
+
+impl PriorityMixWorkload {
+    pub fn new(mut critical: Box<dyn WorkloadGenerator>, mut batch: Box<dyn WorkloadGenerator>) -> Self {
+        let next_critical = critical.next_task();
+        let next_batch = batch.next_task();
+        Self {
+            critical,
+            batch,
+            next_critical,
+            next_batch,
+            elapsed: Duration::ZERO,
+            last_priority: None,
+        }
+    }
+}
+
+impl WorkloadGenerator for PriorityMixWorkload {
+    fn next_task(&mut self) -> Option<(Duration, u64)> {
+        let take_critical = match (self.next_critical, self.next_batch) {
+            (Some((critical_at, _)), Some((batch_at, _))) => critical_at <= batch_at,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => return None,
+        };
+
+        let (at, work_us) = if take_critical {
+            let next = self.next_critical.unwrap();
+            self.next_critical = self.critical.next_task().map(|(wait, work)| (next.0 + wait, work));
+            self.last_priority = Some(0);
+            next
         } else {
-            let mean = task_size_mean;
-            task_sizes.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / task_sizes.len() as f32
+            let next = self.next_batch.unwrap();
+            self.next_batch = self.batch.next_task().map(|(wait, work)| (next.0 + wait, work));
+            self.last_priority = Some(1);
+            next
         };
 
-        // Context switches (estimate: worker count changes + task switches)
-        let ctx_switches_per_sec = (self.workers.len() * 10) as f32; // Placeholder
+        let wait = at - self.elapsed;
+        self.elapsed = at;
+        Some((wait, work_us))
+    }
 
-        ComputeTelemetry {
-            timestamp_us: now.elapsed().as_micros() as u64,
-            runq_len,
-            arrival_rate,
-            completion_rate,
-            task_time_p50_us: task_time_p50,
-            task_time_p95_us: task_time_p95,
-            worker_util,
-            ctx_switches_per_sec,
-            task_size_mean,
-            task_size_var,
-            idle_worker_count,
+    fn priority(&self) -> Option<usize> {
+        self.last_priority
+    }
+}
+
+/// Tags another generator's tasks with types drawn uniformly from `0..types`,
+/// for use with [`ThreadPoolSim::with_warm_cache`]
+pub struct MixedTypeWorkload {
+    inner: Box<dyn WorkloadGenerator>,
+    types: u32,
+    last_type: u32,
+    rng: StdRng,
+}
+
+impl MixedTypeWorkload {
+    pub fn new(inner: Box<dyn WorkloadGenerator>, types: u32) -> Self {
+        assert!(types > 0, "at least one task type required");
+        Self {
+            inner,
+            types,
+            last_type: 0,
+            rng: StdRng::from_entropy(),
         }
     }
 
-    pub fn metrics(&self) -> &Metrics {
-        &self.metrics
+    /// Seed type assignment for a reproducible run
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
     }
 }
 
-/// Workload generator
-pub trait WorkloadGenerator {
-    fn next_task(&mut self) -> Option<(Duration, u64)>; // (wait_time, work_us)
+impl WorkloadGenerator for MixedTypeWorkload {
+    fn next_task(&mut self) -> Option<(Duration, u64)> {
+        let task = self.inner.next_task()?;
+        self.last_type = self.rng.gen_range(0..self.types);
+        Some(task)
+    }
+
+    fn priority(&self) -> Option<usize> {
+        self.inner.priority()
+    }
+
+    fn parents(&self) -> &[u64] {
+        self.inner.parents()
+    }
+
+    fn deadline(&self) -> Option<Duration> {
+        self.inner.deadline()
+    }
+
+    fn task_type(&self) -> u32 {
+        self.last_type
+    }
+}
+
+/// Task work distribution
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkDistribution {
+    Constant(u64),
+    Uniform { min: u64, max: u64 },
+    /// Lognormal parameterized by its median and log-space sigma
+    LogNormal { median: f64, sigma: f64 },
+    /// Bounded Pareto with minimum `scale`, tail index `shape`, truncated at `cap`
+    Pareto { scale: f64, shape: f64, cap: u64 },
+}
+
+impl WorkDistribution {
+    /// Draw a task's work in µs (at least 1)
+    pub fn sample(&self, rng: &mut impl Rng) -> u64 {
+        match *self {
+            WorkDistribution::Constant(work_us) => work_us,
+            WorkDistribution::Uniform { min, max } => rng.gen_range(min..=max),
+            WorkDistribution::LogNormal { median, sigma } => {
+                let z = standard_normal(rng);
+                (median * (sigma * z).exp()).round().max(1.0) as u64
+            }
+            WorkDistribution::Pareto { scale, shape, cap } => {
+                let u: f64 = 1.0 - rng.gen::<f64>();
+                let work_us = scale / u.powf(1.0 / shape);
+                (work_us.round() as u64).clamp(1, cap)
+            }
+        }
+    }
+}
+
+/// Box-Muller standard normal sample
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
 }
 
 /// Steady Poisson workload
 pub struct SteadyWorkload {
     rate_per_sec: f64,
-    task_work_us: u64,
+    task_work: WorkDistribution,
     duration: Duration,
     elapsed: Duration,
-    rng: rand::rngs::ThreadRng,
+    rng: StdRng,
 }
 
 impl SteadyWorkload {
     pub fn new(rate_per_sec: f64, task_work_us: u64, duration: Duration) -> Self {
         Self {
             rate_per_sec,
-            task_work_us,
+            task_work: WorkDistribution::Constant(task_work_us),
             duration,
             elapsed: Duration::ZERO,
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
         }
     }
+
+    /// Draw task work from `dist` instead of a constant size
+    pub fn with_work_distribution(mut self, dist: WorkDistribution) -> Self {
+        self.task_work = dist;
+        self
+    }
+
+    /// Seed arrivals and task sizes for a reproducible run
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
 }
 
 impl WorkloadGenerator for SteadyWorkload {
@@ -453,7 +2401,182 @@ impl WorkloadGenerator for SteadyWorkload {
         let wait = Duration::from_secs_f64(wait_s);
 
         self.elapsed += wait;
-        Some((wait, self.task_work_us))
+        Some((wait, self.task_work.sample(&mut self.rng)))
+    }
+}
+
+/// Poisson requests that each bring a batch of tasks at once, as an RPC
+/// server handling multi-task requests sees
+///
+/// Batch sizes are uniform in `1..=max_batch`; a batch's tasks share one
+/// arrival instant.
+pub struct BatchWorkload {
+    request_rate: f64,
+    max_batch: u32,
+    task_work: WorkDistribution,
+    duration: Duration,
+    elapsed: Duration,
+    batch_left: u32,
+    rng: StdRng,
+}
+
+impl BatchWorkload {
+    pub fn new(request_rate: f64, max_batch: u32, task_work_us: u64, duration: Duration) -> Self {
+        assert!(max_batch > 0, "batches hold at least one task");
+        Self {
+            request_rate,
+            max_batch,
+            task_work: WorkDistribution::Constant(task_work_us),
+            duration,
+            elapsed: Duration::ZERO,
+            batch_left: 0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Draw task work from `dist` instead of a constant size
+    pub fn with_work_distribution(mut self, dist: WorkDistribution) -> Self {
+        self.task_work = dist;
+        self
+    }
+
+    /// Seed arrivals and batch sizes for a reproducible run
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl WorkloadGenerator for BatchWorkload {
+    fn next_task(&mut self) -> Option<(Duration, u64)> {
+        if self.batch_left > 0 {
+            self.batch_left -= 1;
+            return Some((Duration::ZERO, self.task_work.sample(&mut self.rng)));
+        }
+        if self.elapsed >= self.duration {
+            return None;
+        }
+
+        let u: f64 = self.rng.gen();
+        let wait = Duration::from_secs_f64(-u.ln() / self.request_rate);
+        self.elapsed += wait;
+        self.batch_left = self.rng.gen_range(1..=self.max_batch) - 1;
+        Some((wait, self.task_work.sample(&mut self.rng)))
+    }
+}
+
+/// Linear rate ramp
+///
+/// Poisson arrivals whose rate moves linearly from `from_rate` to `to_rate`
+/// over the workload's duration.
+pub struct RampWorkload {
+    from_rate: f64,
+    to_rate: f64,
+    task_work: WorkDistribution,
+    duration: Duration,
+    elapsed: Duration,
+    rng: StdRng,
+}
+
+impl RampWorkload {
+    pub fn new(from_rate: f64, to_rate: f64, task_work_us: u64, duration: Duration) -> Self {
+        Self {
+            from_rate,
+            to_rate,
+            task_work: WorkDistribution::Constant(task_work_us),
+            duration,
+            elapsed: Duration::ZERO,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Draw task work from `dist` instead of a constant size
+    pub fn with_work_distribution(mut self, dist: WorkDistribution) -> Self {
+        self.task_work = dist;
+        self
+    }
+
+    /// Seed arrivals and task sizes for a reproducible run
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    fn current_rate(&self) -> f64 {
+        let progress = (self.elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0);
+        self.from_rate + (self.to_rate - self.from_rate) * progress
+    }
+}
+
+impl WorkloadGenerator for RampWorkload {
+    fn next_task(&mut self) -> Option<(Duration, u64)> {
+        if self.elapsed >= self.duration {
+            return None;
+        }
+
+        let u: f64 = self.rng.gen();
+        let wait = Duration::from_secs_f64(-u.ln() / self.current_rate());
+        self.elapsed += wait;
+        Some((wait, self.task_work.sample(&mut self.rng)))
+    }
+}
+
+/// Periodic load
+///
+/// Poisson arrivals whose rate follows `mean_rate + amplitude·sin(2πt/period)`,
+/// e.g. a compressed diurnal cycle.
+pub struct SinusoidalWorkload {
+    mean_rate: f64,
+    amplitude: f64,
+    period: Duration,
+    task_work: WorkDistribution,
+    duration: Duration,
+    elapsed: Duration,
+    rng: StdRng,
+}
+
+impl SinusoidalWorkload {
+    pub fn new(mean_rate: f64, amplitude: f64, period: Duration, task_work_us: u64, duration: Duration) -> Self {
+        assert!(amplitude < mean_rate, "amplitude must keep the rate positive");
+        Self {
+            mean_rate,
+            amplitude,
+            period,
+            task_work: WorkDistribution::Constant(task_work_us),
+            duration,
+            elapsed: Duration::ZERO,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Draw task work from `dist` instead of a constant size
+    pub fn with_work_distribution(mut self, dist: WorkDistribution) -> Self {
+        self.task_work = dist;
+        self
+    }
+
+    /// Seed arrivals and task sizes for a reproducible run
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    fn current_rate(&self) -> f64 {
+        let phase = self.elapsed.as_secs_f64() / self.period.as_secs_f64();
+        self.mean_rate + self.amplitude * (std::f64::consts::TAU * phase).sin()
+    }
+}
+
+impl WorkloadGenerator for SinusoidalWorkload {
+    fn next_task(&mut self) -> Option<(Duration, u64)> {
+        if self.elapsed >= self.duration {
+            return None;
+        }
+
+        let u: f64 = self.rng.gen();
+        let wait = Duration::from_secs_f64(-u.ln() / self.current_rate());
+        self.elapsed += wait;
+        Some((wait, self.task_work.sample(&mut self.rng)))
     }
 }
 
@@ -461,11 +2584,11 @@ impl WorkloadGenerator for SteadyWorkload {
 pub struct BurstyWorkload {
     high_rate: f64,
     low_rate: f64,
-    task_work_us: u64,
+    task_work: WorkDistribution,
     period: Duration,
     duration: Duration,
     elapsed: Duration,
-    rng: rand::rngs::ThreadRng,
+    rng: StdRng,
 }
 
 impl BurstyWorkload {
@@ -479,14 +2602,26 @@ impl BurstyWorkload {
         Self {
             high_rate,
             low_rate,
-            task_work_us,
+            task_work: WorkDistribution::Constant(task_work_us),
             period,
             duration,
             elapsed: Duration::ZERO,
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
         }
     }
 
+    /// Draw task work from `dist` instead of a constant size
+    pub fn with_work_distribution(mut self, dist: WorkDistribution) -> Self {
+        self.task_work = dist;
+        self
+    }
+
+    /// Seed arrivals and task sizes for a reproducible run
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
     fn current_rate(&self) -> f64 {
         let phase = self.elapsed.as_secs_f64() % (self.period.as_secs_f64() * 2.0);
         if phase < self.period.as_secs_f64() {
@@ -509,7 +2644,7 @@ impl WorkloadGenerator for BurstyWorkload {
         let wait = Duration::from_secs_f64(wait_s);
 
         self.elapsed += wait;
-        Some((wait, self.task_work_us))
+        Some((wait, self.task_work.sample(&mut self.rng)))
     }
 }
 
@@ -519,7 +2654,7 @@ pub struct AdversarialWorkload {
     work_range_us: (u64, u64),
     duration: Duration,
     elapsed: Duration,
-    rng: rand::rngs::ThreadRng,
+    rng: StdRng,
 }
 
 impl AdversarialWorkload {
@@ -533,9 +2668,15 @@ impl AdversarialWorkload {
             work_range_us,
             duration,
             elapsed: Duration::ZERO,
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
         }
     }
+
+    /// Seed arrivals and task sizes for a reproducible run
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
 }
 
 impl WorkloadGenerator for AdversarialWorkload {
@@ -559,3 +2700,410 @@ impl WorkloadGenerator for AdversarialWorkload {
         Some((wait, work_us))
     }
 }
+
+/// Dependency structure of each job in a [`DagWorkload`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DagPattern {
+    /// Fork task, `width` parallel tasks, then a join task waiting on all of them
+    ForkJoin { width: usize },
+    /// Chain of `stages` tasks, each waiting on the previous one
+    Pipeline { stages: usize },
+}
+
+/// Poisson-arriving jobs made of dependent tasks
+///
+/// All of a job's tasks are emitted when the job arrives; the simulator
+/// holds each one back until its parents finish. Only
+/// [`ThreadPoolSim::run_virtual`] resolves dependencies.
+pub struct DagWorkload {
+    pattern: DagPattern,
+    job_rate: f64,
+    task_work: WorkDistribution,
+    duration: Duration,
+    elapsed: Duration,
+    rng: StdRng,
+    pending: VecDeque<Vec<u64>>, // parents of the current job's remaining tasks
+    next_seq: u64,
+    last_parents: Vec<u64>,
+}
+
+impl DagWorkload {
+    pub fn new(pattern: DagPattern, job_rate: f64, task_work_us: u64, duration: Duration) -> Self {
+        match pattern {
+            DagPattern::ForkJoin { width } => assert!(width > 0, "fork-join width must be positive"),
+            DagPattern::Pipeline { stages } => assert!(stages > 0, "pipeline needs at least one stage"),
+        }
+        Self {
+            pattern,
+            job_rate,
+            task_work: WorkDistribution::Constant(task_work_us),
+            duration,
+            elapsed: Duration::ZERO,
+            rng: StdRng::from_entropy(),
+            pending: VecDeque::new(),
+            next_seq: 0,
+            last_parents: Vec::new(),
+        }
+    }
+
+    /// Draw task work from `dist` instead of a constant size
+    pub fn with_work_distribution(mut self, dist: WorkDistribution) -> Self {
+        self.task_work = dist;
+        self
+    }
+
+    /// Seed job arrivals for a reproducible run
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Parents of each task in a job whose first task has sequence number `first`
+    fn job(&self, first: u64) -> VecDeque<Vec<u64>> {
+        match self.pattern {
+            DagPattern::ForkJoin { width } => {
+                let fork = first;
+                let branches = (1..=width as u64).map(|i| first + i);
+                std::iter::once(Vec::new())
+                    .chain(branches.clone().map(|_| vec![fork]))
+                    .chain(std::iter::once(branches.collect()))
+                    .collect()
+            }
+            DagPattern::Pipeline { stages } => (0..stages as u64)
+                .map(|i| if i == 0 { Vec::new() } else { vec![first + i - 1] })
+                .collect(),
+        }
+    }
+}
+
+impl WorkloadGenerator for DagWorkload {
+    fn next_task(&mut self) -> Option<(Duration, u64)> {
+        let mut wait = Duration::ZERO;
+        if self.pending.is_empty() {
+            if self.elapsed >= self.duration {
+                return None;
+            }
+            let u: f64 = self.rng.gen();
+            wait = Duration::from_secs_f64(-u.ln() / self.job_rate);
+            self.elapsed += wait;
+            self.pending = self.job(self.next_seq);
+        }
+
+        self.last_parents = self.pending.pop_front().unwrap();
+        self.next_seq += 1;
+        Some((wait, self.task_work.sample(&mut self.rng)))
+    }
+
+    fn parents(&self) -> &[u64] {
+        &self.last_parents
+    }
+}
+
+/// Replay of recorded task arrivals, e.g. from production task logs
+///
+/// Offsets are from the start of the trace, so the first task waits its own
+/// offset; rows are replayed in offset order.
+#[derive(Debug, Clone)]
+pub struct TraceWorkload {
+    records: Vec<(u64, u64)>, // (arrival_offset_us, work_us)
+    next_idx: usize,
+    last_offset_us: u64,
+}
+
+/// One row of a compute trace CSV file
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct TraceRecord {
+    arrival_offset_us: u64,
+    work_us: u64,
+}
+
+impl TraceWorkload {
+    pub fn new(mut records: Vec<(u64, u64)>) -> Self {
+        records.sort_by_key(|&(offset, _)| offset);
+        Self {
+            records,
+            next_idx: 0,
+            last_offset_us: 0,
+        }
+    }
+
+    /// Load a trace from CSV with an `arrival_offset_us,work_us` header
+    pub fn from_csv(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut reader = csv::Reader::from_path(path)?;
+        let mut records = Vec::new();
+        for row in reader.deserialize() {
+            let record: TraceRecord = row?;
+            records.push((record.arrival_offset_us, record.work_us));
+        }
+        Ok(Self::new(records))
+    }
+
+    /// Offset of the last arrival
+    pub fn span(&self) -> Duration {
+        Duration::from_micros(self.records.last().map_or(0, |&(offset, _)| offset))
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+impl WorkloadGenerator for TraceWorkload {
+    fn next_task(&mut self) -> Option<(Duration, u64)> {
+        let &(offset_us, work_us) = self.records.get(self.next_idx)?;
+        self.next_idx += 1;
+
+        let wait_us = offset_us - self.last_offset_us;
+        self.last_offset_us = offset_us;
+        Some((Duration::from_micros(wait_us), work_us))
+    }
+}
+
+/// Workload phases played back to back
+///
+/// Each phase runs until its generator is exhausted, then the next starts.
+pub struct SequenceWorkload {
+    phases: VecDeque<Box<dyn WorkloadGenerator>>,
+}
+
+impl SequenceWorkload {
+    pub fn new(phases: Vec<Box<dyn WorkloadGenerator>>) -> Self {
+        Self {
+            phases: phases.into(),
+        }
+    }
+}
+
+impl WorkloadGenerator for SequenceWorkload {
+    fn next_task(&mut self) -> Option<(Duration, u64)> {
+        loop {
+            let task = self.phases.front_mut()?.next_task();
+            if task.is_some() {
+                return task;
+            }
+            self.phases.pop_front();
+        }
+    }
+
+    fn priority(&self) -> Option<usize> {
+        self.phases.front().and_then(|phase| phase.priority())
+    }
+
+    fn parents(&self) -> &[u64] {
+        self.phases.front().map_or(&[], |phase| phase.parents())
+    }
+
+    fn deadline(&self) -> Option<Duration> {
+        self.phases.front().and_then(|phase| phase.deadline())
+    }
+
+    fn task_type(&self) -> u32 {
+        self.phases.front().map_or(0, |phase| phase.task_type())
+    }
+}
+
+/// Serializable workload description
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkloadConfig {
+    Steady {
+        rate_per_sec: f64,
+        work: WorkDistribution,
+    },
+    Batch {
+        request_rate: f64,
+        max_batch: u32,
+        work: WorkDistribution,
+    },
+    Bursty {
+        high_rate: f64,
+        low_rate: f64,
+        work: WorkDistribution,
+        period_ms: u64,
+    },
+    Ramp {
+        from_rate: f64,
+        to_rate: f64,
+        work: WorkDistribution,
+    },
+    Sinusoidal {
+        mean_rate: f64,
+        amplitude: f64,
+        period_ms: u64,
+        work: WorkDistribution,
+    },
+    Adversarial {
+        base_rate: f64,
+        min_work_us: u64,
+        max_work_us: u64,
+    },
+    Trace {
+        path: String,
+    },
+    Sequence {
+        phases: Vec<WorkloadPhase>,
+    },
+}
+
+/// One phase of a [`WorkloadConfig::Sequence`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadPhase {
+    pub duration_secs: f64,
+    #[serde(flatten)]
+    pub workload: WorkloadConfig,
+}
+
+impl WorkloadConfig {
+    /// Instantiate a reproducible generator for a run of the given duration
+    ///
+    /// Traces are deterministic anyway and ignore `seed`.
+    pub fn build_seeded(&self, duration: Duration, seed: u64) -> std::io::Result<Box<dyn WorkloadGenerator>> {
+        Ok(match self {
+            WorkloadConfig::Steady { rate_per_sec, work } => Box::new(
+                SteadyWorkload::new(*rate_per_sec, 0, duration)
+                    .with_work_distribution(*work)
+                    .with_seed(seed),
+            ),
+            WorkloadConfig::Batch {
+                request_rate,
+                max_batch,
+                work,
+            } => Box::new(
+                BatchWorkload::new(*request_rate, *max_batch, 0, duration)
+                    .with_work_distribution(*work)
+                    .with_seed(seed),
+            ),
+            WorkloadConfig::Bursty {
+                high_rate,
+                low_rate,
+                work,
+                period_ms,
+            } => Box::new(
+                BurstyWorkload::new(*high_rate, *low_rate, 0, Duration::from_millis(*period_ms), duration)
+                    .with_work_distribution(*work)
+                    .with_seed(seed),
+            ),
+            WorkloadConfig::Ramp { from_rate, to_rate, work } => Box::new(
+                RampWorkload::new(*from_rate, *to_rate, 0, duration)
+                    .with_work_distribution(*work)
+                    .with_seed(seed),
+            ),
+            WorkloadConfig::Sinusoidal {
+                mean_rate,
+                amplitude,
+                period_ms,
+                work,
+            } => Box::new(
+                SinusoidalWorkload::new(*mean_rate, *amplitude, Duration::from_millis(*period_ms), 0, duration)
+                    .with_work_distribution(*work)
+                    .with_seed(seed),
+            ),
+            WorkloadConfig::Adversarial {
+                base_rate,
+                min_work_us,
+                max_work_us,
+            } => Box::new(AdversarialWorkload::new(*base_rate, (*min_work_us, *max_work_us), duration).with_seed(seed)),
+            WorkloadConfig::Trace { path } => Box::new(TraceWorkload::from_csv(path)?),
+            // Phases carry their own durations; each gets a distinct seed
+            WorkloadConfig::Sequence { phases } => {
+                let phases = phases
+                    .iter()
+                    .enumerate()
+                    .map(|(i, phase)| {
+                        let phase_seed = seed.wrapping_add(i as u64);
+                        phase
+                            .workload
+                            .build_seeded(Duration::from_secs_f64(phase.duration_secs), phase_seed)
+                    })
+                    .collect::<std::io::Result<Vec<_>>>()?;
+                Box::new(SequenceWorkload::new(phases))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Always asks for the same pool size
+    struct FixedPolicy(u32);
+
+    impl PoolSizePolicy for FixedPolicy {
+        fn decide(&mut self, _telem: &ComputeTelemetry) -> PoolSizeDecision {
+            PoolSizeDecision {
+                n_workers: self.0,
+                admit_rate: None,
+            }
+        }
+    }
+
+    /// A 10ms batch task at 0 and a 1ms critical task at 4ms
+    fn critical_over_batch() -> PriorityMixWorkload {
+        PriorityMixWorkload::new(
+            Box::new(TraceWorkload::new(vec![(4000, 1000)])),
+            Box::new(TraceWorkload::new(vec![(0, 10_000)])),
+        )
+    }
+
+    #[test]
+    fn test_preemption_displaces_batch_work_and_keeps_its_progress() {
+        let mut sim = ThreadPoolSim::new(FixedPolicy(1), 1).with_priority_levels(2).with_preemption();
+        sim.run_virtual(&mut critical_over_batch(), Duration::from_millis(20));
+
+        // The batch task ran 4ms, waited out the critical task, then finished its last 6ms
+        let metrics = sim.metrics();
+        assert_eq!(metrics.preemptions, 1);
+        assert_eq!(metrics.priority_task_times_us, [vec![1000], vec![11_000]]);
+    }
+
+    #[test]
+    fn test_priorities_past_the_last_level_run_at_the_last() {
+        let mut sim = ThreadPoolSim::new(FixedPolicy(1), 1);
+        sim.run_virtual(&mut critical_over_batch(), Duration::from_millis(20));
+
+        // One level: the critical task queues behind the batch task
+        assert_eq!(sim.metrics().task_times_us, [10_000, 7000]);
+    }
+
+    #[test]
+    fn test_run_virtual_is_deterministic_for_a_seeded_workload() {
+        let run = || {
+            let mut sim = ThreadPoolSim::new(HillClimbPolicy::new(4), 4);
+            let mut workload = SteadyWorkload::new(2000.0, 1500, Duration::from_secs(2))
+                .with_work_distribution(WorkDistribution::LogNormal { median: 1200.0, sigma: 0.8 })
+                .with_seed(7);
+            sim.run_virtual(&mut workload, Duration::from_secs(3));
+            sim.metrics().clone()
+        };
+        let (first, second) = (run(), run());
+        assert!(first.task_times_us.len() > 3000);
+        assert_eq!(first.task_times_us, second.task_times_us);
+        assert_eq!(first.wait_times_us, second.wait_times_us);
+        assert_eq!(first.throughput_samples, second.throughput_samples);
+        assert_eq!((first.decisions, first.decision_changes), (second.decisions, second.decision_changes));
+        assert_eq!((first.busy_worker_us, first.idle_worker_us), (second.busy_worker_us, second.idle_worker_us));
+    }
+
+    #[test]
+    fn test_run_virtual_orders_arrival_completion_and_tick_at_one_instant() {
+        // One worker; at 10ms the first task completes, the second arrives and the policy ticks
+        let tick_us = ThreadPoolSim::<FixedPolicy>::TICK_INTERVAL.as_micros() as u64;
+        let mut sim = ThreadPoolSim::new(FixedPolicy(1), 1).with_decision_log();
+        let mut workload = TraceWorkload::new(vec![(0, tick_us), (tick_us, 1000)]);
+        sim.run_virtual(&mut workload, Duration::from_millis(25));
+
+        // The arrival is enqueued before the tick's dispatch, which frees the worker and starts it at once
+        let metrics = sim.metrics();
+        assert_eq!(metrics.task_times_us, [tick_us, 1000]);
+        assert_eq!(metrics.wait_times_us, [0, 0]);
+        // The policy at 10ms saw the finished task gone and the new one running
+        let log = sim.decision_log().unwrap();
+        assert_eq!(log[1].timestamp_us, tick_us);
+        assert_eq!((log[1].telemetry.runq_len, log[1].telemetry.idle_worker_count), (0, 0));
+    }
+}
@@ -0,0 +1,210 @@
+//! Multi-pool simulation
+//!
+//! Several thread pools (e.g. an IO pool and a CPU pool) with their own
+//! workloads, competing for one physical core budget. Sizing comes either
+//! from an independent policy per pool or from one policy deciding every
+//! pool at once.
+
+use crate::{
+    schedule_arrival, CoreModel, Metrics, PoolSizeDecision, PoolSizePolicy, ThreadPoolSim, WorkloadGenerator,
+};
+use std::time::{Duration, Instant};
+use telemetry_compute::{ComputeTelemetry, Normalizer};
+
+/// Sizing policy for all pools of a [`MultiPoolSim`] at once
+pub trait MultiPoolPolicy {
+    /// One decision per pool, given each pool's telemetry in pool order
+    fn decide(&mut self, telems: &[ComputeTelemetry]) -> Vec<PoolSizeDecision>;
+}
+
+/// Independent policies, one per pool, each seeing only its pool's telemetry
+impl<P: PoolSizePolicy> MultiPoolPolicy for Vec<P> {
+    fn decide(&mut self, telems: &[ComputeTelemetry]) -> Vec<PoolSizeDecision> {
+        assert_eq!(self.len(), telems.len(), "one policy per pool");
+        self.iter_mut().zip(telems).map(|(policy, telem)| policy.decide(telem)).collect()
+    }
+}
+
+/// One reflex sizing every pool from all pools' telemetry
+///
+/// Inputs are each pool's normalized compute-v1 features concatenated in
+/// pool order; output `i` is pool `i`'s worker count.
+pub struct MultiOutputReflexPolicy {
+    reflex: reflex_format::Reflex,
+    normalizer: Normalizer,
+}
+
+impl MultiOutputReflexPolicy {
+    pub fn load(reflex_path: &str, normalizer: Normalizer) -> std::io::Result<Self> {
+        let bytes = std::fs::read(reflex_path)?;
+        let reflex = reflex_format::Reflex::from_bytes(&bytes)?;
+        Ok(Self { reflex, normalizer })
+    }
+}
+
+impl MultiPoolPolicy for MultiOutputReflexPolicy {
+    fn decide(&mut self, telems: &[ComputeTelemetry]) -> Vec<PoolSizeDecision> {
+        let features: Vec<f32> = telems
+            .iter()
+            .flat_map(|telem| self.normalizer.normalize(&telem.to_features()))
+            .collect();
+        let outputs = self.reflex.infer(&features);
+
+        (0..telems.len())
+            .map(|pool| PoolSizeDecision {
+                n_workers: outputs.get(pool).map_or(1.0, |n| n.round().clamp(1.0, 64.0)) as u32,
+                admit_rate: None,
+            })
+            .collect()
+    }
+}
+
+/// Stand-in policy for pools whose decisions come from a [`MultiPoolPolicy`]
+struct External;
+
+impl PoolSizePolicy for External {
+    fn decide(&mut self, _telem: &ComputeTelemetry) -> PoolSizeDecision {
+        unreachable!("multi-pool decisions come from the MultiPoolPolicy")
+    }
+}
+
+/// Thread pools sharing one core budget
+///
+/// Every pool's service times stretch with the total number of busy
+/// workers across all pools, per the shared [`CoreModel`].
+pub struct MultiPoolSim<M: MultiPoolPolicy> {
+    pools: Vec<ThreadPoolSim<External>>,
+    policy: M,
+}
+
+impl<M: MultiPoolPolicy> MultiPoolSim<M> {
+    pub fn new(policy: M, initial_workers: &[u32], cores: CoreModel) -> Self {
+        assert!(!initial_workers.is_empty(), "at least one pool required");
+        let created_at = Instant::now();
+        let pools = initial_workers
+            .iter()
+            .map(|&n| {
+                let mut pool = ThreadPoolSim::new(External, n).with_cores(cores);
+                // One clock for all pools so telemetry timestamps line up
+                pool.created_at = created_at;
+                pool.last_throughput_measurement = created_at;
+                pool
+            })
+            .collect();
+        Self { pools, policy }
+    }
+
+    /// Drive every pool on a shared virtual clock, pool `i` fed by `workloads[i]`
+    ///
+    /// Same event model as [`ThreadPoolSim::run_virtual`]; on each policy
+    /// tick all pools are observed first, then decided together.
+    pub fn run_virtual(&mut self, workloads: &mut [Box<dyn WorkloadGenerator>], duration: Duration) {
+        assert_eq!(workloads.len(), self.pools.len(), "one workload per pool");
+        let start = self.pools[0].virtual_now.unwrap_or(self.pools[0].created_at);
+        let end = start + duration;
+        let first_ids: Vec<u64> = self.pools.iter().map(|p| p.next_task_id).collect();
+        let mut next_arrivals: Vec<_> = workloads
+            .iter_mut()
+            .map(|w| schedule_arrival(w.as_mut(), start))
+            .collect();
+        let mut next_tick = start;
+
+        loop {
+            let next_arrival = next_arrivals.iter().flatten().map(|a| a.at).min();
+            let next_completion = self
+                .pools
+                .iter()
+                .flat_map(|p| p.workers.iter().filter_map(|w| w.task_finish_time))
+                .min();
+            let now = [next_arrival, next_completion, Some(next_tick)]
+                .into_iter()
+                .flatten()
+                .min()
+                .unwrap();
+            if now > end {
+                break;
+            }
+            for pool in &mut self.pools {
+                pool.virtual_now = Some(now);
+            }
+
+            for (i, workload) in workloads.iter_mut().enumerate() {
+                let mut arrivals = Vec::new();
+                while let Some(arrival) = next_arrivals[i].take_if(|a| a.at == now) {
+                    next_arrivals[i] = schedule_arrival(workload.as_mut(), arrival.at);
+                    arrivals.push(arrival);
+                }
+                self.pools[i].enqueue_arrivals(&arrivals, first_ids[i]);
+            }
+
+            if now == next_tick {
+                let mut telems = Vec::with_capacity(self.pools.len());
+                for i in 0..self.pools.len() {
+                    self.share_cores(i);
+                    telems.push(self.pools[i].observe(now));
+                }
+                let decisions = self.policy.decide(&telems);
+                assert_eq!(decisions.len(), self.pools.len(), "one decision per pool");
+                for ((pool, telem), decision) in self.pools.iter_mut().zip(telems).zip(decisions) {
+                    pool.apply_decision(now, telem, decision);
+                }
+                next_tick += ThreadPoolSim::<External>::TICK_INTERVAL;
+            } else {
+                for i in 0..self.pools.len() {
+                    self.share_cores(i);
+                    self.pools[i].dispatch(now);
+                }
+            }
+        }
+        for pool in &mut self.pools {
+            pool.charge_worker_time(end);
+            pool.virtual_now = Some(end);
+        }
+    }
+
+    /// Tell pool `i` how many workers the other pools have running
+    fn share_cores(&mut self, i: usize) {
+        let total: usize = self.pools.iter().map(|p| p.busy_workers()).sum();
+        self.pools[i].external_busy = total - self.pools[i].busy_workers();
+    }
+
+    pub fn pool_count(&self) -> usize {
+        self.pools.len()
+    }
+
+    pub fn pool_metrics(&self, pool: usize) -> &Metrics {
+        self.pools[pool].metrics()
+    }
+
+    /// All pools' metrics combined: task times pooled, throughput and counts summed
+    pub fn aggregate_metrics(&self) -> Metrics {
+        let mut total = Metrics::new();
+        for pool in &self.pools {
+            let m = pool.metrics();
+            total.task_times_us.extend(&m.task_times_us);
+            total.lateness_us.extend(&m.lateness_us);
+            total.wait_times_us.extend(&m.wait_times_us);
+            total.service_times_us.extend(&m.service_times_us);
+            if total.throughput_samples.len() < m.throughput_samples.len() {
+                total.throughput_samples.resize(m.throughput_samples.len(), 0.0);
+            }
+            for (sum, sample) in total.throughput_samples.iter_mut().zip(&m.throughput_samples) {
+                *sum += sample;
+            }
+            total.decision_changes += m.decision_changes;
+            total.decisions += m.decisions;
+            total.output_delta_sum += m.output_delta_sum;
+            total.flip_flops += m.flip_flops;
+            total.preemptions += m.preemptions;
+            total.cold_starts += m.cold_starts;
+            total.shrink_latencies_us.extend(&m.shrink_latencies_us);
+            total.busy_worker_us += m.busy_worker_us;
+            total.idle_worker_us += m.idle_worker_us;
+            total.arrivals += m.arrivals;
+            total.shed_tasks += m.shed_tasks;
+            total.over_budget_decisions += m.over_budget_decisions;
+            total.denied_workers += m.denied_workers;
+        }
+        total
+    }
+}
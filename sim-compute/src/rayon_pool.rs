@@ -0,0 +1,203 @@
+//! Rayon pool-size adapter
+//!
+//! Rayon thread pools have a fixed thread count, so the adapter sizes one
+//! by rebuilding it: jobs submitted through the adapter feed the same
+//! one-second telemetry windows as [`RealThreadPool`](crate::RealThreadPool),
+//! and a [`PoolSizePolicy`] decision for a different size swaps in a fresh
+//! pool. Jobs already on the old pool finish there.
+
+use crate::real_pool::{ExecutionWindows, PoolSnapshot};
+use crate::{Metrics, PoolSizeDecision, PoolSizePolicy};
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+struct Stats {
+    queued: usize,
+    busy: usize,
+    windows: ExecutionWindows,
+    metrics: Metrics,
+}
+
+/// Rayon [`ThreadPool`] resized by a [`PoolSizePolicy`]
+///
+/// Submit work with [`spawn`](Self::spawn) or [`install`](Self::install);
+/// either one ticks the policy when [`TICK_INTERVAL`](Self::TICK_INTERVAL)
+/// has passed, so the pool follows the workload without a controller
+/// thread. Rebuilds are at least the rebuild interval apart (default 1s),
+/// since each one starts a full set of threads.
+pub struct RayonPoolAdapter<P: PoolSizePolicy> {
+    pool: RwLock<Arc<ThreadPool>>,
+    policy: Mutex<P>,
+    stats: Arc<Mutex<Stats>>,
+    last_tick: Mutex<Instant>,
+    last_rebuild: Mutex<Instant>,
+    rebuild_interval: Duration,
+    rebuilds: Mutex<usize>,
+    created_at: Instant,
+}
+
+impl<P: PoolSizePolicy> RayonPoolAdapter<P> {
+    /// Policy tick cadence, matching [`ThreadPoolSim::TICK_INTERVAL`](crate::ThreadPoolSim::TICK_INTERVAL)
+    pub const TICK_INTERVAL: Duration = Duration::from_millis(10);
+
+    pub fn new(policy: P, initial_threads: u32) -> Result<Self, ThreadPoolBuildError> {
+        let created_at = Instant::now();
+        Ok(Self {
+            pool: RwLock::new(Arc::new(build_pool(initial_threads)?)),
+            policy: Mutex::new(policy),
+            stats: Arc::new(Mutex::new(Stats {
+                queued: 0,
+                busy: 0,
+                windows: ExecutionWindows::new(created_at),
+                metrics: Metrics::new(),
+            })),
+            last_tick: Mutex::new(created_at),
+            last_rebuild: Mutex::new(created_at),
+            rebuild_interval: Duration::from_secs(1),
+            rebuilds: Mutex::new(0),
+            created_at,
+        })
+    }
+
+    /// Minimum time between pool rebuilds
+    pub fn with_rebuild_interval(mut self, interval: Duration) -> Self {
+        self.rebuild_interval = interval;
+        self
+    }
+
+    /// The current pool, e.g. for rayon APIs the adapter doesn't wrap
+    pub fn pool(&self) -> Arc<ThreadPool> {
+        Arc::clone(&self.pool.read().unwrap())
+    }
+
+    /// Run `job` asynchronously on the current pool
+    ///
+    /// A panicking job is contained to that job.
+    pub fn spawn(&self, job: impl FnOnce() + Send + 'static) {
+        self.maybe_tick();
+        let enqueued = self.record_arrival();
+        let stats = Arc::clone(&self.stats);
+        self.pool().spawn(move || {
+            let started = record_start(&stats);
+            let _ = panic::catch_unwind(AssertUnwindSafe(job));
+            record_done(&stats, enqueued, started);
+        });
+    }
+
+    /// Run `op` on the current pool and wait for it, counting it as one task
+    ///
+    /// Parallel iterators inside `op` use the pool's threads.
+    pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        self.maybe_tick();
+        let enqueued = self.record_arrival();
+        let stats = &self.stats;
+        self.pool().install(|| {
+            let started = record_start(stats);
+            let result = panic::catch_unwind(AssertUnwindSafe(op));
+            record_done(stats, enqueued, started);
+            result.unwrap_or_else(|payload| panic::resume_unwind(payload))
+        })
+    }
+
+    /// Threads in the current pool
+    pub fn current_num_threads(&self) -> usize {
+        self.pool.read().unwrap().current_num_threads()
+    }
+
+    /// Pools built to replace an earlier one
+    pub fn rebuilds(&self) -> usize {
+        *self.rebuilds.lock().unwrap()
+    }
+
+    /// Snapshot of the run's metrics so far
+    pub fn metrics(&self) -> Metrics {
+        self.stats.lock().unwrap().metrics.clone()
+    }
+
+    /// Collect telemetry, ask the policy, and rebuild the pool if it wants a different size
+    pub fn tick(&self) -> PoolSizeDecision {
+        let now = Instant::now();
+        *self.last_tick.lock().unwrap() = now;
+        let current = self.current_num_threads();
+        let telem = {
+            let stats = self.stats.lock().unwrap();
+            stats.windows.telemetry(PoolSnapshot {
+                timestamp_us: now.duration_since(self.created_at).as_micros() as u64,
+                runq_len: stats.queued,
+                live: current,
+                busy: stats.busy,
+                pool_size: current,
+                oldest_wait_us: 0.0, // rayon's queues aren't visible
+            })
+        };
+        let (decision, shadow, ood) = {
+            let mut policy = self.policy.lock().unwrap();
+            (policy.decide(&telem), policy.shadow_decision(), policy.ood_fallback())
+        };
+        {
+            let mut stats = self.stats.lock().unwrap();
+            let Stats { windows, metrics, .. } = &mut *stats;
+            metrics.record_decision(decision, now);
+            metrics.record_shadow(decision, shadow);
+            metrics.record_ood(ood);
+            windows.roll(metrics, now);
+        }
+
+        let target = decision.n_workers.max(1);
+        let mut last_rebuild = self.last_rebuild.lock().unwrap();
+        if target as usize != current && now.duration_since(*last_rebuild) >= self.rebuild_interval {
+            // Keep the old pool if the new one can't be built
+            if let Ok(pool) = build_pool(target) {
+                *self.pool.write().unwrap() = Arc::new(pool);
+                *last_rebuild = now;
+                *self.rebuilds.lock().unwrap() += 1;
+            }
+        }
+        decision
+    }
+
+    /// Tick if the tick interval has passed and no other thread is ticking
+    fn maybe_tick(&self) {
+        let due = self
+            .last_tick
+            .try_lock()
+            .is_ok_and(|last| last.elapsed() >= Self::TICK_INTERVAL);
+        if due {
+            self.tick();
+        }
+    }
+
+    fn record_arrival(&self) -> Instant {
+        let now = Instant::now();
+        let mut stats = self.stats.lock().unwrap();
+        stats.queued += 1;
+        stats.windows.record_arrival(now);
+        stats.metrics.record_arrival();
+        now
+    }
+}
+
+fn build_pool(threads: u32) -> Result<ThreadPool, ThreadPoolBuildError> {
+    ThreadPoolBuilder::new()
+        .num_threads(threads.max(1) as usize)
+        .thread_name(|i| format!("reflex-rayon-{}", i))
+        .build()
+}
+
+fn record_start(stats: &Mutex<Stats>) -> Instant {
+    let now = Instant::now();
+    let mut stats = stats.lock().unwrap();
+    stats.queued -= 1;
+    stats.busy += 1;
+    stats.windows.record_start(now);
+    now
+}
+
+fn record_done(stats: &Mutex<Stats>, enqueued: Instant, started: Instant) {
+    let mut stats = stats.lock().unwrap();
+    stats.busy -= 1;
+    let Stats { windows, metrics, .. } = &mut *stats;
+    windows.record_completion(metrics, enqueued, started, Instant::now());
+}
@@ -0,0 +1,458 @@
+//! Real thread pool
+//!
+//! Runs actual closures on OS threads and sizes itself with the same
+//! [`PoolSizePolicy`] interface as the simulator: a controller thread
+//! collects [`ComputeTelemetry`] from real execution every tick and applies
+//! the policy's decision. Shrinking is graceful; surplus workers exit after
+//! their current job.
+
+use crate::{prune_window, Metrics, PoolSizePolicy};
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use telemetry_compute::ComputeTelemetry;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct QueuedJob {
+    job: Job,
+    enqueued: Instant,
+}
+
+/// One finished job, for the telemetry windows
+struct Completion {
+    done: Instant,
+    total_us: u64,
+    wait_us: u64,
+    service_us: u64,
+}
+
+/// Pool state the execution windows can't see, at the moment of sampling
+pub(crate) struct PoolSnapshot {
+    pub timestamp_us: u64,
+    pub runq_len: usize,
+    pub live: usize, // threads, including ones about to retire
+    pub busy: usize,
+    pub pool_size: usize,
+    pub oldest_wait_us: f32,
+}
+
+/// One-second windows of job arrivals, starts and completions on real threads
+pub(crate) struct ExecutionWindows {
+    arrivals: VecDeque<Instant>,
+    starts: VecDeque<Instant>,
+    completions: VecDeque<Completion>,
+    completed_since_measurement: usize,
+    last_throughput_measurement: Instant,
+}
+
+impl ExecutionWindows {
+    pub fn new(created_at: Instant) -> Self {
+        Self {
+            arrivals: VecDeque::new(),
+            starts: VecDeque::new(),
+            completions: VecDeque::new(),
+            completed_since_measurement: 0,
+            last_throughput_measurement: created_at,
+        }
+    }
+
+    pub fn record_arrival(&mut self, now: Instant) {
+        self.arrivals.push_back(now);
+    }
+
+    pub fn record_start(&mut self, now: Instant) {
+        self.starts.push_back(now);
+    }
+
+    /// Record a finished job in the windows and `metrics`
+    pub fn record_completion(&mut self, metrics: &mut Metrics, enqueued: Instant, started: Instant, done: Instant) {
+        let wait_us = started.duration_since(enqueued).as_micros() as u64;
+        let service_us = done.duration_since(started).as_micros() as u64;
+        let total_us = wait_us + service_us;
+        metrics.record_task_time(total_us);
+        metrics.record_latency_split(wait_us, service_us);
+        self.completions.push_back(Completion {
+            done,
+            total_us,
+            wait_us,
+            service_us,
+        });
+        self.completed_since_measurement += 1;
+    }
+
+    /// Record throughput in `metrics` once a second, then drop events older than one second
+    pub fn roll(&mut self, metrics: &mut Metrics, now: Instant) {
+        let elapsed = now.duration_since(self.last_throughput_measurement);
+        if elapsed >= Duration::from_secs(1) {
+            metrics.record_throughput(self.completed_since_measurement as f64 / elapsed.as_secs_f64());
+            self.completed_since_measurement = 0;
+            self.last_throughput_measurement = now;
+        }
+
+        let cutoff = now - Duration::from_secs(1);
+        prune_window(&mut self.arrivals, cutoff, |t| *t);
+        prune_window(&mut self.starts, cutoff, |t| *t);
+        prune_window(&mut self.completions, cutoff, |c| c.done);
+    }
+
+    /// Telemetry over the last second of real execution
+    ///
+    /// Job sizes are unknown until a job has run, so the task size statistics
+    /// describe jobs completed in the window rather than queued ones.
+    pub fn telemetry(&self, pool: PoolSnapshot) -> ComputeTelemetry {
+        let percentiles = |values: Vec<u64>| -> (f32, f32) {
+            let mut sorted = values;
+            if sorted.is_empty() {
+                return (0.0, 0.0);
+            }
+            sorted.sort_unstable();
+            let p95_idx = ((sorted.len() as f32 * 0.95) as usize).min(sorted.len() - 1);
+            (sorted[sorted.len() / 2] as f32, sorted[p95_idx] as f32)
+        };
+        let (task_time_p50_us, task_time_p95_us) = percentiles(self.completions.iter().map(|c| c.total_us).collect());
+        let (wait_time_p50_us, wait_time_p95_us) = percentiles(self.completions.iter().map(|c| c.wait_us).collect());
+
+        let completed = self.completions.len() as f32;
+        let (task_size_mean, task_size_var) = if self.completions.is_empty() {
+            (0.0, 0.0)
+        } else {
+            let mean = self.completions.iter().map(|c| c.service_us as f32).sum::<f32>() / completed;
+            let var = self.completions.iter().map(|c| (c.service_us as f32 - mean).powi(2)).sum::<f32>() / completed;
+            (mean, var)
+        };
+
+        ComputeTelemetry {
+            timestamp_us: pool.timestamp_us,
+            runq_len: pool.runq_len as u32,
+            arrival_rate: self.arrivals.len() as f32,
+            completion_rate: completed,
+            task_time_p50_us,
+            task_time_p95_us,
+            worker_util: if pool.live == 0 { 0.0 } else { pool.busy as f32 / pool.live as f32 },
+            ctx_switches_per_sec: self.starts.len() as f32,
+            task_size_mean,
+            task_size_var,
+            idle_worker_count: pool.live.saturating_sub(pool.busy) as u32,
+            memory_headroom_mb: 0.0,
+            pool_size: pool.pool_size as u32,
+            service_time_mean_us: task_size_mean,
+            shed_rate: 0.0,
+            batch_size_mean: if self.arrivals.is_empty() { 0.0 } else { 1.0 },
+            batch_size_var: 0.0,
+            queue_discipline: 0,
+            oldest_wait_us: pool.oldest_wait_us,
+            wait_time_p50_us,
+            wait_time_p95_us,
+        }
+    }
+}
+
+struct State {
+    queue: VecDeque<QueuedJob>,
+    live: usize, // worker threads running, including ones about to retire
+    busy: usize,
+    retirements: VecDeque<Instant>, // one per worker asked to exit, when asked
+    shutdown: bool,
+    windows: ExecutionWindows,
+    metrics: Metrics,
+}
+
+impl State {
+    /// Workers not asked to retire: the pool size in force
+    fn active(&self) -> usize {
+        self.live - self.retirements.len()
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    work_ready: Condvar,
+    controller_wake: Condvar,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+    created_at: Instant,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+/// Thread pool on OS threads, resized by a [`PoolSizePolicy`]
+///
+/// Dropping the pool (or calling [`shutdown`](Self::shutdown)) runs the
+/// jobs still queued, then joins every thread.
+pub struct RealThreadPool {
+    shared: Arc<Shared>,
+    controller: Option<JoinHandle<()>>,
+}
+
+impl RealThreadPool {
+    /// Policy tick cadence, matching [`ThreadPoolSim::TICK_INTERVAL`](crate::ThreadPoolSim::TICK_INTERVAL)
+    pub const TICK_INTERVAL: Duration = Duration::from_millis(10);
+
+    pub fn new<P: PoolSizePolicy + Send + 'static>(policy: P, initial_workers: u32) -> Self {
+        let created_at = Instant::now();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                live: 0,
+                busy: 0,
+                retirements: VecDeque::new(),
+                shutdown: false,
+                windows: ExecutionWindows::new(created_at),
+                metrics: Metrics::new(),
+            }),
+            work_ready: Condvar::new(),
+            controller_wake: Condvar::new(),
+            workers: Mutex::new(Vec::new()),
+            created_at,
+        });
+
+        {
+            let mut state = shared.lock();
+            resize(&shared, &mut state, initial_workers.max(1) as usize, created_at);
+        }
+        let controller_shared = Arc::clone(&shared);
+        let controller = thread::Builder::new()
+            .name("pool-controller".to_string())
+            .spawn(move || controller_loop(controller_shared, policy))
+            .expect("failed to spawn pool controller");
+
+        Self {
+            shared,
+            controller: Some(controller),
+        }
+    }
+
+    /// Queue `job` to run on a worker thread
+    ///
+    /// A panicking job is contained to that job; the worker keeps running.
+    pub fn execute(&self, job: impl FnOnce() + Send + 'static) {
+        let now = Instant::now();
+        let mut state = self.shared.lock();
+        state.queue.push_back(QueuedJob {
+            job: Box::new(job),
+            enqueued: now,
+        });
+        state.windows.record_arrival(now);
+        state.metrics.record_arrival();
+        drop(state);
+        self.shared.work_ready.notify_one();
+    }
+
+    /// Workers currently in the pool, excluding ones about to retire
+    pub fn pool_size(&self) -> usize {
+        self.shared.lock().active()
+    }
+
+    /// Snapshot of the run's metrics so far
+    pub fn metrics(&self) -> Metrics {
+        self.shared.lock().metrics.clone()
+    }
+
+    /// Run the remaining queued jobs, then stop every thread
+    pub fn shutdown(self) {}
+}
+
+impl Drop for RealThreadPool {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.work_ready.notify_all();
+        self.shared.controller_wake.notify_all();
+        if let Some(controller) = self.controller.take() {
+            let _ = controller.join();
+        }
+        // Workers exit once the queue is empty; none are spawned after this
+        let workers = std::mem::take(&mut *self.shared.workers.lock().unwrap());
+        for worker in workers {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Move the pool toward `target` workers
+///
+/// Growing first cancels pending retirements, then spawns threads;
+/// shrinking asks idle workers, or busy ones after their current job, to exit.
+fn resize(shared: &Arc<Shared>, state: &mut State, target: usize, now: Instant) {
+    let active = state.active();
+    if target > active {
+        let mut missing = target - active;
+        while missing > 0 && state.retirements.pop_back().is_some() {
+            missing -= 1;
+        }
+        let mut workers = shared.workers.lock().unwrap();
+        for _ in 0..missing {
+            let worker_shared = Arc::clone(shared);
+            let handle = thread::Builder::new()
+                .name(format!("pool-worker-{}", state.live))
+                .spawn(move || worker_loop(worker_shared))
+                .expect("failed to spawn pool worker");
+            workers.push(handle);
+            state.live += 1;
+        }
+    } else if target < active {
+        for _ in target..active {
+            state.retirements.push_back(now);
+        }
+        shared.work_ready.notify_all();
+    }
+}
+
+fn worker_loop(shared: Arc<Shared>) {
+    let mut state = shared.lock();
+    loop {
+        // Retire before taking more work
+        if let Some(asked) = state.retirements.pop_front() {
+            state.live -= 1;
+            let latency_us = asked.elapsed().as_micros() as u64;
+            state.metrics.record_shrink_latency(latency_us);
+            return;
+        }
+
+        if let Some(QueuedJob { job, enqueued }) = state.queue.pop_front() {
+            let started = Instant::now();
+            state.busy += 1;
+            state.windows.record_start(started);
+            drop(state);
+
+            let _ = panic::catch_unwind(AssertUnwindSafe(job));
+
+            let done = Instant::now();
+            state = shared.lock();
+            state.busy -= 1;
+            let State { windows, metrics, .. } = &mut *state;
+            windows.record_completion(metrics, enqueued, started, done);
+            continue;
+        }
+
+        if state.shutdown {
+            state.live -= 1;
+            return;
+        }
+        state = shared.work_ready.wait(state).unwrap();
+    }
+}
+
+fn controller_loop<P: PoolSizePolicy>(shared: Arc<Shared>, mut policy: P) {
+    let mut state = shared.lock();
+    let mut last_tick = shared.created_at;
+    loop {
+        state = shared.controller_wake.wait_timeout(state, RealThreadPool::TICK_INTERVAL).unwrap().0;
+        if state.shutdown {
+            return;
+        }
+
+        // Worker time is sampled per tick, so jobs shorter than a tick are approximate
+        let now = Instant::now();
+        let (busy, idle) = (state.busy, state.live - state.busy);
+        state.metrics.record_worker_time(busy, idle, now.duration_since(last_tick));
+        last_tick = now;
+        let telem = state.windows.telemetry(PoolSnapshot {
+            timestamp_us: now.duration_since(shared.created_at).as_micros() as u64,
+            runq_len: state.queue.len(),
+            live: state.live,
+            busy: state.busy,
+            pool_size: state.active(),
+            oldest_wait_us: state
+                .queue
+                .front()
+                .map_or(0.0, |q| now.duration_since(q.enqueued).as_micros() as f32),
+        });
+        drop(state);
+        let decision = policy.decide(&telem);
+        state = shared.lock();
+
+        state.metrics.record_decision(decision, now);
+        state.metrics.record_shadow(decision, policy.shadow_decision());
+        state.metrics.record_ood(policy.ood_fallback());
+        resize(&shared, &mut state, decision.n_workers.max(1) as usize, now);
+
+        let State { windows, metrics, .. } = &mut *state;
+        windows.roll(metrics, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PoolSizeDecision, PoolSizePolicy};
+    use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+    /// Asks for whatever pool size the test last set
+    struct Scripted(Arc<AtomicU32>);
+
+    impl PoolSizePolicy for Scripted {
+        fn decide(&mut self, _telem: &ComputeTelemetry) -> PoolSizeDecision {
+            PoolSizeDecision {
+                n_workers: self.0.load(Ordering::SeqCst),
+                admit_rate: None,
+            }
+        }
+    }
+
+    fn scripted(workers: u32) -> (Scripted, Arc<AtomicU32>) {
+        let target = Arc::new(AtomicU32::new(workers));
+        (Scripted(Arc::clone(&target)), target)
+    }
+
+    /// Poll `condition` for up to five seconds
+    fn eventually(mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    #[test]
+    fn test_shutdown_runs_every_queued_job() {
+        let (policy, _) = scripted(4);
+        let pool = RealThreadPool::new(policy, 4);
+        let ran = Arc::new(AtomicUsize::new(0));
+        for _ in 0..500 {
+            let ran = Arc::clone(&ran);
+            pool.execute(move || {
+                ran.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        pool.shutdown();
+        assert_eq!(ran.load(Ordering::SeqCst), 500);
+    }
+
+    #[test]
+    fn test_panicking_job_leaves_its_worker_running() {
+        let (policy, _) = scripted(1);
+        let pool = RealThreadPool::new(policy, 1);
+        let ran = Arc::new(AtomicUsize::new(0));
+        pool.execute(|| panic!("job failed"));
+        let after = Arc::clone(&ran);
+        pool.execute(move || {
+            after.fetch_add(1, Ordering::SeqCst);
+        });
+
+        // The only worker survived the panic to run the next job
+        assert!(eventually(|| ran.load(Ordering::SeqCst) == 1));
+        assert_eq!(pool.pool_size(), 1);
+        assert_eq!(pool.metrics().task_times_us.len(), 2);
+    }
+
+    #[test]
+    fn test_pool_follows_policy_and_records_shrinks() {
+        let (policy, target) = scripted(4);
+        let pool = RealThreadPool::new(policy, 1);
+        assert!(eventually(|| pool.pool_size() == 4));
+
+        target.store(2, Ordering::SeqCst);
+        assert!(eventually(|| pool.pool_size() == 2));
+        // Idle workers retire as soon as they're asked
+        assert!(eventually(|| pool.metrics().shrink_latencies_us.len() == 2));
+    }
+}
@@ -0,0 +1,215 @@
+//! Hot reload of a running reflex policy
+//!
+//! A long-running pool shouldn't need a restart to pick up a retrained
+//! model. [`ReloadableReflexPolicy`] polls its `.reflex` path and, when the
+//! file's modification time or size changes, loads it, checks it as
+//! `reflex:` specs are checked (a normalizer that can feed it) and against
+//! the running model's shape, and swaps it in between two decisions. A
+//! file that fails (often one caught mid-write) leaves the running model
+//! in place and is tried again once it changes.
+
+use crate::{embedded_normalizer, PoolSizeDecision, PoolSizePolicy, ReflexPolicy};
+use reflex_format::{check_shape, FileWatch, Reflex};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+use telemetry_compute::{ComputeTelemetry, Normalizer};
+
+/// Reflex policy that picks up rewrites of its `.reflex` file
+pub struct ReloadableReflexPolicy {
+    watch: FileWatch,
+    policy: ReflexPolicy,
+    normalizer: Option<Normalizer>, // used for every version; otherwise each file's embedded one
+    reloads: usize,
+    rejected: usize,
+    last_error: Option<String>,
+}
+
+impl ReloadableReflexPolicy {
+    pub const DEFAULT_POLL_INTERVAL: Duration = FileWatch::DEFAULT_POLL_INTERVAL;
+
+    /// Watch `path` for models to replace `policy`'s with
+    ///
+    /// New versions bring their embedded normalizer, or keep the running
+    /// one if they have none. The file is first tried at the next check.
+    pub fn new(path: impl AsRef<Path>, policy: ReflexPolicy) -> Self {
+        Self {
+            watch: FileWatch::new(path),
+            policy,
+            normalizer: None,
+            reloads: 0,
+            rejected: 0,
+            last_error: None,
+        }
+    }
+
+    /// Load `path` into a default [`ReflexPolicy`] and watch it
+    ///
+    /// `normalizer`, if given, feeds every version instead of their embedded ones.
+    pub fn load(path: impl AsRef<Path>, normalizer: Option<Normalizer>) -> io::Result<Self> {
+        let mut watch = FileWatch::new(path);
+        let reflex = Reflex::from_bytes(&watch.read()?)?;
+        let running = Self::pick_normalizer(&reflex, normalizer.as_ref(), None)?;
+        trainer::check_normalizer(&reflex, &running)?;
+        let mut reloadable = Self::new(watch.path(), ReflexPolicy::new(reflex, running));
+        reloadable.watch = watch;
+        reloadable.normalizer = normalizer;
+        Ok(reloadable)
+    }
+
+    /// How often `decide` looks at the file (default 1s, wall clock); zero checks every decision
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.watch = self.watch.with_poll_interval(poll_interval);
+        self
+    }
+
+    /// Swap in the file if it changed since it was last tried
+    ///
+    /// Returns whether a new model is now running. A file that fails to
+    /// load, has a different feature or output count than the running
+    /// model, or can't be fed by its normalizer is an error, and the
+    /// running model stays.
+    pub fn check(&mut self) -> io::Result<bool> {
+        let Some(bytes) = self.watch.changed()? else {
+            return Ok(false);
+        };
+        match self.validate(&bytes) {
+            Ok((reflex, normalizer)) => {
+                self.policy.replace_model(reflex, normalizer);
+                self.reloads += 1;
+                self.last_error = None;
+                Ok(true)
+            }
+            Err(e) => {
+                self.rejected += 1;
+                self.last_error = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// Models swapped in since loading
+    pub fn reloads(&self) -> usize {
+        self.reloads
+    }
+
+    /// File versions rejected since loading
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    /// Why the latest version was rejected, until one loads
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    pub fn reflex(&self) -> &Reflex {
+        self.policy.reflex()
+    }
+
+    fn validate(&self, bytes: &[u8]) -> io::Result<(Reflex, Normalizer)> {
+        let reflex = Reflex::from_bytes(bytes)?;
+        check_shape(self.policy.reflex(), &reflex)?;
+        let normalizer = Self::pick_normalizer(&reflex, self.normalizer.as_ref(), Some(self.policy.normalizer()))?;
+        trainer::check_normalizer(&reflex, &normalizer)?;
+        Ok((reflex, normalizer))
+    }
+
+    /// The fixed normalizer, else the embedded one, else the running one
+    fn pick_normalizer(reflex: &Reflex, fixed: Option<&Normalizer>, running: Option<&Normalizer>) -> io::Result<Normalizer> {
+        fixed
+            .cloned()
+            .or_else(|| embedded_normalizer(reflex))
+            .or_else(|| running.cloned())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no normalizer embedded or given"))
+    }
+}
+
+impl PoolSizePolicy for ReloadableReflexPolicy {
+    fn decide(&mut self, telem: &ComputeTelemetry) -> PoolSizeDecision {
+        if self.watch.poll_due() {
+            // A rejected or missing file keeps the running model; `last_error` says why
+            if let Err(e) = self.check() {
+                self.last_error = Some(e.to_string());
+            }
+        }
+        self.policy.decide(telem)
+    }
+
+    fn last_features(&self) -> Option<[f32; ComputeTelemetry::FEATURE_COUNT]> {
+        self.policy.last_features()
+    }
+
+    fn shadow_decision(&self) -> Option<PoolSizeDecision> {
+        self.policy.shadow_decision()
+    }
+
+    fn ood_fallback(&self) -> bool {
+        self.policy.ood_fallback()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reflex_format::*;
+    use std::fs;
+
+    const N: usize = ComputeTelemetry::FEATURE_COUNT;
+
+    fn constant_reflex(n_workers: f32, feature_count: usize) -> Reflex {
+        Reflex {
+            header: ReflexHeader::new(ModelType::DecisionTree, feature_count as u8, 1, 0, 0, 0, 0),
+            trees: vec![vec![TreeNode::leaf(n_workers)]],
+            ensembles: Vec::new(),
+            linear: Vec::new(),
+            multi_output: None,
+            tables: Vec::new(),
+            bounds: OutputBounds {
+                min: vec![1.0],
+                max: vec![64.0],
+            },
+            metadata: ReflexMetadata::default(),
+            report: None,
+        }
+    }
+
+    fn fit_normalizer() -> Normalizer {
+        Normalizer {
+            min: [0.0; N],
+            max: [1.0; N],
+        }
+    }
+
+    #[test]
+    fn test_reload_checks_normalizer_and_shape_before_swapping() {
+        let path = std::env::temp_dir().join(format!("nematode-compute-reload-{}.reflex", std::process::id()));
+        let write = |reflex: &Reflex| fs::write(&path, reflex.to_bytes().unwrap()).unwrap();
+        let running = |policy: &ReloadableReflexPolicy| policy.reflex().infer(&[0.5; N])[0];
+        write(&constant_reflex(4.0, N));
+
+        // Neither embedded nor given, or unfit: nothing can feed the model
+        assert!(ReloadableReflexPolicy::load(&path, None).is_err());
+        assert!(ReloadableReflexPolicy::load(&path, Some(Normalizer::new())).is_err());
+        let mut policy = ReloadableReflexPolicy::load(&path, Some(fit_normalizer()))
+            .unwrap()
+            .with_poll_interval(Duration::ZERO);
+        assert!(!policy.check().unwrap());
+
+        write(&constant_reflex(8.0, N));
+        assert!(policy.check().unwrap());
+        assert_eq!(running(&policy), 8.0);
+
+        // Truncated mid-write, then a model for another schema: both rejected
+        let bytes = constant_reflex(16.0, N).to_bytes().unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 3]).unwrap();
+        assert!(policy.check().is_err());
+        write(&constant_reflex(16.0, N + 2));
+        assert!(policy.check().is_err());
+        assert_eq!(running(&policy), 8.0);
+        assert_eq!((policy.reloads(), policy.rejected()), (1, 2));
+        assert!(policy.last_error().unwrap().contains("features"));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
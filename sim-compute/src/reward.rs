@@ -0,0 +1,83 @@
+//! Reward specification for label generation
+//!
+//! What counts as the "optimal" pool size when `sweep` labels a dataset:
+//! a weighted cost over a run's p95 task time, throughput, decision churn
+//! and SLO violations, lowest wins. The spec carries a version that is
+//! written next to every label, so datasets built under different
+//! definitions of optimal can't be mixed up. Specs load from TOML; see
+//! `sim-compute/rewards/` for examples.
+
+use crate::Metrics;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// Weighted run cost; lower is better
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reward {
+    /// Recorded with every label, e.g. `balanced-v1`
+    pub version: String,
+    /// Cost per µs of p95 task time
+    #[serde(default)]
+    pub p95_weight: f64,
+    /// Credit per task/s of mean throughput
+    #[serde(default)]
+    pub throughput_weight: f64,
+    /// Cost per decision change per minute
+    #[serde(default)]
+    pub decision_change_weight: f64,
+    /// Task time above which a task violates the SLO; 0 disables the term
+    #[serde(default)]
+    pub slo_us: u64,
+    /// Cost per percentage point of tasks over `slo_us`
+    #[serde(default)]
+    pub slo_violation_weight: f64,
+}
+
+impl Reward {
+    /// Plain p95 task time, the sweep's original definition of optimal
+    pub fn p95() -> Self {
+        Self {
+            version: "p95-v1".to_string(),
+            p95_weight: 1.0,
+            throughput_weight: 0.0,
+            decision_change_weight: 0.0,
+            slo_us: 0,
+            slo_violation_weight: 0.0,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        let reward: Self = toml::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if reward.version.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "reward needs a version"));
+        }
+        Ok(reward)
+    }
+
+    /// The run's cost under this reward
+    pub fn cost(&self, metrics: &Metrics) -> f64 {
+        self.p95_weight * metrics.p95_task_time() - self.throughput_weight * metrics.mean_throughput()
+            + self.decision_change_weight * metrics.changes_per_minute()
+            + self.slo_violation_weight * 100.0 * self.slo_violation_fraction(metrics)
+    }
+
+    /// Fraction of completed tasks slower than `slo_us` (0 when the SLO is disabled)
+    pub fn slo_violation_fraction(&self, metrics: &Metrics) -> f64 {
+        if self.slo_us == 0 || metrics.task_times_us.is_empty() {
+            return 0.0;
+        }
+        let violations = metrics.task_times_us.iter().filter(|&&t| t > self.slo_us).count();
+        violations as f64 / metrics.task_times_us.len() as f64
+    }
+}
+
+impl Default for Reward {
+    fn default() -> Self {
+        Self::p95()
+    }
+}
@@ -0,0 +1,79 @@
+//! Scenario definition files
+//!
+//! A scenario is a TOML description of one compute experiment (workload
+//! phases, policy, initial pool size, core limit, seeds), in the same
+//! format as the transport simulator's scenarios. See
+//! `sim-compute/scenarios/` for examples.
+
+use crate::{policy_from_spec, run_repeated, CoreModel, RunResult, ThreadPoolSim, WorkloadConfig, WorkloadPhase};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// One experiment: phases played in order, repeated once per seed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    /// Baseline policy name or `reflex:<model>[,<normalizer>]`
+    pub policy: String,
+    #[serde(default = "default_initial_workers")]
+    pub initial_workers: u32,
+    /// Physical cores shared by the workers (default: one per worker)
+    #[serde(default)]
+    pub cores: Option<u32>,
+    #[serde(default = "default_seeds")]
+    pub seeds: Vec<u64>,
+    pub phases: Vec<WorkloadPhase>,
+}
+
+fn default_initial_workers() -> u32 {
+    8
+}
+
+fn default_seeds() -> Vec<u64> {
+    vec![0]
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        toml::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// The phases as a single sequence workload
+    pub fn workload(&self) -> WorkloadConfig {
+        WorkloadConfig::Sequence {
+            phases: self.phases.clone(),
+        }
+    }
+
+    /// Total length of all phases
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.phases.iter().map(|phase| phase.duration_secs).sum())
+    }
+
+    /// Run the scenario once per seed on the virtual clock
+    pub fn run(&self) -> io::Result<Vec<RunResult>> {
+        // Fail on a bad policy spec before starting any run
+        policy_from_spec(&self.policy)?;
+        let workload = self.workload();
+        run_repeated(
+            &self.policy,
+            || {
+                let policy = policy_from_spec(&self.policy).expect("policy spec validated above");
+                let sim = ThreadPoolSim::new(policy, self.initial_workers);
+                match self.cores {
+                    Some(cores) => sim.with_cores(CoreModel::new(cores)),
+                    None => sim,
+                }
+            },
+            |seed| workload.build_seeded(self.duration(), seed),
+            self.duration(),
+            &self.seeds,
+        )
+    }
+}
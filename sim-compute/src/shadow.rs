@@ -0,0 +1,108 @@
+//! Shadow deployment metrics
+//!
+//! A candidate reflex can ride along with the running one
+//! (`ReflexPolicy::with_candidate`): it is asked on every decision but its
+//! answer is never applied. [`ShadowMetrics`] accumulates how far apart the
+//! candidate and the applied decisions were, so promoting a candidate
+//! rests on how it would have acted on the same traffic rather than on
+//! offline validation alone.
+
+use crate::PoolSizeDecision;
+use std::fmt;
+
+/// Upper bounds, in workers, of the divergence histogram's buckets; a last bucket holds the rest
+pub const DIVERGENCE_BUCKETS: [u32; 6] = [0, 1, 2, 4, 8, 16];
+
+/// Candidate decisions against the applied ones
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShadowMetrics {
+    pub decisions: usize,
+    pub disagreements: usize,     // candidate decision differed from the applied one
+    pub candidate_larger: usize,  // of those, wanting more workers
+    pub candidate_smaller: usize, // of those, wanting fewer workers
+    pub divergence_histogram: [usize; DIVERGENCE_BUCKETS.len() + 1], // |candidate - applied| workers per bucket
+    pub divergence_sum: u64,
+    pub max_divergence: u32,
+}
+
+impl ShadowMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, applied: PoolSizeDecision, candidate: PoolSizeDecision) {
+        let divergence = candidate.n_workers.abs_diff(applied.n_workers);
+        self.decisions += 1;
+        if candidate != applied {
+            self.disagreements += 1;
+        }
+        if candidate.n_workers > applied.n_workers {
+            self.candidate_larger += 1;
+        } else if candidate.n_workers < applied.n_workers {
+            self.candidate_smaller += 1;
+        }
+        let bucket = DIVERGENCE_BUCKETS
+            .iter()
+            .position(|&bound| divergence <= bound)
+            .unwrap_or(DIVERGENCE_BUCKETS.len());
+        self.divergence_histogram[bucket] += 1;
+        self.divergence_sum += divergence as u64;
+        self.max_divergence = self.max_divergence.max(divergence);
+    }
+
+    /// Fraction of decisions where the candidate would have acted differently
+    pub fn disagreement_rate(&self) -> f64 {
+        if self.decisions == 0 {
+            return 0.0;
+        }
+        self.disagreements as f64 / self.decisions as f64
+    }
+
+    /// Mean |candidate - applied| in workers
+    pub fn mean_divergence(&self) -> f64 {
+        if self.decisions == 0 {
+            return 0.0;
+        }
+        self.divergence_sum as f64 / self.decisions as f64
+    }
+
+    /// Fraction of decisions within `workers` of the applied pool size
+    pub fn within(&self, workers: u32) -> f64 {
+        if self.decisions == 0 {
+            return 0.0;
+        }
+        let within: usize = DIVERGENCE_BUCKETS
+            .iter()
+            .zip(&self.divergence_histogram)
+            .filter(|(&bound, _)| bound <= workers)
+            .map(|(_, &count)| count)
+            .sum();
+        within as f64 / self.decisions as f64
+    }
+}
+
+impl fmt::Display for ShadowMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Shadow candidate: {} of {} decisions differ ({:.1}%), {} larger / {} smaller",
+            self.disagreements,
+            self.decisions,
+            self.disagreement_rate() * 100.0,
+            self.candidate_larger,
+            self.candidate_smaller
+        )?;
+        writeln!(f, "Divergence: mean {:.2} workers, max {}", self.mean_divergence(), self.max_divergence)?;
+        let mut low = 0;
+        for (i, &count) in self.divergence_histogram.iter().enumerate() {
+            let label = match DIVERGENCE_BUCKETS.get(i) {
+                Some(&bound) if bound == low => format!("{}", bound),
+                Some(&bound) => format!("{}-{}", low, bound),
+                None => format!(">{}", DIVERGENCE_BUCKETS[DIVERGENCE_BUCKETS.len() - 1]),
+            };
+            writeln!(f, "  {:>6} workers: {}", label, count)?;
+            low = DIVERGENCE_BUCKETS.get(i).map_or(low, |&bound| bound + 1);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+//! Wilcoxon signed-rank test for paired pool runs; `MeanCi` is shared through policy-compare
+
+use policy_compare::normal_cdf;
+use serde::{Deserialize, Serialize};
+
+pub use policy_compare::MeanCi;
+
+/// Wilcoxon signed-rank test result (two-sided, normal approximation)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SignedRank {
+    /// Rank sum of the positive differences
+    pub w: f64,
+    pub z: f64,
+    pub p_value: f64,
+}
+
+/// Wilcoxon signed-rank test of whether paired differences are centered on zero
+///
+/// Zero differences are dropped, tied magnitudes get average ranks and the
+/// variance is tie-corrected.
+pub fn wilcoxon_signed_rank(diffs: &[f64]) -> SignedRank {
+    let mut nonzero: Vec<f64> = diffs.iter().copied().filter(|d| *d != 0.0).collect();
+    nonzero.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+    let n = nonzero.len();
+
+    // Average ranks over tie groups of |d|
+    let mut w = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < n {
+        let mut j = i;
+        while j + 1 < n && nonzero[j + 1].abs() == nonzero[i].abs() {
+            j += 1;
+        }
+        let avg_rank = (i + j) as f64 / 2.0 + 1.0;
+        let ties = (j - i + 1) as f64;
+        tie_term += ties.powi(3) - ties;
+        w += nonzero[i..=j].iter().filter(|d| **d > 0.0).count() as f64 * avg_rank;
+        i = j + 1;
+    }
+
+    let n = n as f64;
+    let mu = n * (n + 1.0) / 4.0;
+    let sigma = (n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - tie_term / 48.0).sqrt();
+    if sigma == 0.0 {
+        return SignedRank {
+            w,
+            z: 0.0,
+            p_value: 1.0,
+        };
+    }
+
+    // Continuity correction toward the mean
+    let z = (w - mu - 0.5 * (w - mu).signum()) / sigma;
+    SignedRank {
+        w,
+        z,
+        p_value: (2.0 * (1.0 - normal_cdf(z.abs()))).min(1.0),
+    }
+}
@@ -0,0 +1,58 @@
+//! Decision timeline recording
+//!
+//! Optional per-invocation audit log of what the sizing policy saw (raw
+//! telemetry and, for reflexes, normalized model inputs) and decided,
+//! plus a fixed-cadence series of pool state (workers, busy workers, run
+//! queue) for plotting when a policy resized the pool.
+
+use crate::PoolSizeDecision;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use telemetry_compute::ComputeTelemetry;
+
+/// One policy invocation: what the policy saw and what it decided
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DecisionRecord {
+    /// Microseconds since the simulator was created
+    pub timestamp_us: u64,
+    pub telemetry: ComputeTelemetry,
+    /// Normalized features fed to the model; None for policies without one
+    /// and for reflex decisions held over from an earlier inference
+    #[serde(default)]
+    pub features: Option<[f32; ComputeTelemetry::FEATURE_COUNT]>,
+    pub decision: PoolSizeDecision,
+}
+
+/// Write records as JSON Lines, one record per line
+pub fn write_decision_log(records: &[DecisionRecord], path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Pool state at one sampling instant
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StateSample {
+    /// Microseconds since the simulator was created
+    pub timestamp_us: u64,
+    /// Workers not draining, i.e. the pool size in force
+    pub n_workers: usize,
+    /// Workers finishing their last task before retiring
+    pub draining_workers: usize,
+    pub busy_workers: usize,
+    pub runq_len: usize,
+}
+
+/// Write samples as CSV, one row per sampling instant
+pub fn write_state_series(samples: &[StateSample], path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for sample in samples {
+        writer.serialize(sample)?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,133 @@
+//! Out-of-distribution watchdog
+//!
+//! A reflex extrapolates silently: a feature far outside anything it was
+//! trained on still lands in some leaf. The normalizer records the
+//! training range, so a normalized feature well outside [0, 1] is input
+//! the model never saw. [`OodWatchdog`] checks every normalized feature
+//! vector before inference, optionally against a density score too, and
+//! trips after a run of out-of-distribution vectors; `ReflexPolicy` then
+//! decides with its fallback until the input has been back in range for a
+//! while. Each trip counts as one alert.
+
+use telemetry_compute::ComputeTelemetry;
+
+type Features = [f32; ComputeTelemetry::FEATURE_COUNT];
+
+/// Scores how typical a normalized feature vector is of the training data; higher is more typical
+pub type DensityScore = Box<dyn Fn(&Features) -> f32 + Send>;
+
+/// Flags sustained out-of-distribution input
+pub struct OodWatchdog {
+    low: f32,  // normalized values below this are out of range
+    high: f32, // and above this
+    density: Option<(DensityScore, f32)>, // and vectors scoring under the minimum
+    trip_after: usize,    // consecutive out-of-distribution vectors before tripping
+    recover_after: usize, // consecutive in-distribution vectors before recovering
+    ood_streak: usize,
+    in_streak: usize,
+    tripped: bool,
+    checked: usize,
+    out_of_distribution: usize,
+    alerts: usize,
+    feature_hits: [usize; ComputeTelemetry::FEATURE_COUNT], // out-of-range count per feature
+}
+
+impl OodWatchdog {
+    /// [-0.05, 1.05] for min-max normalizers, tripping after 5 vectors and recovering after 20
+    pub fn new() -> Self {
+        Self {
+            low: -0.05,
+            high: 1.05,
+            density: None,
+            trip_after: 5,
+            recover_after: 20,
+            ood_streak: 0,
+            in_streak: 0,
+            tripped: false,
+            checked: 0,
+            out_of_distribution: 0,
+            alerts: 0,
+            feature_hits: [0; ComputeTelemetry::FEATURE_COUNT],
+        }
+    }
+
+    /// Normalized range counted as seen in training; widen it for z-scored normalizers
+    pub fn with_range(mut self, low: f32, high: f32) -> Self {
+        assert!(low < high, "need low < high");
+        self.low = low;
+        self.high = high;
+        self
+    }
+
+    /// Also count vectors that `score` rates below `min` as out of distribution
+    pub fn with_density(mut self, score: DensityScore, min: f32) -> Self {
+        self.density = Some((score, min));
+        self
+    }
+
+    /// Trip after `trip_after` out-of-distribution vectors in a row, recover after `recover_after` in range
+    pub fn with_hysteresis(mut self, trip_after: usize, recover_after: usize) -> Self {
+        assert!(trip_after > 0 && recover_after > 0, "streaks are at least one vector");
+        self.trip_after = trip_after;
+        self.recover_after = recover_after;
+        self
+    }
+
+    /// Check one normalized vector; returns whether the watchdog is tripped after it
+    pub fn check(&mut self, features: &Features) -> bool {
+        self.checked += 1;
+        let mut in_range = true;
+        for (value, hits) in features.iter().zip(&mut self.feature_hits) {
+            if !(self.low..=self.high).contains(value) {
+                *hits += 1;
+                in_range = false;
+            }
+        }
+        let typical = self.density.as_ref().is_none_or(|(score, min)| score(features) >= *min);
+        if in_range && typical {
+            self.in_streak += 1;
+            self.ood_streak = 0;
+        } else {
+            self.out_of_distribution += 1;
+            self.ood_streak += 1;
+            self.in_streak = 0;
+        }
+        if !self.tripped && self.ood_streak >= self.trip_after {
+            self.tripped = true;
+            self.alerts += 1;
+        } else if self.tripped && self.in_streak >= self.recover_after {
+            self.tripped = false;
+        }
+        self.tripped
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.tripped
+    }
+
+    /// Vectors checked
+    pub fn checked(&self) -> usize {
+        self.checked
+    }
+
+    /// Vectors out of range or atypical
+    pub fn out_of_distribution(&self) -> usize {
+        self.out_of_distribution
+    }
+
+    /// Times the watchdog tripped
+    pub fn alerts(&self) -> usize {
+        self.alerts
+    }
+
+    /// How often each feature was out of range, in schema order
+    pub fn feature_hits(&self) -> &[usize; ComputeTelemetry::FEATURE_COUNT] {
+        &self.feature_hits
+    }
+}
+
+impl Default for OodWatchdog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
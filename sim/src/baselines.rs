@@ -0,0 +1,427 @@
+//! Classical adaptive flush policies
+//!
+//! Well-known heuristics the reflex has to beat, beyond the static
+//! threshold=16 baseline.
+
+use crate::{BaselinePolicy, FlushDecision, FlushPolicy};
+use std::time::{Duration, Instant};
+use telemetry::TelemetrySample;
+
+/// Look up a non-learned policy by CLI name (static, nagle, codel, aimd, pid, idle)
+pub fn baseline_by_name(name: &str) -> Option<Box<dyn FlushPolicy>> {
+    let policy: Box<dyn FlushPolicy> = match name {
+        "static" => Box::new(BaselinePolicy::new()),
+        "nagle" => Box::new(NaglePolicy::default()),
+        "codel" => Box::new(CoDelPolicy::default()),
+        "aimd" => Box::new(AimdPolicy::default()),
+        "pid" => Box::new(PidPolicy::default()),
+        "idle" => Box::new(IdleFlushPolicy::new(BaselinePolicy::new(), 1000)),
+        _ => return None,
+    };
+    Some(policy)
+}
+
+/// Early flush on an idle link, wrapping any policy
+///
+/// When the link has been idle for at least `idle_after_us`, a partial batch
+/// costs little to send (see [`LinkModel::with_idle_flush_cost`]), so flush
+/// immediately; otherwise defer to the inner policy.
+///
+/// [`LinkModel::with_idle_flush_cost`]: crate::LinkModel::with_idle_flush_cost
+pub struct IdleFlushPolicy<P: FlushPolicy> {
+    inner: P,
+    idle_after_us: f32,
+}
+
+impl<P: FlushPolicy> IdleFlushPolicy<P> {
+    pub fn new(inner: P, idle_after_us: u32) -> Self {
+        Self {
+            inner,
+            idle_after_us: idle_after_us as f32,
+        }
+    }
+}
+
+impl<P: FlushPolicy> FlushPolicy for IdleFlushPolicy<P> {
+    fn decide(&mut self, telem: &TelemetrySample) -> FlushDecision {
+        let decision = self.inner.decide(telem);
+        if telem.link_idle_us >= self.idle_after_us {
+            return FlushDecision {
+                threshold: 1,
+                max_delay_us: 0,
+                ..decision
+            };
+        }
+        decision
+    }
+
+    fn suppressed_changes(&self) -> usize {
+        self.inner.suppressed_changes()
+    }
+}
+
+/// Nagle-like delayed batching
+///
+/// Sends immediately while the link is idle; otherwise holds packets until a
+/// full segment's worth of bytes is queued or roughly one RTT has passed
+/// (standing in for the ACK that would release the buffer).
+pub struct NaglePolicy {
+    mss_bytes: u32,
+    max_delay_cap_us: u32,
+}
+
+impl NaglePolicy {
+    pub fn new(mss_bytes: u32, max_delay_cap_us: u32) -> Self {
+        Self {
+            mss_bytes,
+            max_delay_cap_us,
+        }
+    }
+}
+
+impl Default for NaglePolicy {
+    fn default() -> Self {
+        Self::new(1460 * 16, 2000)
+    }
+}
+
+impl FlushPolicy for NaglePolicy {
+    fn decide(&mut self, telem: &TelemetrySample) -> FlushDecision {
+        // Nothing sent in the last window: no outstanding data to wait on
+        if telem.dequeue_rate == 0.0 {
+            return FlushDecision {
+                threshold: 1,
+                max_delay_us: 0,
+                segment_bytes: None,
+                pacing_rate: None,
+            };
+        }
+
+        let packet_size = telem.packet_size_mean.max(1.0);
+        let threshold = (self.mss_bytes as f32 / packet_size).ceil().max(1.0) as u32;
+        let max_delay_us = (telem.rtt_ewma_us.round() as u32).min(self.max_delay_cap_us);
+
+        FlushDecision {
+            threshold,
+            max_delay_us,
+            segment_bytes: None,
+            pacing_rate: None,
+        }
+    }
+}
+
+/// CoDel-inspired target-delay control
+///
+/// Batches freely while the queueing delay stays under `target`. Once it has
+/// been above target for a whole `interval`, flushing tightens following
+/// CoDel's control law (delay budget shrinks with 1/sqrt(count)) until the
+/// delay drops back under target.
+pub struct CoDelPolicy {
+    base_threshold: u32,
+    target_us: u32,
+    interval: Duration,
+    above_target_since: Option<Instant>,
+    count: u32,
+}
+
+impl CoDelPolicy {
+    pub fn new(base_threshold: u32, target_us: u32, interval: Duration) -> Self {
+        Self {
+            base_threshold,
+            target_us,
+            interval,
+            above_target_since: None,
+            count: 0,
+        }
+    }
+}
+
+impl Default for CoDelPolicy {
+    fn default() -> Self {
+        Self::new(32, 500, Duration::from_millis(10))
+    }
+}
+
+impl FlushPolicy for CoDelPolicy {
+    fn decide(&mut self, telem: &TelemetrySample) -> FlushDecision {
+        let now = Instant::now();
+
+        if telem.latency_p95_us < self.target_us as f32 {
+            self.above_target_since = None;
+            self.count = 0;
+        } else {
+            match self.above_target_since {
+                None => self.above_target_since = Some(now),
+                Some(since) if now.duration_since(since) >= self.interval => {
+                    self.count += 1;
+                    self.above_target_since = Some(now);
+                }
+                Some(_) => {}
+            }
+        }
+
+        let scale = ((self.count + 1) as f32).sqrt();
+        FlushDecision {
+            threshold: ((self.base_threshold as f32 / scale) as u32).max(1),
+            max_delay_us: (self.target_us as f32 / scale) as u32,
+            segment_bytes: None,
+            pacing_rate: None,
+        }
+    }
+}
+
+/// AIMD threshold adjustment
+///
+/// Once per `interval`, grows the batch threshold by one packet while the
+/// queueing delay is under target and cuts it multiplicatively when it is not.
+pub struct AimdPolicy {
+    threshold: f32,
+    min_threshold: u32,
+    max_threshold: u32,
+    target_us: u32,
+    decrease_factor: f32,
+    interval: Duration,
+    last_adjust: Option<Instant>,
+}
+
+impl AimdPolicy {
+    pub fn new(target_us: u32, min_threshold: u32, max_threshold: u32) -> Self {
+        Self {
+            threshold: min_threshold as f32,
+            min_threshold,
+            max_threshold,
+            target_us,
+            decrease_factor: 0.5,
+            interval: Duration::from_millis(10),
+            last_adjust: None,
+        }
+    }
+
+    /// Multiplicative decrease factor (default: 0.5)
+    pub fn with_decrease_factor(mut self, decrease_factor: f32) -> Self {
+        self.decrease_factor = decrease_factor;
+        self
+    }
+
+    /// Adjustment interval (default: 10ms)
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+impl Default for AimdPolicy {
+    fn default() -> Self {
+        Self::new(500, 1, 64)
+    }
+}
+
+impl FlushPolicy for AimdPolicy {
+    fn decide(&mut self, telem: &TelemetrySample) -> FlushDecision {
+        let now = Instant::now();
+        let due = self
+            .last_adjust
+            .is_none_or(|last| now.duration_since(last) >= self.interval);
+
+        if due {
+            if telem.latency_p95_us > self.target_us as f32 {
+                self.threshold *= self.decrease_factor;
+            } else {
+                self.threshold += 1.0;
+            }
+            self.threshold = self
+                .threshold
+                .clamp(self.min_threshold as f32, self.max_threshold as f32);
+            self.last_adjust = Some(now);
+        }
+
+        FlushDecision {
+            threshold: self.threshold as u32,
+            max_delay_us: self.target_us,
+            segment_bytes: None,
+            pacing_rate: None,
+        }
+    }
+}
+
+/// PID controller gains
+///
+/// The derivative term is off by default: per-tick telemetry is noisy enough
+/// that it mostly amplifies jitter.
+#[derive(Debug, Clone, Copy)]
+pub struct PidGains {
+    pub kp: f32,
+    pub ki: f32,
+    pub kd: f32,
+}
+
+impl Default for PidGains {
+    fn default() -> Self {
+        Self {
+            kp: 0.5,
+            ki: 2.0,
+            kd: 0.0,
+        }
+    }
+}
+
+/// PID controller regulating p95 queueing delay toward a target
+///
+/// The error is normalized by the target, so gains are unitless. A positive
+/// control output (delay too high) shrinks both the batch threshold and the
+/// delay budget from their nominal values; a negative one grows them.
+pub struct PidPolicy {
+    target_us: f32,
+    gains: PidGains,
+    nominal_threshold: u32,
+    max_threshold: u32,
+    integral: f32,
+    last_error: Option<f32>,
+    last_time: Option<Instant>,
+}
+
+impl PidPolicy {
+    pub fn new(target_us: u32, gains: PidGains) -> Self {
+        Self {
+            target_us: target_us as f32,
+            gains,
+            nominal_threshold: 16,
+            max_threshold: 64,
+            integral: 0.0,
+            last_error: None,
+            last_time: None,
+        }
+    }
+
+    /// Threshold at zero control output and its upper bound (default: 16, 64)
+    pub fn with_threshold_range(mut self, nominal: u32, max: u32) -> Self {
+        self.nominal_threshold = nominal;
+        self.max_threshold = max;
+        self
+    }
+
+    fn control(&mut self, error: f32, now: Instant) -> f32 {
+        let dt = self
+            .last_time
+            .map_or(0.0, |last| now.duration_since(last).as_secs_f32());
+
+        // Anti-windup: the integral term alone never saturates the output
+        if self.gains.ki > 0.0 {
+            let limit = 1.0 / self.gains.ki;
+            self.integral = (self.integral + error * dt).clamp(-limit, limit);
+        }
+        let derivative = match self.last_error {
+            Some(last) if dt > 0.0 => (error - last) / dt,
+            _ => 0.0,
+        };
+
+        self.last_error = Some(error);
+        self.last_time = Some(now);
+
+        self.gains.kp * error + self.gains.ki * self.integral + self.gains.kd * derivative
+    }
+}
+
+impl Default for PidPolicy {
+    fn default() -> Self {
+        Self::new(500, PidGains::default())
+    }
+}
+
+impl FlushPolicy for PidPolicy {
+    fn decide(&mut self, telem: &TelemetrySample) -> FlushDecision {
+        let error = (telem.latency_p95_us - self.target_us) / self.target_us;
+        let output = self.control(error, Instant::now());
+
+        // Map control output to a multiplier on the nominal operating point
+        let scale = (1.0 - output).clamp(0.0, 4.0);
+        let threshold = (self.nominal_threshold as f32 * scale).round() as u32;
+
+        FlushDecision {
+            threshold: threshold.clamp(1, self.max_threshold),
+            max_delay_us: (self.target_us * scale).round() as u32,
+            segment_bytes: None,
+            pacing_rate: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aimd_cuts_on_high_latency() {
+        let mut policy = AimdPolicy::new(500, 1, 64).with_interval(Duration::ZERO);
+        let calm = TelemetrySample {
+            latency_p95_us: 100.0,
+            ..Default::default()
+        };
+        for _ in 0..9 {
+            policy.decide(&calm);
+        }
+        assert_eq!(policy.decide(&calm).threshold, 11);
+
+        let congested = TelemetrySample {
+            latency_p95_us: 900.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.decide(&congested).threshold, 5);
+    }
+
+    #[test]
+    fn test_pid_shrinks_batches_when_late() {
+        let gains = PidGains { kp: 0.5, ki: 0.0, kd: 0.0 };
+        let mut policy = PidPolicy::new(500, gains);
+
+        let on_target = TelemetrySample {
+            latency_p95_us: 500.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.decide(&on_target).threshold, 16);
+
+        let late = TelemetrySample {
+            latency_p95_us: 1000.0,
+            ..Default::default()
+        };
+        let decision = policy.decide(&late);
+        assert_eq!(decision.threshold, 8);
+        assert_eq!(decision.max_delay_us, 250);
+    }
+
+    #[test]
+    fn test_idle_flush_overrides_inner_policy() {
+        let mut policy = IdleFlushPolicy::new(BaselinePolicy::new(), 1000);
+        let busy = TelemetrySample {
+            link_idle_us: 10.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.decide(&busy).threshold, 16);
+
+        let idle = TelemetrySample {
+            link_idle_us: 5000.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.decide(&idle).threshold, 1);
+    }
+
+    #[test]
+    fn test_nagle_sends_immediately_when_idle() {
+        let mut policy = NaglePolicy::new(16 * 1024, 2000);
+        let idle = TelemetrySample {
+            packet_size_mean: 1024.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.decide(&idle).threshold, 1);
+
+        let busy = TelemetrySample {
+            dequeue_rate: 1000.0,
+            packet_size_mean: 1024.0,
+            rtt_ewma_us: 80.0,
+            ..Default::default()
+        };
+        let decision = policy.decide(&busy);
+        assert_eq!(decision.threshold, 16);
+        assert_eq!(decision.max_delay_us, 80);
+    }
+}
@@ -1,72 +1,82 @@
 //! Baseline policy runner
 //!
-//! Runs the fake transport with static flush policy
+//! Runs the fake transport with a non-learned flush policy
+//! (static by default, or one of the classical adaptive baselines)
 
-use sim::{BaselinePolicy, FakeTransport, SteadyWorkload, BurstyWorkload, AdversarialWorkload, WorkloadGenerator};
+use cli::{take_flag, take_parsed};
+use sim::{baseline_by_name, write_decision_log, write_state_series, DropPolicy, FakeTransport, LatencyHistogram, LatencySlo, RunResult, WorkloadConfig};
 use std::time::Duration;
-use std::thread;
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let policy_name = take_flag(&mut args, "--policy").unwrap_or_else(|| "static".to_string());
+    let output_path = take_flag(&mut args, "--output");
+    let decision_log_path = take_flag(&mut args, "--decision-log");
+    let histogram_path = take_flag(&mut args, "--histogram");
+    let state_series_path = take_flag(&mut args, "--state-series");
+    let state_interval = take_flag(&mut args, "--state-interval-ms")
+        .map(|s| Duration::from_millis(s.parse().expect("--state-interval-ms must be a whole number")))
+        .unwrap_or(Duration::from_millis(10));
+    let slo = take_parsed::<LatencySlo>(&mut args, "--slo");
+    let queue_capacity = take_flag(&mut args, "--queue-capacity")
+        .map(|s| s.parse::<usize>().expect("--queue-capacity must be a packet count"));
+    let backpressure = take_flag(&mut args, "--backpressure")
+        .map(|s| s.parse::<usize>().expect("--backpressure must be a queue depth"));
+    let warmup = take_flag(&mut args, "--warmup-secs")
+        .map(|s| Duration::from_secs_f64(s.parse().expect("--warmup-secs must be a number")))
+        .unwrap_or(Duration::ZERO);
     let workload_type = args.get(1).map(|s| s.as_str()).unwrap_or("steady");
 
-    println!("Running baseline policy with {} workload", workload_type);
+    println!("Running {} policy with {} workload", policy_name, workload_type);
 
-    let mut transport = FakeTransport::new(BaselinePolicy::new());
+    let policy = baseline_by_name(&policy_name).unwrap_or_else(|| {
+        eprintln!("Unknown policy: {}", policy_name);
+        eprintln!("  --policy static | nagle | codel | aimd | pid | idle");
+        std::process::exit(1);
+    });
+    let mut transport = FakeTransport::new(policy).with_warmup(warmup);
+    if let Some(slo) = slo {
+        transport = transport.with_slo(slo);
+    }
+    if let Some(capacity) = queue_capacity {
+        transport = transport.with_queue_capacity(capacity, DropPolicy::Tail);
+    }
+    if decision_log_path.is_some() {
+        transport = transport.with_decision_log();
+    }
+    if state_series_path.is_some() {
+        transport = transport.with_state_series(state_interval);
+    }
 
     // Create workload
     let duration = Duration::from_secs(30);
-    let mut workload: Box<dyn WorkloadGenerator> = match workload_type {
-        "steady" => Box::new(SteadyWorkload::new(1000.0, 1024, duration)),
-        "bursty" => Box::new(BurstyWorkload::new(
-            5000.0,
-            100.0,
-            1024,
-            Duration::from_secs(5),
-            duration,
-        )),
-        "adversarial" => Box::new(AdversarialWorkload::new(
-            1000.0,
-            (256, 2048),
-            duration,
-        )),
-        _ => {
+    let workload_config = match workload_type {
+        "trace" => {
+            let trace_path = args.get(2).unwrap_or_else(|| {
+                eprintln!("Usage: baseline trace <trace.csv>");
+                std::process::exit(1);
+            });
+            WorkloadConfig::Trace {
+                path: trace_path.clone(),
+            }
+        }
+        _ => WorkloadConfig::preset(workload_type).unwrap_or_else(|| {
             eprintln!("Unknown workload type: {}", workload_type);
-            eprintln!("Usage: baseline [steady|bursty|adversarial]");
+            eprintln!("Usage: baseline [steady|bursty|adversarial|heavytail|step|multiflow|trace <trace.csv>] [--policy <name>] [--output results.json] [--decision-log decisions.jsonl] [--histogram latency.csv] [--state-series state.csv] [--state-interval-ms N] [--backpressure DEPTH] [--queue-capacity N] [--slo p99:2000] [--warmup-secs N]");
             std::process::exit(1);
-        }
+        }),
+    };
+    let workload_config = match backpressure {
+        Some(max_queue_depth) => WorkloadConfig::ClosedLoop {
+            inner: Box::new(workload_config),
+            max_queue_depth,
+        },
+        None => workload_config,
     };
+    let mut workload = workload_config.build(duration).expect("Failed to build workload");
 
     // Run simulation
-    let start = std::time::Instant::now();
-    let tick_interval = Duration::from_micros(100); // 10 kHz tick rate
-
-    loop {
-        // Enqueue packets
-        while let Some((wait, size)) = workload.next_packet() {
-            if wait > Duration::ZERO {
-                thread::sleep(wait.min(tick_interval));
-            }
-            transport.enqueue(size);
-
-            // Tick transport
-            transport.tick();
-
-            if start.elapsed() >= duration {
-                break;
-            }
-        }
-
-        if start.elapsed() >= duration {
-            break;
-        }
-
-        thread::sleep(tick_interval);
-        transport.tick();
-    }
-
-    // Final flush
-    transport.tick();
+    transport.run(workload.as_mut(), duration);
 
     // Print metrics
     let metrics = transport.metrics();
@@ -77,5 +87,54 @@ fn main() {
     println!("p99 latency: {:.2} µs", metrics.p99_latency());
     println!("p99/p50 ratio: {:.2}", metrics.p99_latency() / metrics.p50_latency());
     println!("Mean throughput: {:.2} pkts/s", metrics.mean_throughput());
+    println!("Mean goodput: {:.2} bytes/s", metrics.mean_goodput());
+    println!("Loss rate: {:.4}", metrics.loss_rate());
+    println!("Drop rate: {:.4}", metrics.drop_rate());
+    if metrics.flow_delivered_bytes.len() > 1 {
+        println!("Jain fairness: {:.3}", metrics.jain_fairness());
+        println!("Worst flow p99: {:.2} µs", metrics.max_flow_p99());
+    }
     println!("Decision changes: {}", metrics.decision_changes);
+    println!("Changes per minute: {:.2}", metrics.changes_per_minute());
+    println!("Flip-flops: {}", metrics.flip_flops);
+    for report in metrics.slo_reports() {
+        println!(
+            "SLO p{} < {}μs: {}/{} windows violated, longest streak {}, {:.1}s in violation",
+            report.slo.percentile * 100.0,
+            report.slo.target_us,
+            report.violations,
+            report.windows,
+            report.longest_streak,
+            report.time_in_violation_secs
+        );
+    }
+    println!("Suppressed changes: {}", metrics.suppressed_changes);
+    if let WorkloadConfig::Step { step_at_ms, .. } = &workload_config {
+        if let Some(step) = metrics.step_response(Duration::from_millis(*step_at_ms)) {
+            println!("Step overshoot: {:.1}%", step.overshoot * 100.0);
+            println!("Step settling time: {:.1} ms", step.settling_time.as_secs_f64() * 1000.0);
+        }
+    }
+
+    if let Some(path) = output_path {
+        let result = RunResult::from_metrics(&policy_name, workload_config, duration, metrics);
+        RunResult::write_all(&[result], &path).expect("Failed to write results");
+        println!("\nResults written to {}", path);
+    }
+
+    if let Some(path) = histogram_path {
+        let histogram = metrics.latency_histogram(LatencyHistogram::DEFAULT_BUCKETS_PER_DECADE);
+        histogram.write(&path).expect("Failed to write histogram");
+        println!("Latency histogram written to {}", path);
+    }
+
+    if let (Some(path), Some(log)) = (decision_log_path, transport.decision_log()) {
+        write_decision_log(log, &path).expect("Failed to write decision log");
+        println!("Decision log written to {}", path);
+    }
+
+    if let (Some(path), Some(series)) = (state_series_path, transport.state_series()) {
+        write_state_series(series, &path).expect("Failed to write state series");
+        println!("State series written to {}", path);
+    }
 }
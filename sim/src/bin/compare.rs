@@ -0,0 +1,126 @@
+//! Repeated-run policy comparison
+//!
+//! Runs two policies over the same seeds and reports mean ± 95% CI per
+//! metric plus a Mann-Whitney test on each. With --paired, both policies see
+//! the identical arrival sequence per seed and paired differences are shown.
+//! Ends with a report of effect sizes, bootstrap CIs and a better / worse /
+//! inconclusive verdict for B against A (`--report` writes it as JSON).
+
+use cli::{take_flag, take_switch};
+use sim::{
+    compare_runs, paired_differences, policy_from_spec, run_paired, run_repeated, FakeTransport, FlushPolicy,
+    RunResult, RunSummary, WorkloadConfig,
+};
+use policy_compare::{ComparisonReport, Goal};
+use std::time::Duration;
+
+type Metric = fn(&RunResult) -> f64;
+
+/// Build a policy from a baseline name or `reflex:<model>[,<normalizer>]`, exiting on error
+fn make_policy(spec: &str) -> Box<dyn FlushPolicy> {
+    policy_from_spec(spec).unwrap_or_else(|e| {
+        eprintln!("Failed to build policy {}: {}", spec, e);
+        eprintln!("  policy: static | nagle | codel | aimd | pid | idle | reflex:<model>[,<normalizer>]");
+        std::process::exit(1);
+    })
+}
+
+fn run_policy(spec: &str, workload: &WorkloadConfig, duration: Duration, seeds: &[u64]) -> Vec<RunResult> {
+    println!("Running {} x{}", spec, seeds.len());
+    run_repeated(spec, || FakeTransport::new(make_policy(spec)), workload, duration, seeds)
+        .expect("Failed to build workload")
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let runs: u64 = take_flag(&mut args, "--runs")
+        .map(|s| s.parse().expect("--runs must be an integer"))
+        .unwrap_or(10);
+    let duration = take_flag(&mut args, "--duration-secs")
+        .map(|s| Duration::from_secs_f64(s.parse().expect("--duration-secs must be a number")))
+        .unwrap_or(Duration::from_secs(5));
+    let output_path = take_flag(&mut args, "--output");
+    let report_path = take_flag(&mut args, "--report");
+    let paired = take_switch(&mut args, "--paired");
+    if args.len() < 4 {
+        eprintln!("Usage: compare <workload_type> <policy_a> <policy_b> [--runs N] [--duration-secs S] [--paired] [--output results.json] [--report report.json]");
+        eprintln!("  workload_type: steady | bursty | adversarial | heavytail | multiflow");
+        eprintln!("  policy: static | nagle | codel | aimd | pid | idle | reflex:<model>[,<normalizer>]");
+        std::process::exit(1);
+    }
+
+    let workload = WorkloadConfig::preset(&args[1]).unwrap_or_else(|| {
+        eprintln!("Unknown workload type: {}", args[1]);
+        std::process::exit(1);
+    });
+    let seeds: Vec<u64> = (0..runs).collect();
+
+    let (results_a, results_b): (Vec<RunResult>, Vec<RunResult>) = if paired {
+        println!("Running {} vs {} on paired arrivals x{}", args[2], args[3], seeds.len());
+        let pairs = run_paired(
+            (&args[2], &args[3]),
+            || FakeTransport::new(make_policy(&args[2])),
+            || FakeTransport::new(make_policy(&args[3])),
+            &workload,
+            duration,
+            &seeds,
+        )
+        .expect("Failed to build workload");
+
+        println!("\n=== Paired differences ({} - {}, mean ± 95% CI) ===", args[3], args[2]);
+        for d in paired_differences(&pairs) {
+            println!("  {:<18} {:+.2}", d.metric, d.diff);
+        }
+        pairs.into_iter().unzip()
+    } else {
+        (
+            run_policy(&args[2], &workload, duration, &seeds),
+            run_policy(&args[3], &workload, duration, &seeds),
+        )
+    };
+
+    let summaries = [RunSummary::from_results(&results_a), RunSummary::from_results(&results_b)];
+    println!("\n=== Summary ({} runs, mean ± 95% CI) ===", runs);
+    for s in &summaries {
+        println!("{}:", s.policy);
+        println!("  p50 latency: {:.2} μs", s.p50_latency_us);
+        println!("  p95 latency: {:.2} μs", s.p95_latency_us);
+        println!("  p99 latency: {:.2} μs", s.p99_latency_us);
+        println!("  throughput:  {:.2} pps", s.mean_throughput);
+    }
+
+    println!("\n=== Mann-Whitney ({} vs {}) ===", args[2], args[3]);
+    let metrics: [(&str, Metric); 4] = [
+        ("p50 latency", |r| r.p50_latency_us),
+        ("p95 latency", |r| r.p95_latency_us),
+        ("p99 latency", |r| r.p99_latency_us),
+        ("throughput", |r| r.mean_throughput),
+    ];
+    for (name, metric) in metrics {
+        let test = compare_runs(&results_a, &results_b, metric);
+        println!("  {:<12} U={:.1} z={:.2} p={:.4}", name, test.u, test.z, test.p_value);
+    }
+
+    let mut report = ComparisonReport::new(&args[2], &args[3]);
+    for (name, metric, goal) in [
+        ("p50_latency_us", metrics[0].1, Goal::Lower),
+        ("p95_latency_us", metrics[1].1, Goal::Lower),
+        ("p99_latency_us", metrics[2].1, Goal::Lower),
+        ("mean_throughput", metrics[3].1, Goal::Higher),
+    ] {
+        let a: Vec<f64> = results_a.iter().map(metric).collect();
+        let b: Vec<f64> = results_b.iter().map(metric).collect();
+        report.add_metric(name, goal, &a, &b);
+    }
+    println!("\n=== Report ===\n{}", report);
+    if let Some(path) = report_path {
+        report.write_json(&path).expect("Failed to write report");
+        println!("Report written to {}", path);
+    }
+
+    if let Some(path) = output_path {
+        let all: Vec<RunResult> = results_a.into_iter().chain(results_b).collect();
+        RunResult::write_all(&all, &path).expect("Failed to write results");
+        println!("\nResults written to {}", path);
+    }
+}
@@ -1,16 +1,50 @@
 //! Reflex policy runner
 //!
-//! Runs the fake transport with reflex-driven flush policy
+//! Runs the fake transport with reflex-driven flush policy. With
+//! `--guard-p99-us` or `--guard-drops-per-sec` the reflex runs inside a
+//! `GuardedPolicy` that hands control to `--guard-fallback` (default
+//! `static`) after `--guard-windows` (default 3) breaching 1s windows.
+//! The model is fed by the normalizer embedded in its metadata, or by the
+//! JSON sidecar given with `--normalizer`.
+//! `--audit-log decisions.jsonl` records every inferred decision with its
+//! inputs and raw outputs, rotating at `--audit-max-mb` (default 64).
 
-use sim::{ReflexPolicy, FakeTransport, SteadyWorkload, BurstyWorkload, AdversarialWorkload, WorkloadGenerator};
+use cli::{take_flag, take_parsed};
+use audit_log::AuditLogger;
+use sim::{baseline_by_name, read_normalizer, FlushPolicy, GuardedPolicy, ReflexPolicy, DropPolicy, FakeTransport, LatencyHistogram, LatencySlo, RunResult, WorkloadConfig, write_decision_log, write_state_series};
 use std::time::Duration;
-use std::thread;
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let output_path = take_flag(&mut args, "--output");
+    let decision_log_path = take_flag(&mut args, "--decision-log");
+    let histogram_path = take_flag(&mut args, "--histogram");
+    let state_series_path = take_flag(&mut args, "--state-series");
+    let state_interval = take_flag(&mut args, "--state-interval-ms")
+        .map(|s| Duration::from_millis(s.parse().expect("--state-interval-ms must be a whole number")))
+        .unwrap_or(Duration::from_millis(10));
+    let slo = take_parsed::<LatencySlo>(&mut args, "--slo");
+    let queue_capacity = take_flag(&mut args, "--queue-capacity")
+        .map(|s| s.parse::<usize>().expect("--queue-capacity must be a packet count"));
+    let backpressure = take_flag(&mut args, "--backpressure")
+        .map(|s| s.parse::<usize>().expect("--backpressure must be a queue depth"));
+    let warmup = take_flag(&mut args, "--warmup-secs")
+        .map(|s| Duration::from_secs_f64(s.parse().expect("--warmup-secs must be a number")))
+        .unwrap_or(Duration::ZERO);
+    let guard_p99 = take_flag(&mut args, "--guard-p99-us")
+        .map(|s| s.parse::<f32>().expect("--guard-p99-us must be microseconds"));
+    let guard_drops_per_sec = take_flag(&mut args, "--guard-drops-per-sec")
+        .map(|s| s.parse::<f32>().expect("--guard-drops-per-sec must be packets/s"));
+    let guard_windows = take_flag(&mut args, "--guard-windows")
+        .map_or(3, |s| s.parse::<usize>().expect("--guard-windows must be a count"));
+    let guard_fallback = take_flag(&mut args, "--guard-fallback").unwrap_or_else(|| "static".to_string());
+    let normalizer_path = take_flag(&mut args, "--normalizer");
+    let audit_path = take_flag(&mut args, "--audit-log");
+    let audit_max_mb: u64 = take_flag(&mut args, "--audit-max-mb")
+        .map_or(64, |s| s.parse().expect("--audit-max-mb must be a whole number"));
     if args.len() < 3 {
-        eprintln!("Usage: reflex <reflex_file> <workload_type>");
-        eprintln!("  workload_type: steady | bursty | adversarial");
+        eprintln!("Usage: reflex <reflex_file> <workload_type> [trace.csv] [--normalizer normalizer.json] [--output results.json] [--decision-log decisions.jsonl] [--histogram latency.csv] [--state-series state.csv] [--state-interval-ms N] [--backpressure DEPTH] [--queue-capacity N] [--slo p99:2000] [--warmup-secs N] [--guard-p99-us N] [--guard-drops-per-sec R] [--guard-windows N] [--guard-fallback BASELINE] [--audit-log audit.jsonl] [--audit-max-mb N]");
+        eprintln!("  workload_type: steady | bursty | adversarial | heavytail | step | multiflow | trace");
         std::process::exit(1);
     }
 
@@ -20,64 +54,77 @@ fn main() {
     println!("Loading reflex from: {}", reflex_path);
     println!("Running with {} workload", workload_type);
 
-    // TODO: Load normalizer from training metadata
-    let normalizer = telemetry::Normalizer::new();
+    let normalizer = normalizer_path.map(|path| read_normalizer(&path).expect("Failed to read normalizer"));
 
-    let policy = ReflexPolicy::load(reflex_path, normalizer)
+    let mut reflex = ReflexPolicy::load(reflex_path, normalizer)
         .expect("Failed to load reflex");
+    if let Some(path) = &audit_path {
+        let logger = AuditLogger::create(path)
+            .expect("Failed to open audit log")
+            .with_rotation(audit_max_mb << 20, AuditLogger::DEFAULT_KEEP);
+        reflex = reflex.with_audit_log(logger, format!("reflex:{}", reflex_path));
+        println!("Audit log: {}", path);
+    }
+    let policy: Box<dyn FlushPolicy> = if guard_p99.is_some() || guard_drops_per_sec.is_some() {
+        let fallback = baseline_by_name(&guard_fallback).unwrap_or_else(|| {
+            eprintln!("Unknown fallback baseline: {}", guard_fallback);
+            std::process::exit(1);
+        });
+        let mut guarded = GuardedPolicy::new(reflex, fallback).with_window(Duration::from_secs(1), guard_windows);
+        if let Some(target) = guard_p99 {
+            guarded = guarded.with_p99_target(target);
+        }
+        if let Some(rate) = guard_drops_per_sec {
+            guarded = guarded.with_max_drops_per_sec(rate);
+        }
+        println!("Guarded: falls back to {} after {} breaching windows", guard_fallback, guard_windows);
+        Box::new(guarded)
+    } else {
+        Box::new(reflex)
+    };
 
-    let mut transport = FakeTransport::new(policy);
+    let mut transport = FakeTransport::new(policy).with_warmup(warmup);
+    if let Some(slo) = slo {
+        transport = transport.with_slo(slo);
+    }
+    if let Some(capacity) = queue_capacity {
+        transport = transport.with_queue_capacity(capacity, DropPolicy::Tail);
+    }
+    if decision_log_path.is_some() {
+        transport = transport.with_decision_log();
+    }
+    if state_series_path.is_some() {
+        transport = transport.with_state_series(state_interval);
+    }
 
     // Create workload
     let duration = Duration::from_secs(30);
-    let mut workload: Box<dyn WorkloadGenerator> = match workload_type.as_str() {
-        "steady" => Box::new(SteadyWorkload::new(1000.0, 1024, duration)),
-        "bursty" => Box::new(BurstyWorkload::new(
-            5000.0,
-            100.0,
-            1024,
-            Duration::from_secs(5),
-            duration,
-        )),
-        "adversarial" => Box::new(AdversarialWorkload::new(
-            1000.0,
-            (256, 2048),
-            duration,
-        )),
-        _ => {
+    let workload_config = match workload_type.as_str() {
+        "trace" => {
+            let trace_path = args.get(3).unwrap_or_else(|| {
+                eprintln!("Usage: reflex <reflex_file> trace <trace.csv>");
+                std::process::exit(1);
+            });
+            WorkloadConfig::Trace {
+                path: trace_path.clone(),
+            }
+        }
+        _ => WorkloadConfig::preset(workload_type).unwrap_or_else(|| {
             eprintln!("Unknown workload type: {}", workload_type);
             std::process::exit(1);
-        }
+        }),
     };
+    let workload_config = match backpressure {
+        Some(max_queue_depth) => WorkloadConfig::ClosedLoop {
+            inner: Box::new(workload_config),
+            max_queue_depth,
+        },
+        None => workload_config,
+    };
+    let mut workload = workload_config.build(duration).expect("Failed to build workload");
 
     // Run simulation
-    let start = std::time::Instant::now();
-    let tick_interval = Duration::from_micros(100);
-
-    loop {
-        // Enqueue packets
-        while let Some((wait, size)) = workload.next_packet() {
-            if wait > Duration::ZERO {
-                thread::sleep(wait.min(tick_interval));
-            }
-            transport.enqueue(size);
-            transport.tick();
-
-            if start.elapsed() >= duration {
-                break;
-            }
-        }
-
-        if start.elapsed() >= duration {
-            break;
-        }
-
-        thread::sleep(tick_interval);
-        transport.tick();
-    }
-
-    // Final flush
-    transport.tick();
+    transport.run(workload.as_mut(), duration);
 
     // Print metrics
     let metrics = transport.metrics();
@@ -88,5 +135,55 @@ fn main() {
     println!("p99 latency: {:.2} µs", metrics.p99_latency());
     println!("p99/p50 ratio: {:.2}", metrics.p99_latency() / metrics.p50_latency());
     println!("Mean throughput: {:.2} pkts/s", metrics.mean_throughput());
+    println!("Mean goodput: {:.2} bytes/s", metrics.mean_goodput());
+    println!("Loss rate: {:.4}", metrics.loss_rate());
+    println!("Drop rate: {:.4}", metrics.drop_rate());
+    if metrics.flow_delivered_bytes.len() > 1 {
+        println!("Jain fairness: {:.3}", metrics.jain_fairness());
+        println!("Worst flow p99: {:.2} µs", metrics.max_flow_p99());
+    }
     println!("Decision changes: {}", metrics.decision_changes);
+    println!("Changes per minute: {:.2}", metrics.changes_per_minute());
+    println!("Flip-flops: {}", metrics.flip_flops);
+    for report in metrics.slo_reports() {
+        println!(
+            "SLO p{} < {}μs: {}/{} windows violated, longest streak {}, {:.1}s in violation",
+            report.slo.percentile * 100.0,
+            report.slo.target_us,
+            report.violations,
+            report.windows,
+            report.longest_streak,
+            report.time_in_violation_secs
+        );
+    }
+    println!("Suppressed changes: {}", metrics.suppressed_changes);
+    if let WorkloadConfig::Step { step_at_ms, .. } = &workload_config {
+        if let Some(step) = metrics.step_response(Duration::from_millis(*step_at_ms)) {
+            println!("Step overshoot: {:.1}%", step.overshoot * 100.0);
+            println!("Step settling time: {:.1} ms", step.settling_time.as_secs_f64() * 1000.0);
+        }
+    }
+
+    if let Some(path) = output_path {
+        let policy_id = format!("reflex:{}", reflex_path);
+        let result = RunResult::from_metrics(&policy_id, workload_config, duration, metrics);
+        RunResult::write_all(&[result], &path).expect("Failed to write results");
+        println!("\nResults written to {}", path);
+    }
+
+    if let Some(path) = histogram_path {
+        let histogram = metrics.latency_histogram(LatencyHistogram::DEFAULT_BUCKETS_PER_DECADE);
+        histogram.write(&path).expect("Failed to write histogram");
+        println!("Latency histogram written to {}", path);
+    }
+
+    if let (Some(path), Some(log)) = (decision_log_path, transport.decision_log()) {
+        write_decision_log(log, &path).expect("Failed to write decision log");
+        println!("Decision log written to {}", path);
+    }
+
+    if let (Some(path), Some(series)) = (state_series_path, transport.state_series()) {
+        write_state_series(series, &path).expect("Failed to write state series");
+        println!("State series written to {}", path);
+    }
 }
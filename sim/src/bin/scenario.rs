@@ -0,0 +1,40 @@
+//! Scenario runner
+//!
+//! Runs a TOML scenario file (see sim/scenarios/) once per seed and reports
+//! mean ± 95% CI across runs.
+
+use cli::take_flag;
+use sim::{RunResult, RunSummary, Scenario};
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let output_path = take_flag(&mut args, "--output");
+    if args.len() < 2 {
+        eprintln!("Usage: scenario <scenario.toml> [--output results.json]");
+        std::process::exit(1);
+    }
+
+    let scenario = Scenario::load(&args[1]).expect("Failed to load scenario");
+    println!(
+        "Running scenario '{}': {} policy, {} phases, {:.1}s x{} seeds",
+        scenario.name,
+        scenario.policy,
+        scenario.phases.len(),
+        scenario.duration().as_secs_f64(),
+        scenario.seeds.len()
+    );
+
+    let results = scenario.run().expect("Scenario run failed");
+
+    let s = RunSummary::from_results(&results);
+    println!("\n=== Summary ({} runs, mean ± 95% CI) ===", s.runs);
+    println!("p50 latency: {:.2} μs", s.p50_latency_us);
+    println!("p95 latency: {:.2} μs", s.p95_latency_us);
+    println!("p99 latency: {:.2} μs", s.p99_latency_us);
+    println!("throughput:  {:.2} pps", s.mean_throughput);
+
+    if let Some(path) = output_path {
+        RunResult::write_all(&results, &path).expect("Failed to write results");
+        println!("\nResults written to {}", path);
+    }
+}
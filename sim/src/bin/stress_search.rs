@@ -0,0 +1,52 @@
+//! Adversarial workload search
+//!
+//! Hill-climbs bursty workload parameters to maximize p99 latency under a
+//! policy and writes every evaluated workload, worst first.
+
+use cli::take_flag;
+use sim::{policy_from_spec, FakeTransport, RunResult, StressSearch};
+use std::time::Duration;
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let iterations: usize = take_flag(&mut args, "--iterations")
+        .map(|s| s.parse().expect("--iterations must be an integer"))
+        .unwrap_or(20);
+    let eval_duration = take_flag(&mut args, "--eval-secs")
+        .map(|s| Duration::from_secs_f64(s.parse().expect("--eval-secs must be a number")))
+        .unwrap_or(Duration::from_secs(2));
+    let seed: u64 = take_flag(&mut args, "--seed")
+        .map(|s| s.parse().expect("--seed must be an integer"))
+        .unwrap_or(0);
+    let output_path = take_flag(&mut args, "--output");
+    if args.len() < 2 {
+        eprintln!("Usage: stress-search <policy> [--iterations N] [--eval-secs S] [--seed N] [--output stress.json]");
+        eprintln!("  policy: static | nagle | codel | aimd | pid | idle | reflex:<model>[,<normalizer>]");
+        std::process::exit(1);
+    }
+
+    let spec = &args[1];
+    policy_from_spec(spec).unwrap_or_else(|e| {
+        eprintln!("Failed to build policy {}: {}", spec, e);
+        std::process::exit(1);
+    });
+
+    println!("Searching {} iterations x {:.1}s against {}", iterations, eval_duration.as_secs_f64(), spec);
+    let mut search = StressSearch::new(iterations, eval_duration).with_seed(seed);
+    let mut results = search.run(spec, || FakeTransport::new(policy_from_spec(spec).unwrap()));
+    results.sort_by(|a, b| b.p99_latency_us.total_cmp(&a.p99_latency_us));
+
+    println!("\n=== Worst workloads ===");
+    for result in results.iter().take(5) {
+        println!(
+            "p99 {:>8.1} μs  {}",
+            result.p99_latency_us,
+            serde_json::to_string(&result.workload).unwrap()
+        );
+    }
+
+    if let Some(path) = output_path {
+        RunResult::write_all(&results, &path).expect("Failed to write results");
+        println!("\nResults written to {}", path);
+    }
+}
@@ -0,0 +1,270 @@
+//! Guardrails around a flush policy
+//!
+//! A learned policy can be confidently wrong. [`GuardedPolicy`] holds any
+//! policy to operator limits: every decision is clamped to hard bounds,
+//! each change the wrapped policy asks for is capped relative to the last
+//! applied decision, and once the transport breaches its SLO (rolling
+//! p99 latency or drops per second over target) for several windows in a row,
+//! a baseline policy takes over.
+
+use crate::{FlushDecision, FlushPolicy};
+use std::time::{Duration, Instant};
+use telemetry::TelemetrySample;
+
+/// A flush policy held to hard limits, with an SLO-triggered fallback
+pub struct GuardedPolicy<P: FlushPolicy> {
+    inner: P,
+    fallback: Box<dyn FlushPolicy>,
+    threshold_range: (u32, u32),
+    delay_range_us: (u32, u32),
+    max_step: Option<f32>,      // largest relative change per decision, inner policy only
+    p99_target_us: Option<f32>, // a window breaches when its mean p99 exceeds this
+    max_drops_per_sec: Option<f32>, // or its mean drops per second exceed this
+    window: Duration,
+    breach_windows: usize,      // consecutive breaching windows before falling back
+    recovery_windows: Option<usize>, // consecutive healthy windows before handing back; None stays on the fallback
+    window_start: Option<Instant>,
+    window_p99_sum: f64,
+    window_drop_sum: f64,
+    window_samples: usize,
+    breach_streak: usize,
+    healthy_streak: usize,
+    reverted: bool,
+    reverts: usize,
+    clamped: usize,
+    rate_limited: usize,
+    last_applied: Option<FlushDecision>,
+}
+
+impl<P: FlushPolicy> GuardedPolicy<P> {
+    /// Guard `inner` with `fallback` as the baseline; no limits or SLO until configured
+    pub fn new(inner: P, fallback: Box<dyn FlushPolicy>) -> Self {
+        Self {
+            inner,
+            fallback,
+            threshold_range: (0, u32::MAX),
+            delay_range_us: (0, u32::MAX),
+            max_step: None,
+            p99_target_us: None,
+            max_drops_per_sec: None,
+            window: Duration::from_secs(1),
+            breach_windows: 3,
+            recovery_windows: None,
+            window_start: None,
+            window_p99_sum: 0.0,
+            window_drop_sum: 0.0,
+            window_samples: 0,
+            breach_streak: 0,
+            healthy_streak: 0,
+            reverted: false,
+            reverts: 0,
+            clamped: 0,
+            rate_limited: 0,
+            last_applied: None,
+        }
+    }
+
+    /// Hard bounds on every applied threshold and delay, fallback included
+    pub fn with_limits(mut self, threshold: (u32, u32), delay_us: (u32, u32)) -> Self {
+        assert!(threshold.0 <= threshold.1 && delay_us.0 <= delay_us.1, "limits need min <= max");
+        self.threshold_range = threshold;
+        self.delay_range_us = delay_us;
+        self
+    }
+
+    /// Move threshold and delay by at most `fraction` of their last applied value (at least 1) per decision
+    pub fn with_max_step(mut self, fraction: f32) -> Self {
+        assert!(fraction > 0.0, "step must be positive");
+        self.max_step = Some(fraction);
+        self
+    }
+
+    /// Fall back when the mean p99 latency of a window exceeds `target_us`
+    pub fn with_p99_target(mut self, target_us: f32) -> Self {
+        self.p99_target_us = Some(target_us);
+        self
+    }
+
+    /// Fall back when a window's mean drops per second exceed `packets_per_sec`
+    pub fn with_max_drops_per_sec(mut self, packets_per_sec: f32) -> Self {
+        self.max_drops_per_sec = Some(packets_per_sec);
+        self
+    }
+
+    /// SLO windows of `window` (default 1s, wall clock); `breach_windows` breaching in a row (default 3) trigger the fallback
+    pub fn with_window(mut self, window: Duration, breach_windows: usize) -> Self {
+        assert!(breach_windows > 0, "need at least one breaching window");
+        self.window = window;
+        self.breach_windows = breach_windows;
+        self
+    }
+
+    /// Hand control back after `windows` healthy windows in a row
+    pub fn with_recovery(mut self, windows: usize) -> Self {
+        assert!(windows > 0, "need at least one healthy window");
+        self.recovery_windows = Some(windows);
+        self
+    }
+
+    /// Whether the fallback is in control
+    pub fn is_reverted(&self) -> bool {
+        self.reverted
+    }
+
+    /// Times control passed to the fallback
+    pub fn reverts(&self) -> usize {
+        self.reverts
+    }
+
+    /// Decisions the hard limits changed
+    pub fn clamped(&self) -> usize {
+        self.clamped
+    }
+
+    /// Decisions the step limit slowed down
+    pub fn rate_limited(&self) -> usize {
+        self.rate_limited
+    }
+
+    /// Fold `telem` into the current window, closing it once `window` has passed
+    fn observe(&mut self, telem: &TelemetrySample) {
+        let now = Instant::now();
+        let start = *self.window_start.get_or_insert(now);
+        self.window_p99_sum += telem.latency_p99_us as f64;
+        self.window_drop_sum += telem.drops_per_sec as f64;
+        self.window_samples += 1;
+        if now.duration_since(start) < self.window {
+            return;
+        }
+
+        let samples = self.window_samples as f64;
+        let over = |sum: f64, limit: Option<f32>| limit.is_some_and(|limit| sum / samples > limit as f64);
+        let breached = over(self.window_p99_sum, self.p99_target_us) || over(self.window_drop_sum, self.max_drops_per_sec);
+        if breached {
+            self.breach_streak += 1;
+            self.healthy_streak = 0;
+        } else {
+            self.breach_streak = 0;
+            self.healthy_streak += 1;
+        }
+        if !self.reverted && self.breach_streak >= self.breach_windows {
+            self.reverted = true;
+            self.reverts += 1;
+        } else if self.reverted && self.recovery_windows.is_some_and(|windows| self.healthy_streak >= windows) {
+            self.reverted = false;
+        }
+
+        self.window_start = Some(now);
+        self.window_p99_sum = 0.0;
+        self.window_drop_sum = 0.0;
+        self.window_samples = 0;
+    }
+
+    /// `decision` moved at most `max_step` away from the last applied one
+    fn limit_step(&mut self, decision: FlushDecision) -> FlushDecision {
+        let (Some(fraction), Some(last)) = (self.max_step, self.last_applied) else {
+            return decision;
+        };
+        let step = |last: u32, next: u32| {
+            let max_step = (last as f32 * fraction).max(1.0) as u32;
+            next.clamp(last.saturating_sub(max_step), last.saturating_add(max_step))
+        };
+        let limited = FlushDecision {
+            threshold: step(last.threshold, decision.threshold),
+            max_delay_us: step(last.max_delay_us, decision.max_delay_us),
+            ..decision
+        };
+        if limited != decision {
+            self.rate_limited += 1;
+        }
+        limited
+    }
+
+    fn clamp(&mut self, decision: FlushDecision) -> FlushDecision {
+        let clamped = FlushDecision {
+            threshold: decision.threshold.clamp(self.threshold_range.0, self.threshold_range.1),
+            max_delay_us: decision.max_delay_us.clamp(self.delay_range_us.0, self.delay_range_us.1),
+            ..decision
+        };
+        if clamped != decision {
+            self.clamped += 1;
+        }
+        clamped
+    }
+}
+
+impl<P: FlushPolicy> FlushPolicy for GuardedPolicy<P> {
+    fn decide(&mut self, telem: &TelemetrySample) -> FlushDecision {
+        self.observe(telem);
+        // The wrapped policy keeps deciding while reverted, so it's current when handed back
+        let proposed = self.inner.decide(telem);
+        let decision = if self.reverted {
+            self.fallback.decide(telem)
+        } else {
+            self.limit_step(proposed)
+        };
+        let decision = self.clamp(decision);
+        self.last_applied = Some(decision);
+        decision
+    }
+
+    fn suppressed_changes(&self) -> usize {
+        self.inner.suppressed_changes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BaselinePolicy;
+
+    /// Replays a fixed sequence of thresholds
+    struct Scripted(Vec<u32>);
+
+    impl FlushPolicy for Scripted {
+        fn decide(&mut self, _telem: &TelemetrySample) -> FlushDecision {
+            let threshold = if self.0.len() > 1 { self.0.remove(0) } else { self.0[0] };
+            FlushDecision {
+                threshold,
+                max_delay_us: 1000,
+                segment_bytes: None,
+                pacing_rate: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_guard_clamps_limits_steps_and_rate() {
+        let mut policy = GuardedPolicy::new(Scripted(vec![4, 200, 200, 2]), Box::new(BaselinePolicy::new()))
+            .with_limits((8, 128), (0, 800))
+            .with_max_step(0.5);
+        let calm = TelemetrySample::default();
+        let decisions: Vec<u32> = (0..4).map(|_| policy.decide(&calm).threshold).collect();
+        // 4 -> floor 8; 200 -> +4 then +6; 2 -> -9 from 18
+        assert_eq!(decisions, [8, 12, 18, 9]);
+        assert_eq!(policy.decide(&calm).max_delay_us, 800);
+        assert_eq!((policy.clamped(), policy.rate_limited()), (5, 4));
+    }
+
+    #[test]
+    fn test_guard_falls_back_after_consecutive_breaches() {
+        let mut policy = GuardedPolicy::new(Scripted(vec![64]), Box::new(BaselinePolicy::new()))
+            .with_p99_target(2000.0)
+            .with_max_drops_per_sec(10.0)
+            .with_window(Duration::ZERO, 3)
+            .with_recovery(2);
+        let calm = TelemetrySample { latency_p99_us: 500.0, ..Default::default() };
+        let slow = TelemetrySample { latency_p99_us: 5000.0, ..Default::default() };
+        let dropping = TelemetrySample { drops_per_sec: 50.0, ..calm };
+
+        // A healthy window breaks the streak
+        for telem in [&slow, &dropping, &calm, &slow, &dropping] {
+            assert_eq!(policy.decide(telem).threshold, 64);
+        }
+        assert_eq!(policy.decide(&slow).threshold, 16);
+        assert!(policy.is_reverted());
+        assert_eq!(policy.decide(&calm).threshold, 16);
+        assert_eq!(policy.decide(&calm).threshold, 64);
+        assert_eq!(policy.reverts(), 1);
+    }
+}
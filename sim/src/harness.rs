@@ -0,0 +1,158 @@
+//! Repeated-run harness
+//!
+//! Runs a transport configuration once per seed and summarizes the spread,
+//! so policy comparisons aren't made on a single noisy run.
+
+use crate::stats::{mann_whitney, MannWhitney, MeanCi};
+use crate::{baseline_by_name, FakeTransport, FlushPolicy, ReflexPolicy, ReloadableReflexPolicy, RunResult, TraceWorkload, WorkloadConfig};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::time::Duration;
+
+/// Build a policy from a baseline name, `reflex:<model>[,<normalizer>]`, or `reload:<model>[,<normalizer>]` to pick up rewrites of the model
+///
+/// Without a normalizer file the model's embedded normalizer feeds it.
+pub fn policy_from_spec(spec: &str) -> io::Result<Box<dyn FlushPolicy>> {
+    if let Some(paths) = spec.strip_prefix("reflex:") {
+        let (model, normalizer) = split_model_spec(paths)?;
+        return Ok(Box::new(ReflexPolicy::load(model, normalizer)?));
+    }
+    if let Some(paths) = spec.strip_prefix("reload:") {
+        let (model, normalizer) = split_model_spec(paths)?;
+        return Ok(Box::new(ReloadableReflexPolicy::load(model, normalizer)?));
+    }
+    baseline_by_name(spec)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("unknown policy: {}", spec)))
+}
+
+/// The model path and, if one is named, the normalizer read from its sidecar
+fn split_model_spec(paths: &str) -> io::Result<(&str, Option<telemetry::Normalizer>)> {
+    match paths.split_once(',') {
+        Some((model, normalizer)) => Ok((model, Some(read_normalizer(normalizer)?))),
+        None => Ok((paths, None)),
+    }
+}
+
+/// Read a normalizer written by the trainer as JSON
+pub fn read_normalizer(path: &str) -> io::Result<telemetry::Normalizer> {
+    serde_json::from_str(&std::fs::read_to_string(path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Run `workload` once per seed on a fresh transport from `make_transport`
+///
+/// Both the workload and the transport are seeded with the run's seed.
+pub fn run_repeated<P: FlushPolicy>(
+    policy_id: &str,
+    mut make_transport: impl FnMut() -> FakeTransport<P>,
+    workload: &WorkloadConfig,
+    duration: Duration,
+    seeds: &[u64],
+) -> io::Result<Vec<RunResult>> {
+    let mut results = Vec::with_capacity(seeds.len());
+    for &seed in seeds {
+        let mut transport = make_transport().with_seed(seed);
+        let mut generator = workload.build_seeded(duration, seed)?;
+        transport.run(generator.as_mut(), duration);
+
+        let mut result = RunResult::from_metrics(policy_id, workload.clone(), duration, transport.metrics());
+        result.seed = Some(seed);
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// Mean and 95% CI of the headline metrics over repeated runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub policy: String,
+    pub runs: usize,
+    pub p50_latency_us: MeanCi,
+    pub p95_latency_us: MeanCi,
+    pub p99_latency_us: MeanCi,
+    pub mean_throughput: MeanCi,
+}
+
+impl RunSummary {
+    pub fn from_results(results: &[RunResult]) -> Self {
+        let ci = |metric: fn(&RunResult) -> f64| {
+            MeanCi::from_samples(&results.iter().map(metric).collect::<Vec<_>>())
+        };
+        Self {
+            policy: results.first().map(|r| r.policy.clone()).unwrap_or_default(),
+            runs: results.len(),
+            p50_latency_us: ci(|r| r.p50_latency_us),
+            p95_latency_us: ci(|r| r.p95_latency_us),
+            p99_latency_us: ci(|r| r.p99_latency_us),
+            mean_throughput: ci(|r| r.mean_throughput),
+        }
+    }
+}
+
+/// Mann-Whitney test of one metric between two sets of runs
+pub fn compare_runs(a: &[RunResult], b: &[RunResult], metric: impl Fn(&RunResult) -> f64) -> MannWhitney {
+    let a: Vec<f64> = a.iter().map(&metric).collect();
+    let b: Vec<f64> = b.iter().map(&metric).collect();
+    mann_whitney(&a, &b)
+}
+
+/// Run two policies per seed on the identical arrival sequence
+///
+/// Each seed's arrivals are generated once and replayed to both transports,
+/// so per-seed differences come from the policies rather than the workload.
+/// Arrivals are fixed, so closed-loop backpressure is not applied.
+pub fn run_paired<A: FlushPolicy, B: FlushPolicy>(
+    policy_ids: (&str, &str),
+    mut make_a: impl FnMut() -> FakeTransport<A>,
+    mut make_b: impl FnMut() -> FakeTransport<B>,
+    workload: &WorkloadConfig,
+    duration: Duration,
+    seeds: &[u64],
+) -> io::Result<Vec<(RunResult, RunResult)>> {
+    let mut pairs = Vec::with_capacity(seeds.len());
+    for &seed in seeds {
+        let arrivals = TraceWorkload::capture(workload.build_seeded(duration, seed)?.as_mut());
+
+        let mut a = make_a().with_seed(seed);
+        a.run(&mut arrivals.clone(), duration);
+        let mut b = make_b().with_seed(seed);
+        b.run(&mut arrivals.clone(), duration);
+
+        let mut result_a = RunResult::from_metrics(policy_ids.0, workload.clone(), duration, a.metrics());
+        let mut result_b = RunResult::from_metrics(policy_ids.1, workload.clone(), duration, b.metrics());
+        result_a.seed = Some(seed);
+        result_b.seed = Some(seed);
+        pairs.push((result_a, result_b));
+    }
+    Ok(pairs)
+}
+
+/// Mean per-seed difference (B - A) of one metric, with a paired 95% CI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedDifference {
+    pub metric: String,
+    pub diff: MeanCi,
+}
+
+type Metric = fn(&RunResult) -> f64;
+
+/// Paired differences of the headline metrics over `run_paired` output
+pub fn paired_differences(pairs: &[(RunResult, RunResult)]) -> Vec<PairedDifference> {
+    let metrics: [(&str, Metric); 6] = [
+        ("p50_latency_us", |r| r.p50_latency_us),
+        ("p95_latency_us", |r| r.p95_latency_us),
+        ("p99_latency_us", |r| r.p99_latency_us),
+        ("mean_throughput", |r| r.mean_throughput),
+        ("loss_rate", |r| r.loss_rate),
+        ("decision_changes", |r| r.decision_changes as f64),
+    ];
+    metrics
+        .iter()
+        .map(|(name, metric)| {
+            let diffs: Vec<f64> = pairs.iter().map(|(a, b)| metric(b) - metric(a)).collect();
+            PairedDifference {
+                metric: name.to_string(),
+                diff: MeanCi::from_samples(&diffs),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,113 @@
+//! Latency histograms
+//!
+//! Log-scaled buckets so a run's full latency distribution can be exported
+//! and CDFs compared across policies, not just three percentile points.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+/// One histogram bucket covering (previous upper bound, `upper_us`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    pub upper_us: f64,
+    pub count: u64,
+}
+
+/// Latency histogram with log-spaced bucket bounds
+///
+/// Bucket `i` has upper bound 10^(i / buckets_per_decade) µs; latencies of
+/// 0-1µs land in bucket 0. Empty buckets below the maximum are kept so the
+/// bucket grid is identical across runs with the same resolution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    pub buckets_per_decade: u32,
+    pub buckets: Vec<HistogramBucket>,
+    pub total: u64,
+}
+
+impl LatencyHistogram {
+    /// ~12% bucket width: fine enough to tell p99s apart, small enough to plot
+    pub const DEFAULT_BUCKETS_PER_DECADE: u32 = 20;
+
+    pub fn from_latencies(latencies_us: &[u64], buckets_per_decade: u32) -> Self {
+        assert!(buckets_per_decade > 0, "buckets_per_decade must be positive");
+        let bpd = buckets_per_decade as f64;
+        let mut counts: Vec<u64> = Vec::new();
+        for &latency in latencies_us {
+            let idx = if latency <= 1 {
+                0
+            } else {
+                // Small epsilon keeps exact bucket bounds in their own bucket
+                ((latency as f64).log10() * bpd - 1e-9).ceil() as usize
+            };
+            if counts.len() <= idx {
+                counts.resize(idx + 1, 0);
+            }
+            counts[idx] += 1;
+        }
+
+        let buckets = counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| HistogramBucket {
+                upper_us: 10f64.powf(i as f64 / bpd),
+                count,
+            })
+            .collect();
+        Self {
+            buckets_per_decade,
+            buckets,
+            total: latencies_us.len() as u64,
+        }
+    }
+
+    /// Cumulative fraction of samples at or below each bucket's upper bound
+    pub fn cdf(&self) -> Vec<(f64, f64)> {
+        let mut cumulative = 0;
+        self.buckets
+            .iter()
+            .map(|bucket| {
+                cumulative += bucket.count;
+                (bucket.upper_us, cumulative as f64 / self.total.max(1) as f64)
+            })
+            .collect()
+    }
+
+    /// Write as CSV (`.csv` extension, with a cumulative column) or JSON
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            let mut writer = csv::Writer::from_path(path)?;
+            writer.write_record(["upper_us", "count", "cumulative_fraction"])?;
+            for (bucket, (_, fraction)) in self.buckets.iter().zip(self.cdf()) {
+                writer.write_record([
+                    bucket.upper_us.to_string(),
+                    bucket.count.to_string(),
+                    fraction.to_string(),
+                ])?;
+            }
+            writer.flush()
+        } else {
+            let json = serde_json::to_string_pretty(self)?;
+            std::fs::write(path, json)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_buckets() {
+        let hist = LatencyHistogram::from_latencies(&[0, 1, 10, 11, 100, 1000], 1);
+        let counts: Vec<u64> = hist.buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![2, 1, 2, 1]);
+        assert_eq!(hist.buckets[3].upper_us, 1000.0);
+
+        let cdf = hist.cdf();
+        assert_eq!(cdf.last().unwrap().1, 1.0);
+        assert!((cdf[1].1 - 0.5).abs() < 1e-9);
+    }
+}
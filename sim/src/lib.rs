@@ -2,10 +2,37 @@
 //!
 //! Simulates a packet queue with configurable flush policies.
 
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::time::{Duration, Instant};
 use telemetry::TelemetrySample;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+mod baselines;
+mod guard;
+mod harness;
+mod histogram;
+mod reload;
+mod results;
+mod scenario;
+mod search;
+mod slo;
+pub mod stats;
+mod timeline;
+mod transient;
+
+pub use baselines::{baseline_by_name, AimdPolicy, CoDelPolicy, IdleFlushPolicy, NaglePolicy, PidGains, PidPolicy};
+pub use guard::GuardedPolicy;
+pub use harness::{compare_runs, paired_differences, policy_from_spec, read_normalizer, run_paired, run_repeated, PairedDifference, RunSummary};
+pub use histogram::{HistogramBucket, LatencyHistogram};
+pub use reload::{check_normalizer, ReloadableReflexPolicy};
+pub use results::RunResult;
+pub use scenario::Scenario;
+pub use search::StressSearch;
+pub use slo::{LatencySlo, SloReport};
+pub use timeline::{write_decision_log, write_state_series, DecisionRecord, StateSample};
+pub use transient::{step_response, StepResponse};
 
 /// Simulated packet
 #[derive(Debug, Clone)]
@@ -13,18 +40,39 @@ pub struct Packet {
     pub id: u64,
     pub size_bytes: usize,
     pub arrival_time: Instant,
+    pub class: usize,
+    pub flow: u32,
 }
 
 /// Flush policy decision
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FlushDecision {
     pub threshold: u32,        // packets
     pub max_delay_us: u32,     // microseconds
+    #[serde(default)]
+    pub segment_bytes: Option<u32>, // max bytes per segment; None = unsegmented
+    #[serde(default)]
+    pub pacing_rate: Option<u64>,   // bytes/s spacing departures; None = send back to back
 }
 
 /// Flush policy trait
 pub trait FlushPolicy {
     fn decide(&mut self, telem: &TelemetrySample) -> FlushDecision;
+
+    /// Decision changes the policy chose not to apply (e.g. via hysteresis)
+    fn suppressed_changes(&self) -> usize {
+        0
+    }
+}
+
+impl<P: FlushPolicy + ?Sized> FlushPolicy for Box<P> {
+    fn decide(&mut self, telem: &TelemetrySample) -> FlushDecision {
+        (**self).decide(telem)
+    }
+
+    fn suppressed_changes(&self) -> usize {
+        (**self).suppressed_changes()
+    }
 }
 
 /// Baseline static policy
@@ -53,10 +101,21 @@ impl FlushPolicy for BaselinePolicy {
         FlushDecision {
             threshold: self.threshold,
             max_delay_us: self.max_delay_us,
+            segment_bytes: None,
+            pacing_rate: None,
         }
     }
 }
 
+/// The normalizer stored in a reflex's metadata, if it has one of the telemetry schema's width
+pub fn embedded_normalizer(reflex: &reflex_format::Reflex) -> Option<telemetry::Normalizer> {
+    let embedded = reflex.metadata.normalizer.as_ref()?;
+    Some(telemetry::Normalizer {
+        min: embedded.min.as_slice().try_into().ok()?,
+        max: embedded.max.as_slice().try_into().ok()?,
+    })
+}
+
 /// Reflex policy (loaded from .reflex file)
 pub struct ReflexPolicy {
     reflex: reflex_format::Reflex,
@@ -65,21 +124,94 @@ pub struct ReflexPolicy {
     last_decision: Option<FlushDecision>,
     last_decision_time: Option<Instant>,
     hold_time: Duration,
+    suppressed_changes: usize,
+    audit: Option<audit_log::PolicyAudit>,
 }
 
 impl ReflexPolicy {
-    pub fn load(reflex_path: &str, normalizer: telemetry::Normalizer) -> std::io::Result<Self> {
-        let bytes = std::fs::read(reflex_path)?;
-        let reflex = reflex_format::Reflex::from_bytes(&bytes)?;
+    pub const DEFAULT_HOLD_TIME: Duration = Duration::from_millis(300);
+    pub const DEFAULT_HYSTERESIS: f32 = 0.05;
 
-        Ok(Self {
+    /// Create a policy that re-decides at most once per `hold_time` and ignores
+    /// output changes smaller than `hysteresis_threshold` (relative)
+    pub fn new(
+        reflex: reflex_format::Reflex,
+        normalizer: telemetry::Normalizer,
+        hold_time: Duration,
+        hysteresis_threshold: f32,
+    ) -> Self {
+        Self {
             reflex,
             normalizer,
-            hysteresis_threshold: 0.05,
+            hysteresis_threshold,
             last_decision: None,
             last_decision_time: None,
-            hold_time: Duration::from_millis(300),
-        })
+            hold_time,
+            suppressed_changes: 0,
+            audit: None,
+        }
+    }
+
+    /// Load `reflex_path` fed by `normalizer`, or by the one embedded in its metadata
+    ///
+    /// Missing both, or a normalizer that fails [`check_normalizer`], is an error.
+    pub fn load(reflex_path: &str, normalizer: Option<telemetry::Normalizer>) -> std::io::Result<Self> {
+        let bytes = std::fs::read(reflex_path)?;
+        let reflex = reflex_format::Reflex::from_bytes(&bytes)?;
+        let normalizer = normalizer.or_else(|| embedded_normalizer(&reflex)).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "no normalizer embedded or given")
+        })?;
+        check_normalizer(&reflex, &normalizer)?;
+
+        Ok(Self::new(
+            reflex,
+            normalizer,
+            Self::DEFAULT_HOLD_TIME,
+            Self::DEFAULT_HYSTERESIS,
+        ))
+    }
+
+    pub fn reflex(&self) -> &reflex_format::Reflex {
+        &self.reflex
+    }
+
+    pub fn normalizer(&self) -> &telemetry::Normalizer {
+        &self.normalizer
+    }
+
+    /// Run `reflex` fed by `normalizer` from the next decision on, keeping the hold and hysteresis state
+    pub(crate) fn replace_model(&mut self, reflex: reflex_format::Reflex, normalizer: telemetry::Normalizer) {
+        if let Some(audit) = self.audit.as_mut() {
+            audit.set_reflex(&reflex);
+        }
+        self.reflex = reflex;
+        self.normalizer = normalizer;
+    }
+
+    /// Log every inferred decision to `logger` as `policy_id`, with the model's hash; held decisions aren't logged
+    pub fn with_audit_log(mut self, logger: audit_log::AuditLogger, policy_id: impl Into<String>) -> Self {
+        self.audit = Some(audit_log::PolicyAudit::new(logger, policy_id, &self.reflex));
+        self
+    }
+
+    pub fn audit_log(&self) -> Option<&audit_log::AuditLogger> {
+        self.audit.as_ref().map(|audit| audit.logger())
+    }
+
+    /// True if every output moved by less than the hysteresis band
+    fn within_hysteresis(&self, last: FlushDecision, next: FlushDecision) -> bool {
+        let relative_change = |old: f64, new: f64| (new - old).abs() / old.max(1.0);
+        let small = |old: f64, new: f64| relative_change(old, new) < self.hysteresis_threshold as f64;
+        small(last.threshold as f64, next.threshold as f64)
+            && small(last.max_delay_us as f64, next.max_delay_us as f64)
+            && small(
+                last.pacing_rate.unwrap_or(0) as f64,
+                next.pacing_rate.unwrap_or(0) as f64,
+            )
+            && small(
+                last.segment_bytes.unwrap_or(0) as f64,
+                next.segment_bytes.unwrap_or(0) as f64,
+            )
     }
 }
 
@@ -101,51 +233,313 @@ impl FlushPolicy for ReflexPolicy {
         // Infer
         let outputs = self.reflex.infer(&norm_features);
 
-        // Decode outputs (assume first output is threshold, second is delay)
+        // Decode outputs: threshold, delay, then optional segment size and pacing rate
+        // (appended in that order, so older 3-output reflexes keep their meaning)
         let threshold = outputs[0].round() as u32;
         let max_delay_us = outputs[1].round() as u32;
+        let segment_bytes = outputs.get(2).map(|bytes| bytes.round().max(1.0) as u32);
+        let pacing_rate = outputs.get(3).map(|rate| rate.round().max(1.0) as u64);
 
-        let decision = FlushDecision {
+        let mut decision = FlushDecision {
             threshold,
             max_delay_us,
+            segment_bytes,
+            pacing_rate,
         };
 
+        // Hysteresis: keep the previous decision unless an output moved enough
+        if let Some(last) = self.last_decision {
+            if last != decision && self.within_hysteresis(last, decision) {
+                self.suppressed_changes += 1;
+                decision = last;
+            }
+        }
+
         self.last_decision = Some(decision);
         self.last_decision_time = Some(now);
 
+        // A failed write is counted by the logger and never blocks the decision
+        if let Some(audit) = self.audit.as_mut() {
+            let raw_outputs = self.reflex.raw_outputs(&norm_features);
+            let _ = audit.log(telem, &norm_features, &raw_outputs, &decision, false);
+        }
+
         decision
     }
+
+    fn suppressed_changes(&self) -> usize {
+        self.suppressed_changes
+    }
 }
 
 /// Metrics collector
+///
+/// With a warm-up period, nothing is recorded until it has elapsed, so the
+/// initial queue build-up doesn't skew percentiles.
 #[derive(Debug, Clone)]
 pub struct Metrics {
     pub latencies_us: Vec<u64>,
+    pub delivery_times_us: Vec<u64>, // since metrics creation, parallel to latencies_us
+    pub class_latencies_us: Vec<Vec<u64>>, // per priority class
+    pub flow_latencies_us: BTreeMap<u32, Vec<u64>>,
+    pub flow_delivered_bytes: BTreeMap<u32, usize>,
     pub throughput_samples: Vec<f64>, // packets/s
+    pub goodput_samples: Vec<f64>,    // delivered bytes/s
     pub decision_changes: usize,
+    pub decisions: usize,
+    pub output_delta_sum: f64, // summed mean relative output change per decision
+    pub flip_flops: usize,     // changes reverting the previous one within FLIP_FLOP_WINDOW
+    pub suppressed_changes: usize,
+    pub transmissions: usize,
+    pub losses: usize,
+    pub energy_uj: f64, // total modeled energy, microjoules
+    pub arrivals: usize,
+    pub drops: usize, // arrivals rejected by a full queue
+    pub slos: Vec<LatencySlo>,
+    warmup: Duration,
+    started_at: Instant,
+    suppressed_offset: usize,
+    decision_history: Vec<DecisionHistory>, // per priority class
+    decision_span: Option<(Instant, Instant)>, // first and last recorded decision
+}
+
+/// Recent decisions of one class, for change and flip-flop detection
+#[derive(Debug, Clone, Default)]
+struct DecisionHistory {
+    last: Option<FlushDecision>,
+    last_change: Option<(FlushDecision, Instant)>, // decision replaced, when
+}
+
+/// Mean relative change across a decision's outputs
+fn decision_delta(old: &FlushDecision, new: &FlushDecision) -> f64 {
+    let relative = |old: f64, new: f64| (new - old).abs() / old.max(1.0);
+    let mut deltas = vec![
+        relative(old.threshold as f64, new.threshold as f64),
+        relative(old.max_delay_us as f64, new.max_delay_us as f64),
+    ];
+    if old.pacing_rate.is_some() || new.pacing_rate.is_some() {
+        deltas.push(relative(
+            old.pacing_rate.unwrap_or(0) as f64,
+            new.pacing_rate.unwrap_or(0) as f64,
+        ));
+    }
+    if old.segment_bytes.is_some() || new.segment_bytes.is_some() {
+        deltas.push(relative(
+            old.segment_bytes.unwrap_or(0) as f64,
+            new.segment_bytes.unwrap_or(0) as f64,
+        ));
+    }
+    deltas.iter().sum::<f64>() / deltas.len() as f64
 }
 
 impl Metrics {
+    /// A change back to the previous decision within this window is a flip-flop
+    pub const FLIP_FLOP_WINDOW: Duration = Duration::from_secs(1);
+
     pub fn new() -> Self {
+        Self::with_warmup(Duration::ZERO)
+    }
+
+    /// Ignore everything recorded during the first `warmup` of the run
+    pub fn with_warmup(warmup: Duration) -> Self {
         Self {
             latencies_us: Vec::new(),
+            delivery_times_us: Vec::new(),
+            class_latencies_us: Vec::new(),
+            flow_latencies_us: BTreeMap::new(),
+            flow_delivered_bytes: BTreeMap::new(),
             throughput_samples: Vec::new(),
+            goodput_samples: Vec::new(),
             decision_changes: 0,
+            decisions: 0,
+            output_delta_sum: 0.0,
+            flip_flops: 0,
+            suppressed_changes: 0,
+            transmissions: 0,
+            losses: 0,
+            energy_uj: 0.0,
+            arrivals: 0,
+            drops: 0,
+            slos: Vec::new(),
+            warmup,
+            started_at: Instant::now(),
+            suppressed_offset: 0,
+            decision_history: Vec::new(),
+            decision_span: None,
         }
     }
 
+    /// False while still inside the warm-up period
+    pub fn is_recording(&self) -> bool {
+        self.started_at.elapsed() >= self.warmup
+    }
+
     pub fn record_latency(&mut self, latency_us: u64) {
+        if !self.is_recording() {
+            return;
+        }
         self.latencies_us.push(latency_us);
+        self.delivery_times_us.push(self.started_at.elapsed().as_micros() as u64);
+    }
+
+    pub fn record_class_latency(&mut self, class: usize, latency_us: u64) {
+        if !self.is_recording() {
+            return;
+        }
+        if self.class_latencies_us.len() <= class {
+            self.class_latencies_us.resize(class + 1, Vec::new());
+        }
+        self.class_latencies_us[class].push(latency_us);
+    }
+
+    pub fn record_flow_latency(&mut self, flow: u32, latency_us: u64) {
+        if !self.is_recording() {
+            return;
+        }
+        self.flow_latencies_us.entry(flow).or_default().push(latency_us);
+    }
+
+    pub fn record_flow_delivery(&mut self, flow: u32, bytes: usize) {
+        if !self.is_recording() {
+            return;
+        }
+        *self.flow_delivered_bytes.entry(flow).or_default() += bytes;
     }
 
     pub fn record_throughput(&mut self, pkts_per_sec: f64) {
+        if !self.is_recording() {
+            return;
+        }
         self.throughput_samples.push(pkts_per_sec);
     }
 
+    pub fn record_goodput(&mut self, bytes_per_sec: f64) {
+        if !self.is_recording() {
+            return;
+        }
+        self.goodput_samples.push(bytes_per_sec);
+    }
+
     pub fn record_decision_change(&mut self) {
+        if !self.is_recording() {
+            return;
+        }
         self.decision_changes += 1;
     }
 
+    /// Record one policy decision for a priority class, tracking stability
+    pub fn record_decision(&mut self, class: usize, decision: FlushDecision) {
+        let now = Instant::now();
+        let recording = self.is_recording();
+        if self.decision_history.len() <= class {
+            self.decision_history.resize(class + 1, DecisionHistory::default());
+        }
+        let history = &mut self.decision_history[class];
+        let last = history.last.replace(decision);
+
+        let mut change = None;
+        if let Some(last) = last.filter(|last| *last != decision) {
+            let flip_flop = history.last_change.is_some_and(|(replaced, at)| {
+                replaced == decision && now.duration_since(at) <= Self::FLIP_FLOP_WINDOW
+            });
+            history.last_change = Some((last, now));
+            change = Some((decision_delta(&last, &decision), flip_flop));
+        }
+
+        if !recording {
+            return;
+        }
+        self.decisions += 1;
+        self.decision_span = Some((self.decision_span.map_or(now, |(first, _)| first), now));
+        if let Some((delta, flip_flop)) = change {
+            self.record_decision_change();
+            self.output_delta_sum += delta;
+            if flip_flop {
+                self.flip_flops += 1;
+            }
+        }
+    }
+
+    pub fn record_transmission(&mut self, lost: bool) {
+        if !self.is_recording() {
+            return;
+        }
+        self.transmissions += 1;
+        if lost {
+            self.losses += 1;
+        }
+    }
+
+    /// Count an arrival and whether the queue dropped a packet to admit or reject it
+    pub fn record_arrival(&mut self, dropped: bool) {
+        if !self.is_recording() {
+            return;
+        }
+        self.arrivals += 1;
+        if dropped {
+            self.drops += 1;
+        }
+    }
+
+    pub fn record_energy(&mut self, energy_uj: f64) {
+        if !self.is_recording() {
+            return;
+        }
+        self.energy_uj += energy_uj;
+    }
+
+    /// Update suppressed changes from the policies' running total
+    pub fn record_suppressed_total(&mut self, total: usize) {
+        if !self.is_recording() {
+            self.suppressed_offset = total;
+            return;
+        }
+        self.suppressed_changes = total - self.suppressed_offset;
+    }
+
+    /// Compliance with each configured SLO
+    pub fn slo_reports(&self) -> Vec<SloReport> {
+        self.slos
+            .iter()
+            .map(|slo| slo.evaluate(&self.delivery_times_us, &self.latencies_us))
+            .collect()
+    }
+
+    /// Decision changes per minute of recorded decisions
+    pub fn changes_per_minute(&self) -> f64 {
+        let minutes = self
+            .decision_span
+            .map_or(0.0, |(first, last)| last.duration_since(first).as_secs_f64() / 60.0);
+        if minutes == 0.0 {
+            return 0.0;
+        }
+        self.decision_changes as f64 / minutes
+    }
+
+    /// Mean relative output change per decision (0 for a constant policy)
+    pub fn mean_output_delta(&self) -> f64 {
+        if self.decisions == 0 {
+            return 0.0;
+        }
+        self.output_delta_sum / self.decisions as f64
+    }
+
+    /// Fraction of arrivals that cost a packet to queue overflow
+    pub fn drop_rate(&self) -> f64 {
+        if self.arrivals == 0 {
+            return 0.0;
+        }
+        self.drops as f64 / self.arrivals as f64
+    }
+
+    /// Fraction of transmissions (including retransmissions) that were lost
+    pub fn loss_rate(&self) -> f64 {
+        if self.transmissions == 0 {
+            return 0.0;
+        }
+        self.losses as f64 / self.transmissions as f64
+    }
+
     pub fn p50_latency(&self) -> f64 {
         self.percentile(0.50)
     }
@@ -158,14 +552,75 @@ impl Metrics {
         self.percentile(0.99)
     }
 
-    fn percentile(&self, p: f64) -> f64 {
-        if self.latencies_us.is_empty() {
-            return 0.0;
+    /// Latency percentile for one priority class
+    pub fn class_percentile(&self, class: usize, p: f64) -> f64 {
+        self.class_latencies_us
+            .get(class)
+            .map_or(0.0, |latencies| percentile(latencies, p))
+    }
+
+    /// Latency percentile for one flow
+    pub fn flow_percentile(&self, flow: u32, p: f64) -> f64 {
+        self.flow_latencies_us
+            .get(&flow)
+            .map_or(0.0, |latencies| percentile(latencies, p))
+    }
+
+    /// Latency fairness across flows: min/max of per-flow mean latency
+    ///
+    /// 1.0 means every flow sees the same mean latency; values near 0 mean
+    /// some flow waits far longer than another.
+    pub fn flow_latency_fairness(&self) -> f64 {
+        let means: Vec<f64> = self
+            .flow_latencies_us
+            .values()
+            .filter(|latencies| !latencies.is_empty())
+            .map(|latencies| latencies.iter().sum::<u64>() as f64 / latencies.len() as f64)
+            .collect();
+        let max = means.iter().cloned().fold(0.0, f64::max);
+        if means.len() < 2 || max == 0.0 {
+            return 1.0;
         }
-        let mut sorted = self.latencies_us.clone();
-        sorted.sort_unstable();
-        let idx = ((sorted.len() as f64) * p).floor() as usize;
-        sorted[idx.min(sorted.len() - 1)] as f64
+        let min = means.iter().cloned().fold(f64::MAX, f64::min);
+        min / max
+    }
+
+    /// Jain's fairness index over per-flow throughput
+    ///
+    /// (Σx)² / (n·Σx²) with x = delivered bytes per flow, which is
+    /// proportional to throughput since every flow shares the measurement
+    /// window. Ranges from 1/n (one flow gets everything) to 1.0 (equal share).
+    pub fn jain_fairness(&self) -> f64 {
+        let n = self.flow_delivered_bytes.len();
+        let sum: f64 = self.flow_delivered_bytes.values().map(|&b| b as f64).sum();
+        let sum_sq: f64 = self.flow_delivered_bytes.values().map(|&b| (b as f64).powi(2)).sum();
+        if n < 2 || sum_sq == 0.0 {
+            return 1.0;
+        }
+        sum * sum / (n as f64 * sum_sq)
+    }
+
+    /// Worst per-flow p99 latency, exposing flows starved by batching
+    pub fn max_flow_p99(&self) -> f64 {
+        self.flow_latencies_us
+            .values()
+            .map(|latencies| percentile(latencies, 0.99))
+            .fold(0.0, f64::max)
+    }
+
+    fn percentile(&self, p: f64) -> f64 {
+        percentile(&self.latencies_us, p)
+    }
+
+    /// Full latency distribution as a log-bucketed histogram
+    pub fn latency_histogram(&self, buckets_per_decade: u32) -> LatencyHistogram {
+        LatencyHistogram::from_latencies(&self.latencies_us, buckets_per_decade)
+    }
+
+    /// Latency histogram for one priority class
+    pub fn class_latency_histogram(&self, class: usize, buckets_per_decade: u32) -> LatencyHistogram {
+        let latencies = self.class_latencies_us.get(class).map_or(&[][..], |l| l.as_slice());
+        LatencyHistogram::from_latencies(latencies, buckets_per_decade)
     }
 
     pub fn mean_throughput(&self) -> f64 {
@@ -174,6 +629,34 @@ impl Metrics {
         }
         self.throughput_samples.iter().sum::<f64>() / self.throughput_samples.len() as f64
     }
+
+    pub fn mean_goodput(&self) -> f64 {
+        if self.goodput_samples.is_empty() {
+            return 0.0;
+        }
+        self.goodput_samples.iter().sum::<f64>() / self.goodput_samples.len() as f64
+    }
+
+    /// Overshoot and settling time of windowed p95 latency after a load step
+    ///
+    /// Uses 100ms windows and a ±20% settling band.
+    pub fn step_response(&self, step_at: Duration) -> Option<StepResponse> {
+        step_response(
+            &self.delivery_times_us,
+            &self.latencies_us,
+            step_at,
+            Duration::from_millis(100),
+            0.2,
+        )
+    }
+
+    /// Energy per delivered packet in microjoules (retransmissions included)
+    pub fn energy_per_packet(&self) -> f64 {
+        if self.latencies_us.is_empty() {
+            return 0.0;
+        }
+        self.energy_uj / self.latencies_us.len() as f64
+    }
 }
 
 impl Default for Metrics {
@@ -182,60 +665,438 @@ impl Default for Metrics {
     }
 }
 
+/// Pop entries older than `cutoff` off the front of a window kept in time order
+fn prune_window<T>(window: &mut VecDeque<T>, cutoff: Instant, time: impl Fn(&T) -> Instant) {
+    while window.front().is_some_and(|entry| time(entry) < cutoff) {
+        window.pop_front();
+    }
+}
+
+fn percentile(values: &[u64], p: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let idx = ((sorted.len() as f64) * p).floor() as usize;
+    sorted[idx.min(sorted.len() - 1)] as f64
+}
+
+/// Link model (serialization delay)
+///
+/// Each flush pays a fixed cost (syscall, doorbell, framing) and then
+/// transmits its packets back to back at the link capacity. A flush issued
+/// while the link is still busy waits for the previous one to drain.
+#[derive(Debug, Clone, Copy)]
+pub struct LinkModel {
+    pub bytes_per_sec: f64,
+    pub per_flush_cost_us: u64,
+    /// Overhead per segment when a decision caps segment size
+    pub per_segment_cost_us: u64,
+    /// Flushes onto a link idle at least this long pay `idle_flush_cost_us` instead
+    pub idle_after: Option<Duration>,
+    pub idle_flush_cost_us: u64,
+}
+
+impl LinkModel {
+    pub fn new(bytes_per_sec: f64, per_flush_cost_us: u64) -> Self {
+        Self {
+            bytes_per_sec,
+            per_flush_cost_us,
+            per_segment_cost_us: 0,
+            idle_after: None,
+            idle_flush_cost_us: per_flush_cost_us,
+        }
+    }
+
+    /// Make flushes cheap once the link has been idle for `idle_after`
+    /// (nothing in flight to coalesce with, no doorbell batching to lose)
+    pub fn with_idle_flush_cost(mut self, idle_after: Duration, idle_flush_cost_us: u64) -> Self {
+        self.idle_after = Some(idle_after);
+        self.idle_flush_cost_us = idle_flush_cost_us;
+        self
+    }
+
+    /// Fixed flush cost given how long the link has been idle
+    pub fn flush_cost_us(&self, idle: Duration) -> u64 {
+        match self.idle_after {
+            Some(idle_after) if idle >= idle_after => self.idle_flush_cost_us,
+            _ => self.per_flush_cost_us,
+        }
+    }
+
+    /// Per-segment overhead for segmented flushes (default: 0)
+    pub fn with_per_segment_cost(mut self, per_segment_cost_us: u64) -> Self {
+        self.per_segment_cost_us = per_segment_cost_us;
+        self
+    }
+
+    /// Time to put `bytes` on the wire
+    pub fn serialization_delay(&self, bytes: usize) -> Duration {
+        Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec)
+    }
+}
+
+/// Energy model for radio/NIC power
+///
+/// Every flush wakes the radio (fixed cost) and every transmitted byte,
+/// including retransmissions, costs energy on top. Small frequent batches
+/// trade energy for latency.
+#[derive(Debug, Clone, Copy)]
+pub struct EnergyModel {
+    pub per_flush_uj: f64,
+    pub per_byte_uj: f64,
+}
+
+impl EnergyModel {
+    pub fn new(per_flush_uj: f64, per_byte_uj: f64) -> Self {
+        Self {
+            per_flush_uj,
+            per_byte_uj,
+        }
+    }
+}
+
+/// What a full queue discards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropPolicy {
+    /// Reject the arriving packet
+    #[default]
+    Tail,
+    /// Evict the oldest queued packet to admit the new one
+    Head,
+}
+
+/// Packet loss model
+#[derive(Debug, Clone, Copy)]
+pub enum LossModel {
+    /// Independent loss with fixed probability per transmission
+    Random { loss_prob: f64 },
+    /// Gilbert-Elliott burst loss: the channel flips between a good (lossless)
+    /// and a bad state, dropping `loss_prob_bad` of transmissions while bad
+    Burst {
+        p_enter_bad: f64,
+        p_exit_bad: f64,
+        loss_prob_bad: f64,
+    },
+}
+
+/// Loss model plus retransmission state
+struct LossChannel {
+    model: LossModel,
+    rto: Duration,
+    in_bad_state: bool,
+    pending_retransmits: VecDeque<(Instant, Packet)>, // (due, packet)
+}
+
+impl LossChannel {
+    fn is_lost(&mut self, rng: &mut impl Rng) -> bool {
+        match self.model {
+            LossModel::Random { loss_prob } => rng.gen_bool(loss_prob),
+            LossModel::Burst {
+                p_enter_bad,
+                p_exit_bad,
+                loss_prob_bad,
+            } => {
+                let flip = if self.in_bad_state { p_exit_bad } else { p_enter_bad };
+                if rng.gen_bool(flip) {
+                    self.in_bad_state = !self.in_bad_state;
+                }
+                self.in_bad_state && rng.gen_bool(loss_prob_bad)
+            }
+        }
+    }
+}
+
+/// EWMA gain for smoothed RTT (RFC 6298 alpha)
+const RTT_EWMA_ALPHA: f32 = 0.125;
+
+/// RTT model
+///
+/// Each delivered packet yields an RTT sample of base propagation delay plus
+/// queueing delay proportional to the bytes queued ahead of it, with optional
+/// uniform jitter.
+#[derive(Debug, Clone, Copy)]
+pub struct RttModel {
+    pub base_us: f64,
+    pub queueing_us_per_kb: f64,
+    pub jitter_us: f64,
+}
+
+impl RttModel {
+    pub fn new(base_us: f64, queueing_us_per_kb: f64) -> Self {
+        Self {
+            base_us,
+            queueing_us_per_kb,
+            jitter_us: 0.0,
+        }
+    }
+
+    /// Add uniform jitter in [-jitter_us, +jitter_us]
+    pub fn with_jitter(mut self, jitter_us: f64) -> Self {
+        self.jitter_us = jitter_us;
+        self
+    }
+
+    fn sample_us(&self, outstanding_bytes: usize, rng: &mut impl Rng) -> f64 {
+        let queueing = self.queueing_us_per_kb * outstanding_bytes as f64 / 1024.0;
+        let jitter = if self.jitter_us > 0.0 {
+            rng.gen_range(-self.jitter_us..=self.jitter_us)
+        } else {
+            0.0
+        };
+        (self.base_us + queueing + jitter).max(0.0)
+    }
+}
+
+/// Priority class configuration
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriorityClass {
+    /// Queued packets of this class flush every class on the next tick
+    pub urgent: bool,
+}
+
 /// Fake transport simulator
+///
+/// Packets are queued per priority class (index 0 = highest priority). By
+/// default there is a single class driven by a single policy.
 pub struct FakeTransport<P: FlushPolicy> {
-    queue: VecDeque<Packet>,
-    policy: P,
+    queues: Vec<VecDeque<Packet>>,
+    classes: Vec<PriorityClass>,
+    policies: Vec<P>, // one shared policy, or one per class
     metrics: Metrics,
     next_packet_id: u64,
-    last_decision: Option<FlushDecision>,
     sent_packets: usize,
     last_throughput_measurement: Instant,
+    link: Option<LinkModel>,
+    link_free_at: Option<Instant>,
+    loss: Option<LossChannel>,
+    energy: Option<EnergyModel>,
+    queue_capacity: Option<(usize, DropPolicy)>,
+    drop_window: VecDeque<Instant>,
+    delivered_bytes: usize,
+    transmit_window: VecDeque<(Instant, bool)>, // (sent, lost)
+    enqueue_window: VecDeque<(Instant, usize)>, // (arrival, bytes)
+    dequeue_window: VecDeque<(Instant, usize)>, // (sent, bytes)
+    rtt: Option<RttModel>,
+    rtt_ewma_us: f32,
+    rng: StdRng,
+    created_at: Instant,
+    decision_log: Option<Vec<DecisionRecord>>,
+    state_series: Option<StateSeries>,
+}
+
+/// Fixed-cadence sampler behind [`FakeTransport::with_state_series`]
+struct StateSeries {
+    interval: Duration,
+    next_at: Instant,
+    samples: Vec<StateSample>,
 }
 
 impl<P: FlushPolicy> FakeTransport<P> {
     pub fn new(policy: P) -> Self {
         Self {
-            queue: VecDeque::new(),
-            policy,
+            queues: vec![VecDeque::new()],
+            classes: vec![PriorityClass::default()],
+            policies: vec![policy],
             metrics: Metrics::new(),
             next_packet_id: 0,
-            last_decision: None,
             sent_packets: 0,
             last_throughput_measurement: Instant::now(),
+            link: None,
+            link_free_at: None,
+            loss: None,
+            energy: None,
+            queue_capacity: None,
+            drop_window: VecDeque::new(),
+            delivered_bytes: 0,
+            transmit_window: VecDeque::new(),
+            enqueue_window: VecDeque::new(),
+            dequeue_window: VecDeque::new(),
+            rtt: None,
+            rtt_ewma_us: 50.0,
+            rng: StdRng::from_entropy(),
+            created_at: Instant::now(),
+            decision_log: None,
+            state_series: None,
         }
     }
 
-    /// Enqueue a packet
+    /// Model link capacity and per-flush cost (default: free, instantaneous flushes)
+    pub fn with_link(mut self, link: LinkModel) -> Self {
+        self.link = Some(link);
+        self
+    }
+
+    /// Drop transmissions according to `model`; lost packets are requeued after `rto`
+    pub fn with_loss(mut self, model: LossModel, rto: Duration) -> Self {
+        self.loss = Some(LossChannel {
+            model,
+            rto,
+            in_bad_state: false,
+            pending_retransmits: VecDeque::new(),
+        });
+        self
+    }
+
+    /// Bound each class's queue to `capacity` packets (default: unbounded)
+    pub fn with_queue_capacity(mut self, capacity: usize, drop_policy: DropPolicy) -> Self {
+        assert!(capacity > 0, "queue capacity must be positive");
+        self.queue_capacity = Some((capacity, drop_policy));
+        self
+    }
+
+    /// Account flush and transmit energy in metrics (default: not modeled)
+    pub fn with_energy(mut self, energy: EnergyModel) -> Self {
+        self.energy = Some(energy);
+        self
+    }
+
+    /// Derive rtt_ewma_us from per-packet RTT samples (default: constant 50µs)
+    pub fn with_rtt(mut self, rtt: RttModel) -> Self {
+        self.rtt = Some(rtt);
+        self.rtt_ewma_us = rtt.base_us as f32;
+        self
+    }
+
+    /// Seed the loss and RTT jitter models
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Exclude the first `warmup` of the run from metrics
+    pub fn with_warmup(mut self, warmup: Duration) -> Self {
+        self.metrics.warmup = warmup;
+        self
+    }
+
+    /// Track compliance with a latency SLO in metrics
+    pub fn with_slo(mut self, slo: LatencySlo) -> Self {
+        self.metrics.slos.push(slo);
+        self
+    }
+
+    /// Record telemetry and decision for every policy invocation
+    pub fn with_decision_log(mut self) -> Self {
+        self.decision_log = Some(Vec::new());
+        self
+    }
+
+    /// Sample queue depth, oldest-packet age and the decision in force every `interval`
+    pub fn with_state_series(mut self, interval: Duration) -> Self {
+        assert!(interval > Duration::ZERO, "state sampling interval must be positive");
+        self.state_series = Some(StateSeries {
+            interval,
+            next_at: self.created_at,
+            samples: Vec::new(),
+        });
+        self
+    }
+
+    /// Split the queue into priority classes (index 0 = highest priority)
+    ///
+    /// The existing policy is shared by all classes unless
+    /// [`with_class_policies`](Self::with_class_policies) is called afterwards.
+    pub fn with_priority_classes(mut self, classes: Vec<PriorityClass>) -> Self {
+        assert!(!classes.is_empty(), "at least one priority class required");
+        self.queues = classes.iter().map(|_| VecDeque::new()).collect();
+        self.classes = classes;
+        self
+    }
+
+    /// Give each priority class its own policy, fed with that class's telemetry
+    pub fn with_class_policies(mut self, policies: Vec<P>) -> Self {
+        assert_eq!(policies.len(), self.classes.len(), "one policy per priority class");
+        self.policies = policies;
+        self
+    }
+
+    /// Enqueue a packet in the lowest priority class
     pub fn enqueue(&mut self, size_bytes: usize) {
+        self.enqueue_class(size_bytes, self.queues.len() - 1);
+    }
+
+    /// Enqueue a packet in a given priority class
+    pub fn enqueue_class(&mut self, size_bytes: usize, class: usize) {
+        self.enqueue_packet(size_bytes, class, 0);
+    }
+
+    /// Enqueue a packet for a given flow in the lowest priority class
+    pub fn enqueue_flow(&mut self, size_bytes: usize, flow: u32) {
+        self.enqueue_packet(size_bytes, self.queues.len() - 1, flow);
+    }
+
+    /// Enqueue a packet with explicit priority class and flow ID
+    pub fn enqueue_packet(&mut self, size_bytes: usize, class: usize, flow: u32) {
+        let now = Instant::now();
         let packet = Packet {
             id: self.next_packet_id,
             size_bytes,
-            arrival_time: Instant::now(),
+            arrival_time: now,
+            class,
+            flow,
         };
         self.next_packet_id += 1;
-        self.queue.push_back(packet);
+        // Register the flow so one that never gets anything delivered still counts against fairness
+        self.metrics.record_flow_delivery(flow, 0);
+
+        let mut dropped = false;
+        if let Some((capacity, drop_policy)) = self.queue_capacity {
+            if self.queues[class].len() >= capacity {
+                dropped = true;
+                self.drop_window.push_back(now);
+                match drop_policy {
+                    DropPolicy::Tail => {
+                        self.metrics.record_arrival(true);
+                        return;
+                    }
+                    DropPolicy::Head => {
+                        self.queues[class].pop_front();
+                    }
+                }
+            }
+        }
+        self.metrics.record_arrival(dropped);
+        self.queues[class].push_back(packet);
+
+        // Track arrivals
+        self.enqueue_window.push_back((now, size_bytes));
     }
 
     /// Tick the simulator
     pub fn tick(&mut self) {
-        let telem = self.collect_telemetry();
-        let decision = self.policy.decide(&telem);
+        self.requeue_retransmits();
 
-        // Track decision changes
-        if let Some(last) = self.last_decision {
-            if last.threshold != decision.threshold || last.max_delay_us != decision.max_delay_us {
-                self.metrics.record_decision_change();
-            }
-        }
-        self.last_decision = Some(decision);
+        let decisions = self.decide();
 
-        // Flush if conditions met
-        let should_flush = self.queue.len() >= decision.threshold as usize
-            || self.oldest_packet_age_us() >= decision.max_delay_us as u64;
+        // Track decision changes; a shared policy made one decision, not one per class
+        let decided = if self.policies.len() == 1 { &decisions[..1] } else { &decisions[..] };
+        for (class, decision) in decided.iter().enumerate() {
+            self.metrics.record_decision(class, *decision);
+        }
+        let suppressed = self.policies.iter().map(|p| p.suppressed_changes()).sum();
+        self.metrics.record_suppressed_total(suppressed);
+        self.sample_state(&decisions);
+
+        // Flush if conditions met; urgent traffic flushes every class
+        let urgent = self
+            .classes
+            .iter()
+            .zip(&self.queues)
+            .any(|(class, queue)| class.urgent && !queue.is_empty());
+        let to_flush: Vec<usize> = (0..self.queues.len())
+            .filter(|&class| {
+                let decision = decisions[class];
+                !self.queues[class].is_empty()
+                    && (urgent
+                        || self.queues[class].len() >= decision.threshold as usize
+                        || self.oldest_packet_age_us(class) >= decision.max_delay_us as u64)
+            })
+            .collect();
 
-        if should_flush {
-            self.flush();
+        if !to_flush.is_empty() {
+            self.flush(&to_flush, &decisions);
         }
 
         // Measure throughput every second
@@ -244,48 +1105,276 @@ impl<P: FlushPolicy> FakeTransport<P> {
             let elapsed = now.duration_since(self.last_throughput_measurement).as_secs_f64();
             let throughput = self.sent_packets as f64 / elapsed;
             self.metrics.record_throughput(throughput);
+            self.metrics.record_goodput(self.delivered_bytes as f64 / elapsed);
             self.sent_packets = 0;
+            self.delivered_bytes = 0;
             self.last_throughput_measurement = now;
         }
-    }
 
-    fn flush(&mut self) {
-        let now = Instant::now();
-        while let Some(packet) = self.queue.pop_front() {
-            let latency_us = now.duration_since(packet.arrival_time).as_micros() as u64;
-            self.metrics.record_latency(latency_us);
-            self.sent_packets += 1;
-        }
+        // Cleanup old window data
+        let cutoff = now - Duration::from_secs(1);
+        prune_window(&mut self.transmit_window, cutoff, |(t, _)| *t);
+        prune_window(&mut self.enqueue_window, cutoff, |(t, _)| *t);
+        prune_window(&mut self.dequeue_window, cutoff, |(t, _)| *t);
+        prune_window(&mut self.drop_window, cutoff, |t| *t);
     }
 
-    fn oldest_packet_age_us(&self) -> u64 {
-        self.queue.front().map_or(0, |p| {
-            Instant::now().duration_since(p.arrival_time).as_micros() as u64
+    /// Drive the transport in real time from `workload` until `duration` elapses
+    pub fn run(&mut self, workload: &mut dyn WorkloadGenerator, duration: Duration) {
+        let start = Instant::now();
+        let tick_interval = Duration::from_micros(100); // 10 kHz tick rate
+
+        loop {
+            // Enqueue packets
+            while let Some((wait, size)) = workload.next_packet() {
+                if wait > Duration::ZERO {
+                    std::thread::sleep(wait.min(tick_interval));
+                }
+
+                // Closed-loop sources stall until the transport drains
+                if let Some(limit) = workload.backpressure_limit() {
+                    while self.queue_depth() >= limit && start.elapsed() < duration {
+                        std::thread::sleep(tick_interval);
+                        self.tick();
+                    }
+                    if start.elapsed() >= duration {
+                        break;
+                    }
+                }
+                self.enqueue_flow(size, workload.flow());
+                self.tick();
+
+                if start.elapsed() >= duration {
+                    break;
+                }
+            }
+
+            if start.elapsed() >= duration {
+                break;
+            }
+
+            std::thread::sleep(tick_interval);
+            self.tick();
+        }
+
+        // Final flush
+        self.tick();
+    }
+
+    /// One decision per class, from either the shared or the per-class policies
+    fn decide(&mut self) -> Vec<FlushDecision> {
+        if self.policies.len() == 1 {
+            let telem = self.collect_telemetry(None);
+            let decision = self.policies[0].decide(&telem);
+            self.log_decision(None, telem, decision);
+            return vec![decision; self.queues.len()];
+        }
+
+        let mut decisions = Vec::with_capacity(self.policies.len());
+        for class in 0..self.policies.len() {
+            let telem = self.collect_telemetry(Some(class));
+            let decision = self.policies[class].decide(&telem);
+            self.log_decision(Some(class), telem, decision);
+            decisions.push(decision);
+        }
+        decisions
+    }
+
+    fn log_decision(&mut self, class: Option<usize>, telemetry: TelemetrySample, decision: FlushDecision) {
+        if let Some(log) = self.decision_log.as_mut() {
+            log.push(DecisionRecord {
+                timestamp_us: self.created_at.elapsed().as_micros() as u64,
+                class,
+                telemetry,
+                decision,
+            });
+        }
+    }
+
+    /// Append one state sample per class if the sampling interval has elapsed
+    fn sample_state(&mut self, decisions: &[FlushDecision]) {
+        let now = Instant::now();
+        if self.state_series.as_ref().is_none_or(|series| now < series.next_at) {
+            return;
+        }
+
+        let timestamp_us = now.duration_since(self.created_at).as_micros() as u64;
+        let samples: Vec<StateSample> = decisions
+            .iter()
+            .enumerate()
+            .map(|(class, decision)| StateSample {
+                timestamp_us,
+                class,
+                queue_depth: self.queues[class].len(),
+                oldest_packet_age_us: self.oldest_packet_age_us(class),
+                threshold: decision.threshold,
+                max_delay_us: decision.max_delay_us,
+                segment_bytes: decision.segment_bytes,
+                pacing_rate: decision.pacing_rate,
+            })
+            .collect();
+
+        let series = self.state_series.as_mut().unwrap();
+        series.samples.extend(samples);
+        // Skip missed slots rather than bursting to catch up after a stall
+        while series.next_at <= now {
+            series.next_at += series.interval;
+        }
+    }
+
+    /// Move retransmissions whose RTO has expired back to the head of their queue
+    fn requeue_retransmits(&mut self) {
+        let Some(loss) = self.loss.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        let mut due = Vec::new();
+        while loss.pending_retransmits.front().is_some_and(|(t, _)| *t <= now) {
+            due.push(loss.pending_retransmits.pop_front().unwrap().1);
+        }
+        for packet in due.into_iter().rev() {
+            self.queues[packet.class].push_front(packet);
+        }
+    }
+
+    /// Flush the given classes, in order, as one batch
+    ///
+    /// A class whose decision sets `segment_bytes` goes out as segments of at
+    /// most that size (GSO-style): every packet in a segment departs once the
+    /// whole segment is serialized, and each segment pays the link's
+    /// per-segment cost. Otherwise packets depart individually.
+    ///
+    /// A pacing rate spaces departures (segments or packets) so the batch
+    /// drains no faster than that rate, even on an unmodeled link.
+    fn flush(&mut self, classes: &[usize], decisions: &[FlushDecision]) {
+        let now = Instant::now();
+
+        // Wait for the previous flush to drain; with a link model, pay the fixed cost
+        let idle = self.link_idle_time(now);
+        let flush_cost = self
+            .link
+            .map_or(Duration::ZERO, |link| Duration::from_micros(link.flush_cost_us(idle)));
+        let mut departure = self.link_free_at.map_or(now, |free_at| free_at.max(now)) + flush_cost;
+
+        if let Some(energy) = self.energy {
+            self.metrics.record_energy(energy.per_flush_uj);
+        }
+
+        // Bytes still draining from earlier flushes sit ahead of this batch
+        let mut outstanding_bytes = match (self.link, self.link_free_at) {
+            (Some(link), Some(free_at)) => {
+                (free_at.saturating_duration_since(now).as_secs_f64() * link.bytes_per_sec) as usize
+            }
+            _ => 0,
+        };
+
+        for &class in classes {
+            let segment_limit = decisions[class].segment_bytes.map(|limit| limit as usize);
+            let pacing_rate = decisions[class].pacing_rate;
+            let mut queue = std::mem::take(&mut self.queues[class]);
+            while let Some(first) = queue.pop_front() {
+                let mut segment_bytes = first.size_bytes;
+                let mut segment = vec![first];
+                if let Some(limit) = segment_limit {
+                    while queue.front().is_some_and(|p| segment_bytes + p.size_bytes <= limit) {
+                        let packet = queue.pop_front().unwrap();
+                        segment_bytes += packet.size_bytes;
+                        segment.push(packet);
+                    }
+                }
+
+                let wire_time = self
+                    .link
+                    .map_or(Duration::ZERO, |link| link.serialization_delay(segment_bytes));
+                let pace_time = pacing_rate.map_or(Duration::ZERO, |rate| {
+                    Duration::from_secs_f64(segment_bytes as f64 / rate.max(1) as f64)
+                });
+                departure += wire_time.max(pace_time);
+                if let (Some(link), Some(_)) = (self.link, segment_limit) {
+                    departure += Duration::from_micros(link.per_segment_cost_us);
+                }
+                for packet in segment {
+                    self.transmit(packet, now, departure, &mut outstanding_bytes);
+                }
+            }
+        }
+
+        self.link_free_at = Some(departure);
+    }
+
+    /// Time since the link finished its last transmission (zero while busy)
+    fn link_idle_time(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.link_free_at.unwrap_or(self.created_at))
+    }
+
+    /// Send one packet that leaves the link at `departure`
+    fn transmit(&mut self, packet: Packet, now: Instant, departure: Instant, outstanding_bytes: &mut usize) {
+        self.sent_packets += 1;
+        self.dequeue_window.push_back((now, packet.size_bytes));
+        if let Some(energy) = self.energy {
+            self.metrics.record_energy(energy.per_byte_uj * packet.size_bytes as f64);
+        }
+
+        let rng = &mut self.rng;
+        let lost = self.loss.as_mut().is_some_and(|loss| loss.is_lost(rng));
+        self.metrics.record_transmission(lost);
+        self.transmit_window.push_back((now, lost));
+        if lost {
+            let loss = self.loss.as_mut().unwrap();
+            let due = departure + loss.rto;
+            loss.pending_retransmits.push_back((due, packet));
+            return;
+        }
+
+        let latency_us = departure.duration_since(packet.arrival_time).as_micros() as u64;
+        self.metrics.record_latency(latency_us);
+        self.metrics.record_class_latency(packet.class, latency_us);
+        self.metrics.record_flow_latency(packet.flow, latency_us);
+        self.metrics.record_flow_delivery(packet.flow, packet.size_bytes);
+        self.delivered_bytes += packet.size_bytes;
+
+        if let Some(rtt) = self.rtt {
+            let sample = rtt.sample_us(*outstanding_bytes, &mut self.rng) as f32;
+            self.rtt_ewma_us += RTT_EWMA_ALPHA * (sample - self.rtt_ewma_us);
+        }
+        *outstanding_bytes += packet.size_bytes;
+    }
+
+    fn oldest_packet_age_us(&self, class: usize) -> u64 {
+        self.queues[class].front().map_or(0, |p| {
+            Instant::now().duration_since(p.arrival_time).as_micros() as u64
         })
     }
 
-    fn collect_telemetry(&self) -> TelemetrySample {
+    /// Telemetry over one class's queue, or over all queues when `class` is None
+    ///
+    /// Rate, RTT and loss features always describe the whole transport.
+    fn collect_telemetry(&self, class: Option<usize>) -> TelemetrySample {
         let now = Instant::now();
 
+        let queued: Vec<&Packet> = match class {
+            Some(class) => self.queues[class].iter().collect(),
+            None => self.queues.iter().flatten().collect(),
+        };
+
         // Compute simple statistics
-        let queue_depth = self.queue.len() as u32;
+        let queue_depth = queued.len() as u32;
 
-        let latencies: Vec<u64> = self.queue.iter()
+        let latencies: Vec<u64> = queued.iter()
             .map(|p| now.duration_since(p.arrival_time).as_micros() as u64)
             .collect();
 
-        let (latency_p50, latency_p95) = if latencies.is_empty() {
-            (0.0, 0.0)
+        let (latency_p50, latency_p95, latency_p99) = if latencies.is_empty() {
+            (0.0, 0.0, 0.0)
         } else {
             let mut sorted = latencies.clone();
             sorted.sort_unstable();
             let p50 = sorted[sorted.len() / 2] as f32;
-            let p95_idx = ((sorted.len() as f32 * 0.95) as usize).min(sorted.len() - 1);
-            let p95 = sorted[p95_idx] as f32;
-            (p50, p95)
+            let at = |q: f32| sorted[((sorted.len() as f32 * q) as usize).min(sorted.len() - 1)] as f32;
+            (p50, at(0.95), at(0.99))
         };
 
-        let packet_sizes: Vec<f32> = self.queue.iter().map(|p| p.size_bytes as f32).collect();
+        let packet_sizes: Vec<f32> = queued.iter().map(|p| p.size_bytes as f32).collect();
         let packet_size_mean = if packet_sizes.is_empty() {
             0.0
         } else {
@@ -299,50 +1388,135 @@ impl<P: FlushPolicy> FakeTransport<P> {
             packet_sizes.iter().map(|s| (s - mean).powi(2)).sum::<f32>() / packet_sizes.len() as f32
         };
 
+        let loss_rate = if self.transmit_window.is_empty() {
+            0.0
+        } else {
+            let lost = self.transmit_window.iter().filter(|(_, lost)| *lost).count();
+            lost as f32 / self.transmit_window.len() as f32
+        };
+
         TelemetrySample {
             timestamp_us: now.elapsed().as_micros() as u64,
             queue_depth,
-            enqueue_rate: 0.0, // TODO: track
-            dequeue_rate: 0.0, // TODO: track
+            enqueue_rate: self.enqueue_window.len() as f32,
+            dequeue_rate: self.dequeue_window.len() as f32,
             latency_p50_us: latency_p50,
             latency_p95_us: latency_p95,
-            bytes_in_per_sec: 0.0, // TODO: track
-            bytes_out_per_sec: 0.0, // TODO: track
+            bytes_in_per_sec: self.enqueue_window.iter().map(|(_, b)| *b).sum::<usize>() as f64,
+            bytes_out_per_sec: self.dequeue_window.iter().map(|(_, b)| *b).sum::<usize>() as f64,
             packet_size_mean,
             packet_size_var,
-            rtt_ewma_us: 50.0, // TODO: track
+            rtt_ewma_us: self.rtt_ewma_us,
+            loss_rate,
+            drops_per_sec: self.drop_window.len() as f32,
+            link_idle_us: self.link_idle_time(now).as_micros() as f32,
+            latency_p99_us: latency_p99,
         }
     }
 
     pub fn metrics(&self) -> &Metrics {
         &self.metrics
     }
+
+    /// Packets currently queued across all priority classes
+    pub fn queue_depth(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    /// Recorded decisions, if enabled with [`with_decision_log`](Self::with_decision_log)
+    pub fn decision_log(&self) -> Option<&[DecisionRecord]> {
+        self.decision_log.as_deref()
+    }
+
+    /// Sampled state, if enabled with [`with_state_series`](Self::with_state_series)
+    pub fn state_series(&self) -> Option<&[StateSample]> {
+        self.state_series.as_ref().map(|series| series.samples.as_slice())
+    }
 }
 
 /// Workload generator
 pub trait WorkloadGenerator {
     fn next_packet(&mut self) -> Option<(Duration, usize)>; // (wait_time, size_bytes)
+
+    /// Queue depth at which a closed-loop source stops generating (default: open loop)
+    fn backpressure_limit(&self) -> Option<usize> {
+        None
+    }
+
+    /// Flow ID of the packet `next_packet` last returned (default: flow 0)
+    fn flow(&self) -> u32 {
+        0
+    }
+}
+
+/// Packet size distribution
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeDistribution {
+    Constant(usize),
+    Uniform { min: usize, max: usize },
+    /// Lognormal parameterized by its median and log-space sigma
+    LogNormal { median: f64, sigma: f64 },
+    /// Bounded Pareto with minimum `scale`, tail index `shape`, truncated at `cap`
+    Pareto { scale: f64, shape: f64, cap: usize },
+}
+
+impl SizeDistribution {
+    pub fn sample(&self, rng: &mut impl Rng) -> usize {
+        match *self {
+            SizeDistribution::Constant(size) => size,
+            SizeDistribution::Uniform { min, max } => rng.gen_range(min..=max),
+            SizeDistribution::LogNormal { median, sigma } => {
+                let z = standard_normal(rng);
+                (median * (sigma * z).exp()).round().max(1.0) as usize
+            }
+            SizeDistribution::Pareto { scale, shape, cap } => {
+                let u: f64 = 1.0 - rng.gen::<f64>();
+                let size = scale / u.powf(1.0 / shape);
+                (size.round() as usize).clamp(1, cap)
+            }
+        }
+    }
+}
+
+/// Box-Muller standard normal sample
+pub(crate) fn standard_normal(rng: &mut impl Rng) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
 }
 
 /// Steady Poisson workload
 pub struct SteadyWorkload {
     rate_per_sec: f64,
-    packet_size: usize,
+    packet_size: SizeDistribution,
     duration: Duration,
     elapsed: Duration,
-    rng: rand::rngs::ThreadRng,
+    rng: StdRng,
 }
 
 impl SteadyWorkload {
     pub fn new(rate_per_sec: f64, packet_size: usize, duration: Duration) -> Self {
         Self {
             rate_per_sec,
-            packet_size,
+            packet_size: SizeDistribution::Constant(packet_size),
             duration,
             elapsed: Duration::ZERO,
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
         }
     }
+
+    /// Draw packet sizes from `dist` instead of a constant size
+    pub fn with_size_distribution(mut self, dist: SizeDistribution) -> Self {
+        self.packet_size = dist;
+        self
+    }
+
+    /// Reproducible arrivals and sizes
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
 }
 
 impl WorkloadGenerator for SteadyWorkload {
@@ -358,7 +1532,7 @@ impl WorkloadGenerator for SteadyWorkload {
         let wait = Duration::from_secs_f64(wait_s);
 
         self.elapsed += wait;
-        Some((wait, self.packet_size))
+        Some((wait, self.packet_size.sample(&mut self.rng)))
     }
 }
 
@@ -366,11 +1540,11 @@ impl WorkloadGenerator for SteadyWorkload {
 pub struct BurstyWorkload {
     high_rate: f64,
     low_rate: f64,
-    packet_size: usize,
+    packet_size: SizeDistribution,
     period: Duration,
     duration: Duration,
     elapsed: Duration,
-    rng: rand::rngs::ThreadRng,
+    rng: StdRng,
 }
 
 impl BurstyWorkload {
@@ -384,14 +1558,26 @@ impl BurstyWorkload {
         Self {
             high_rate,
             low_rate,
-            packet_size,
+            packet_size: SizeDistribution::Constant(packet_size),
             period,
             duration,
             elapsed: Duration::ZERO,
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
         }
     }
 
+    /// Draw packet sizes from `dist` instead of a constant size
+    pub fn with_size_distribution(mut self, dist: SizeDistribution) -> Self {
+        self.packet_size = dist;
+        self
+    }
+
+    /// Reproducible arrivals and sizes
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
     fn current_rate(&self) -> f64 {
         let phase = self.elapsed.as_secs_f64() % (self.period.as_secs_f64() * 2.0);
         if phase < self.period.as_secs_f64() {
@@ -414,7 +1600,125 @@ impl WorkloadGenerator for BurstyWorkload {
         let wait = Duration::from_secs_f64(wait_s);
 
         self.elapsed += wait;
-        Some((wait, self.packet_size))
+        Some((wait, self.packet_size.sample(&mut self.rng)))
+    }
+}
+
+/// Linear rate ramp
+///
+/// Poisson arrivals whose rate moves linearly from `from_rate` to `to_rate`
+/// over the workload's duration.
+pub struct RampWorkload {
+    from_rate: f64,
+    to_rate: f64,
+    packet_size: SizeDistribution,
+    duration: Duration,
+    elapsed: Duration,
+    rng: StdRng,
+}
+
+impl RampWorkload {
+    pub fn new(from_rate: f64, to_rate: f64, packet_size: usize, duration: Duration) -> Self {
+        Self {
+            from_rate,
+            to_rate,
+            packet_size: SizeDistribution::Constant(packet_size),
+            duration,
+            elapsed: Duration::ZERO,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Draw packet sizes from `dist` instead of a constant size
+    pub fn with_size_distribution(mut self, dist: SizeDistribution) -> Self {
+        self.packet_size = dist;
+        self
+    }
+
+    /// Reproducible arrivals and sizes
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    fn current_rate(&self) -> f64 {
+        let progress = (self.elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0);
+        self.from_rate + (self.to_rate - self.from_rate) * progress
+    }
+}
+
+impl WorkloadGenerator for RampWorkload {
+    fn next_packet(&mut self) -> Option<(Duration, usize)> {
+        if self.elapsed >= self.duration {
+            return None;
+        }
+
+        let lambda = self.current_rate();
+        let u: f64 = self.rng.gen();
+        let wait_s = -u.ln() / lambda;
+        let wait = Duration::from_secs_f64(wait_s);
+
+        self.elapsed += wait;
+        Some((wait, self.packet_size.sample(&mut self.rng)))
+    }
+}
+
+/// Step-change workload
+///
+/// Poisson arrivals at `rate_before` until `step_at`, then instantly at
+/// `rate_after`, for transient-response analysis.
+pub struct StepWorkload {
+    rate_before: f64,
+    rate_after: f64,
+    step_at: Duration,
+    packet_size: SizeDistribution,
+    duration: Duration,
+    elapsed: Duration,
+    rng: StdRng,
+}
+
+impl StepWorkload {
+    pub fn new(rate_before: f64, rate_after: f64, step_at: Duration, packet_size: usize, duration: Duration) -> Self {
+        Self {
+            rate_before,
+            rate_after,
+            step_at,
+            packet_size: SizeDistribution::Constant(packet_size),
+            duration,
+            elapsed: Duration::ZERO,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Draw packet sizes from `dist` instead of a constant size
+    pub fn with_size_distribution(mut self, dist: SizeDistribution) -> Self {
+        self.packet_size = dist;
+        self
+    }
+
+    /// Reproducible arrivals and sizes
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl WorkloadGenerator for StepWorkload {
+    fn next_packet(&mut self) -> Option<(Duration, usize)> {
+        if self.elapsed >= self.duration {
+            return None;
+        }
+
+        let lambda = if self.elapsed < self.step_at {
+            self.rate_before
+        } else {
+            self.rate_after
+        };
+        let u: f64 = self.rng.gen();
+        let wait = Duration::from_secs_f64(-u.ln() / lambda);
+
+        self.elapsed += wait;
+        Some((wait, self.packet_size.sample(&mut self.rng)))
     }
 }
 
@@ -424,7 +1728,7 @@ pub struct AdversarialWorkload {
     packet_size_range: (usize, usize),
     duration: Duration,
     elapsed: Duration,
-    rng: rand::rngs::ThreadRng,
+    rng: StdRng,
 }
 
 impl AdversarialWorkload {
@@ -438,9 +1742,15 @@ impl AdversarialWorkload {
             packet_size_range,
             duration,
             elapsed: Duration::ZERO,
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
         }
     }
+
+    /// Reproducible arrivals and sizes
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
 }
 
 impl WorkloadGenerator for AdversarialWorkload {
@@ -464,3 +1774,822 @@ impl WorkloadGenerator for AdversarialWorkload {
         Some((wait, size))
     }
 }
+
+/// Trace replay workload
+///
+/// Replays recorded (timestamp_us, size_bytes) pairs, e.g. exported from a
+/// pcap capture. Inter-arrival gaps are taken from consecutive timestamps, so
+/// absolute and trace-relative timestamps both work.
+#[derive(Debug, Clone)]
+pub struct TraceWorkload {
+    records: Vec<(u64, usize)>,
+    next_idx: usize,
+    last_timestamp_us: Option<u64>,
+}
+
+/// One row of a trace CSV file
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct TraceRecord {
+    timestamp_us: u64,
+    size_bytes: usize,
+}
+
+impl TraceWorkload {
+    pub fn new(mut records: Vec<(u64, usize)>) -> Self {
+        records.sort_by_key(|&(t, _)| t);
+        Self {
+            records,
+            next_idx: 0,
+            last_timestamp_us: None,
+        }
+    }
+
+    /// Load a trace from CSV with a `timestamp_us,size_bytes` header
+    pub fn from_csv(path: &str) -> std::io::Result<Self> {
+        let mut reader = csv::Reader::from_path(path)?;
+        let mut records = Vec::new();
+        for row in reader.deserialize() {
+            let record: TraceRecord = row?;
+            records.push((record.timestamp_us, record.size_bytes));
+        }
+        Ok(Self::new(records))
+    }
+
+    /// Drain `workload` into a replayable trace of its arrivals
+    ///
+    /// Gaps are kept to the microsecond; as with any trace, the first
+    /// arrival replays with no initial wait.
+    pub fn capture(workload: &mut dyn WorkloadGenerator) -> Self {
+        let mut records = Vec::new();
+        let mut elapsed_us = 0;
+        while let Some((wait, size)) = workload.next_packet() {
+            elapsed_us += wait.as_micros() as u64;
+            records.push((elapsed_us, size));
+        }
+        Self::new(records)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+impl WorkloadGenerator for TraceWorkload {
+    fn next_packet(&mut self) -> Option<(Duration, usize)> {
+        let &(timestamp_us, size) = self.records.get(self.next_idx)?;
+        self.next_idx += 1;
+
+        let wait_us = self
+            .last_timestamp_us
+            .map_or(0, |last| timestamp_us.saturating_sub(last));
+        self.last_timestamp_us = Some(timestamp_us);
+
+        Some((Duration::from_micros(wait_us), size))
+    }
+}
+
+/// Closed-loop wrapper modeling application backpressure
+///
+/// Arrivals come from `inner`, but [`FakeTransport::run`] holds each packet
+/// back while the transport's total queue depth is at `max_queue_depth`, so
+/// overload slows the source instead of growing the queue without bound.
+pub struct ClosedLoopWorkload {
+    inner: Box<dyn WorkloadGenerator>,
+    max_queue_depth: usize,
+}
+
+impl ClosedLoopWorkload {
+    pub fn new(inner: Box<dyn WorkloadGenerator>, max_queue_depth: usize) -> Self {
+        assert!(max_queue_depth > 0, "max_queue_depth must be positive");
+        Self { inner, max_queue_depth }
+    }
+}
+
+impl WorkloadGenerator for ClosedLoopWorkload {
+    fn next_packet(&mut self) -> Option<(Duration, usize)> {
+        self.inner.next_packet()
+    }
+
+    fn backpressure_limit(&self) -> Option<usize> {
+        Some(self.max_queue_depth)
+    }
+
+    fn flow(&self) -> u32 {
+        self.inner.flow()
+    }
+}
+
+/// Workload phases played back to back
+///
+/// Each phase runs until its generator is exhausted, then the next starts.
+pub struct SequenceWorkload {
+    phases: VecDeque<Box<dyn WorkloadGenerator>>,
+}
+
+impl SequenceWorkload {
+    pub fn new(phases: Vec<Box<dyn WorkloadGenerator>>) -> Self {
+        Self {
+            phases: phases.into(),
+        }
+    }
+}
+
+impl WorkloadGenerator for SequenceWorkload {
+    fn next_packet(&mut self) -> Option<(Duration, usize)> {
+        loop {
+            let packet = self.phases.front_mut()?.next_packet();
+            if packet.is_some() {
+                return packet;
+            }
+            self.phases.pop_front();
+        }
+    }
+
+    fn backpressure_limit(&self) -> Option<usize> {
+        self.phases.front().and_then(|phase| phase.backpressure_limit())
+    }
+
+    fn flow(&self) -> u32 {
+        self.phases.front().map_or(0, |phase| phase.flow())
+    }
+}
+
+/// Several workload generators interleaved onto one transport
+///
+/// Each generator keeps its own arrival process; packets come out in global
+/// arrival order tagged with the generator's flow ID.
+pub struct MultiFlowWorkload {
+    flows: Vec<(u32, Box<dyn WorkloadGenerator>)>,
+    next_arrivals: Vec<Option<(Duration, usize)>>, // (absolute arrival, size) per flow
+    elapsed: Duration,
+    last_flow: u32,
+}
+
+impl MultiFlowWorkload {
+    pub fn new(mut flows: Vec<(u32, Box<dyn WorkloadGenerator>)>) -> Self {
+        let next_arrivals = flows
+            .iter_mut()
+            .map(|(_, workload)| workload.next_packet())
+            .collect();
+        Self {
+            flows,
+            next_arrivals,
+            elapsed: Duration::ZERO,
+            last_flow: 0,
+        }
+    }
+
+    /// Next packet across all flows: (wait_time, size_bytes, flow)
+    pub fn next_flow_packet(&mut self) -> Option<(Duration, usize, u32)> {
+        let (idx, (arrival, size)) = self
+            .next_arrivals
+            .iter()
+            .enumerate()
+            .filter_map(|(i, next)| next.map(|n| (i, n)))
+            .min_by_key(|(_, (arrival, _))| *arrival)?;
+
+        let (flow, workload) = &mut self.flows[idx];
+        self.next_arrivals[idx] = workload.next_packet().map(|(wait, size)| (arrival + wait, size));
+
+        let wait = arrival.saturating_sub(self.elapsed);
+        self.elapsed = self.elapsed.max(arrival);
+        self.last_flow = *flow;
+        Some((wait, size, *flow))
+    }
+}
+
+impl WorkloadGenerator for MultiFlowWorkload {
+    fn next_packet(&mut self) -> Option<(Duration, usize)> {
+        self.next_flow_packet().map(|(wait, size, _)| (wait, size))
+    }
+
+    fn flow(&self) -> u32 {
+        self.last_flow
+    }
+}
+
+/// Serializable workload description
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkloadConfig {
+    Steady {
+        rate_per_sec: f64,
+        size: SizeDistribution,
+    },
+    Bursty {
+        high_rate: f64,
+        low_rate: f64,
+        size: SizeDistribution,
+        period_ms: u64,
+    },
+    Adversarial {
+        base_rate: f64,
+        min_size: usize,
+        max_size: usize,
+    },
+    Trace {
+        path: String,
+    },
+    ClosedLoop {
+        inner: Box<WorkloadConfig>,
+        max_queue_depth: usize,
+    },
+    Ramp {
+        from_rate: f64,
+        to_rate: f64,
+        size: SizeDistribution,
+    },
+    Sequence {
+        phases: Vec<WorkloadPhase>,
+    },
+    Step {
+        rate_before: f64,
+        rate_after: f64,
+        step_at_ms: u64,
+        size: SizeDistribution,
+    },
+    /// Concurrent flows, numbered by position from 0
+    MultiFlow {
+        flows: Vec<WorkloadConfig>,
+    },
+}
+
+/// One phase of a [`WorkloadConfig::Sequence`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkloadPhase {
+    pub duration_secs: f64,
+    #[serde(flatten)]
+    pub workload: WorkloadConfig,
+}
+
+impl WorkloadConfig {
+    /// Standard workloads used by the runner binaries
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "steady" => Some(WorkloadConfig::Steady {
+                rate_per_sec: 1000.0,
+                size: SizeDistribution::Constant(1024),
+            }),
+            "heavytail" => Some(WorkloadConfig::Steady {
+                rate_per_sec: 1000.0,
+                size: SizeDistribution::Pareto { scale: 256.0, shape: 1.3, cap: 65536 },
+            }),
+            "bursty" => Some(WorkloadConfig::Bursty {
+                high_rate: 5000.0,
+                low_rate: 100.0,
+                size: SizeDistribution::Constant(1024),
+                period_ms: 5000,
+            }),
+            "step" => Some(WorkloadConfig::Step {
+                rate_before: 1000.0,
+                rate_after: 5000.0,
+                step_at_ms: 10_000,
+                size: SizeDistribution::Constant(1024),
+            }),
+            "adversarial" => Some(WorkloadConfig::Adversarial {
+                base_rate: 1000.0,
+                min_size: 256,
+                max_size: 2048,
+            }),
+            // A bulk flow sharing the queue with a sparse one that batching can starve
+            "multiflow" => Some(WorkloadConfig::MultiFlow {
+                flows: vec![
+                    WorkloadConfig::Steady {
+                        rate_per_sec: 2000.0,
+                        size: SizeDistribution::Constant(1400),
+                    },
+                    WorkloadConfig::Steady {
+                        rate_per_sec: 50.0,
+                        size: SizeDistribution::Constant(64),
+                    },
+                ],
+            }),
+            _ => None,
+        }
+    }
+
+    /// Instantiate the generator for a run of the given duration
+    pub fn build(&self, duration: Duration) -> std::io::Result<Box<dyn WorkloadGenerator>> {
+        self.build_with(duration, None)
+    }
+
+    /// Instantiate a reproducible generator (traces are deterministic anyway)
+    pub fn build_seeded(&self, duration: Duration, seed: u64) -> std::io::Result<Box<dyn WorkloadGenerator>> {
+        self.build_with(duration, Some(seed))
+    }
+
+    fn build_with(&self, duration: Duration, seed: Option<u64>) -> std::io::Result<Box<dyn WorkloadGenerator>> {
+        Ok(match self {
+            WorkloadConfig::Steady { rate_per_sec, size } => {
+                let workload = SteadyWorkload::new(*rate_per_sec, 0, duration).with_size_distribution(*size);
+                match seed {
+                    Some(seed) => Box::new(workload.with_seed(seed)),
+                    None => Box::new(workload),
+                }
+            }
+            WorkloadConfig::Bursty {
+                high_rate,
+                low_rate,
+                size,
+                period_ms,
+            } => {
+                let workload =
+                    BurstyWorkload::new(*high_rate, *low_rate, 0, Duration::from_millis(*period_ms), duration)
+                        .with_size_distribution(*size);
+                match seed {
+                    Some(seed) => Box::new(workload.with_seed(seed)),
+                    None => Box::new(workload),
+                }
+            }
+            WorkloadConfig::Adversarial {
+                base_rate,
+                min_size,
+                max_size,
+            } => {
+                let workload = AdversarialWorkload::new(*base_rate, (*min_size, *max_size), duration);
+                match seed {
+                    Some(seed) => Box::new(workload.with_seed(seed)),
+                    None => Box::new(workload),
+                }
+            }
+            WorkloadConfig::Trace { path } => Box::new(TraceWorkload::from_csv(path)?),
+            WorkloadConfig::ClosedLoop { inner, max_queue_depth } => Box::new(ClosedLoopWorkload::new(
+                inner.build_with(duration, seed)?,
+                *max_queue_depth,
+            )),
+            WorkloadConfig::Ramp {
+                from_rate,
+                to_rate,
+                size,
+            } => {
+                let workload = RampWorkload::new(*from_rate, *to_rate, 0, duration).with_size_distribution(*size);
+                match seed {
+                    Some(seed) => Box::new(workload.with_seed(seed)),
+                    None => Box::new(workload),
+                }
+            }
+            WorkloadConfig::Step {
+                rate_before,
+                rate_after,
+                step_at_ms,
+                size,
+            } => {
+                let workload =
+                    StepWorkload::new(*rate_before, *rate_after, Duration::from_millis(*step_at_ms), 0, duration)
+                        .with_size_distribution(*size);
+                match seed {
+                    Some(seed) => Box::new(workload.with_seed(seed)),
+                    None => Box::new(workload),
+                }
+            }
+            // Phases carry their own durations; each gets a distinct seed
+            WorkloadConfig::Sequence { phases } => {
+                let phases = phases
+                    .iter()
+                    .enumerate()
+                    .map(|(i, phase)| {
+                        let phase_seed = seed.map(|seed| seed.wrapping_add(i as u64));
+                        phase
+                            .workload
+                            .build_with(Duration::from_secs_f64(phase.duration_secs), phase_seed)
+                    })
+                    .collect::<std::io::Result<Vec<_>>>()?;
+                Box::new(SequenceWorkload::new(phases))
+            }
+            // Flows run concurrently for the whole duration; each gets a distinct seed
+            WorkloadConfig::MultiFlow { flows } => {
+                let flows = flows
+                    .iter()
+                    .enumerate()
+                    .map(|(i, flow)| {
+                        let flow_seed = seed.map(|seed| seed.wrapping_add(i as u64));
+                        Ok((i as u32, flow.build_with(duration, flow_seed)?))
+                    })
+                    .collect::<std::io::Result<Vec<_>>>()?;
+                Box::new(MultiFlowWorkload::new(flows))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_workload_gaps() {
+        let mut trace = TraceWorkload::new(vec![(1_000_250, 512), (1_000_000, 64), (1_001_250, 1500)]);
+        assert_eq!(trace.len(), 3);
+
+        assert_eq!(trace.next_packet(), Some((Duration::ZERO, 64)));
+        assert_eq!(trace.next_packet(), Some((Duration::from_micros(250), 512)));
+        assert_eq!(trace.next_packet(), Some((Duration::from_micros(1000), 1500)));
+        assert_eq!(trace.next_packet(), None);
+    }
+
+    fn step_reflex(low: f32, high: f32) -> reflex_format::Reflex {
+        use reflex_format::*;
+        Reflex {
+            header: ReflexHeader::new(ModelType::DecisionTree, 10, 2, 0, 0, 0, 0),
+            trees: vec![
+                vec![TreeNode::split(0, 0.5, 1, 2), TreeNode::leaf(low), TreeNode::leaf(high)],
+                vec![TreeNode::leaf(500.0)],
+            ],
+            ensembles: Vec::new(),
+            linear: Vec::new(),
+            multi_output: None,
+            tables: Vec::new(),
+            bounds: OutputBounds {
+                min: vec![1.0, 0.0],
+                max: vec![256.0, 10_000.0],
+            },
+            metadata: ReflexMetadata {
+                created_at: String::new(),
+                trainer_commit: String::new(),
+                feature_schema: "v1".to_string(),
+                telemetry_hash: String::new(),
+                lambda: 0.0,
+                notes: String::new(),
+                ..ReflexMetadata::default()
+            },
+            report: None,
+        }
+    }
+
+    #[test]
+    fn test_reflex_hysteresis_suppresses_small_changes() {
+        let normalizer = telemetry::Normalizer {
+            min: [0.0; TelemetrySample::FEATURE_COUNT],
+            max: [100.0; TelemetrySample::FEATURE_COUNT],
+        };
+        let shallow = TelemetrySample { queue_depth: 10, ..Default::default() };
+        let deep = TelemetrySample { queue_depth: 90, ..Default::default() };
+
+        let mut policy = ReflexPolicy::new(step_reflex(100.0, 103.0), normalizer.clone(), Duration::ZERO, 0.05);
+        assert_eq!(policy.decide(&shallow).threshold, 100);
+        assert_eq!(policy.decide(&deep).threshold, 100);
+        assert_eq!(policy.suppressed_changes(), 1);
+
+        let mut policy = ReflexPolicy::new(step_reflex(100.0, 120.0), normalizer, Duration::ZERO, 0.05);
+        policy.decide(&shallow);
+        assert_eq!(policy.decide(&deep).threshold, 120);
+        assert_eq!(policy.suppressed_changes(), 0);
+    }
+
+    #[test]
+    fn test_reflex_output_layout_is_append_only() {
+        let constant = |outputs: &[f32]| {
+            let mut reflex = step_reflex(16.0, 16.0);
+            reflex.header.output_count = outputs.len() as u8;
+            reflex.trees = outputs.iter().map(|&value| vec![reflex_format::TreeNode::leaf(value)]).collect();
+            reflex.bounds = reflex_format::OutputBounds {
+                min: vec![0.0; outputs.len()],
+                max: vec![f32::MAX; outputs.len()],
+            };
+            ReflexPolicy::new(reflex, telemetry::Normalizer::new(), Duration::ZERO, 0.0)
+        };
+        let telem = TelemetrySample::default();
+
+        let decision = constant(&[16.0, 500.0, 1400.0]).decide(&telem);
+        assert_eq!((decision.segment_bytes, decision.pacing_rate), (Some(1400), None));
+        let decision = constant(&[16.0, 500.0, 1400.0, 1e6]).decide(&telem);
+        assert_eq!((decision.segment_bytes, decision.pacing_rate), (Some(1400), Some(1_000_000)));
+    }
+
+    #[test]
+    fn test_reflex_audit_log_records_outputs_and_model() {
+        let path = std::env::temp_dir().join(format!("nematode-sim-audit-{}.jsonl", std::process::id()));
+        let normalizer = telemetry::Normalizer {
+            min: [0.0; TelemetrySample::FEATURE_COUNT],
+            max: [100.0; TelemetrySample::FEATURE_COUNT],
+        };
+        let reflex = step_reflex(0.5, 300.0);
+        let hash = audit_log::reflex_hash(&reflex);
+        let logger = audit_log::AuditLogger::create(&path).unwrap();
+        let mut policy = ReflexPolicy::new(reflex, normalizer, Duration::ZERO, 0.0).with_audit_log(logger, "reflex:test");
+        policy.decide(&TelemetrySample { queue_depth: 10, ..Default::default() });
+        policy.decide(&TelemetrySample { queue_depth: 90, ..Default::default() });
+        assert_eq!(policy.audit_log().unwrap().records(), 2);
+
+        // Raw outputs before bounds, decisions after
+        let records: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records[1]["policy_id"], "reflex:test");
+        assert_eq!(records[1]["reflex_hash"], hash.as_str());
+        assert_eq!(records[1]["telemetry"]["queue_depth"], 90);
+        assert!((records[1]["features"][0].as_f64().unwrap() - 0.9).abs() < 1e-6);
+        assert_eq!((records[0]["raw_outputs"][0].as_f64(), records[0]["decision"]["threshold"].as_u64()), (Some(0.5), Some(1)));
+        assert_eq!((records[1]["raw_outputs"][0].as_f64(), records[1]["decision"]["threshold"].as_u64()), (Some(300.0), Some(256)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_urgent_class_flushes_all_queues() {
+        let mut transport = FakeTransport::new(BaselinePolicy::new()).with_priority_classes(vec![
+            PriorityClass { urgent: true },
+            PriorityClass::default(),
+        ]);
+
+        for _ in 0..3 {
+            transport.enqueue(1024);
+        }
+        transport.enqueue_class(64, 0);
+        transport.tick();
+
+        let metrics = transport.metrics();
+        assert_eq!(metrics.latencies_us.len(), 4);
+        assert_eq!(metrics.class_latencies_us[0].len(), 1);
+        assert_eq!(metrics.class_latencies_us[1].len(), 3);
+    }
+
+    #[test]
+    fn test_trace_capture_replays_arrivals() {
+        let mut original = SteadyWorkload::new(1000.0, 512, Duration::from_millis(50)).with_seed(3);
+        let mut replay = TraceWorkload::capture(&mut SteadyWorkload::new(1000.0, 512, Duration::from_millis(50)).with_seed(3));
+        let (_, first_size) = original.next_packet().unwrap();
+        assert_eq!(replay.next_packet(), Some((Duration::ZERO, first_size)));
+        while let Some((wait, size)) = original.next_packet() {
+            let (replay_wait, replay_size) = replay.next_packet().unwrap();
+            assert_eq!(size, replay_size);
+            assert!(wait.as_micros().abs_diff(replay_wait.as_micros()) <= 1);
+        }
+        assert_eq!(replay.next_packet(), None);
+    }
+
+    #[test]
+    fn test_state_series_samples_at_cadence() {
+        let mut transport = FakeTransport::new(FixedPolicy(NEVER_FLUSH)).with_state_series(Duration::from_millis(5));
+        transport.enqueue(100);
+        transport.tick();
+        std::thread::sleep(Duration::from_millis(6));
+        transport.enqueue(100);
+        transport.tick();
+        transport.tick();
+
+        let series = transport.state_series().unwrap();
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[0].queue_depth, 1);
+        assert_eq!(series[1].queue_depth, 2);
+        assert!(series[1].oldest_packet_age_us >= 5_000);
+        assert_eq!(series[1].threshold, NEVER_FLUSH.threshold);
+    }
+
+    #[test]
+    fn test_multi_flow_interleaving() {
+        let a = TraceWorkload::new(vec![(0, 100), (300, 100)]);
+        let b = TraceWorkload::new(vec![(0, 200), (100, 200)]);
+        let mut workload = MultiFlowWorkload::new(vec![(1, Box::new(a)), (2, Box::new(b))]);
+
+        assert_eq!(workload.next_flow_packet(), Some((Duration::ZERO, 100, 1)));
+        assert_eq!(workload.next_flow_packet(), Some((Duration::ZERO, 200, 2)));
+        assert_eq!(workload.next_flow_packet(), Some((Duration::from_micros(100), 200, 2)));
+        assert_eq!(workload.next_flow_packet(), Some((Duration::from_micros(200), 100, 1)));
+        assert_eq!(workload.next_flow_packet(), None);
+    }
+
+    #[test]
+    fn test_multi_flow_run_records_each_flow() {
+        // After the first batch, each flow 1 packet waits until a flow 2 packet fills the batch
+        let a = TraceWorkload::new(vec![(0, 100), (500, 100), (1500, 100), (2500, 100)]);
+        let b = TraceWorkload::new(vec![(0, 100), (1000, 100), (2000, 100)]);
+        let mut workload = MultiFlowWorkload::new(vec![(1, Box::new(a)), (2, Box::new(b))]);
+        let decision = FlushDecision {
+            threshold: 2,
+            ..NEVER_FLUSH
+        };
+        let mut transport = FakeTransport::new(FixedPolicy(decision));
+
+        transport.run(&mut workload, Duration::from_millis(20));
+        let metrics = transport.metrics();
+        assert_eq!(metrics.flow_latencies_us[&1].len(), 3);
+        assert_eq!(metrics.flow_latencies_us[&2].len(), 3);
+        assert!(metrics.flow_percentile(1, 0.99) >= 100.0);
+        assert!(metrics.flow_percentile(1, 0.99) > metrics.flow_percentile(2, 0.99));
+    }
+
+    #[test]
+    fn test_multi_flow_config_numbers_flows_by_position() {
+        let config = WorkloadConfig::preset("multiflow").unwrap();
+        let mut workload = config.build_seeded(Duration::from_secs(1), 7).unwrap();
+        let mut flows = std::collections::BTreeSet::new();
+        while workload.next_packet().is_some() {
+            flows.insert(workload.flow());
+        }
+        assert_eq!(flows.into_iter().collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_decision_log_records_each_invocation() {
+        let mut transport = FakeTransport::new(BaselinePolicy::new()).with_decision_log();
+        transport.enqueue(512);
+        transport.tick();
+        transport.tick();
+
+        let log = transport.decision_log().unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].class, None);
+        assert_eq!(log[0].telemetry.queue_depth, 1);
+        assert_eq!(log[0].decision, BaselinePolicy::new().decide(&log[0].telemetry));
+    }
+
+    struct FixedPolicy(FlushDecision);
+
+    impl FlushPolicy for FixedPolicy {
+        fn decide(&mut self, _telem: &TelemetrySample) -> FlushDecision {
+            self.0
+        }
+    }
+
+    const NEVER_FLUSH: FlushDecision = FlushDecision {
+        threshold: u32::MAX,
+        max_delay_us: u32::MAX,
+        segment_bytes: None,
+        pacing_rate: None,
+    };
+
+    #[test]
+    fn test_closed_loop_caps_queue_depth() {
+        let steady = SteadyWorkload::new(100_000.0, 64, Duration::from_secs(1)).with_seed(1);
+        let mut workload = ClosedLoopWorkload::new(Box::new(steady), 8);
+        let mut transport = FakeTransport::new(FixedPolicy(NEVER_FLUSH));
+
+        transport.run(&mut workload, Duration::from_millis(20));
+        assert_eq!(transport.queue_depth(), 8);
+    }
+
+    #[test]
+    fn test_segments_depart_together() {
+        let decision = FlushDecision {
+            threshold: 4,
+            max_delay_us: u32::MAX,
+            segment_bytes: Some(2000),
+            pacing_rate: None,
+        };
+        // 1µs per byte, 100µs per segment
+        let link = LinkModel::new(1_000_000.0, 0).with_per_segment_cost(100);
+        let mut transport = FakeTransport::new(FixedPolicy(decision)).with_link(link);
+        for _ in 0..4 {
+            transport.enqueue(1000);
+        }
+        transport.tick();
+
+        let latencies = &transport.metrics().latencies_us;
+        assert_eq!(latencies.len(), 4);
+        assert!((2100..2200).contains(&latencies[1]));
+        assert!((4200..4300).contains(&latencies[2]));
+        assert!(latencies[3].abs_diff(latencies[2]) < 10);
+    }
+
+    #[test]
+    fn test_pacing_spaces_departures() {
+        let decision = FlushDecision {
+            threshold: 3,
+            max_delay_us: u32::MAX,
+            segment_bytes: None,
+            pacing_rate: Some(1_000_000), // 1µs per byte
+        };
+        let mut transport = FakeTransport::new(FixedPolicy(decision));
+        for _ in 0..3 {
+            transport.enqueue(1000);
+        }
+        transport.tick();
+
+        let latencies = &transport.metrics().latencies_us;
+        assert!((1000..1100).contains(&latencies[0]));
+        assert!((2000..2100).contains(&latencies[1]));
+        assert!((3000..3100).contains(&latencies[2]));
+    }
+
+    #[test]
+    fn test_energy_per_packet() {
+        let decision = FlushDecision {
+            threshold: 4,
+            max_delay_us: u32::MAX,
+            segment_bytes: None,
+            pacing_rate: None,
+        };
+        let mut transport = FakeTransport::new(FixedPolicy(decision)).with_energy(EnergyModel::new(10.0, 0.01));
+        for _ in 0..4 {
+            transport.enqueue(1000);
+        }
+        transport.tick();
+
+        // One wakeup + 4000 bytes
+        assert!((transport.metrics().energy_uj - 50.0).abs() < 1e-9);
+        assert!((transport.metrics().energy_per_packet() - 12.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_bounded_queue_drops() {
+        let mut tail = FakeTransport::new(FixedPolicy(NEVER_FLUSH)).with_queue_capacity(2, DropPolicy::Tail);
+        let mut head = FakeTransport::new(FixedPolicy(NEVER_FLUSH)).with_queue_capacity(2, DropPolicy::Head);
+        for size in [100, 200, 300] {
+            tail.enqueue(size);
+            head.enqueue(size);
+        }
+
+        assert_eq!(tail.queue_depth(), 2);
+        assert_eq!(tail.metrics().drops, 1);
+        assert!((tail.metrics().drop_rate() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(tail.queues[0].back().unwrap().size_bytes, 200);
+
+        assert_eq!(head.queue_depth(), 2);
+        assert_eq!(head.queues[0].front().unwrap().size_bytes, 200);
+        assert_eq!(head.collect_telemetry(None).drops_per_sec, 1.0);
+    }
+
+    #[test]
+    fn test_flip_flop_detection() {
+        let a = BaselinePolicy::new().decide(&TelemetrySample::default());
+        let b = FlushDecision { threshold: 32, ..a };
+        let mut metrics = Metrics::new();
+        for decision in [a, a, b, a, a] {
+            metrics.record_decision(0, decision);
+        }
+
+        assert_eq!(metrics.decisions, 5);
+        assert_eq!(metrics.decision_changes, 2);
+        assert_eq!(metrics.flip_flops, 1);
+        // Two changes of threshold 16 <-> 32 (relative 1.0 and 0.5), delay unchanged
+        assert!((metrics.mean_output_delta() - 0.75 / 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_shared_policy_decisions_count_once_across_classes() {
+        struct Alternating(bool);
+        impl FlushPolicy for Alternating {
+            fn decide(&mut self, _telem: &TelemetrySample) -> FlushDecision {
+                self.0 = !self.0;
+                FlushDecision { threshold: if self.0 { 8 } else { 16 }, ..NEVER_FLUSH }
+            }
+        }
+
+        let mut transport = FakeTransport::new(Alternating(false))
+            .with_priority_classes(vec![PriorityClass::default(), PriorityClass::default(), PriorityClass::default()]);
+        for _ in 0..4 {
+            transport.tick();
+        }
+        let metrics = transport.metrics();
+        assert_eq!((metrics.decisions, metrics.decision_changes, metrics.flip_flops), (4, 3, 2));
+    }
+
+    #[test]
+    fn test_jain_fairness() {
+        let mut metrics = Metrics::new();
+        metrics.record_flow_delivery(1, 1000);
+        metrics.record_flow_delivery(2, 1000);
+        assert_eq!(metrics.jain_fairness(), 1.0);
+
+        // Two of four flows starved: (2x)^2 / (4 * 2x^2) = 0.5
+        metrics.record_flow_delivery(3, 0);
+        metrics.record_flow_delivery(4, 0);
+        assert_eq!(metrics.jain_fairness(), 0.5);
+
+        metrics.record_flow_latency(1, 100);
+        metrics.record_flow_latency(2, 5000);
+        assert_eq!(metrics.max_flow_p99(), 5000.0);
+    }
+
+    #[test]
+    fn test_starved_flow_counts_against_fairness() {
+        let decision = FlushDecision { threshold: 4, ..NEVER_FLUSH };
+        let mut transport = FakeTransport::new(FixedPolicy(decision));
+        for _ in 0..4 {
+            transport.enqueue_flow(1000, 1);
+        }
+        transport.tick();
+        // Flow 2's lone packet never reaches the threshold
+        transport.enqueue_flow(1000, 2);
+        transport.tick();
+
+        let metrics = transport.metrics();
+        assert_eq!(metrics.flow_delivered_bytes.get(&2), Some(&0));
+        assert_eq!(metrics.jain_fairness(), 0.5);
+    }
+
+    #[test]
+    fn test_heavy_tailed_sizes() {
+        let mut rng = rand::thread_rng();
+        let pareto = SizeDistribution::Pareto { scale: 64.0, shape: 1.2, cap: 65536 };
+        let sizes: Vec<usize> = (0..10_000).map(|_| pareto.sample(&mut rng)).collect();
+        assert!(sizes.iter().all(|&s| (64..=65536).contains(&s)));
+        // Heavy tail: the largest sample dwarfs the median
+        let mut sorted = sizes.clone();
+        sorted.sort_unstable();
+        assert!(sorted[sorted.len() - 1] > 20 * sorted[sorted.len() / 2]);
+
+        let lognormal = SizeDistribution::LogNormal { median: 512.0, sigma: 1.0 };
+        let mut sizes: Vec<usize> = (0..10_000).map(|_| lognormal.sample(&mut rng)).collect();
+        sizes.sort_unstable();
+        let median = sizes[sizes.len() / 2] as f64;
+        assert!((400.0..650.0).contains(&median));
+    }
+}
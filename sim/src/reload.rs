@@ -0,0 +1,236 @@
+//! Hot reload of a running reflex policy
+//!
+//! A long-running transport shouldn't need a restart to pick up a retrained
+//! model. [`ReloadableReflexPolicy`] polls its `.reflex` path and, when the
+//! file's modification time or size changes, loads it, checks it as
+//! `reflex:` specs are checked (a normalizer that can feed it, see
+//! [`check_normalizer`]) and against the running model's shape, and swaps
+//! it in between two decisions. A file that fails (often one caught
+//! mid-write) leaves the running model in place and is tried again once it
+//! changes.
+
+use crate::{embedded_normalizer, FlushDecision, FlushPolicy, ReflexPolicy};
+use reflex_format::{check_shape, FileWatch, Reflex};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+use telemetry::{Normalizer, TelemetrySample};
+
+/// Check `normalizer` can feed `reflex`
+///
+/// The model must take the telemetry schema's feature count, and every
+/// feature's bounds must be finite with `min <= max`, so an unfit
+/// `Normalizer::new()`, which would feed the model constant 0.5s, fails.
+pub fn check_normalizer(reflex: &Reflex, normalizer: &Normalizer) -> io::Result<()> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    if reflex.header.feature_count as usize != TelemetrySample::FEATURE_COUNT {
+        return Err(invalid(format!(
+            "model takes {} features, the normalizer scales {}",
+            reflex.header.feature_count,
+            TelemetrySample::FEATURE_COUNT
+        )));
+    }
+    let bounds = normalizer.min.iter().zip(&normalizer.max);
+    for (name, (&min, &max)) in TelemetrySample::feature_names().iter().zip(bounds) {
+        if !min.is_finite() || !max.is_finite() || min > max {
+            return Err(invalid(format!("normalizer bounds for {} are [{}, {}]", name, min, max)));
+        }
+    }
+    Ok(())
+}
+
+/// Reflex policy that picks up rewrites of its `.reflex` file
+pub struct ReloadableReflexPolicy {
+    watch: FileWatch,
+    policy: ReflexPolicy,
+    normalizer: Option<Normalizer>, // used for every version; otherwise each file's embedded one
+    reloads: usize,
+    rejected: usize,
+    last_error: Option<String>,
+}
+
+impl ReloadableReflexPolicy {
+    pub const DEFAULT_POLL_INTERVAL: Duration = FileWatch::DEFAULT_POLL_INTERVAL;
+
+    /// Load `path` into a default [`ReflexPolicy`] and watch it
+    ///
+    /// `normalizer`, if given, feeds every version instead of their embedded ones.
+    pub fn load(path: impl AsRef<Path>, normalizer: Option<Normalizer>) -> io::Result<Self> {
+        let mut watch = FileWatch::new(path);
+        let reflex = Reflex::from_bytes(&watch.read()?)?;
+        let running = Self::pick_normalizer(&reflex, normalizer.as_ref(), None)?;
+        check_normalizer(&reflex, &running)?;
+        let policy = ReflexPolicy::new(reflex, running, ReflexPolicy::DEFAULT_HOLD_TIME, ReflexPolicy::DEFAULT_HYSTERESIS);
+        let mut reloadable = Self::new(watch.path(), policy);
+        reloadable.watch = watch;
+        reloadable.normalizer = normalizer;
+        Ok(reloadable)
+    }
+
+    /// Watch `path` for models to replace `policy`'s with
+    ///
+    /// New versions bring their embedded normalizer, or keep the running
+    /// one if they have none. The file is first tried at the next check.
+    pub fn new(path: impl AsRef<Path>, policy: ReflexPolicy) -> Self {
+        Self {
+            watch: FileWatch::new(path),
+            policy,
+            normalizer: None,
+            reloads: 0,
+            rejected: 0,
+            last_error: None,
+        }
+    }
+
+    /// How often `decide` looks at the file (default 1s, wall clock); zero checks every decision
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.watch = self.watch.with_poll_interval(poll_interval);
+        self
+    }
+
+    /// Swap in the file if it changed since it was last tried
+    ///
+    /// Returns whether a new model is now running. A file that fails to
+    /// load, has a different feature or output count than the running
+    /// model, or can't be fed by its normalizer is an error, and the
+    /// running model stays.
+    pub fn check(&mut self) -> io::Result<bool> {
+        let Some(bytes) = self.watch.changed()? else {
+            return Ok(false);
+        };
+        match self.validate(&bytes) {
+            Ok((reflex, normalizer)) => {
+                self.policy.replace_model(reflex, normalizer);
+                self.reloads += 1;
+                self.last_error = None;
+                Ok(true)
+            }
+            Err(e) => {
+                self.rejected += 1;
+                self.last_error = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// Models swapped in since loading
+    pub fn reloads(&self) -> usize {
+        self.reloads
+    }
+
+    /// File versions rejected since loading
+    pub fn rejected(&self) -> usize {
+        self.rejected
+    }
+
+    /// Why the latest version was rejected, until one loads
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    pub fn reflex(&self) -> &Reflex {
+        self.policy.reflex()
+    }
+
+    fn validate(&self, bytes: &[u8]) -> io::Result<(Reflex, Normalizer)> {
+        let reflex = Reflex::from_bytes(bytes)?;
+        check_shape(self.policy.reflex(), &reflex)?;
+        let normalizer = Self::pick_normalizer(&reflex, self.normalizer.as_ref(), Some(self.policy.normalizer()))?;
+        check_normalizer(&reflex, &normalizer)?;
+        Ok((reflex, normalizer))
+    }
+
+    /// The fixed normalizer, else the embedded one, else the running one
+    fn pick_normalizer(reflex: &Reflex, fixed: Option<&Normalizer>, running: Option<&Normalizer>) -> io::Result<Normalizer> {
+        fixed
+            .cloned()
+            .or_else(|| embedded_normalizer(reflex))
+            .or_else(|| running.cloned())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no normalizer embedded or given"))
+    }
+}
+
+impl FlushPolicy for ReloadableReflexPolicy {
+    fn decide(&mut self, telem: &TelemetrySample) -> FlushDecision {
+        if self.watch.poll_due() {
+            // A rejected or missing file keeps the running model; `last_error` says why
+            if let Err(e) = self.check() {
+                self.last_error = Some(e.to_string());
+            }
+        }
+        self.policy.decide(telem)
+    }
+
+    fn suppressed_changes(&self) -> usize {
+        self.policy.suppressed_changes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reflex_format::*;
+    use std::fs;
+
+    fn constant_reflex(threshold: f32, feature_count: u8) -> Reflex {
+        Reflex {
+            header: ReflexHeader::new(ModelType::DecisionTree, feature_count, 2, 0, 0, 0, 0),
+            trees: vec![vec![TreeNode::leaf(threshold)], vec![TreeNode::leaf(500.0)]],
+            ensembles: Vec::new(),
+            linear: Vec::new(),
+            multi_output: None,
+            tables: Vec::new(),
+            bounds: OutputBounds {
+                min: vec![1.0, 0.0],
+                max: vec![256.0, 10_000.0],
+            },
+            metadata: ReflexMetadata::default(),
+            report: None,
+        }
+    }
+
+    fn fit_normalizer() -> Normalizer {
+        Normalizer {
+            min: [0.0; TelemetrySample::FEATURE_COUNT],
+            max: [1.0; TelemetrySample::FEATURE_COUNT],
+        }
+    }
+
+    #[test]
+    fn test_reload_swaps_valid_files_and_keeps_the_model_otherwise() {
+        let path = std::env::temp_dir().join(format!("nematode-reload-{}.reflex", std::process::id()));
+        let write = |reflex: &Reflex| fs::write(&path, reflex.to_bytes().unwrap()).unwrap();
+        write(&constant_reflex(16.0, 10));
+        let running = ReflexPolicy::new(constant_reflex(8.0, 10), fit_normalizer(), Duration::ZERO, 0.0);
+        let mut policy = ReloadableReflexPolicy::new(&path, running).with_poll_interval(Duration::ZERO);
+        let telem = TelemetrySample::default();
+        assert_eq!(policy.decide(&telem).threshold, 16);
+        assert!(!policy.check().unwrap());
+
+        write(&constant_reflex(32.0, 10));
+        assert!(policy.check().unwrap());
+        assert_eq!(policy.decide(&telem).threshold, 32);
+
+        // Truncated mid-write, then a model for another schema: both rejected
+        let bytes = constant_reflex(64.0, 10).to_bytes().unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 3]).unwrap();
+        assert!(policy.check().is_err());
+        write(&constant_reflex(64.0, 12));
+        assert!(policy.check().is_err());
+        assert_eq!(policy.decide(&telem).threshold, 32);
+        assert_eq!((policy.reloads(), policy.rejected()), (2, 2));
+        assert!(policy.last_error().unwrap().contains("12 features"));
+
+        // Loading checks the normalizer too
+        write(&constant_reflex(64.0, 10));
+        let mut inverted = fit_normalizer();
+        inverted.min[0] = 2.0;
+        let error = ReloadableReflexPolicy::load(&path, Some(inverted)).err().unwrap();
+        assert!(error.to_string().contains("normalizer bounds"));
+        assert!(ReloadableReflexPolicy::load(&path, Some(Normalizer::new())).is_err());
+        assert!(ReloadableReflexPolicy::load(&path, None).is_err());
+        assert!(ReloadableReflexPolicy::load(&path, Some(fit_normalizer())).is_ok());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+//! Structured run results
+//!
+//! One record per simulation run, exportable to JSON or CSV so experiment
+//! aggregation doesn't depend on scraping stdout.
+
+use crate::{Metrics, SloReport, WorkloadConfig};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Summary of one simulation run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunResult {
+    pub policy: String,
+    pub workload: WorkloadConfig,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub duration_secs: f64,
+    pub packets: usize,
+    pub p50_latency_us: f64,
+    pub p95_latency_us: f64,
+    pub p99_latency_us: f64,
+    pub mean_throughput: f64,
+    pub mean_goodput: f64,
+    pub loss_rate: f64,
+    #[serde(default)]
+    pub drop_rate: f64,
+    #[serde(default)]
+    pub jain_fairness: f64,
+    #[serde(default)]
+    pub max_flow_p99_us: f64,
+    #[serde(default)]
+    pub energy_per_packet_uj: f64,
+    pub decision_changes: usize,
+    #[serde(default)]
+    pub changes_per_minute: f64,
+    #[serde(default)]
+    pub mean_output_delta: f64,
+    #[serde(default)]
+    pub flip_flops: usize,
+    pub suppressed_changes: usize,
+    #[serde(default)]
+    pub slo_reports: Vec<SloReport>,
+}
+
+impl RunResult {
+    pub fn from_metrics(
+        policy: &str,
+        workload: WorkloadConfig,
+        duration: Duration,
+        metrics: &Metrics,
+    ) -> Self {
+        Self {
+            policy: policy.to_string(),
+            workload,
+            seed: None,
+            duration_secs: duration.as_secs_f64(),
+            packets: metrics.latencies_us.len(),
+            p50_latency_us: metrics.p50_latency(),
+            p95_latency_us: metrics.p95_latency(),
+            p99_latency_us: metrics.p99_latency(),
+            mean_throughput: metrics.mean_throughput(),
+            mean_goodput: metrics.mean_goodput(),
+            loss_rate: metrics.loss_rate(),
+            drop_rate: metrics.drop_rate(),
+            jain_fairness: metrics.jain_fairness(),
+            max_flow_p99_us: metrics.max_flow_p99(),
+            energy_per_packet_uj: metrics.energy_per_packet(),
+            decision_changes: metrics.decision_changes,
+            changes_per_minute: metrics.changes_per_minute(),
+            mean_output_delta: metrics.mean_output_delta(),
+            flip_flops: metrics.flip_flops,
+            suppressed_changes: metrics.suppressed_changes,
+            slo_reports: metrics.slo_reports(),
+        }
+    }
+
+    /// Write results as CSV (`.csv` extension) or a JSON array (anything else)
+    pub fn write_all(results: &[RunResult], path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            Self::write_csv(results, path)
+        } else {
+            let json = serde_json::to_string_pretty(results)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            std::fs::write(path, json)
+        }
+    }
+
+    /// CSV with one row per run; the workload config is embedded as JSON
+    fn write_csv(results: &[RunResult], path: &Path) -> io::Result<()> {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record([
+            "policy",
+            "workload",
+            "seed",
+            "duration_secs",
+            "packets",
+            "p50_latency_us",
+            "p95_latency_us",
+            "p99_latency_us",
+            "mean_throughput",
+            "mean_goodput",
+            "loss_rate",
+            "drop_rate",
+            "jain_fairness",
+            "max_flow_p99_us",
+            "energy_per_packet_uj",
+            "decision_changes",
+            "changes_per_minute",
+            "mean_output_delta",
+            "flip_flops",
+            "suppressed_changes",
+            "slo_reports",
+        ])?;
+        for r in results {
+            let workload = serde_json::to_string(&r.workload)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let slo_reports = serde_json::to_string(&r.slo_reports)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            writer.write_record([
+                r.policy.clone(),
+                workload,
+                r.seed.map_or(String::new(), |seed| seed.to_string()),
+                r.duration_secs.to_string(),
+                r.packets.to_string(),
+                r.p50_latency_us.to_string(),
+                r.p95_latency_us.to_string(),
+                r.p99_latency_us.to_string(),
+                r.mean_throughput.to_string(),
+                r.mean_goodput.to_string(),
+                r.loss_rate.to_string(),
+                r.drop_rate.to_string(),
+                r.jain_fairness.to_string(),
+                r.max_flow_p99_us.to_string(),
+                r.energy_per_packet_uj.to_string(),
+                r.decision_changes.to_string(),
+                r.changes_per_minute.to_string(),
+                r.mean_output_delta.to_string(),
+                r.flip_flops.to_string(),
+                r.suppressed_changes.to_string(),
+                slo_reports,
+            ])?;
+        }
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_roundtrip() {
+        let mut metrics = Metrics::new();
+        metrics.record_latency(100);
+        metrics.record_latency(300);
+        let workload = WorkloadConfig::preset("heavytail").unwrap();
+        let result = RunResult::from_metrics("static", workload, Duration::from_secs(5), &metrics);
+
+        let path = std::env::temp_dir().join("nematode-run-result-test.json");
+        RunResult::write_all(&[result], &path).unwrap();
+        let loaded: Vec<RunResult> =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].packets, 2);
+        assert_eq!(loaded[0].p99_latency_us, 300.0);
+        assert!(matches!(loaded[0].workload, WorkloadConfig::Steady { .. }));
+    }
+}
@@ -0,0 +1,93 @@
+//! Scenario definition files
+//!
+//! A scenario is a TOML description of one experiment (workload phases,
+//! policy, seeds), so experiments are reviewable config artifacts rather
+//! than hardcoded runner binaries. See `sim/scenarios/` for examples.
+
+use crate::{policy_from_spec, run_repeated, FakeTransport, RunResult, WorkloadConfig, WorkloadPhase};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// One experiment: phases played in order, repeated once per seed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    /// Baseline policy name or `reflex:<model>[,<normalizer>]`
+    pub policy: String,
+    #[serde(default = "default_seeds")]
+    pub seeds: Vec<u64>,
+    #[serde(default)]
+    pub warmup_secs: f64,
+    pub phases: Vec<WorkloadPhase>,
+}
+
+fn default_seeds() -> Vec<u64> {
+    vec![0]
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(text: &str) -> io::Result<Self> {
+        toml::from_str(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// The phases as a single sequence workload
+    pub fn workload(&self) -> WorkloadConfig {
+        WorkloadConfig::Sequence {
+            phases: self.phases.clone(),
+        }
+    }
+
+    /// Total length of all phases
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.phases.iter().map(|phase| phase.duration_secs).sum())
+    }
+
+    /// Run the scenario once per seed
+    pub fn run(&self) -> io::Result<Vec<RunResult>> {
+        // Fail on a bad policy spec before starting any run
+        policy_from_spec(&self.policy)?;
+        let warmup = Duration::from_secs_f64(self.warmup_secs);
+        run_repeated(
+            &self.policy,
+            || {
+                let policy = policy_from_spec(&self.policy).expect("policy spec validated above");
+                FakeTransport::new(policy).with_warmup(warmup)
+            },
+            &self.workload(),
+            self.duration(),
+            &self.seeds,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example_scenario_parses() {
+        let scenario = Scenario::from_toml(include_str!("../scenarios/steady_burst_ramp.toml")).unwrap();
+        assert_eq!(scenario.phases.len(), 3);
+        assert_eq!(scenario.seeds.len(), 5);
+        assert_eq!(scenario.duration(), Duration::from_secs(25));
+        assert!(matches!(scenario.phases[2].workload, WorkloadConfig::Ramp { .. }));
+
+        let mut workload = scenario.workload().build_seeded(scenario.duration(), 0).unwrap();
+        assert!(workload.next_packet().is_some());
+    }
+
+    #[test]
+    fn test_multi_flow_scenario_parses() {
+        let scenario = Scenario::from_toml(include_str!("../scenarios/competing_flows.toml")).unwrap();
+        let WorkloadConfig::MultiFlow { flows } = &scenario.phases[0].workload else {
+            panic!("expected a multi_flow phase");
+        };
+        assert_eq!(flows.len(), 2);
+    }
+}
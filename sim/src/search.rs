@@ -0,0 +1,146 @@
+//! Adversarial workload search
+//!
+//! Black-box (1+1) hill climbing over bursty workload parameters to find
+//! traffic that maximizes p99 latency under a given policy. The worst cases
+//! found are the stress scenarios to fold back into training data.
+
+use crate::{standard_normal, FakeTransport, FlushPolicy, RunResult, SizeDistribution, WorkloadConfig};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::time::Duration;
+
+/// Hill-climbing search for workloads that hurt a policy's tail latency
+///
+/// Each iteration perturbs the current best parameters log-normally and keeps
+/// the candidate if its p99 is higher. All candidates are evaluated with the
+/// same workload seed so differences come from the parameters, not the noise.
+pub struct StressSearch {
+    iterations: usize,
+    eval_duration: Duration,
+    step: f64,
+    workload_seed: u64,
+    rng: StdRng,
+}
+
+impl StressSearch {
+    pub fn new(iterations: usize, eval_duration: Duration) -> Self {
+        Self {
+            iterations,
+            eval_duration,
+            step: 0.5,
+            workload_seed: 0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Log-space perturbation scale (default: 0.5)
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Reproducible search and evaluations
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self.workload_seed = seed;
+        self
+    }
+
+    /// Run the search, returning every evaluation in order (the first is the start point)
+    pub fn run<P: FlushPolicy>(
+        &mut self,
+        policy_id: &str,
+        mut make_transport: impl FnMut() -> FakeTransport<P>,
+    ) -> Vec<RunResult> {
+        let mut evaluate = |workload: WorkloadConfig| {
+            let mut transport = make_transport();
+            let mut generator = workload
+                .build_seeded(self.eval_duration, self.workload_seed)
+                .expect("generated workloads never read files");
+            transport.run(generator.as_mut(), self.eval_duration);
+
+            let mut result = RunResult::from_metrics(policy_id, workload, self.eval_duration, transport.metrics());
+            result.seed = Some(self.workload_seed);
+            result
+        };
+
+        let mut params = StressParams::default();
+        let mut best = evaluate(params.to_workload());
+        let mut results = vec![best.clone()];
+
+        for _ in 0..self.iterations {
+            let candidate = params.perturb(&mut self.rng, self.step);
+            let result = evaluate(candidate.to_workload());
+            if result.p99_latency_us > best.p99_latency_us {
+                params = candidate;
+                best = result.clone();
+            }
+            results.push(result);
+        }
+        results
+    }
+}
+
+/// Searched workload parameters
+#[derive(Debug, Clone, Copy)]
+struct StressParams {
+    high_rate: f64,
+    low_rate: f64,
+    size_median: f64,
+    size_sigma: f64,
+    period_ms: f64,
+}
+
+impl Default for StressParams {
+    fn default() -> Self {
+        Self {
+            high_rate: 2000.0,
+            low_rate: 200.0,
+            size_median: 1024.0,
+            size_sigma: 0.5,
+            period_ms: 100.0,
+        }
+    }
+}
+
+impl StressParams {
+    fn perturb(&self, rng: &mut StdRng, step: f64) -> Self {
+        let mut scale = |value: f64, min: f64, max: f64| (value * (step * standard_normal(rng)).exp()).clamp(min, max);
+        Self {
+            high_rate: scale(self.high_rate, 10.0, 20_000.0),
+            low_rate: scale(self.low_rate, 10.0, 20_000.0),
+            size_median: scale(self.size_median, 64.0, 8192.0),
+            size_sigma: scale(self.size_sigma, 0.05, 1.5),
+            period_ms: scale(self.period_ms, 1.0, 2000.0),
+        }
+    }
+
+    fn to_workload(self) -> WorkloadConfig {
+        WorkloadConfig::Bursty {
+            high_rate: self.high_rate,
+            low_rate: self.low_rate,
+            size: SizeDistribution::LogNormal {
+                median: self.size_median,
+                sigma: self.size_sigma,
+            },
+            period_ms: self.period_ms.round() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perturb_stays_in_bounds() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut params = StressParams::default();
+        for _ in 0..100 {
+            params = params.perturb(&mut rng, 3.0);
+            assert!((10.0..=20_000.0).contains(&params.high_rate));
+            assert!((0.05..=1.5).contains(&params.size_sigma));
+            assert!((1.0..=2000.0).contains(&params.period_ms));
+        }
+    }
+}
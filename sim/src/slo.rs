@@ -0,0 +1,125 @@
+//! Latency SLO accounting
+//!
+//! Operators care whether latency stayed within its objective, not about a
+//! single whole-run percentile. An SLO is checked per fixed window of
+//! delivery time; each window whose latency percentile exceeds the target
+//! counts as a violation.
+
+use crate::percentile;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Latency objective, e.g. p99 < 2ms over each 1s window
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LatencySlo {
+    pub percentile: f64,
+    pub target_us: u64,
+    pub window: Duration,
+}
+
+impl LatencySlo {
+    /// `percentile` of latency must stay under `target_us` in every 1s window
+    pub fn new(percentile: f64, target_us: u64) -> Self {
+        Self {
+            percentile,
+            target_us,
+            window: Duration::from_secs(1),
+        }
+    }
+
+    /// Evaluation window (default: 1s)
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Check `(delivery time, latency)` samples against the objective
+    ///
+    /// Windows without deliveries count as compliant.
+    pub fn evaluate(&self, delivery_times_us: &[u64], latencies_us: &[u64]) -> SloReport {
+        let window_us = self.window.as_micros().max(1) as u64;
+        let mut bins: Vec<Vec<u64>> = Vec::new();
+        for (&time, &latency) in delivery_times_us.iter().zip(latencies_us) {
+            let idx = (time / window_us) as usize;
+            if bins.len() <= idx {
+                bins.resize(idx + 1, Vec::new());
+            }
+            bins[idx].push(latency);
+        }
+
+        // Leading empty windows are warm-up, not compliance
+        let first = bins.iter().position(|bin| !bin.is_empty()).unwrap_or(bins.len());
+        let bins = &bins[first..];
+
+        let mut violations = 0;
+        let mut streak = 0;
+        let mut longest_streak = 0;
+        for bin in bins {
+            if !bin.is_empty() && percentile(bin, self.percentile) > self.target_us as f64 {
+                violations += 1;
+                streak += 1;
+                longest_streak = longest_streak.max(streak);
+            } else {
+                streak = 0;
+            }
+        }
+
+        SloReport {
+            slo: *self,
+            windows: bins.len(),
+            violations,
+            longest_streak,
+            time_in_violation_secs: (self.window * violations as u32).as_secs_f64(),
+        }
+    }
+}
+
+/// Parses `p99:2000` into a p99 < 2000µs SLO
+impl FromStr for LatencySlo {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, String> {
+        spec.strip_prefix('p')
+            .and_then(|rest| rest.split_once(':'))
+            .and_then(|(p, target)| Some(Self::new(p.parse::<f64>().ok()? / 100.0, target.parse().ok()?)))
+            .ok_or_else(|| format!("expected p<percentile>:<target_us>, e.g. p99:2000, got {}", spec))
+    }
+}
+
+/// SLO compliance over one run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SloReport {
+    pub slo: LatencySlo,
+    pub windows: usize,
+    pub violations: usize,
+    /// Most consecutive violating windows
+    pub longest_streak: usize,
+    pub time_in_violation_secs: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slo_streaks() {
+        // One sample per 1s window: ok, bad, bad, ok, bad
+        let times = [500_000, 1_500_000, 2_500_000, 3_500_000, 4_500_000];
+        let latencies = [100, 3000, 2500, 100, 5000];
+        let report = LatencySlo::new(0.99, 2000).evaluate(&times, &latencies);
+
+        assert_eq!(report.windows, 5);
+        assert_eq!(report.violations, 3);
+        assert_eq!(report.longest_streak, 2);
+        assert_eq!(report.time_in_violation_secs, 3.0);
+    }
+
+    #[test]
+    fn test_slo_parses_percentile_and_target() {
+        let slo: LatencySlo = "p99:2000".parse().unwrap();
+        assert_eq!((slo.percentile, slo.target_us), (0.99, 2000));
+        assert!("99:2000".parse::<LatencySlo>().is_err());
+        assert!("p99".parse::<LatencySlo>().is_err());
+    }
+}
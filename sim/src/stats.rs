@@ -0,0 +1,90 @@
+//! Mann-Whitney test for comparing transport runs; `MeanCi` is shared through policy-compare
+
+use policy_compare::normal_cdf;
+use serde::{Deserialize, Serialize};
+
+pub use policy_compare::MeanCi;
+
+/// Mann-Whitney U test result (two-sided, normal approximation)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct MannWhitney {
+    /// U statistic for the first sample
+    pub u: f64,
+    pub z: f64,
+    pub p_value: f64,
+}
+
+/// Mann-Whitney U test of whether `a` and `b` come from the same distribution
+///
+/// Ties get average ranks and the variance is tie-corrected.
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> MannWhitney {
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    if a.is_empty() || b.is_empty() {
+        return MannWhitney {
+            u: 0.0,
+            z: 0.0,
+            p_value: 1.0,
+        };
+    }
+
+    let mut pooled: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&x| (x, true))
+        .chain(b.iter().map(|&x| (x, false)))
+        .collect();
+    pooled.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    // Average ranks over tie groups
+    let n = pooled.len();
+    let mut rank_sum_a = 0.0;
+    let mut tie_term = 0.0;
+    let mut i = 0;
+    while i < n {
+        let mut j = i;
+        while j + 1 < n && pooled[j + 1].0 == pooled[i].0 {
+            j += 1;
+        }
+        let avg_rank = (i + j) as f64 / 2.0 + 1.0;
+        let ties = (j - i + 1) as f64;
+        tie_term += ties.powi(3) - ties;
+        rank_sum_a += pooled[i..=j].iter().filter(|(_, in_a)| *in_a).count() as f64 * avg_rank;
+        i = j + 1;
+    }
+
+    let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+    let mu = n1 * n2 / 2.0;
+    let n = n as f64;
+    let sigma = (n1 * n2 / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)))).sqrt();
+    if sigma == 0.0 {
+        return MannWhitney {
+            u,
+            z: 0.0,
+            p_value: 1.0,
+        };
+    }
+
+    // Continuity correction toward the mean
+    let z = (u - mu - 0.5 * (u - mu).signum()) / sigma;
+    MannWhitney {
+        u,
+        z,
+        p_value: (2.0 * (1.0 - normal_cdf(z.abs()))).min(1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mann_whitney() {
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let b = [11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, 18.0];
+        let separated = mann_whitney(&a, &b);
+        assert_eq!(separated.u, 0.0);
+        assert!(separated.p_value < 0.01);
+
+        let same = mann_whitney(&a, &a);
+        assert!(same.p_value > 0.9);
+    }
+}
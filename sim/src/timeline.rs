@@ -0,0 +1,58 @@
+//! Decision timeline recording
+//!
+//! Optional per-invocation log of what each policy saw and decided. Used to
+//! debug oscillating policies and as raw material for training datasets.
+//! A fixed-cadence state series (queue depth, oldest-packet age, decision in
+//! force) complements it for plotting a run after the fact.
+
+use crate::FlushDecision;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use telemetry::TelemetrySample;
+
+/// One policy invocation
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DecisionRecord {
+    /// Microseconds since the transport was created
+    pub timestamp_us: u64,
+    /// Priority class the decision applies to; None for a shared policy
+    pub class: Option<usize>,
+    pub telemetry: TelemetrySample,
+    pub decision: FlushDecision,
+}
+
+/// Write records as JSON Lines, one record per line
+pub fn write_decision_log(records: &[DecisionRecord], path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Internal transport state at one sampling instant
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StateSample {
+    /// Microseconds since the transport was created
+    pub timestamp_us: u64,
+    pub class: usize,
+    pub queue_depth: usize,
+    pub oldest_packet_age_us: u64,
+    /// Decision in force for this class at the sampling instant
+    pub threshold: u32,
+    pub max_delay_us: u32,
+    pub segment_bytes: Option<u32>,
+    pub pacing_rate: Option<u64>,
+}
+
+/// Write samples as CSV, one row per class per sampling instant
+pub fn write_state_series(samples: &[StateSample], path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for sample in samples {
+        writer.serialize(sample)?;
+    }
+    writer.flush()
+}
@@ -0,0 +1,109 @@
+//! Transient response analysis
+//!
+//! Quantifies how a policy reacts to a regime change: after a step in the
+//! offered load, how far windowed p95 latency overshoots its new steady
+//! state and how long it takes to settle there.
+
+use crate::percentile;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Latency response to a step change
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StepResponse {
+    /// Steady-state windowed p95 after the step (median over the final quarter)
+    pub final_p95_us: f64,
+    /// Highest windowed p95 after the step
+    pub peak_p95_us: f64,
+    /// (peak - final) / final; 0 when latency never exceeds its final value
+    pub overshoot: f64,
+    /// Time from the step until windowed p95 stays within the band of final
+    pub settling_time: Duration,
+}
+
+/// Analyze `(delivery time, latency)` samples around a step at `step_at`
+///
+/// Latencies are grouped into `window`-sized bins by delivery time. Returns
+/// None if fewer than four non-empty windows follow the step.
+pub fn step_response(
+    delivery_times_us: &[u64],
+    latencies_us: &[u64],
+    step_at: Duration,
+    window: Duration,
+    band: f64,
+) -> Option<StepResponse> {
+    let step_us = step_at.as_micros() as u64;
+    let window_us = window.as_micros().max(1) as u64;
+
+    let mut bins: Vec<Vec<u64>> = Vec::new();
+    for (&time, &latency) in delivery_times_us.iter().zip(latencies_us) {
+        if time < step_us {
+            continue;
+        }
+        let idx = ((time - step_us) / window_us) as usize;
+        if bins.len() <= idx {
+            bins.resize(idx + 1, Vec::new());
+        }
+        bins[idx].push(latency);
+    }
+
+    // (window start offset, p95) for windows that saw traffic
+    let p95s: Vec<(usize, f64)> = bins
+        .iter()
+        .enumerate()
+        .filter(|(_, bin)| !bin.is_empty())
+        .map(|(i, bin)| (i, percentile(bin, 0.95)))
+        .collect();
+    if p95s.len() < 4 {
+        return None;
+    }
+
+    let mut tail: Vec<f64> = p95s[p95s.len() * 3 / 4..].iter().map(|&(_, p95)| p95).collect();
+    tail.sort_by(f64::total_cmp);
+    let final_p95_us = tail[tail.len() / 2];
+    let peak_p95_us = p95s.iter().map(|&(_, p95)| p95).fold(0.0, f64::max);
+
+    // First window after which every window stays inside the band
+    let tolerance = band * final_p95_us.max(1.0);
+    let settled_from = p95s
+        .iter()
+        .rposition(|&(_, p95)| (p95 - final_p95_us).abs() > tolerance)
+        .map_or(0, |last_outside| p95s.get(last_outside + 1).map_or(bins.len(), |&(i, _)| i));
+
+    Some(StepResponse {
+        final_p95_us,
+        peak_p95_us,
+        overshoot: ((peak_p95_us - final_p95_us) / final_p95_us.max(1.0)).max(0.0),
+        settling_time: window * settled_from as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_response() {
+        // 10 windows of 100ms after a step at 1s: spike to 400µs, then 100µs
+        let window_p95 = [400, 300, 200, 100, 100, 100, 100, 100, 100, 100];
+        let mut times = Vec::new();
+        let mut latencies = Vec::new();
+        for (i, &latency) in window_p95.iter().enumerate() {
+            times.push(1_000_000 + i as u64 * 100_000 + 50_000);
+            latencies.push(latency);
+        }
+
+        let response = step_response(
+            &times,
+            &latencies,
+            Duration::from_secs(1),
+            Duration::from_millis(100),
+            0.2,
+        )
+        .unwrap();
+        assert_eq!(response.final_p95_us, 100.0);
+        assert_eq!(response.peak_p95_us, 400.0);
+        assert_eq!(response.overshoot, 3.0);
+        assert_eq!(response.settling_time, Duration::from_millis(300));
+    }
+}
@@ -0,0 +1,242 @@
+//! Active-learning label acquisition
+//!
+//! Sweeps are the expensive part of training: every workload cell costs a
+//! simulator run per pool size. Rather than sweep a dense grid up front,
+//! [`ActiveLearner`] ranks the cells swept so far by how little the model
+//! can be trusted around them, so the next sweep refines those regions
+//! first. Uncertainty is the disagreement between the trees of a bagged
+//! forest fit to the rows; sparse coverage is the distance from a cell's
+//! windows to the nearest windows of any other cell, both in the
+//! normalized feature space the reflex sees. The loop stops once
+//! validation error stops improving ([`ActiveLearner::converged`]).
+
+use crate::{CartTrainer, Dataset, ForestTrainer};
+use reflex_format::eval_tree;
+use std::collections::BTreeMap;
+
+/// How much one workload cell would gain from a denser sweep around it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionScore {
+    pub cell: String,
+    pub rows: usize,
+    pub uncertainty: f64, // mean per-row standard deviation across forest trees
+    pub sparsity: f64,    // mean per-row distance to the nearest rows of other cells
+    pub score: f64,       // both, each scaled by its largest value over all cells
+}
+
+/// Picks where to sweep next and when to stop
+#[derive(Debug, Clone)]
+pub struct ActiveLearner {
+    pub forest: ForestTrainer, // uncertainty estimator, independent of the model trained
+    pub neighbors: usize,      // nearest other-cell rows averaged into sparsity
+    pub batch: usize,          // cells refined per round
+    pub min_improvement: f64,  // relative drop in validation error that counts as progress
+    pub patience: usize,       // rounds without progress before stopping
+}
+
+impl ActiveLearner {
+    /// 20 depth-6 trees, 5 neighbors, 4 cells a round, stop after one round under 1% better
+    pub fn new() -> Self {
+        Self {
+            forest: ForestTrainer::new()
+                .with_n_trees(20)
+                .with_tree(CartTrainer::new().with_max_depth(6)),
+            neighbors: 5,
+            batch: 4,
+            min_improvement: 0.01,
+            patience: 1,
+        }
+    }
+
+    pub fn with_forest(mut self, forest: ForestTrainer) -> Self {
+        self.forest = forest;
+        self
+    }
+
+    /// Seed the uncertainty forest's bootstrap samples, so rounds pick the same cells
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.forest = self.forest.with_seed(seed);
+        self
+    }
+
+    pub fn with_neighbors(mut self, neighbors: usize) -> Self {
+        assert!(neighbors > 0, "sparsity needs at least one neighbor");
+        self.neighbors = neighbors;
+        self
+    }
+
+    pub fn with_batch(mut self, batch: usize) -> Self {
+        assert!(batch > 0, "a round refines at least one cell");
+        self.batch = batch;
+        self
+    }
+
+    pub fn with_min_improvement(mut self, min_improvement: f64) -> Self {
+        assert!(min_improvement >= 0.0, "improvement is a fraction");
+        self.min_improvement = min_improvement;
+        self
+    }
+
+    pub fn with_patience(mut self, patience: usize) -> Self {
+        assert!(patience > 0, "patience is at least one round");
+        self.patience = patience;
+        self
+    }
+
+    /// Every cell's score, most in need of refinement first
+    ///
+    /// `data` holds normalized rows and `cells` each row's workload cell.
+    pub fn score_cells(&self, data: &Dataset, cells: &[String]) -> Vec<RegionScore> {
+        assert_eq!(cells.len(), data.len(), "one cell per row");
+        if data.is_empty() {
+            return Vec::new();
+        }
+        let forest = self.forest.fit(data);
+        let uncertainty: Vec<f64> = data
+            .features
+            .iter()
+            .map(|row| {
+                let votes: Vec<f64> = forest
+                    .trees
+                    .iter()
+                    .map(|tree| eval_tree(tree, row) as f64)
+                    .collect();
+                let mean = votes.iter().sum::<f64>() / votes.len() as f64;
+                (votes.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / votes.len() as f64).sqrt()
+            })
+            .collect();
+        let sparsity: Vec<f64> = (0..data.len())
+            .map(|i| self.distance_to_other_cells(data, cells, i))
+            .collect();
+
+        let mut sums: BTreeMap<&str, (usize, f64, f64)> = BTreeMap::new();
+        for (i, cell) in cells.iter().enumerate() {
+            let entry = sums.entry(cell).or_default();
+            entry.0 += 1;
+            entry.1 += uncertainty[i];
+            entry.2 += sparsity[i];
+        }
+        let mut scores: Vec<RegionScore> = sums
+            .into_iter()
+            .map(|(cell, (rows, uncertainty, sparsity))| RegionScore {
+                cell: cell.to_string(),
+                rows,
+                uncertainty: uncertainty / rows as f64,
+                sparsity: sparsity / rows as f64,
+                score: 0.0,
+            })
+            .collect();
+        let max_uncertainty = scores.iter().map(|s| s.uncertainty).fold(0.0, f64::max);
+        let max_sparsity = scores.iter().map(|s| s.sparsity).fold(0.0, f64::max);
+        let scaled = |value: f64, max: f64| if max > 0.0 { value / max } else { 0.0 };
+        for score in &mut scores {
+            score.score =
+                scaled(score.uncertainty, max_uncertainty) + scaled(score.sparsity, max_sparsity);
+        }
+        scores.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.cell.cmp(&b.cell)));
+        scores
+    }
+
+    /// The `batch` cells to refine next
+    pub fn select(&self, data: &Dataset, cells: &[String]) -> Vec<String> {
+        let mut scores = self.score_cells(data, cells);
+        scores.truncate(self.batch);
+        scores.into_iter().map(|score| score.cell).collect()
+    }
+
+    /// Whether validation error, one entry per round, has stopped improving
+    ///
+    /// True once the last `patience` rounds all fail to beat the best
+    /// earlier round by `min_improvement` of its error.
+    pub fn converged(&self, validation_errors: &[f64]) -> bool {
+        if validation_errors.len() <= self.patience {
+            return false;
+        }
+        let (earlier, recent) = validation_errors.split_at(validation_errors.len() - self.patience);
+        let best = earlier.iter().copied().fold(f64::INFINITY, f64::min);
+        recent
+            .iter()
+            .all(|&error| error > best * (1.0 - self.min_improvement))
+    }
+
+    /// Mean distance from row `i` to its nearest rows in other cells
+    ///
+    /// A cell with no other cells around it is as sparse as the feature
+    /// space is wide.
+    fn distance_to_other_cells(&self, data: &Dataset, cells: &[String], i: usize) -> f64 {
+        let mut distances: Vec<f64> = data
+            .features
+            .iter()
+            .zip(cells)
+            .filter(|(_, cell)| **cell != cells[i])
+            .map(|(row, _)| {
+                row.iter()
+                    .zip(&data.features[i])
+                    .map(|(a, b)| ((a - b) as f64).powi(2))
+                    .sum::<f64>()
+                    .sqrt()
+            })
+            .collect();
+        if distances.is_empty() {
+            return (data.feature_count() as f64).sqrt();
+        }
+        let k = self.neighbors.min(distances.len());
+        distances.select_nth_unstable_by(k - 1, f64::total_cmp);
+        distances[..k].iter().sum::<f64>() / k as f64
+    }
+}
+
+impl Default for ActiveLearner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_isolated_and_noisy_cells_rank_first() {
+        // Cells a..d tile [0, 0.32] with clean labels, "noisy" sits between
+        // b and c with labels that alternate, and "far" sits alone at 0.9
+        let mut features = Vec::new();
+        let mut targets = Vec::new();
+        let mut cells = Vec::new();
+        for (c, name) in ["a", "b", "c", "d"].iter().enumerate() {
+            for i in 0..20 {
+                features.push(vec![c as f32 * 0.1 + i as f32 * 0.001, 0.5]);
+                targets.push(4.0 + c as f32);
+                cells.push(name.to_string());
+            }
+        }
+        for i in 0..20 {
+            features.push(vec![0.9 + i as f32 * 0.001, 0.5]);
+            targets.push(32.0);
+            cells.push("far".to_string());
+            features.push(vec![0.15 + i as f32 * 0.001, 0.5]);
+            targets.push(if i % 2 == 0 { 4.0 } else { 16.0 });
+            cells.push("noisy".to_string());
+        }
+        let data = Dataset::new(features, targets).unwrap();
+
+        let learner = ActiveLearner::new().with_batch(2);
+        let scores = learner.score_cells(&data, &cells);
+        assert_eq!(scores.len(), 6);
+        assert_eq!(learner.select(&data, &cells), ["far", "d"]);
+        assert!(scores[0].sparsity > 0.5, "{:?}", scores[0]);
+        // The boundary between d and far is unknown too; past that, noise beats clean labels
+        let rank = |cell: &str| scores.iter().position(|s| s.cell == cell).unwrap();
+        assert!(["a", "b", "c"]
+            .iter()
+            .all(|&clean| rank("noisy") < rank(clean)));
+    }
+
+    #[test]
+    fn test_converges_when_validation_error_plateaus() {
+        let learner = ActiveLearner::new().with_patience(2);
+        assert!(!learner.converged(&[4.0, 3.0]));
+        assert!(!learner.converged(&[4.0, 3.0, 2.995, 2.5]));
+        assert!(learner.converged(&[4.0, 3.0, 2.995, 3.2]));
+    }
+}
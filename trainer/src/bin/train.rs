@@ -0,0 +1,248 @@
+//! Train a reflex from a labeled dataset and a YAML config
+//!
+//! Reads a `sweep grid` dataset (CSV or JSON) of raw compute-v1 telemetry,
+//! runs `TrainConfig::train`, and writes the `.reflex` plus, when asked or
+//! when the config doesn't embed it, the normalizer as a sidecar JSON file.
+//! With `--warm-start parent.reflex` it runs `TrainConfig::retrain` from
+//! that reflex instead, normalizing with the parent's embedded normalizer
+//! or `--parent-normalizer`. `--hard-examples divergences.jsonl` adds the
+//! shadow-mode decisions the last candidate got badly wrong to the
+//! training rows. A config's `label_min_run` smooths transient label
+//! flips out before training, and its `max_model_bytes` prunes the model until the
+//! written file fits. `--calibrate` fits an isotonic output correction
+//! against the dataset's per-workload optimum and prints the gap it closes.
+//! A config with a `curriculum` trains stage by stage on the dataset's
+//! `workload` regimes; `--per-regime` reports per-regime validation error
+//! for a pooled fit too. A `multi_tree` config also reads its
+//! `extra_targets` columns and runs `TrainConfig::train_multi`.
+//! Training draws only from the config's `seed`, so with `SOURCE_DATE_EPOCH`
+//! pinning the timestamps, reruns on the same inputs write identical bytes.
+
+use cli::{take_flag, take_switch};
+use reflex_format::Reflex;
+use std::env;
+use telemetry_compute::{ComputeTelemetry, Normalizer};
+use trainer::dataset::{read_sweep_cells, read_sweep_dataset, read_sweep_regimes};
+use trainer::{
+    calibrate, cell_gap, compare_cells, label_flips, read_divergence_log, write_reflex,
+    HardExampleMiner, ModelKind, TrainConfig,
+};
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    let output = take_flag(&mut args, "--output");
+    let normalizer_out = take_flag(&mut args, "--normalizer-out");
+    let warm_start = take_flag(&mut args, "--warm-start");
+    let parent_normalizer = take_flag(&mut args, "--parent-normalizer");
+    let hard_examples = take_flag(&mut args, "--hard-examples");
+    let calibrate_cells = take_switch(&mut args, "--calibrate");
+    let per_regime = take_switch(&mut args, "--per-regime");
+    let (Some(output), [_, dataset_path, config_path]) = (output, &args[..]) else {
+        eprintln!("Usage: train <dataset.csv|dataset.json> <config.yaml> --output model.reflex [--normalizer-out normalizer.json] [--warm-start parent.reflex [--parent-normalizer normalizer.json]] [--hard-examples divergences.jsonl] [--calibrate] [--per-regime]");
+        eprintln!("Example: train data/telemetry/compute-sweep.csv trainer/configs/tree.yaml --output data/models/thread-pool.reflex");
+        std::process::exit(1);
+    };
+
+    let config = TrainConfig::load(config_path).unwrap_or_else(|e| {
+        eprintln!("Failed to load config {}: {}", config_path, e);
+        std::process::exit(1);
+    });
+    let raw = read_sweep_dataset(dataset_path, &config.target).unwrap_or_else(|e| {
+        eprintln!("Failed to read dataset {}: {}", dataset_path, e);
+        std::process::exit(1);
+    });
+
+    println!("=== Train ===");
+    println!("Dataset: {} ({} rows)", dataset_path, raw.len());
+    println!(
+        "Config: {} (hash {}, seed {})",
+        config_path,
+        config.hash(),
+        config.seed
+    );
+    println!(
+        "Model: {:?} -> {}",
+        config.model,
+        [&config.target]
+            .into_iter()
+            .chain(&config.extra_targets)
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    if config.label_min_run > 1 || config.label_deadband > 0.0 {
+        let smoothed = config.hysteresis().smooth(&raw.targets);
+        println!(
+            "Label hysteresis: {} label flips -> {} (min run {}, deadband {})",
+            label_flips(&raw.targets),
+            label_flips(&smoothed),
+            config.label_min_run,
+            config.label_deadband
+        );
+    }
+    let divergences = match &hard_examples {
+        Some(path) => read_divergence_log(path).unwrap_or_else(|e| {
+            eprintln!("Failed to read divergence log {}: {}", path, e);
+            std::process::exit(1);
+        }),
+        None => Vec::new(),
+    };
+    if let Some(path) = &hard_examples {
+        let hard = HardExampleMiner::new()
+            .with_min_divergence(config.hard_example_divergence)
+            .mine(&divergences)
+            .map_or(0, |hard| hard.len());
+        println!(
+            "Hard examples: {} of {} shadow decisions in {} (x{})",
+            hard,
+            divergences.len(),
+            path,
+            config.hard_example_repeats
+        );
+    }
+
+    let regimes = if per_regime || !config.curriculum.is_empty() {
+        if warm_start.is_some() || hard_examples.is_some() {
+            eprintln!("Per-regime training can't be combined with --warm-start or --hard-examples");
+            std::process::exit(1);
+        }
+        let regimes = read_sweep_regimes(dataset_path).unwrap_or_else(|e| {
+            eprintln!(
+                "Failed to read workload regimes from {}: {}",
+                dataset_path, e
+            );
+            std::process::exit(1);
+        });
+        if !config.curriculum.is_empty() {
+            println!("Curriculum: {}", config.curriculum.join(" -> "));
+        }
+        Some(regimes)
+    } else {
+        None
+    };
+
+    let extra_targets = if config.model == ModelKind::MultiTree {
+        if warm_start.is_some() || hard_examples.is_some() || regimes.is_some() {
+            eprintln!("multi_tree training can't be combined with --warm-start, --hard-examples or --per-regime");
+            std::process::exit(1);
+        }
+        let columns = config
+            .extra_targets
+            .iter()
+            .map(|target| read_sweep_dataset(dataset_path, target).map(|data| data.targets))
+            .collect::<std::io::Result<Vec<Vec<f32>>>>()
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to read extra targets from {}: {}", dataset_path, e);
+                std::process::exit(1);
+            });
+        Some(columns)
+    } else {
+        None
+    };
+
+    let mut trained = match &warm_start {
+        Some(parent_path) => {
+            let (parent, normalizer) = load_parent(parent_path, parent_normalizer.as_deref());
+            println!(
+                "Warm start: {} ({})",
+                parent_path, parent.metadata.created_at
+            );
+            config.retrain(&parent, &normalizer, &raw, &divergences)
+        }
+        None => match (&regimes, &extra_targets) {
+            (_, Some(columns)) => config.train_multi(&raw, columns),
+            (Some(regimes), None) => config.train_with_regimes(&raw, regimes),
+            (None, None) => config.train_with_hard_examples(&raw, &divergences),
+        },
+    }
+    .unwrap_or_else(|e| {
+        eprintln!("Training failed: {}", e);
+        std::process::exit(1);
+    });
+    if let Some(report) = &trained.reflex.report {
+        print!("\n{}", report);
+    }
+    let names = ComputeTelemetry::feature_names();
+    for table in &trained.reflex.tables {
+        println!(
+            "\nLookup table: rows f{} = {}, columns f{} = {} (normalized)",
+            table.row_feature,
+            names[table.row_feature as usize],
+            table.col_feature,
+            names[table.col_feature as usize]
+        );
+        print!("{}", table);
+    }
+    println!("\nValidation MAE: {:.3}", trained.validation_mae);
+    if calibrate_cells {
+        let cells = read_sweep_cells(dataset_path).unwrap_or_else(|e| {
+            eprintln!("Failed to read workload cells from {}: {}", dataset_path, e);
+            std::process::exit(1);
+        });
+        let data = trained.normalize(&raw);
+        let (calibrated, before) = calibrate(&trained.reflex, &data, &cells).unwrap_or_else(|e| {
+            eprintln!("Calibration failed: {}", e);
+            std::process::exit(1);
+        });
+        let after = compare_cells(&data, &cells, |row| calibrated.infer(row)[0]);
+        println!(
+            "Calibration: {} workload cells, mean gap to optimum {:.3} -> {:.3} ({} knots)",
+            before.len(),
+            cell_gap(&before),
+            cell_gap(&after),
+            calibrated.metadata.calibration[0].x.len()
+        );
+        trained.reflex = calibrated;
+    }
+    if let Some(lineage) = &trained.reflex.metadata.lineage {
+        println!(
+            "Lineage: generation {} via {} (parent CRC {:08x})",
+            lineage.generation, lineage.method, lineage.parent_checksum
+        );
+    }
+
+    write_reflex(&trained.reflex, &output).expect("Failed to write reflex");
+    let bytes = std::fs::metadata(&output).map_or(0, |m| m.len());
+    println!("Wrote {} ({} bytes)", output, bytes);
+    let normalizer_out = normalizer_out.or_else(|| {
+        (!config.embed_normalizer)
+            .then(|| format!("{}-normalizer.json", output.trim_end_matches(".reflex")))
+    });
+    if let Some(path) = normalizer_out {
+        std::fs::write(
+            &path,
+            serde_json::to_string_pretty(&trained.normalizer).unwrap(),
+        )
+        .expect("Failed to write normalizer");
+        println!("Wrote {}", path);
+    }
+}
+
+/// The parent reflex and the normalizer its inputs were scaled with
+fn load_parent(path: &str, normalizer_path: Option<&str>) -> (Reflex, Normalizer) {
+    let parent = std::fs::read(path)
+        .and_then(|bytes| Reflex::from_bytes(&bytes))
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load parent {}: {}", path, e);
+            std::process::exit(1);
+        });
+    let normalizer = match normalizer_path {
+        Some(path) => std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok()),
+        None => parent.metadata.normalizer.as_ref().and_then(|embedded| {
+            Some(Normalizer {
+                min: embedded.min.as_slice().try_into().ok()?,
+                max: embedded.max.as_slice().try_into().ok()?,
+            })
+        }),
+    };
+    let Some(normalizer) = normalizer else {
+        eprintln!(
+            "No usable normalizer for {}; pass --parent-normalizer",
+            path
+        );
+        std::process::exit(1);
+    };
+    (parent, normalizer)
+}
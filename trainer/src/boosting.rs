@@ -0,0 +1,292 @@
+//! Gradient-boosted regression trees
+//!
+//! Start from the best constant prediction, then fit each tree to the
+//! loss's negative gradient (the residuals, for squared loss) and add it
+//! scaled by the learning rate. Leaves take the loss's optimum over the
+//! residuals they hold (for quantile loss the τ-quantile, since the
+//! gradient only carries their sign). A held-out validation fold decides
+//! how many rounds to keep. Monotone constraints on the tree trainer carry
+//! over, as a sum of monotone trees is monotone. The validation fold is a
+//! seeded shuffle by default, or the latest rows by time with
+//! [`BoostingTrainer::with_time_validation`].
+
+use crate::split::latest_rows;
+use crate::{CartTrainer, Dataset, Loss};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use reflex_format::{eval_tree, TreeEnsemble};
+use std::time::Duration;
+
+/// Gradient-boosting trainer with shrinkage and early stopping
+#[derive(Debug, Clone)]
+pub struct BoostingTrainer {
+    pub max_rounds: usize,
+    pub learning_rate: f32,
+    pub loss: Loss,
+    pub tree: CartTrainer,
+    pub validation_fraction: f64,
+    pub patience: usize, // rounds without validation improvement before stopping
+    pub seed: u64,
+    pub time_gap_us: Option<u64>, // validate on the latest rows instead of shuffled ones
+}
+
+impl BoostingTrainer {
+    /// 200 rounds of depth-3 trees at rate 0.1, validating on 20% of rows
+    pub fn new() -> Self {
+        Self {
+            max_rounds: 200,
+            learning_rate: 0.1,
+            loss: Loss::Squared,
+            tree: CartTrainer::new().with_max_depth(3),
+            validation_fraction: 0.2,
+            patience: 10,
+            seed: 0,
+            time_gap_us: None,
+        }
+    }
+
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    pub fn with_learning_rate(mut self, learning_rate: f32) -> Self {
+        assert!(
+            learning_rate > 0.0 && learning_rate <= 1.0,
+            "learning rate must be in (0, 1]"
+        );
+        self.learning_rate = learning_rate;
+        self
+    }
+
+    /// Loss the ensemble minimizes, and validates on
+    pub fn with_loss(mut self, loss: Loss) -> Self {
+        loss.validate();
+        self.loss = loss;
+        self
+    }
+
+    /// Settings for each tree
+    pub fn with_tree(mut self, tree: CartTrainer) -> Self {
+        self.tree = tree;
+        self
+    }
+
+    /// Share of rows held out for early stopping (0 trains on everything for `max_rounds`)
+    pub fn with_validation_fraction(mut self, fraction: f64) -> Self {
+        assert!(
+            (0.0..1.0).contains(&fraction),
+            "validation fraction must be in [0, 1)"
+        );
+        self.validation_fraction = fraction;
+        self
+    }
+
+    pub fn with_patience(mut self, patience: usize) -> Self {
+        self.patience = patience;
+        self
+    }
+
+    /// Seed for the validation split
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Validate on the latest rows by time, dropping training rows within `gap` of them
+    pub fn with_time_validation(mut self, gap: Duration) -> Self {
+        self.time_gap_us = Some(gap.as_micros() as u64);
+        self
+    }
+
+    /// Fit one output, keeping the rounds up to the best validation loss
+    pub fn fit(&self, data: &Dataset) -> TreeEnsemble {
+        assert!(!data.is_empty(), "cannot boost on no rows");
+        let (train_rows, valid_rows) = self.split(data);
+        let mut train_targets: Vec<f32> = train_rows.iter().map(|&r| data.targets[r]).collect();
+        let ensemble = TreeEnsemble {
+            base: self.loss.optimum(&mut train_targets),
+            scale: self.learning_rate,
+            trees: Vec::new(),
+        };
+        self.boost(data, ensemble, &train_rows, &valid_rows)
+    }
+
+    /// Append up to `max_rounds` rounds to a boosted `ensemble`, fit to `data`
+    ///
+    /// Warm start for retraining on fresh data: the existing trees are
+    /// kept and new ones correct what they get wrong. New rounds use the
+    /// ensemble's `scale` as their learning rate, since it applies to every
+    /// tree alike.
+    pub fn fit_more(&self, data: &Dataset, ensemble: TreeEnsemble) -> TreeEnsemble {
+        assert!(!data.is_empty(), "cannot boost on no rows");
+        let (train_rows, valid_rows) = self.split(data);
+        self.boost(data, ensemble, &train_rows, &valid_rows)
+    }
+}
+
+impl BoostingTrainer {
+    /// (train rows, validation rows)
+    fn split(&self, data: &Dataset) -> (Vec<usize>, Vec<usize>) {
+        let n_valid = ((data.len() as f64 * self.validation_fraction) as usize).min(data.len() - 1);
+        let (train_rows, valid_rows) = match self.time_gap_us {
+            Some(gap_us) => latest_rows(data, n_valid, gap_us),
+            None => {
+                let mut rows: Vec<usize> = (0..data.len()).collect();
+                rows.shuffle(&mut StdRng::seed_from_u64(self.seed));
+                let train = rows.split_off(n_valid);
+                (train, rows)
+            }
+        };
+        assert!(
+            !train_rows.is_empty(),
+            "the time gap leaves no training rows"
+        );
+        (train_rows, valid_rows)
+    }
+
+    /// Add rounds to `ensemble` until `max_rounds` or early stopping
+    fn boost(
+        &self,
+        data: &Dataset,
+        mut ensemble: TreeEnsemble,
+        train_rows: &[usize],
+        valid_rows: &[usize],
+    ) -> TreeEnsemble {
+        let kept = ensemble.trees.len();
+        // Running predictions for every row, so each round costs one tree evaluation per row
+        let mut predictions: Vec<f32> =
+            data.features.iter().map(|row| ensemble.eval(row)).collect();
+        let tree_trainer = self.tree.clone().with_loss(self.loss);
+        let mut gradients = vec![0.0f32; data.len()];
+        let mut residuals = vec![0.0f32; data.len()];
+        let mut best = (self.validation_loss(data, valid_rows, &predictions), 0);
+
+        for round in 1..=self.max_rounds {
+            for &r in train_rows {
+                gradients[r] = self.loss.negative_gradient(data.targets[r], predictions[r]);
+                residuals[r] = data.targets[r] - predictions[r];
+            }
+            let tree = tree_trainer
+                .fit_rows_split_on(&data.features, &gradients, &residuals, train_rows)
+                .nodes;
+            for (prediction, row) in predictions.iter_mut().zip(&data.features) {
+                *prediction += ensemble.scale * eval_tree(&tree, row);
+            }
+            ensemble.trees.push(tree);
+
+            if valid_rows.is_empty() {
+                continue;
+            }
+            let loss = self.validation_loss(data, valid_rows, &predictions);
+            if loss < best.0 {
+                best = (loss, round);
+            } else if round - best.1 >= self.patience {
+                break;
+            }
+        }
+        if !valid_rows.is_empty() {
+            ensemble.trees.truncate(kept + best.1);
+        }
+        ensemble
+    }
+
+    fn validation_loss(&self, data: &Dataset, rows: &[usize], predictions: &[f32]) -> f64 {
+        if rows.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = rows
+            .iter()
+            .map(|&r| self.loss.value(data.targets[r], predictions[r]))
+            .sum();
+        total / rows.len() as f64
+    }
+}
+
+impl Default for BoostingTrainer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mean_absolute_error;
+
+    fn ramp_data() -> Dataset {
+        let features: Vec<Vec<f32>> = (0..200).map(|i| vec![i as f32 / 200.0]).collect();
+        let targets = features.iter().map(|r| 2.0 + 30.0 * r[0] * r[0]).collect();
+        Dataset::new(features, targets).unwrap()
+    }
+
+    #[test]
+    fn test_boosting_fits_smooth_curve() {
+        let data = ramp_data();
+        let single = CartTrainer::new()
+            .with_max_depth(2)
+            .with_min_samples_leaf(5)
+            .fit(&data);
+        let boosted = BoostingTrainer::new()
+            .with_tree(
+                CartTrainer::new()
+                    .with_max_depth(2)
+                    .with_min_samples_leaf(5),
+            )
+            .with_learning_rate(0.3)
+            .fit(&data);
+
+        let boosted_mae = mean_absolute_error(&data, |row| boosted.eval(row));
+        assert!(boosted_mae < mean_absolute_error(&data, |row| single.predict(row)) / 2.0);
+        assert!(boosted_mae < 1.0);
+    }
+
+    #[test]
+    fn test_early_stopping_truncates_rounds() {
+        let data = ramp_data();
+        let trainer = BoostingTrainer::new()
+            .with_max_rounds(500)
+            .with_learning_rate(0.5)
+            .with_patience(5)
+            .with_tree(CartTrainer::new().with_min_samples_leaf(5));
+        let stopped = trainer.fit(&data);
+        assert!(!stopped.trees.is_empty() && stopped.trees.len() < 500);
+
+        let full = trainer
+            .with_validation_fraction(0.0)
+            .with_max_rounds(30)
+            .fit(&data);
+        assert_eq!(full.trees.len(), 30);
+    }
+
+    #[test]
+    fn test_quantile_boosting_covers_the_target() {
+        // Noisy ramp: ±3 around 10 + 20x
+        let features: Vec<Vec<f32>> = (0..300).map(|i| vec![i as f32 / 300.0]).collect();
+        let targets: Vec<f32> = features
+            .iter()
+            .enumerate()
+            .map(|(i, r)| 10.0 + 20.0 * r[0] + ((i * 13) % 7) as f32 - 3.0)
+            .collect();
+        let data = Dataset::new(features, targets).unwrap();
+
+        let p90 = BoostingTrainer::new()
+            .with_loss(Loss::Quantile(0.9))
+            .with_learning_rate(0.3)
+            .with_tree(
+                CartTrainer::new()
+                    .with_max_depth(2)
+                    .with_min_samples_leaf(10),
+            )
+            .fit(&data);
+        let covered = data
+            .features
+            .iter()
+            .zip(&data.targets)
+            .filter(|(row, &target)| p90.eval(row) >= target)
+            .count() as f64
+            / data.len() as f64;
+        assert!(covered > 0.8 && covered < 0.98, "coverage {}", covered);
+    }
+}
@@ -0,0 +1,146 @@
+//! Output bounds learned from the labels
+//!
+//! A reflex clamps every output to its bounds, the last line of defense
+//! against a model extrapolating somewhere no sweep ever measured. Fixed
+//! bounds like `[1, 64]` are too loose to catch much. [`BoundsLearner`]
+//! instead takes the central quantile range of the training labels and
+//! widens it by a margin, and [`clamping`] reports how often the bounds
+//! had to step in on held-out rows.
+
+use crate::Dataset;
+use reflex_format::{OutputBounds, OutputClamping, Reflex};
+
+/// Derives an output's bounds from its label distribution
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundsLearner {
+    pub quantile: f32, // bounds start at the quantile..1 - quantile label range
+    pub margin: f32,   // widened by this fraction of the range on each side
+}
+
+impl BoundsLearner {
+    /// 1st to 99th percentile, widened 10% each way
+    pub fn new() -> Self {
+        Self {
+            quantile: 0.01,
+            margin: 0.1,
+        }
+    }
+
+    pub fn with_quantile(mut self, quantile: f32) -> Self {
+        assert!(
+            (0.0..0.5).contains(&quantile),
+            "quantile must be in [0, 0.5)"
+        );
+        self.quantile = quantile;
+        self
+    }
+
+    pub fn with_margin(mut self, margin: f32) -> Self {
+        assert!(margin >= 0.0, "margin is a fraction of the range");
+        self.margin = margin;
+        self
+    }
+
+    /// `(min, max)` for labels `targets`
+    ///
+    /// Constant labels get a margin of their magnitude (at least 1) instead.
+    pub fn fit(&self, targets: &[f32]) -> (f32, f32) {
+        assert!(!targets.is_empty(), "cannot learn bounds from no labels");
+        let mut sorted = targets.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let at = |q: f32| sorted[((sorted.len() - 1) as f32 * q).round() as usize];
+        let (low, high) = (at(self.quantile), at(1.0 - self.quantile));
+        let width = if high > low {
+            high - low
+        } else {
+            high.abs().max(1.0)
+        };
+        (low - self.margin * width, high + self.margin * width)
+    }
+
+    /// Bounds for the single output `data` is labeled with
+    pub fn fit_bounds(&self, data: &Dataset) -> OutputBounds {
+        let (min, max) = self.fit(&data.targets);
+        OutputBounds {
+            min: vec![min],
+            max: vec![max],
+        }
+    }
+}
+
+impl Default for BoundsLearner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per output, how many of `data`'s rows `reflex`'s bounds clamped
+///
+/// Counts raw model outputs outside the bounds, before calibration.
+/// `learned` marks the bounds as derived from labels in the report.
+pub fn clamping(reflex: &Reflex, data: &Dataset, learned: bool) -> Vec<OutputClamping> {
+    let mut clamping: Vec<OutputClamping> = reflex
+        .bounds
+        .min
+        .iter()
+        .zip(&reflex.bounds.max)
+        .map(|(&min, &max)| OutputClamping {
+            min,
+            max,
+            learned,
+            rows: data.len(),
+            below: 0,
+            above: 0,
+        })
+        .collect();
+    for row in &data.features {
+        for (output, c) in reflex.raw_outputs(row).into_iter().zip(&mut clamping) {
+            c.below += (output < c.min) as usize;
+            c.above += (output > c.max) as usize;
+        }
+    }
+    clamping
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_linear_reflex, training_metadata};
+    use reflex_format::LinearModel;
+
+    #[test]
+    fn test_bounds_follow_the_labels_and_count_clamps() {
+        // Labels 4..=16 with a single outlier at 60
+        let mut targets: Vec<f32> = (0..200).map(|i| 4.0 + (i % 13) as f32).collect();
+        targets[17] = 60.0;
+        let learner = BoundsLearner::new();
+        assert_eq!(learner.fit(&targets), (2.8, 17.2));
+        assert_eq!(
+            learner.with_quantile(0.0).with_margin(0.0).fit(&targets),
+            (4.0, 60.0)
+        );
+        assert_eq!(BoundsLearner::new().fit(&[8.0, 8.0]), (7.2, 8.8));
+
+        let data = Dataset::new(
+            (0..10).map(|i| vec![i as f32 / 9.0]).collect(),
+            vec![0.0; 10],
+        )
+        .unwrap();
+        let reflex = build_linear_reflex(
+            vec![LinearModel {
+                weights: vec![30.0],
+                bias: 0.0,
+            }],
+            1,
+            OutputBounds {
+                min: vec![2.8],
+                max: vec![17.2],
+            },
+            training_metadata("v1", "", 0.0, ""),
+        )
+        .unwrap();
+        let c = &clamping(&reflex, &data, true)[0];
+        // Outputs 0, 3.3, ..., 30: one under the floor, four over the ceiling
+        assert_eq!((c.rows, c.below, c.above), (10, 1, 4));
+    }
+}
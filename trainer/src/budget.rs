@@ -0,0 +1,314 @@
+//! Cost-complexity pruning to a model-size budget
+//!
+//! Edge targets cap how large a reflex may be. Rather than guess a depth
+//! that fits, the trainer grows trees as configured and
+//! [`prune_to_bytes`] collapses weakest links until the serialized reflex
+//! is small enough. A weakest link is the split whose subtree buys the
+//! least training loss per extra leaf, `(R(t) - R(T_t)) / (|T_t| - 1)`,
+//! the same order CART's cost-complexity pruning removes them in.
+
+use crate::Dataset;
+use crate::Loss;
+use reflex_format::{eval_tree, ModelType, Reflex, TreeEnsemble, TreeNode};
+use std::io;
+
+/// `ensemble` with its weakest link collapsed to a leaf, or `None` if every tree is a leaf
+///
+/// Each tree is scored against its partial residual (the target less
+/// every other tree's contribution, in the tree's own units), so links
+/// compare across trees and a collapsed node's new value is the loss's
+/// optimum given the rest of the ensemble.
+pub fn prune_weakest_link(
+    ensemble: &TreeEnsemble,
+    data: &Dataset,
+    loss: Loss,
+) -> Option<TreeEnsemble> {
+    assert!(
+        ensemble.scale != 0.0,
+        "a zero-scale ensemble ignores its trees"
+    );
+    let outputs: Vec<Vec<f32>> = ensemble
+        .trees
+        .iter()
+        .map(|tree| {
+            data.features
+                .iter()
+                .map(|row| eval_tree(tree, row))
+                .collect()
+        })
+        .collect();
+    let totals: Vec<f32> = (0..data.len())
+        .map(|i| outputs.iter().map(|output| output[i]).sum())
+        .collect();
+
+    let mut weakest: Option<(usize, Link)> = None;
+    for (t, tree) in ensemble.trees.iter().enumerate() {
+        let partial: Vec<f32> = (0..data.len())
+            .map(|i| {
+                (data.targets[i] - ensemble.base) / ensemble.scale - (totals[i] - outputs[t][i])
+            })
+            .collect();
+        let mut link = None;
+        walk(
+            tree,
+            0,
+            (0..data.len()).collect(),
+            data,
+            &partial,
+            loss,
+            &mut link,
+        );
+        if let Some(link) = link {
+            if weakest.as_ref().is_none_or(|(_, w)| link.alpha < w.alpha) {
+                weakest = Some((t, link));
+            }
+        }
+    }
+
+    let (t, link) = weakest?;
+    let mut pruned = ensemble.clone();
+    pruned.trees[t] = collapse(&ensemble.trees[t], link.node, link.value);
+    Some(pruned)
+}
+
+/// Prune `reflex` until it serializes to at most `max_bytes`
+///
+/// Works on single-output tree and ensemble reflexes, with `data` the
+/// normalized training rows. Ensemble trees pruned to a single leaf are
+/// folded into the base. Fails if even the fully pruned model is too
+/// large, and for linear and table models, which have nothing to prune.
+pub fn prune_to_bytes(
+    reflex: &Reflex,
+    data: &Dataset,
+    loss: Loss,
+    max_bytes: usize,
+) -> io::Result<Reflex> {
+    if reflex.to_bytes()?.len() <= max_bytes {
+        return Ok(reflex.clone());
+    }
+    if reflex.header.output_count != 1 {
+        return Err(unsupported("size budgets need a single-output reflex"));
+    }
+    let model_type = ModelType::try_from(reflex.header.model_type)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut ensemble = match model_type {
+        ModelType::DecisionTree => TreeEnsemble {
+            base: 0.0,
+            scale: 1.0,
+            trees: reflex.trees.clone(),
+        },
+        ModelType::Ensemble => reflex.ensembles[0].clone(),
+        ModelType::Linear | ModelType::LookupTable => {
+            return Err(unsupported("linear and table models can't be pruned"))
+        }
+        ModelType::MultiOutputTree => {
+            return Err(unsupported("size budgets need a single-output reflex"))
+        }
+    };
+
+    loop {
+        ensemble = match prune_weakest_link(&ensemble, data, loss) {
+            Some(pruned) => pruned,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("even fully pruned the reflex exceeds {} bytes", max_bytes),
+                ))
+            }
+        };
+        let mut pruned = reflex.clone();
+        match model_type {
+            ModelType::DecisionTree => pruned.trees = ensemble.trees.clone(),
+            _ => {
+                fold_constant_trees(&mut ensemble);
+                pruned.ensembles = vec![ensemble.clone()];
+            }
+        }
+        if pruned.to_bytes()?.len() <= max_bytes {
+            return Ok(pruned);
+        }
+    }
+}
+
+/// A split that could be collapsed, and what collapsing it costs
+struct Link {
+    node: usize,
+    alpha: f64, // loss added per leaf removed
+    value: f32, // leaf value if collapsed
+}
+
+/// Loss and leaf count of the subtree at `idx`, recording its weakest link
+fn walk(
+    tree: &[TreeNode],
+    idx: usize,
+    rows: Vec<usize>,
+    data: &Dataset,
+    target: &[f32],
+    loss: Loss,
+    weakest: &mut Option<Link>,
+) -> (f64, usize) {
+    let node = tree[idx];
+    let cost = |value: f32| -> f64 { rows.iter().map(|&r| loss.value(target[r], value)).sum() };
+    if node.is_leaf() {
+        return (cost(node.threshold), 1);
+    }
+
+    // Rows no longer reach this node: keep the leftmost leaf's value
+    let value = if rows.is_empty() {
+        leftmost_leaf(tree, idx)
+    } else {
+        loss.optimum(&mut rows.iter().map(|&r| target[r]).collect::<Vec<_>>())
+    };
+    let collapsed = cost(value);
+    let (left, right): (Vec<usize>, Vec<usize>) = rows
+        .into_iter()
+        .partition(|&r| data.features[r][node.feature_idx as usize] <= node.threshold);
+    let (left_loss, left_leaves) =
+        walk(tree, node.left as usize, left, data, target, loss, weakest);
+    let (right_loss, right_leaves) = walk(
+        tree,
+        node.right as usize,
+        right,
+        data,
+        target,
+        loss,
+        weakest,
+    );
+
+    let (subtree, leaves) = (left_loss + right_loss, left_leaves + right_leaves);
+    let alpha = (collapsed - subtree).max(0.0) / (leaves - 1) as f64;
+    if weakest.as_ref().is_none_or(|w| alpha < w.alpha) {
+        *weakest = Some(Link {
+            node: idx,
+            alpha,
+            value,
+        });
+    }
+    (subtree, leaves)
+}
+
+fn leftmost_leaf(tree: &[TreeNode], mut idx: usize) -> f32 {
+    while !tree[idx].is_leaf() {
+        idx = tree[idx].left as usize;
+    }
+    tree[idx].threshold
+}
+
+/// `tree` with the subtree at `at` replaced by a leaf, renumbered in preorder
+fn collapse(tree: &[TreeNode], at: usize, value: f32) -> Vec<TreeNode> {
+    fn emit(tree: &[TreeNode], idx: usize, at: usize, value: f32, out: &mut Vec<TreeNode>) -> u16 {
+        let new = out.len();
+        if idx == at {
+            out.push(TreeNode::leaf(value));
+        } else {
+            out.push(tree[idx]);
+            if !tree[idx].is_leaf() {
+                out[new].left = emit(tree, tree[idx].left as usize, at, value, out);
+                out[new].right = emit(tree, tree[idx].right as usize, at, value, out);
+            }
+        }
+        new as u16
+    }
+    let mut out = Vec::new();
+    emit(tree, 0, at, value, &mut out);
+    out
+}
+
+/// Move single-leaf trees into the base, keeping at least one tree
+fn fold_constant_trees(ensemble: &mut TreeEnsemble) {
+    while ensemble.trees.len() > 1 {
+        let Some(t) = ensemble.trees.iter().position(|tree| tree.len() == 1) else {
+            break;
+        };
+        ensemble.base += ensemble.scale * ensemble.trees.remove(t)[0].threshold;
+    }
+}
+
+fn unsupported(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        build_ensemble_reflex, build_reflex, mean_absolute_error, training_metadata,
+        BoostingTrainer, CartTrainer,
+    };
+    use reflex_format::OutputBounds;
+
+    /// Steps of 1 every 0.1 along feature 0, plus a small bump on feature 1
+    fn steps() -> Dataset {
+        let features: Vec<Vec<f32>> = (0..400)
+            .map(|i| vec![(i % 100) as f32 / 100.0, (i / 100) as f32 / 4.0])
+            .collect();
+        let targets = features
+            .iter()
+            .map(|r| 2.0 + (r[0] * 10.0).floor() + 0.2 * r[1])
+            .collect();
+        Dataset::new(features, targets).unwrap()
+    }
+
+    fn bounds() -> OutputBounds {
+        OutputBounds {
+            min: vec![1.0],
+            max: vec![64.0],
+        }
+    }
+
+    #[test]
+    fn test_weakest_links_go_first() {
+        let data = steps();
+        let tree = CartTrainer::new()
+            .with_max_depth(6)
+            .with_min_samples_leaf(5)
+            .fit(&data);
+        let full = build_reflex(
+            vec![tree],
+            2,
+            bounds(),
+            training_metadata("v1", "", 0.0, ""),
+        )
+        .unwrap();
+        let full_bytes = full.to_bytes().unwrap().len();
+        let mae = |reflex: &Reflex| mean_absolute_error(&data, |row| reflex.infer(row)[0]);
+
+        let pruned = prune_to_bytes(&full, &data, Loss::Squared, full_bytes / 3).unwrap();
+        assert!(pruned.to_bytes().unwrap().len() <= full_bytes / 3);
+        // The feature-1 bump splits go before the feature-0 steps
+        assert!(mae(&pruned) < 0.2, "{}", mae(&pruned));
+        assert!(mae(&pruned) >= mae(&full));
+        let splits_on = |reflex: &Reflex, feature| {
+            let nodes = reflex.trees[0].iter();
+            nodes
+                .filter(|n| !n.is_leaf() && n.feature_idx == feature)
+                .count()
+        };
+        assert_eq!(splits_on(&pruned, 0), 9);
+        assert!(splits_on(&pruned, 1) < splits_on(&full, 1) / 2);
+
+        assert!(prune_to_bytes(&full, &data, Loss::Squared, 100).is_err());
+    }
+
+    #[test]
+    fn test_ensembles_fold_pruned_trees_into_the_base() {
+        let data = steps();
+        let ensemble = BoostingTrainer::new()
+            .with_max_rounds(30)
+            .with_learning_rate(0.3)
+            .fit(&data);
+        let full = build_ensemble_reflex(
+            vec![ensemble],
+            2,
+            bounds(),
+            training_metadata("v1", "", 0.0, ""),
+        )
+        .unwrap();
+        let budget = full.to_bytes().unwrap().len() / 3;
+        let pruned = prune_to_bytes(&full, &data, Loss::Squared, budget).unwrap();
+        assert!(pruned.to_bytes().unwrap().len() <= budget);
+        assert!(pruned.ensembles[0].trees.len() < full.ensembles[0].trees.len());
+        let mae = mean_absolute_error(&data, |row| pruned.infer(row)[0]);
+        assert!(mae < 1.0, "{}", mae);
+    }
+}
@@ -0,0 +1,192 @@
+//! Post-training calibration against the sweep's measured optimum
+//!
+//! A model can rank workloads correctly and still sit off the optimum by
+//! a bias that varies across the output range: regularized trees shrink
+//! toward the mean, so light workloads get too many workers and heavy
+//! ones too few. [`calibrate`] averages the reflex's prediction over each
+//! workload cell of a sweep, pairs it with the cell's measured optimum,
+//! and fits a monotone correction by isotonic regression. The correction
+//! ships in the reflex as an output transform, so it can't reorder what
+//! the model learned, only move it onto the measured scale.
+
+use crate::Dataset;
+use reflex_format::{Calibration, Reflex};
+use std::collections::BTreeMap;
+use std::io;
+
+/// One workload cell's mean prediction against its measured optimum
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellComparison {
+    pub cell: String,
+    pub rows: usize,
+    pub prediction: f32, // mean clamped model output over the cell's rows
+    pub optimum: f32,    // mean target over the cell's rows
+}
+
+/// Per-cell mean of `predict` and of the targets, cells in name order
+pub fn compare_cells(
+    data: &Dataset,
+    cells: &[String],
+    predict: impl Fn(&[f32]) -> f32,
+) -> Vec<CellComparison> {
+    assert_eq!(cells.len(), data.len(), "one cell per row");
+    let mut sums: BTreeMap<&str, (usize, f64, f64)> = BTreeMap::new();
+    for ((row, &target), cell) in data.features.iter().zip(&data.targets).zip(cells) {
+        let entry = sums.entry(cell).or_default();
+        entry.0 += 1;
+        entry.1 += predict(row) as f64;
+        entry.2 += target as f64;
+    }
+    sums.into_iter()
+        .map(|(cell, (rows, prediction, optimum))| CellComparison {
+            cell: cell.to_string(),
+            rows,
+            prediction: (prediction / rows as f64) as f32,
+            optimum: (optimum / rows as f64) as f32,
+        })
+        .collect()
+}
+
+/// Row-weighted mean absolute gap between prediction and optimum
+pub fn cell_gap(comparisons: &[CellComparison]) -> f64 {
+    let rows: usize = comparisons.iter().map(|c| c.rows).sum();
+    if rows == 0 {
+        return 0.0;
+    }
+    let total: f64 = comparisons
+        .iter()
+        .map(|c| c.rows as f64 * (c.prediction - c.optimum).abs() as f64)
+        .sum();
+    total / rows as f64
+}
+
+/// Weighted isotonic (non-decreasing) fit of `y` on `x` by pool-adjacent-violators
+///
+/// `points` are `(x, y, weight)`. Each pooled block becomes one knot at its
+/// weighted mean `x` and `y`, so the result interpolates between blocks.
+pub fn fit_isotonic(points: &[(f32, f32, f64)]) -> Calibration {
+    let mut sorted: Vec<(f32, f32, f64)> = points
+        .iter()
+        .copied()
+        .filter(|&(_, _, weight)| weight > 0.0)
+        .collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    // Blocks of (weight, weighted x sum, weighted y sum); equal x starts pooled
+    let mut blocks: Vec<(f64, f64, f64)> = Vec::new();
+    let mut last_x = None;
+    for (x, y, weight) in sorted {
+        let point = (weight, weight * x as f64, weight * y as f64);
+        if last_x == Some(x) {
+            let block = blocks.last_mut().expect("x seen before");
+            *block = (block.0 + point.0, block.1 + point.1, block.2 + point.2);
+        } else {
+            blocks.push(point);
+        }
+        last_x = Some(x);
+        while blocks.len() >= 2 {
+            let (b, a) = (blocks[blocks.len() - 1], blocks[blocks.len() - 2]);
+            if a.2 / a.0 <= b.2 / b.0 {
+                break;
+            }
+            blocks.pop();
+            *blocks.last_mut().expect("two blocks") = (a.0 + b.0, a.1 + b.1, a.2 + b.2);
+        }
+    }
+
+    let mut calibration = Calibration {
+        x: Vec::with_capacity(blocks.len()),
+        y: Vec::with_capacity(blocks.len()),
+    };
+    for (weight, x_sum, y_sum) in blocks {
+        calibration.x.push((x_sum / weight) as f32);
+        calibration.y.push((y_sum / weight) as f32);
+    }
+    calibration
+}
+
+/// `reflex` with an isotonic output correction fitted per workload cell
+///
+/// `data` holds the normalized sweep rows and `cells` each row's workload
+/// cell. Any existing correction is replaced, and the fit compares raw
+/// (clamped) outputs. Returns the calibrated reflex and the per-cell
+/// comparison it was fitted on.
+pub fn calibrate(
+    reflex: &Reflex,
+    data: &Dataset,
+    cells: &[String],
+) -> io::Result<(Reflex, Vec<CellComparison>)> {
+    if reflex.header.output_count != 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "calibration needs a single-output reflex",
+        ));
+    }
+    if cells.len() != data.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} cells for {} rows", cells.len(), data.len()),
+        ));
+    }
+    let mut raw = reflex.clone();
+    raw.metadata.calibration.clear();
+    let comparisons = compare_cells(data, cells, |row| raw.infer(row)[0]);
+    let points: Vec<(f32, f32, f64)> = comparisons
+        .iter()
+        .map(|c| (c.prediction, c.optimum, c.rows as f64))
+        .collect();
+    raw.metadata.calibration = vec![fit_isotonic(&points)];
+    Ok((raw, comparisons))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_linear_reflex, training_metadata};
+    use reflex_format::{LinearModel, OutputBounds};
+
+    #[test]
+    fn test_isotonic_pools_violators() {
+        let fit = fit_isotonic(&[
+            (1.0, 2.0, 1.0),
+            (2.0, 6.0, 1.0),
+            (3.0, 4.0, 1.0),
+            (4.0, 8.0, 2.0),
+            (4.0, 10.0, 0.0),
+        ]);
+        assert_eq!(fit.x, [1.0, 2.5, 4.0]);
+        assert_eq!(fit.y, [2.0, 5.0, 8.0]);
+    }
+
+    #[test]
+    fn test_calibration_closes_the_cell_gap() {
+        // The model halves the distance from 10 in every cell; the optimum doesn't
+        let features: Vec<Vec<f32>> = (0..60).map(|i| vec![(i / 10) as f32 / 5.0]).collect();
+        let targets: Vec<f32> = features.iter().map(|r| 4.0 + 12.0 * r[0]).collect();
+        let cells: Vec<String> = (0..60).map(|i| format!("cell{}", i / 10)).collect();
+        let data = Dataset::new(features, targets).unwrap();
+        let reflex = build_linear_reflex(
+            vec![LinearModel {
+                weights: vec![6.0],
+                bias: 7.0,
+            }],
+            1,
+            OutputBounds {
+                min: vec![1.0],
+                max: vec![64.0],
+            },
+            training_metadata("v1", "", 0.0, ""),
+        )
+        .unwrap();
+
+        let (calibrated, before) = calibrate(&reflex, &data, &cells).unwrap();
+        assert_eq!(before.len(), 6);
+        assert!(cell_gap(&before) > 1.0);
+        let after = compare_cells(&data, &cells, |row| calibrated.infer(row)[0]);
+        assert!(cell_gap(&after) < 1e-4, "{}", cell_gap(&after));
+        let reloaded = Reflex::from_bytes(&calibrated.to_bytes().unwrap()).unwrap();
+        assert_eq!(reloaded.infer(&[0.0])[0], 4.0);
+
+        assert!(calibrate(&reflex, &data, &cells[1..]).is_err());
+    }
+}
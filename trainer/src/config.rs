@@ -0,0 +1,1012 @@
+//! Training configs and the end-to-end pipeline behind the `train` binary
+//!
+//! A [`TrainConfig`] is a YAML file naming the model and its
+//! hyperparameters. [`TrainConfig::train`] takes raw (unnormalized)
+//! compute-v1 rows, holds out the latest rows for validation, fits the
+//! normalizer on the training rows only, fits the model, checks the
+//! normalizer can feed it, and returns a reflex carrying a training
+//! report, the schema and config hashes, and optionally the normalizer. [`TrainConfig::retrain`] instead
+//! warm-starts from an existing reflex and its normalizer. With
+//! `max_model_bytes` set, either prunes the model until the whole file,
+//! report included, fits. [`TrainConfig::train_with_regimes`] takes each
+//! row's workload regime too, holds out the latest rows of every regime,
+//! optionally trains regime by regime along the config's `curriculum`, and
+//! reports validation error per regime. A `multi_tree` config predicts
+//! `target` and its `extra_targets` from one tree through
+//! [`TrainConfig::train_multi`].
+
+use crate::split::latest_rows;
+use crate::{
+    build_ensemble_reflex, build_linear_reflex, build_multi_output_reflex, build_reflex,
+    build_table_reflex, check_normalizer, clamping, fit_normalizer, mean_absolute_error,
+    prune_to_bytes, schema_hash, training_metadata, training_report, warm_start, BoostingTrainer,
+    BoundsLearner, CartTrainer, Dataset, DivergenceRecord, ForestTrainer, HardExampleMiner,
+    LabelHysteresis, Loss, PermutationImportance, RidgeTrainer, Scaling, TableTrainer, WarmStart,
+};
+use reflex_format::{
+    Normalization, OutputBounds, Reflex, ReflexMetadata, RegimeMetrics, SizeBudget,
+};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use telemetry_compute::{ComputeTelemetry, Normalizer};
+
+/// Model family to fit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    Tree,
+    Forest,
+    Boosting,
+    Ridge,
+    Table,     // two-feature lookup table
+    MultiTree, // one tree predicting `target` and every `extra_targets` column
+}
+
+/// One training run; every field is optional in YAML
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrainConfig {
+    pub model: ModelKind,
+    pub schema: String,
+    pub target: String,             // dataset column to predict
+    pub extra_targets: Vec<String>, // columns a multi_tree predicts as outputs 1.., after `target`
+    pub max_depth: usize,
+    pub min_samples_leaf: usize,
+    pub n_trees: usize,    // forest
+    pub max_rounds: usize, // boosting
+    pub learning_rate: f32,
+    pub lambda: f64,           // ridge
+    pub smoothness: f32, // tree, forest, boosting: penalty on output changes between adjacent rows
+    pub table_bins: usize, // per feature, lookup table
+    pub quantile: Option<f32>, // pinball loss at this τ instead of squared loss
+    pub label_min_run: usize, // windows a new label must hold before training on it; 1 is off
+    pub label_deadband: f32, // label changes this small are ignored
+    pub validation_fraction: f64,
+    pub min_output: f32,
+    pub max_output: f32,
+    pub learn_bounds: bool, // bounds from the training labels, inside [min_output, max_output]
+    pub bounds_quantile: f32, // learned bounds start at this..1 - this label quantile
+    pub bounds_margin: f32, // and widen by this fraction of that range each way
+    pub scaling: Scaling,   // feature scaling fit on the training rows
+    pub embed_normalizer: bool, // store the normalizer in the reflex's metadata
+    pub hard_example_divergence: f32, // shadow decisions this far off are hard examples
+    pub hard_example_repeats: usize, // copies of each hard example trained on
+    pub max_model_bytes: Option<usize>, // prune until the serialized reflex fits
+    pub curriculum: Vec<String>, // regimes to train on in stages, e.g. [steady, bursty, adversarial]
+    pub seed: u64, // every random draw: bootstrap samples, boosting shuffles, importance permutations
+    pub notes: String,
+}
+
+impl Default for TrainConfig {
+    /// A depth-4 tree on `optimal_n_workers`, as `trainer_compute.py` fits
+    fn default() -> Self {
+        Self {
+            model: ModelKind::Tree,
+            schema: "compute-v1".to_string(),
+            target: "optimal_n_workers".to_string(),
+            extra_targets: Vec::new(),
+            max_depth: 4,
+            min_samples_leaf: 20,
+            n_trees: 50,
+            max_rounds: 200,
+            learning_rate: 0.1,
+            lambda: 1.0,
+            smoothness: 0.0,
+            table_bins: 4,
+            quantile: None,
+            label_min_run: 1,
+            label_deadband: 0.0,
+            validation_fraction: 0.2,
+            min_output: 1.0,
+            max_output: 64.0,
+            learn_bounds: false,
+            bounds_quantile: 0.01,
+            bounds_margin: 0.1,
+            scaling: Scaling::MinMax,
+            embed_normalizer: true,
+            hard_example_divergence: 2.0,
+            hard_example_repeats: 3,
+            max_model_bytes: None,
+            curriculum: Vec::new(),
+            seed: 0,
+            notes: String::new(),
+        }
+    }
+}
+
+/// A fitted reflex and the normalizer its inputs need
+#[derive(Debug, Clone)]
+pub struct Trained {
+    pub reflex: Reflex,
+    pub normalizer: Normalizer,
+    pub validation_mae: f64, // 0 without validation rows
+}
+
+/// One regime's report entry and its rows among the validation rows
+struct RegimeRows {
+    metrics: RegimeMetrics,
+    valid_rows: Vec<usize>,
+}
+
+impl Trained {
+    /// Raw compute-v1 rows in the space this reflex reads
+    pub fn normalize(&self, raw: &Dataset) -> Dataset {
+        normalize(raw, &self.normalizer)
+    }
+}
+
+impl TrainConfig {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_yaml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_yaml(text: &str) -> io::Result<Self> {
+        let config: Self = serde_yaml::from_str(text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// CRC32 of the config with every default filled in, as hex
+    ///
+    /// Equal for configs that train the same way, however they were written.
+    pub fn hash(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("config serializes");
+        format!("{:08x}", crc32fast::hash(&bytes))
+    }
+
+    fn validate(&self) -> io::Result<()> {
+        let problem = if !(0.0..1.0).contains(&self.validation_fraction) {
+            "validation_fraction must be in [0, 1)"
+        } else if self.min_output > self.max_output {
+            "min_output is above max_output"
+        } else if !(0.0..0.5).contains(&self.bounds_quantile) || self.bounds_margin < 0.0 {
+            "bounds_quantile must be in [0, 0.5) and bounds_margin non-negative"
+        } else if self.quantile.is_some_and(|tau| !(tau > 0.0 && tau < 1.0)) {
+            "quantile must be in (0, 1)"
+        } else if self.label_min_run == 0 || self.label_deadband < 0.0 {
+            "label_min_run must be at least 1 and label_deadband non-negative"
+        } else if self.smoothness < 0.0
+            || (self.smoothness > 0.0
+                && !matches!(
+                    self.model,
+                    ModelKind::Tree | ModelKind::Forest | ModelKind::Boosting
+                ))
+        {
+            "smoothness must be non-negative, and applies to tree, forest and boosting models"
+        } else if self.table_bins == 0 {
+            "table_bins must be at least 1"
+        } else if !self.curriculum.is_empty()
+            && matches!(self.model, ModelKind::Ridge | ModelKind::Table)
+        {
+            "curriculum training needs a tree, forest or boosting model"
+        } else if (self.model == ModelKind::MultiTree) == self.extra_targets.is_empty() {
+            "extra_targets must be set for a multi_tree model, and only for one"
+        } else if self.model == ModelKind::MultiTree
+            && (!self.curriculum.is_empty() || self.max_model_bytes.is_some())
+        {
+            "multi_tree models can't be trained by curriculum or pruned to max_model_bytes"
+        } else if self.max_depth == 0 && self.model != ModelKind::Ridge {
+            "max_depth must be at least 1"
+        } else {
+            return Ok(());
+        };
+        Err(io::Error::new(io::ErrorKind::InvalidInput, problem))
+    }
+
+    /// Fit a reflex to raw compute-v1 rows, in time order
+    pub fn train(&self, raw: &Dataset) -> io::Result<Trained> {
+        self.train_with_hard_examples(raw, &[])
+    }
+
+    /// [`TrainConfig::train`], with hard shadow-mode decisions oversampled into the training rows
+    ///
+    /// Only the training rows get the extra copies; validation stays the
+    /// latest raw rows. Label hysteresis runs first, on every row, so
+    /// training and validation see the same smoothed labels.
+    pub fn train_with_hard_examples(
+        &self,
+        raw: &Dataset,
+        divergences: &[DivergenceRecord],
+    ) -> io::Result<Trained> {
+        self.validate()?;
+        check_rows(raw)?;
+        let raw = &self.hysteresis().smooth_dataset(raw);
+
+        let n_valid = (raw.len() as f64 * self.validation_fraction) as usize;
+        let (train_rows, valid_rows) = latest_rows(raw, n_valid, 0);
+        let train_raw = self
+            .miner()
+            .oversample(&raw.subset(&train_rows), divergences)?;
+        let normalizer = fit_normalizer(&train_raw, self.scaling)?;
+        let data = normalize(raw, &normalizer);
+        let (train, valid) = (normalize(&train_raw, &normalizer), data.subset(&valid_rows));
+
+        let reflex = self.fit(&train, self.metadata(&data, &normalizer))?;
+        check_normalizer(&reflex, &normalizer)?;
+        self.evaluate_within_budget(reflex, &train, &valid, normalizer, &[])
+    }
+
+    /// [`TrainConfig::train`] on rows tagged with their workload regime, scored per regime
+    ///
+    /// Each regime holds out its own latest rows, so a rare regime is
+    /// validated even when the pooled latest rows miss it. With a
+    /// `curriculum`, the model is fit on the first regime alone and then
+    /// warm-started on each later regime added to the rows before it, so
+    /// rare regimes get their own rounds instead of being averaged into a
+    /// pooled fit; every regime must be in the curriculum. Without one, the
+    /// fit is pooled. Either way the normalizer is fit on all training rows
+    /// and the report gets one [`RegimeMetrics`] per regime.
+    pub fn train_with_regimes(&self, raw: &Dataset, regimes: &[String]) -> io::Result<Trained> {
+        self.validate()?;
+        check_rows(raw)?;
+        if regimes.len() != raw.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} regimes for {} rows", regimes.len(), raw.len()),
+            ));
+        }
+        let order: Vec<&str> = if self.curriculum.is_empty() {
+            let mut order: Vec<&str> = regimes.iter().map(String::as_str).collect();
+            order.sort();
+            order.dedup();
+            order
+        } else {
+            if let Some(stray) = regimes.iter().find(|r| !self.curriculum.contains(r)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("regime `{}` is not in the curriculum", stray),
+                ));
+            }
+            self.curriculum.iter().map(String::as_str).collect()
+        };
+        let raw = &self.hysteresis().smooth_dataset(raw);
+
+        // Per regime: (training rows, validation rows), indices into `raw`
+        let mut splits = Vec::with_capacity(order.len());
+        for regime in &order {
+            let rows: Vec<usize> = (0..raw.len()).filter(|&i| regimes[i] == *regime).collect();
+            if rows.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("no rows for curriculum regime `{}`", regime),
+                ));
+            }
+            let n_valid = (rows.len() as f64 * self.validation_fraction) as usize;
+            let (train, valid) = latest_rows(&raw.subset(&rows), n_valid, 0);
+            splits.push((
+                train.iter().map(|&i| rows[i]).collect::<Vec<usize>>(),
+                valid.iter().map(|&i| rows[i]).collect::<Vec<usize>>(),
+            ));
+        }
+        let mut train_rows: Vec<usize> =
+            splits.iter().flat_map(|(train, _)| train.clone()).collect();
+        let mut valid_rows: Vec<usize> =
+            splits.iter().flat_map(|(_, valid)| valid.clone()).collect();
+        train_rows.sort_unstable();
+        valid_rows.sort_unstable();
+        let normalizer = fit_normalizer(&raw.subset(&train_rows), self.scaling)?;
+        let data = normalize(raw, &normalizer);
+        let (train, valid) = (data.subset(&train_rows), data.subset(&valid_rows));
+        let metadata = self.metadata(&data, &normalizer);
+
+        let staged = !self.curriculum.is_empty();
+        let reflex = if staged {
+            let mut seen: Vec<usize> = Vec::new();
+            let mut reflex: Option<Reflex> = None;
+            for (stage_train, _) in &splits {
+                seen.extend(stage_train);
+                seen.sort_unstable();
+                let rows = data.subset(&seen);
+                reflex = Some(match reflex {
+                    None => self.fit(&rows, metadata.clone())?,
+                    Some(parent) => {
+                        let method = if self.model == ModelKind::Boosting {
+                            WarmStart::AppendRounds(self.boosting_trainer())
+                        } else {
+                            WarmStart::RefitLeaves(self.loss())
+                        };
+                        warm_start(&parent, &rows, &method)?
+                    }
+                });
+            }
+            let mut reflex = reflex.expect("at least one regime");
+            // One fresh model, not a chain of warm starts
+            reflex.metadata = metadata;
+            reflex.bounds = self.output_bounds(&train)?;
+            reflex
+        } else {
+            self.fit(&train, metadata)?
+        };
+        check_normalizer(&reflex, &normalizer)?;
+
+        let regime_rows: Vec<RegimeRows> = order
+            .iter()
+            .zip(&splits)
+            .enumerate()
+            .map(|(stage, (regime, (stage_train, stage_valid)))| RegimeRows {
+                metrics: RegimeMetrics {
+                    regime: regime.to_string(),
+                    stage: staged.then_some(stage),
+                    train_rows: stage_train.len(),
+                    validation_rows: stage_valid.len(),
+                    mae: 0.0,
+                },
+                valid_rows: stage_valid
+                    .iter()
+                    .map(|row| valid_rows.binary_search(row).expect("held out"))
+                    .collect(),
+            })
+            .collect();
+        self.evaluate_within_budget(reflex, &train, &valid, normalizer, &regime_rows)
+    }
+
+    /// Fit a `multi_tree` reflex to raw compute-v1 rows and their `extra_targets` columns
+    ///
+    /// `extra_targets` holds one column per configured name, indexed like
+    /// `raw`'s rows; `raw.targets` is output 0. Label hysteresis smooths
+    /// every column. Output 0 takes the configured bounds, the others
+    /// bounds learned from their training labels. The training report
+    /// scores output 0.
+    pub fn train_multi(&self, raw: &Dataset, extra_targets: &[Vec<f32>]) -> io::Result<Trained> {
+        self.validate()?;
+        check_rows(raw)?;
+        if self.model != ModelKind::MultiTree {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "train_multi needs a multi_tree model",
+            ));
+        }
+        if extra_targets.len() != self.extra_targets.len()
+            || extra_targets.iter().any(|column| column.len() != raw.len())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "need {} extra target columns of {} rows",
+                    self.extra_targets.len(),
+                    raw.len()
+                ),
+            ));
+        }
+        let hysteresis = self.hysteresis();
+        let raw = &hysteresis.smooth_dataset(raw);
+        let extra_targets: Vec<Vec<f32>> = extra_targets
+            .iter()
+            .map(|column| hysteresis.smooth(column))
+            .collect();
+
+        let n_valid = (raw.len() as f64 * self.validation_fraction) as usize;
+        let (train_rows, valid_rows) = latest_rows(raw, n_valid, 0);
+        let normalizer = fit_normalizer(&raw.subset(&train_rows), self.scaling)?;
+        let data = normalize(raw, &normalizer);
+        let (train, valid) = (data.subset(&train_rows), data.subset(&valid_rows));
+
+        let mut columns = vec![train.targets.clone()];
+        columns.extend(
+            extra_targets
+                .iter()
+                .map(|column| train_rows.iter().map(|&r| column[r]).collect::<Vec<f32>>()),
+        );
+        let tree = self.tree_trainer().fit_multi(&train.features, &columns);
+        let mut bounds = self.output_bounds(&train)?;
+        for column in &columns[1..] {
+            let (min, max) = BoundsLearner::new()
+                .with_quantile(self.bounds_quantile)
+                .with_margin(self.bounds_margin)
+                .fit(column);
+            bounds.min.push(min);
+            bounds.max.push(max);
+        }
+        let reflex = build_multi_output_reflex(
+            tree,
+            train.feature_count(),
+            bounds,
+            self.metadata(&data, &normalizer),
+        )?;
+        check_normalizer(&reflex, &normalizer)?;
+        self.evaluate_within_budget(reflex, &train, &valid, normalizer, &[])
+    }
+
+    /// Retrain `parent` on fresh raw rows instead of fitting from scratch
+    ///
+    /// Rows are normalized with the parent's `normalizer`. A boosting config
+    /// appends rounds to a boosted parent; otherwise the parent's leaves are
+    /// refit. The child records its parent in its metadata's lineage.
+    pub fn retrain(
+        &self,
+        parent: &Reflex,
+        normalizer: &Normalizer,
+        raw: &Dataset,
+        divergences: &[DivergenceRecord],
+    ) -> io::Result<Trained> {
+        self.validate()?;
+        check_rows(raw)?;
+        let raw = &self.hysteresis().smooth_dataset(raw);
+        let n_valid = (raw.len() as f64 * self.validation_fraction) as usize;
+        let (train_rows, valid_rows) = latest_rows(raw, n_valid, 0);
+        let train_raw = self
+            .miner()
+            .oversample(&raw.subset(&train_rows), divergences)?;
+        let train = normalize(&train_raw, normalizer);
+        let valid = normalize(&raw.subset(&valid_rows), normalizer);
+
+        let method = if self.model == ModelKind::Boosting && !parent.ensembles.is_empty() {
+            WarmStart::AppendRounds(self.boosting_trainer())
+        } else {
+            WarmStart::RefitLeaves(self.loss())
+        };
+        let mut reflex = warm_start(parent, &train, &method)?;
+        if self.learn_bounds {
+            reflex.bounds = self.output_bounds(&train)?;
+        }
+        reflex.metadata.config_hash = self.hash();
+        reflex.metadata.seed = Some(self.seed);
+        check_normalizer(&reflex, normalizer)?;
+        self.evaluate_within_budget(reflex, &train, &valid, normalizer.clone(), &[])
+    }
+
+    /// [`TrainConfig::evaluate`], pruning first if the file would exceed `max_model_bytes`
+    ///
+    /// The report counts toward the budget, so the model is pruned to the
+    /// budget less the report's size and re-evaluated until the file fits.
+    /// The report records the validation MAE before and after.
+    fn evaluate_within_budget(
+        &self,
+        reflex: Reflex,
+        train: &Dataset,
+        valid: &Dataset,
+        normalizer: Normalizer,
+        regimes: &[RegimeRows],
+    ) -> io::Result<Trained> {
+        let Some(max_bytes) = self.max_model_bytes else {
+            return Ok(self.evaluate(reflex, train, valid, normalizer, regimes));
+        };
+        let unpruned = self.evaluate(reflex.clone(), train, valid, normalizer.clone(), regimes);
+        let unpruned_bytes = unpruned.reflex.to_bytes()?.len();
+        let mut model = reflex;
+        let mut trained = unpruned.clone();
+        loop {
+            if let Some(report) = &mut trained.reflex.report {
+                report.size_budget = Some(SizeBudget {
+                    max_bytes,
+                    unpruned_bytes,
+                    unpruned_mae: unpruned.validation_mae,
+                    mae: trained.validation_mae,
+                });
+            }
+            let bytes = trained.reflex.to_bytes()?.len();
+            if bytes <= max_bytes {
+                return Ok(trained);
+            }
+            let model_bytes = model.to_bytes()?.len();
+            let Some(model_budget) = max_bytes.checked_sub(bytes - model_bytes) else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("the training report alone exceeds {} bytes", max_bytes),
+                ));
+            };
+            // Always shrink, in case the report grew since the last pass
+            model = prune_to_bytes(
+                &model,
+                train,
+                self.loss(),
+                model_budget.min(model_bytes - 1),
+            )?;
+            trained = self.evaluate(model.clone(), train, valid, normalizer.clone(), regimes);
+        }
+    }
+
+    /// Attach a training report and score the validation rows, overall and per regime
+    fn evaluate(
+        &self,
+        reflex: Reflex,
+        train: &Dataset,
+        valid: &Dataset,
+        normalizer: Normalizer,
+        regimes: &[RegimeRows],
+    ) -> Trained {
+        let predict = |row: &[f32]| reflex.infer(row)[0];
+        let mut report = training_report(train, valid, predict);
+        report.clamping = clamping(
+            &reflex,
+            if valid.is_empty() { train } else { valid },
+            self.learn_bounds,
+        );
+        report.regimes = regimes
+            .iter()
+            .map(|r| RegimeMetrics {
+                mae: mean_absolute_error(&valid.subset(&r.valid_rows), predict),
+                ..r.metrics.clone()
+            })
+            .collect();
+        if !valid.is_empty() {
+            PermutationImportance::new()
+                .with_seed(self.seed)
+                .measure(valid, predict)
+                .write_report(&mut report, &ComputeTelemetry::feature_names());
+        }
+        let validation_mae = mean_absolute_error(valid, predict);
+        Trained {
+            reflex: reflex.with_report(report),
+            normalizer,
+            validation_mae,
+        }
+    }
+
+    /// Metadata for a reflex fit from scratch on normalized `data`
+    fn metadata(&self, data: &Dataset, normalizer: &Normalizer) -> ReflexMetadata {
+        let mut metadata = training_metadata(
+            &self.schema,
+            &data.fingerprint(),
+            match self.model {
+                ModelKind::Ridge => self.lambda as f32,
+                ModelKind::Tree | ModelKind::Forest | ModelKind::Boosting => self.smoothness,
+                ModelKind::Table | ModelKind::MultiTree => 0.0,
+            },
+            &self.notes,
+        );
+        metadata.schema_hash = schema_hash(&ComputeTelemetry::feature_names());
+        metadata.config_hash = self.hash();
+        metadata.seed = Some(self.seed);
+        if self.embed_normalizer {
+            metadata.normalizer = Some(Normalization {
+                min: normalizer.min.to_vec(),
+                max: normalizer.max.to_vec(),
+            });
+        }
+        metadata
+    }
+
+    /// Hysteresis applied to the raw labels before they're split
+    pub fn hysteresis(&self) -> LabelHysteresis {
+        LabelHysteresis::new()
+            .with_min_run(self.label_min_run)
+            .with_deadband(self.label_deadband)
+    }
+
+    fn miner(&self) -> HardExampleMiner {
+        HardExampleMiner::new()
+            .with_min_divergence(self.hard_example_divergence)
+            .with_repeats(self.hard_example_repeats)
+    }
+
+    fn loss(&self) -> Loss {
+        self.quantile.map_or(Loss::Squared, Loss::Quantile)
+    }
+
+    fn tree_trainer(&self) -> CartTrainer {
+        CartTrainer::new()
+            .with_max_depth(self.max_depth)
+            .with_min_samples_leaf(self.min_samples_leaf)
+            .with_smoothness(self.smoothness)
+            .with_loss(self.loss())
+    }
+
+    fn boosting_trainer(&self) -> BoostingTrainer {
+        BoostingTrainer::new()
+            .with_max_rounds(self.max_rounds)
+            .with_learning_rate(self.learning_rate)
+            .with_loss(self.loss())
+            .with_tree(self.tree_trainer())
+            .with_seed(self.seed)
+    }
+
+    /// Configured bounds, or bounds learned from `train`'s labels clipped to them
+    fn output_bounds(&self, train: &Dataset) -> io::Result<OutputBounds> {
+        if !self.learn_bounds {
+            return Ok(OutputBounds {
+                min: vec![self.min_output],
+                max: vec![self.max_output],
+            });
+        }
+        let (min, max) = BoundsLearner::new()
+            .with_quantile(self.bounds_quantile)
+            .with_margin(self.bounds_margin)
+            .fit(&train.targets);
+        let (min, max) = (min.max(self.min_output), max.min(self.max_output));
+        if min > max {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "labels lie outside [min_output, max_output]",
+            ));
+        }
+        Ok(OutputBounds {
+            min: vec![min],
+            max: vec![max],
+        })
+    }
+
+    fn fit(&self, train: &Dataset, metadata: ReflexMetadata) -> io::Result<Reflex> {
+        let tree = self.tree_trainer();
+        let bounds = self.output_bounds(train)?;
+        let features = train.feature_count();
+        match self.model {
+            ModelKind::Tree => build_reflex(vec![tree.fit(train)], features, bounds, metadata),
+            ModelKind::Forest => {
+                let forest = ForestTrainer::new()
+                    .with_n_trees(self.n_trees)
+                    .with_tree(tree)
+                    .with_seed(self.seed)
+                    .fit(train);
+                build_ensemble_reflex(vec![forest], features, bounds, metadata)
+            }
+            ModelKind::Boosting => {
+                let ensemble = self.boosting_trainer().fit(train);
+                build_ensemble_reflex(vec![ensemble], features, bounds, metadata)
+            }
+            ModelKind::Ridge => {
+                let model = RidgeTrainer::new().with_lambda(self.lambda).fit(train);
+                build_linear_reflex(vec![model], features, bounds, metadata)
+            }
+            ModelKind::Table => {
+                let table = TableTrainer::new()
+                    .with_bins(self.table_bins)
+                    .with_loss(self.loss())
+                    .fit(train);
+                build_table_reflex(vec![table], features, bounds, metadata)
+            }
+            ModelKind::MultiTree => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "multi_tree models train through TrainConfig::train_multi",
+            )),
+        }
+    }
+}
+
+/// Rows must be compute-v1 and there must be enough to split
+fn check_rows(raw: &Dataset) -> io::Result<()> {
+    if raw.feature_count() != ComputeTelemetry::FEATURE_COUNT {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} features per row, compute-v1 has {}",
+                raw.feature_count(),
+                ComputeTelemetry::FEATURE_COUNT
+            ),
+        ));
+    }
+    if raw.len() < 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "need at least two rows",
+        ));
+    }
+    Ok(())
+}
+
+/// `data` with every row scaled by `normalizer`
+fn normalize(data: &Dataset, normalizer: &Normalizer) -> Dataset {
+    Dataset {
+        features: data
+            .features
+            .iter()
+            .map(|row| normalizer.normalize(&compute_row(row)).to_vec())
+            .collect(),
+        ..data.clone()
+    }
+}
+
+fn compute_row(row: &[f32]) -> [f32; ComputeTelemetry::FEATURE_COUNT] {
+    row.try_into()
+        .expect("row width checked against compute-v1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Raw rows where the best N grows with the run queue
+    fn raw_sweep() -> Dataset {
+        let features: Vec<Vec<f32>> = (0..300)
+            .map(|i| {
+                let mut row = vec![0.0; ComputeTelemetry::FEATURE_COUNT];
+                row[0] = (i % 60) as f32; // runq_len
+                row[1] = 1000.0 + (i * 17 % 500) as f32; // arrival_rate, noise
+                row
+            })
+            .collect();
+        let targets = features
+            .iter()
+            .map(|r| 2.0 + (r[0] / 4.0).floor())
+            .collect();
+        Dataset::new(features, targets).unwrap()
+    }
+
+    #[test]
+    fn test_yaml_defaults_and_hash() {
+        let config = TrainConfig::from_yaml("model: forest\nn_trees: 10\n").unwrap();
+        assert_eq!(config.model, ModelKind::Forest);
+        assert_eq!(config.max_depth, 4);
+        let spelled_out = TrainConfig::from_yaml("model: forest\nn_trees: 10\nmax_depth: 4\n");
+        assert_eq!(config.hash(), spelled_out.unwrap().hash());
+        assert_ne!(config.hash(), TrainConfig::default().hash());
+
+        assert!(TrainConfig::from_yaml("max_dpeth: 3\n").is_err());
+        assert!(TrainConfig::from_yaml("quantile: 1.5\n").is_err());
+    }
+
+    #[test]
+    fn test_train_embeds_normalizer_and_hashes() {
+        let config = TrainConfig::from_yaml("max_depth: 5\nmin_samples_leaf: 5\n").unwrap();
+        let trained = config.train(&raw_sweep()).unwrap();
+        let loaded = Reflex::from_bytes(&trained.reflex.to_bytes().unwrap()).unwrap();
+
+        assert!(trained.validation_mae < 1.0);
+        assert_eq!(loaded.metadata.config_hash, config.hash());
+        assert_eq!(
+            loaded.metadata.schema_hash,
+            schema_hash(&ComputeTelemetry::feature_names())
+        );
+        let embedded = loaded.metadata.normalizer.unwrap();
+        assert_eq!(embedded.max[0], trained.normalizer.max[0]);
+        // Normalizer fit on the first 80% of rows only
+        assert_eq!(trained.normalizer.max[0], 59.0);
+
+        let report = loaded.report.unwrap();
+        assert_eq!((report.train_rows, report.validation_rows), (240, 60));
+        assert_eq!(report.feature_importance[0].feature, "runq_len");
+    }
+
+    #[test]
+    fn test_same_seed_trains_bit_identical_reflexes() {
+        let config = TrainConfig::from_yaml("model: forest\nn_trees: 8\nseed: 11\n").unwrap();
+        let bytes = |config: &TrainConfig| {
+            let mut reflex = config.train(&raw_sweep()).unwrap().reflex;
+            // Only the wall clock may differ between runs
+            reflex.header.created_at_unix = 0;
+            reflex.metadata.created_at.clear();
+            reflex.to_bytes().unwrap()
+        };
+        assert_eq!(bytes(&config), bytes(&config));
+        let reseeded = TrainConfig {
+            seed: 12,
+            ..config.clone()
+        };
+        assert_ne!(bytes(&config), bytes(&reseeded));
+
+        let trained = config.train(&raw_sweep()).unwrap();
+        assert_eq!(trained.reflex.metadata.seed, Some(11));
+        assert_eq!(trained.reflex.metadata.config_hash, config.hash());
+        assert_ne!(config.hash(), reseeded.hash());
+    }
+
+    #[test]
+    fn test_smoothness_trains_trees_and_lands_in_the_metadata_lambda() {
+        for model in ["tree", "forest", "boosting"] {
+            let yaml = format!(
+                "model: {}\nn_trees: 5\nmax_rounds: 20\nsmoothness: 0.5\n",
+                model
+            );
+            let config = TrainConfig::from_yaml(&yaml).unwrap();
+            let trained = config.train(&raw_sweep()).unwrap();
+            let loaded = Reflex::from_bytes(&trained.reflex.to_bytes().unwrap()).unwrap();
+            assert_eq!(loaded.metadata.lambda, 0.5, "{}", model);
+        }
+        let smooth = TrainConfig::from_yaml("smoothness: 50.0\n").unwrap();
+        assert_eq!(smooth.tree_trainer().smoothness, 50.0);
+
+        assert!(TrainConfig::from_yaml("smoothness: -1.0\n").is_err());
+        assert!(TrainConfig::from_yaml("model: ridge\nsmoothness: 1.0\n").is_err());
+    }
+
+    #[test]
+    fn test_learned_bounds_follow_the_labels() {
+        let config = TrainConfig::from_yaml("learn_bounds: true\n").unwrap();
+        let trained = config.train(&raw_sweep()).unwrap();
+        // Labels span 2..=16: 1.4 of margin each way, floored at min_output
+        let bounds = &trained.reflex.bounds;
+        assert_eq!((bounds.min[0], bounds.max[0]), (1.0, 17.4));
+        let clamping = &trained.reflex.report.as_ref().unwrap().clamping[0];
+        assert!(clamping.learned);
+        assert_eq!(clamping.rows, 60);
+
+        let fixed = TrainConfig::default().train(&raw_sweep()).unwrap();
+        assert_eq!(fixed.reflex.bounds.max[0], 64.0);
+        assert!(!fixed.reflex.report.unwrap().clamping[0].learned);
+        assert!(TrainConfig::from_yaml("bounds_quantile: 0.5\n").is_err());
+    }
+
+    #[test]
+    fn test_table_model_reads_the_run_queue() {
+        let config = TrainConfig::from_yaml("model: table\ntable_bins: 8\n").unwrap();
+        let trained = config.train(&raw_sweep()).unwrap();
+        let loaded = Reflex::from_bytes(&trained.reflex.to_bytes().unwrap()).unwrap();
+        let table = &loaded.tables[0];
+        assert_eq!(table.row_feature, 0);
+        assert_eq!(table.values.len(), 8);
+        assert!(trained.validation_mae < 2.0, "{}", trained.validation_mae);
+        assert!(TrainConfig::from_yaml("model: table\ntable_bins: 0\n").is_err());
+    }
+
+    #[test]
+    fn test_multi_tree_predicts_every_target_from_one_tree() {
+        let config = TrainConfig::from_yaml(
+            "model: multi_tree\nextra_targets: [admit_rate]\nmax_depth: 5\nmin_samples_leaf: 5\n",
+        )
+        .unwrap();
+        let raw = raw_sweep();
+        // Admission falls as the run queue grows
+        let admit: Vec<Vec<f32>> =
+            vec![raw.features.iter().map(|r| 1000.0 - 10.0 * r[0]).collect()];
+        let trained = config.train_multi(&raw, &admit).unwrap();
+        let loaded = Reflex::from_bytes(&trained.reflex.to_bytes().unwrap()).unwrap();
+        assert_eq!(
+            loaded.header.model_type,
+            reflex_format::ModelType::MultiOutputTree as u8
+        );
+        assert_eq!(loaded.header.output_count, 2);
+        assert!(trained.validation_mae < 1.0, "{}", trained.validation_mae);
+        let outputs = loaded.infer(&trained.normalize(&raw).features[100]);
+        assert!((outputs[0] - raw.targets[100]).abs() <= 1.0);
+        assert!((outputs[1] - admit[0][100]).abs() <= 40.0, "{:?}", outputs);
+
+        // The columns must match the config, and only multi_tree takes them
+        assert!(config.train(&raw).is_err());
+        assert!(config.train_multi(&raw, &[]).is_err());
+        assert!(TrainConfig::from_yaml("model: multi_tree\n").is_err());
+        assert!(TrainConfig::from_yaml("extra_targets: [admit_rate]\n").is_err());
+    }
+
+    #[test]
+    fn test_curriculum_trains_in_stages_and_scores_each_regime() {
+        // Every tenth row is bursty: flagged in feature 2 and needing 8 more workers
+        let mut raw = raw_sweep();
+        let mut regimes = vec!["steady".to_string(); raw.len()];
+        for i in (0..raw.len()).step_by(10) {
+            raw.features[i][2] = 1.0;
+            raw.targets[i] += 8.0;
+            regimes[i] = "bursty".to_string();
+        }
+        let config = TrainConfig::from_yaml(
+            "model: boosting\nmax_rounds: 30\nmax_depth: 4\nmin_samples_leaf: 2\ncurriculum: [steady, bursty]\n",
+        )
+        .unwrap();
+        let trained = config.train_with_regimes(&raw, &regimes).unwrap();
+        assert!(trained.reflex.metadata.lineage.is_none());
+        let report = trained.reflex.report.as_ref().unwrap();
+        assert_eq!(report.regimes.len(), 2);
+        let (steady, bursty) = (&report.regimes[0], &report.regimes[1]);
+        assert_eq!((steady.regime.as_str(), steady.stage), ("steady", Some(0)));
+        assert_eq!((bursty.regime.as_str(), bursty.stage), ("bursty", Some(1)));
+        // Each regime holds out its own latest 20%
+        assert_eq!((bursty.train_rows, bursty.validation_rows), (24, 6));
+        assert_eq!((steady.train_rows, steady.validation_rows), (216, 54));
+        assert!(bursty.mae < 2.0, "{:?}", bursty);
+
+        let pooled = TrainConfig {
+            curriculum: Vec::new(),
+            ..config.clone()
+        };
+        let report = pooled
+            .train_with_regimes(&raw, &regimes)
+            .unwrap()
+            .reflex
+            .report;
+        let names: Vec<(&str, Option<usize>)> = report
+            .as_ref()
+            .unwrap()
+            .regimes
+            .iter()
+            .map(|r| (r.regime.as_str(), r.stage))
+            .collect();
+        assert_eq!(names, [("bursty", None), ("steady", None)]);
+
+        regimes[1] = "adversarial".to_string();
+        assert!(config.train_with_regimes(&raw, &regimes).is_err());
+        assert!(config.train_with_regimes(&raw, &regimes[1..]).is_err());
+        assert!(TrainConfig::from_yaml("model: ridge\ncurriculum: [steady]\n").is_err());
+    }
+
+    #[test]
+    fn test_label_hysteresis_smooths_before_training() {
+        // One-window blips to 40 workers every 25 rows
+        let mut noisy = raw_sweep();
+        for target in noisy.targets.iter_mut().step_by(25) {
+            *target = 40.0;
+        }
+        let config = TrainConfig::from_yaml("max_depth: 8\nmin_samples_leaf: 1\n").unwrap();
+        let smoothed = TrainConfig {
+            label_min_run: 2,
+            ..config.clone()
+        };
+        assert_ne!(config.hash(), smoothed.hash());
+        assert!(TrainConfig::from_yaml("label_min_run: 0\n").is_err());
+
+        let clean = smoothed.train(&raw_sweep()).unwrap();
+        let trained = smoothed.train(&noisy).unwrap();
+        let blip = config.train(&noisy).unwrap();
+        let predict = |trained: &Trained, row: &[f32]| {
+            let row = compute_row(row);
+            trained.reflex.infer(&trained.normalizer.normalize(&row))[0]
+        };
+        assert_eq!(predict(&blip, &noisy.features[25]), 40.0);
+        assert_eq!(
+            predict(&trained, &noisy.features[25]),
+            predict(&clean, &noisy.features[25])
+        );
+    }
+
+    #[test]
+    fn test_max_model_bytes_prunes_and_reports_the_cost() {
+        let config = TrainConfig::from_yaml("max_depth: 8\nmin_samples_leaf: 2\n").unwrap();
+        let full = config.train(&raw_sweep()).unwrap();
+        let full_bytes = full.reflex.to_bytes().unwrap().len();
+
+        let budget = TrainConfig {
+            max_model_bytes: Some(full_bytes * 3 / 4),
+            ..config
+        };
+        let pruned = budget.train(&raw_sweep()).unwrap();
+        assert!(pruned.reflex.to_bytes().unwrap().len() <= full_bytes * 3 / 4);
+        assert!(pruned.reflex.trees[0].len() < full.reflex.trees[0].len());
+        let cost = pruned.reflex.report.unwrap().size_budget.unwrap();
+        assert_eq!(cost.unpruned_mae, full.validation_mae);
+        assert_eq!(cost.mae, pruned.validation_mae);
+        assert_eq!(cost.unpruned_bytes, full_bytes);
+
+        let tiny = TrainConfig {
+            max_model_bytes: Some(100),
+            ..budget
+        };
+        assert!(tiny.train(&raw_sweep()).is_err());
+    }
+
+    #[test]
+    fn test_retrain_keeps_parent_normalizer_and_records_lineage() {
+        let config = TrainConfig::from_yaml("model: boosting\nmax_rounds: 30\n").unwrap();
+        let parent = config.train(&raw_sweep()).unwrap();
+        let mut fresh = raw_sweep();
+        for target in &mut fresh.targets {
+            *target += 3.0;
+        }
+
+        let child = config
+            .retrain(&parent.reflex, &parent.normalizer, &fresh, &[])
+            .unwrap();
+        assert!(child.validation_mae < 1.0);
+        assert_eq!(child.normalizer.max, parent.normalizer.max);
+        let lineage = child.reflex.metadata.lineage.clone().unwrap();
+        assert_eq!(lineage.method, "append_rounds");
+        assert_eq!(lineage.parent_checksum, parent.reflex.checksum().unwrap());
+        assert!(child.reflex.report.is_some());
+    }
+
+    #[test]
+    fn test_hard_examples_pull_the_model_toward_them() {
+        // The candidate kept 16 workers at a run queue the sweep never reached
+        let divergences: Vec<DivergenceRecord> = (0..10)
+            .map(|i| {
+                serde_json::from_value(serde_json::json!({
+                    "telemetry": {
+                        "timestamp_us": 0, "runq_len": 100 + i, "arrival_rate": 1000.0,
+                        "completion_rate": 0.0, "task_time_p50_us": 0.0, "task_time_p95_us": 0.0,
+                        "worker_util": 0.0, "ctx_switches_per_sec": 0.0, "task_size_mean": 0.0,
+                        "task_size_var": 0.0, "idle_worker_count": 0
+                    },
+                    "candidate": 16.0,
+                    "incumbent": 40.0
+                }))
+                .unwrap()
+            })
+            .collect();
+        let config = TrainConfig::from_yaml("max_depth: 5\nmin_samples_leaf: 5\n").unwrap();
+        let plain = config.train(&raw_sweep()).unwrap();
+        let mined = config
+            .train_with_hard_examples(&raw_sweep(), &divergences)
+            .unwrap();
+
+        let mut hard_row = [0.0; ComputeTelemetry::FEATURE_COUNT];
+        hard_row[0] = 105.0;
+        hard_row[1] = 1000.0;
+        let predict = |trained: &Trained| {
+            trained
+                .reflex
+                .infer(&trained.normalizer.normalize(&hard_row))[0]
+        };
+        assert!(predict(&plain) < 20.0);
+        assert!(predict(&mined) > 35.0);
+        assert_eq!(mined.reflex.report.unwrap().train_rows, 240 + 30);
+    }
+}
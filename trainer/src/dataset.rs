@@ -0,0 +1,406 @@
+//! Building training sets from recorded telemetry
+//!
+//! Telemetry logs and sweep labels are separate timelines: windows are
+//! recorded at the policy's cadence, labels wherever the sweep measured an
+//! empirical optimum. [`DatasetBuilder`] pairs each telemetry window with
+//! the label nearest in time, dropping windows with no label within the
+//! tolerance, and emits rows in time order so adjacent rows stay adjacent
+//! for the smoothness penalty.
+
+use crate::Dataset;
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::time::Duration;
+use telemetry_compute::{ComputeTelemetry, Normalizer};
+
+/// A target value observed at a point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Label {
+    pub timestamp_us: u64,
+    pub value: f32,
+}
+
+/// Joined rows, stamped with their telemetry window times, and how many windows found no label
+#[derive(Debug, Clone)]
+pub struct JoinedDataset {
+    pub dataset: Dataset,
+    pub unmatched: usize,
+}
+
+/// Nearest-window join of telemetry with labels
+#[derive(Debug, Clone)]
+pub struct DatasetBuilder {
+    pub tolerance_us: u64,
+    pub normalizer: Option<Normalizer>,
+}
+
+impl DatasetBuilder {
+    /// Half-second tolerance, the sweep's sampling interval
+    pub fn new() -> Self {
+        Self {
+            tolerance_us: 500_000,
+            normalizer: None,
+        }
+    }
+
+    /// Largest gap between a window and its label
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance_us = tolerance.as_micros() as u64;
+        self
+    }
+
+    /// Normalize features as the runtime will (rows are raw compute-v1 features otherwise)
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = Some(normalizer);
+        self
+    }
+
+    /// Pair every window with its nearest label, if one is within the tolerance
+    ///
+    /// Both inputs are one run's timeline; ties between two equally near
+    /// labels go to the earlier one.
+    pub fn join(
+        &self,
+        telemetry: &[ComputeTelemetry],
+        labels: &[Label],
+    ) -> io::Result<JoinedDataset> {
+        let mut labels = labels.to_vec();
+        labels.sort_by_key(|label| label.timestamp_us);
+        let mut windows = telemetry.to_vec();
+        windows.sort_by_key(|window| window.timestamp_us);
+
+        let (mut features, mut targets, mut timestamps_us) = (Vec::new(), Vec::new(), Vec::new());
+        let mut unmatched = 0;
+        for window in &windows {
+            let Some(label) = nearest(&labels, window.timestamp_us) else {
+                unmatched += 1;
+                continue;
+            };
+            if label.timestamp_us.abs_diff(window.timestamp_us) > self.tolerance_us {
+                unmatched += 1;
+                continue;
+            }
+            let raw = window.to_features();
+            let row = match &self.normalizer {
+                Some(normalizer) => normalizer.normalize(&raw),
+                None => raw,
+            };
+            features.push(row.to_vec());
+            targets.push(label.value);
+            timestamps_us.push(window.timestamp_us);
+        }
+        Ok(JoinedDataset {
+            dataset: Dataset::new(features, targets)?.with_timestamps(timestamps_us)?,
+            unmatched,
+        })
+    }
+}
+
+impl Default for DatasetBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Label closest to `timestamp_us` in time-sorted `labels`, earlier on ties
+fn nearest(labels: &[Label], timestamp_us: u64) -> Option<&Label> {
+    let after = labels.partition_point(|label| label.timestamp_us < timestamp_us);
+    let before = after.checked_sub(1).map(|i| &labels[i]);
+    match (before, labels.get(after)) {
+        (Some(b), Some(a)) if a.timestamp_us - timestamp_us < timestamp_us - b.timestamp_us => {
+            Some(a)
+        }
+        (Some(b), _) => Some(b),
+        (None, a) => a,
+    }
+}
+
+/// Read telemetry windows from JSON Lines
+///
+/// Lines are decision-log records (the `telemetry` field is used) or bare
+/// `ComputeTelemetry` objects; blank lines are skipped.
+pub fn read_telemetry_log(path: impl AsRef<Path>) -> io::Result<Vec<ComputeTelemetry>> {
+    let reader = BufReader::new(std::fs::File::open(path)?);
+    let mut windows = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut value: Value = serde_json::from_str(&line).map_err(|e| bad_line(i, e))?;
+        if let Some(telemetry) = value.get_mut("telemetry") {
+            value = telemetry.take();
+        }
+        windows.push(serde_json::from_value(value).map_err(|e| bad_line(i, e))?);
+    }
+    Ok(windows)
+}
+
+/// Read `optimal_n_workers` labels from a `sweep grid` JSON dataset
+///
+/// Every pool size swept reports the same label at the same window times,
+/// so repeated timestamps are collapsed to one label.
+pub fn read_sweep_labels(path: impl AsRef<Path>) -> io::Result<Vec<Label>> {
+    #[derive(Deserialize)]
+    struct SweepRow {
+        telemetry: ComputeTelemetry,
+        optimal_n_workers: u32,
+    }
+
+    let rows: Vec<SweepRow> = serde_json::from_reader(BufReader::new(std::fs::File::open(path)?))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut labels: Vec<Label> = rows
+        .iter()
+        .map(|row| Label {
+            timestamp_us: row.telemetry.timestamp_us,
+            value: row.optimal_n_workers as f32,
+        })
+        .collect();
+    labels.sort_by_key(|label| label.timestamp_us);
+    labels.dedup_by_key(|label| label.timestamp_us);
+    Ok(labels)
+}
+
+/// Read a `sweep grid` dataset (CSV, or JSON for a `.json` path) as raw compute-v1 features and `target`
+///
+/// Rows keep file order, the only clock the dataset has; CSV columns are
+/// found by name.
+pub fn read_sweep_dataset(path: impl AsRef<Path>, target: &str) -> io::Result<Dataset> {
+    let path = path.as_ref();
+    let (features, targets) = if path.extension().is_some_and(|ext| ext == "json") {
+        let rows: Vec<Value> = serde_json::from_reader(BufReader::new(std::fs::File::open(path)?))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut features = Vec::with_capacity(rows.len());
+        let mut targets = Vec::with_capacity(rows.len());
+        for (i, row) in rows.iter().enumerate() {
+            let telemetry: ComputeTelemetry =
+                serde_json::from_value(row["telemetry"].clone()).map_err(|e| bad_line(i, e))?;
+            let value = row[target].as_f64().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("row {}: no numeric {}", i + 1, target),
+                )
+            })?;
+            features.push(telemetry.to_features().to_vec());
+            targets.push(value as f32);
+        }
+        (features, targets)
+    } else {
+        read_csv_columns(path, target)?
+    };
+    Dataset::new(features, targets)
+}
+
+/// Workload cell of every row of a sweep grid dataset, in row order
+///
+/// Rows swept under the same workload (kind, rate, task size and
+/// burstiness) share a cell and its measured optimum.
+pub fn read_sweep_cells(path: impl AsRef<Path>) -> io::Result<Vec<String>> {
+    read_text_columns(
+        path.as_ref(),
+        &[
+            "workload",
+            "workload_rate",
+            "workload_task_us",
+            "workload_burstiness",
+        ],
+    )
+}
+
+/// Workload regime (`steady`, `bursty`, `adversarial`) of every row of a sweep grid dataset
+pub fn read_sweep_regimes(path: impl AsRef<Path>) -> io::Result<Vec<String>> {
+    read_text_columns(path.as_ref(), &["workload"])
+}
+
+/// `columns` of every row as text, joined with `/`
+fn read_text_columns(path: &Path, columns: &[&str]) -> io::Result<Vec<String>> {
+    let missing = |i: usize| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("row {}: no {} column", i + 1, columns.join("/")),
+        )
+    };
+    if path.extension().is_some_and(|ext| ext == "json") {
+        let rows: Vec<Value> = serde_json::from_reader(BufReader::new(std::fs::File::open(path)?))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        return rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let parts: Option<Vec<String>> = columns
+                    .iter()
+                    .map(|name| {
+                        row.get(*name)
+                            .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                    })
+                    .collect();
+                parts.map(|parts| parts.join("/")).ok_or_else(|| missing(i))
+            })
+            .collect();
+    }
+
+    let mut reader = csv::Reader::from_path(path).map_err(io::Error::other)?;
+    let headers = reader.headers().map_err(io::Error::other)?.clone();
+    let indices: Vec<usize> = columns
+        .iter()
+        .map(|name| {
+            headers
+                .iter()
+                .position(|h| h == *name)
+                .ok_or_else(|| missing(0))
+        })
+        .collect::<io::Result<_>>()?;
+    let mut values = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.map_err(io::Error::other)?;
+        let parts: Option<Vec<&str>> = indices.iter().map(|&c| record.get(c)).collect();
+        values.push(parts.ok_or_else(|| missing(i))?.join("/"));
+    }
+    Ok(values)
+}
+
+/// compute-v1 feature columns and the `target` column of a CSV file
+fn read_csv_columns(path: &Path, target: &str) -> io::Result<(Vec<Vec<f32>>, Vec<f32>)> {
+    let mut reader = csv::Reader::from_path(path).map_err(io::Error::other)?;
+    let headers = reader.headers().map_err(io::Error::other)?.clone();
+    let column = |name: &str| {
+        headers.iter().position(|h| h == name).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("no {} column", name))
+        })
+    };
+    let feature_columns = ComputeTelemetry::feature_names()
+        .iter()
+        .map(|name| column(name))
+        .collect::<io::Result<Vec<usize>>>()?;
+    let target_column = column(target)?;
+
+    let (mut features, mut targets) = (Vec::new(), Vec::new());
+    for (i, record) in reader.records().enumerate() {
+        let record = record.map_err(io::Error::other)?;
+        let value = |c: usize| -> io::Result<f32> {
+            record.get(c).and_then(|v| v.parse().ok()).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("row {}: {} is not a number", i + 1, &headers[c]),
+                )
+            })
+        };
+        features.push(
+            feature_columns
+                .iter()
+                .map(|&c| value(c))
+                .collect::<io::Result<Vec<f32>>>()?,
+        );
+        targets.push(value(target_column)?);
+    }
+    Ok((features, targets))
+}
+
+fn bad_line(index: usize, error: serde_json::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {}: {}", index + 1, error),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(timestamp_us: u64, runq_len: u32) -> ComputeTelemetry {
+        let mut telemetry: ComputeTelemetry = serde_json::from_value(serde_json::json!({
+            "timestamp_us": 0, "runq_len": 0, "arrival_rate": 0.0, "completion_rate": 0.0,
+            "task_time_p50_us": 0.0, "task_time_p95_us": 0.0, "worker_util": 0.0,
+            "ctx_switches_per_sec": 0.0, "task_size_mean": 0.0, "task_size_var": 0.0,
+            "idle_worker_count": 0
+        }))
+        .unwrap();
+        telemetry.timestamp_us = timestamp_us;
+        telemetry.runq_len = runq_len;
+        telemetry
+    }
+
+    #[test]
+    fn test_nearest_window_join() {
+        let labels = [
+            Label {
+                timestamp_us: 1_000_000,
+                value: 4.0,
+            },
+            Label {
+                timestamp_us: 2_000_000,
+                value: 8.0,
+            },
+        ];
+        // Out of order on purpose; 1.5 s is a tie and goes to the earlier label
+        let telemetry = [
+            window(2_100_000, 3),
+            window(900_000, 1),
+            window(1_500_000, 2),
+            window(5_000_000, 9),
+        ];
+        let joined = DatasetBuilder::new().join(&telemetry, &labels).unwrap();
+        assert_eq!(
+            joined.dataset.timestamps_us,
+            vec![900_000, 1_500_000, 2_100_000]
+        );
+        assert_eq!(joined.dataset.targets, vec![4.0, 4.0, 8.0]);
+        assert_eq!(joined.dataset.features[2][0], 3.0); // runq_len
+        assert_eq!(joined.unmatched, 1);
+
+        let strict = DatasetBuilder::new().with_tolerance(Duration::from_millis(150));
+        let joined = strict.join(&telemetry, &labels).unwrap();
+        assert_eq!(joined.dataset.timestamps_us, vec![900_000, 2_100_000]);
+        assert_eq!(joined.unmatched, 2);
+    }
+
+    #[test]
+    fn test_reads_decision_log_and_sweep_labels() {
+        let dir = std::env::temp_dir().join(format!("trainer-dataset-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let log = dir.join("decisions.jsonl");
+        let record = serde_json::json!({ "timestamp_us": 7, "telemetry": window(1_000_000, 5), "decision": {} });
+        let bare = serde_json::to_string(&window(1_500_000, 6)).unwrap();
+        std::fs::write(&log, format!("{}\n\n{}\n", record, bare)).unwrap();
+        let windows = read_telemetry_log(&log).unwrap();
+        assert_eq!(windows.len(), 2);
+        assert_eq!(
+            (windows[0].timestamp_us, windows[1].runq_len),
+            (1_000_000, 6)
+        );
+
+        let sweep = dir.join("sweep.json");
+        let rows: Vec<Value> = [(1, 1_000_000), (2, 1_000_000), (1, 1_500_000)]
+            .iter()
+            .map(|&(n, t)| serde_json::json!({ "n_workers": n, "telemetry": window(t, 0), "optimal_n_workers": 16 }))
+            .collect();
+        std::fs::write(&sweep, serde_json::to_string(&rows).unwrap()).unwrap();
+        let labels = read_sweep_labels(&sweep).unwrap();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(
+            labels[1],
+            Label {
+                timestamp_us: 1_500_000,
+                value: 16.0
+            }
+        );
+        assert!(read_sweep_cells(&sweep).is_err()); // no workload fields
+
+        let grid = dir.join("grid.csv");
+        std::fs::write(
+            &grid,
+            "workload,workload_rate,workload_task_us,workload_burstiness,n_workers\n\
+             steady,100,500,0,1\nsteady,100,500,0,2\nbursty,100,500,0.5,1\n",
+        )
+        .unwrap();
+        let cells = read_sweep_cells(&grid).unwrap();
+        assert_eq!(cells[0], cells[1]);
+        assert_eq!(cells[2], "bursty/100/500/0.5");
+        assert_eq!(read_sweep_regimes(&grid).unwrap()[2], "bursty");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
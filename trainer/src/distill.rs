@@ -0,0 +1,155 @@
+//! Teacher-student distillation
+//!
+//! An online tuner (hill climbing, PID) run in the simulator is a teacher:
+//! every decision it logs is a demonstration of what pool size it chose
+//! for the telemetry it saw. Fitting a tree to those pairs gives a reflex
+//! that imitates the tuner without its search, state or warm-up.
+
+use crate::Dataset;
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use telemetry_compute::{ComputeTelemetry, Normalizer};
+
+/// One teacher decision: the telemetry it saw and the pool size it chose
+#[derive(Debug, Clone, Copy)]
+pub struct Demonstration {
+    pub telemetry: ComputeTelemetry,
+    pub action: u32,
+}
+
+/// Normalizer spanning every demonstration's features
+pub fn demonstration_normalizer(demos: &[Demonstration]) -> Normalizer {
+    let mut normalizer = Normalizer::new();
+    for demo in demos {
+        normalizer.observe(&demo.telemetry.to_features());
+    }
+    normalizer
+}
+
+/// Normalized features against the teacher's actions, in demonstration order
+pub fn imitation_dataset(demos: &[Demonstration], normalizer: &Normalizer) -> io::Result<Dataset> {
+    let features = demos
+        .iter()
+        .map(|demo| normalizer.normalize(&demo.telemetry.to_features()).to_vec())
+        .collect();
+    let targets = demos.iter().map(|demo| demo.action as f32).collect();
+    Dataset::new(features, targets)
+}
+
+/// How closely a student reproduces the teacher's actions
+#[derive(Debug, Clone, Copy)]
+pub struct Agreement {
+    pub mae_workers: f64,
+    pub exact_rate: f64, // share of rows where the rounded output is the teacher's action
+}
+
+impl Agreement {
+    pub fn measure(data: &Dataset, predict: impl Fn(&[f32]) -> f32) -> Self {
+        if data.is_empty() {
+            return Self {
+                mae_workers: 0.0,
+                exact_rate: 0.0,
+            };
+        }
+        let (mut abs_error, mut exact) = (0.0, 0);
+        for (row, &action) in data.features.iter().zip(&data.targets) {
+            let output = predict(row);
+            abs_error += (output - action).abs() as f64;
+            if output.round() == action {
+                exact += 1;
+            }
+        }
+        Self {
+            mae_workers: abs_error / data.len() as f64,
+            exact_rate: exact as f64 / data.len() as f64,
+        }
+    }
+}
+
+/// Read demonstrations from a decision log (JSON Lines of decision records)
+pub fn read_demonstrations(path: impl AsRef<Path>) -> io::Result<Vec<Demonstration>> {
+    let reader = BufReader::new(std::fs::File::open(path)?);
+    let mut demos = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let bad = |e: String| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 1, e))
+        };
+        let mut record: Value = serde_json::from_str(&line).map_err(|e| bad(e.to_string()))?;
+        let action = record
+            .pointer("/decision/n_workers")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| bad("missing decision.n_workers".to_string()))?;
+        let telemetry =
+            serde_json::from_value(record["telemetry"].take()).map_err(|e| bad(e.to_string()))?;
+        demos.push(Demonstration {
+            telemetry,
+            action: action as u32,
+        });
+    }
+    Ok(demos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CartTrainer;
+
+    fn telemetry(runq_len: u32) -> ComputeTelemetry {
+        serde_json::from_value(serde_json::json!({
+            "timestamp_us": 0, "runq_len": runq_len, "arrival_rate": 1000.0, "completion_rate": 1000.0,
+            "task_time_p50_us": 500.0, "task_time_p95_us": 900.0, "worker_util": 0.5,
+            "ctx_switches_per_sec": 100.0, "task_size_mean": 500.0, "task_size_var": 10.0,
+            "idle_worker_count": 2
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_student_imitates_teacher() {
+        // Teacher: 4 workers until the queue passes 20, then 12
+        let demos: Vec<Demonstration> = (0..100)
+            .map(|i| Demonstration {
+                telemetry: telemetry(i % 50),
+                action: if i % 50 > 20 { 12 } else { 4 },
+            })
+            .collect();
+        let normalizer = demonstration_normalizer(&demos);
+        let data = imitation_dataset(&demos, &normalizer).unwrap();
+        let student = CartTrainer::new().with_min_samples_leaf(5).fit(&data);
+
+        let agreement = Agreement::measure(&data, |row| student.predict(row));
+        assert_eq!(agreement.exact_rate, 1.0);
+        assert_eq!(agreement.mae_workers, 0.0);
+    }
+
+    #[test]
+    fn test_reads_decision_log() {
+        let path = std::env::temp_dir().join(format!("trainer-demos-{}.jsonl", std::process::id()));
+        let record = serde_json::json!({
+            "timestamp_us": 10_000,
+            "telemetry": telemetry(7),
+            "decision": { "n_workers": 6, "admit_rate": null }
+        });
+        std::fs::write(
+            &path,
+            format!(
+                "{}\n{}\n",
+                record,
+                serde_json::json!({ "telemetry": telemetry(1) })
+            ),
+        )
+        .unwrap();
+        let err = read_demonstrations(&path).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+
+        std::fs::write(&path, format!("{}\n", record)).unwrap();
+        let demos = read_demonstrations(&path).unwrap();
+        assert_eq!((demos[0].telemetry.runq_len, demos[0].action), (7, 6));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
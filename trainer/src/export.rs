@@ -0,0 +1,419 @@
+//! Writing trained models as `.reflex` files
+
+use crate::RegressionTree;
+use reflex_format::{
+    LinearModel, LookupTable, ModelType, MultiOutputTree, OutputBounds, Reflex, ReflexHeader,
+    ReflexMetadata, TreeEnsemble, TreeNode,
+};
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Assemble a tree reflex, one tree per output
+///
+/// Section sizes in the header are filled in when the reflex is serialized.
+pub fn build_reflex(
+    trees: Vec<RegressionTree>,
+    feature_count: usize,
+    bounds: OutputBounds,
+    metadata: ReflexMetadata,
+) -> io::Result<Reflex> {
+    check_shape(
+        feature_count,
+        trees.len(),
+        &bounds,
+        trees.iter().flat_map(|tree| &tree.nodes),
+    )?;
+    Ok(Reflex {
+        header: ReflexHeader::new(
+            ModelType::DecisionTree,
+            feature_count as u8,
+            trees.len() as u8,
+            unix_now(),
+            0,
+            0,
+            0,
+        ),
+        trees: trees.into_iter().map(|tree| tree.nodes).collect(),
+        ensembles: Vec::new(),
+        linear: Vec::new(),
+        multi_output: None,
+        tables: Vec::new(),
+        bounds,
+        metadata,
+        report: None,
+    })
+}
+
+/// Assemble an ensemble reflex, one ensemble per output
+pub fn build_ensemble_reflex(
+    ensembles: Vec<TreeEnsemble>,
+    feature_count: usize,
+    bounds: OutputBounds,
+    metadata: ReflexMetadata,
+) -> io::Result<Reflex> {
+    let nodes = ensembles
+        .iter()
+        .flat_map(|ensemble| ensemble.trees.iter().flatten());
+    check_shape(feature_count, ensembles.len(), &bounds, nodes)?;
+    Ok(Reflex {
+        header: ReflexHeader::new(
+            ModelType::Ensemble,
+            feature_count as u8,
+            ensembles.len() as u8,
+            unix_now(),
+            0,
+            0,
+            0,
+        ),
+        trees: Vec::new(),
+        ensembles,
+        linear: Vec::new(),
+        multi_output: None,
+        tables: Vec::new(),
+        bounds,
+        metadata,
+        report: None,
+    })
+}
+
+/// Assemble a linear reflex, one model per output
+pub fn build_linear_reflex(
+    models: Vec<LinearModel>,
+    feature_count: usize,
+    bounds: OutputBounds,
+    metadata: ReflexMetadata,
+) -> io::Result<Reflex> {
+    check_shape(feature_count, models.len(), &bounds, std::iter::empty())?;
+    if models
+        .iter()
+        .any(|model| model.weights.len() != feature_count)
+    {
+        return Err(invalid("need one weight per feature".to_string()));
+    }
+    Ok(Reflex {
+        header: ReflexHeader::new(
+            ModelType::Linear,
+            feature_count as u8,
+            models.len() as u8,
+            unix_now(),
+            0,
+            0,
+            0,
+        ),
+        trees: Vec::new(),
+        ensembles: Vec::new(),
+        linear: models,
+        multi_output: None,
+        tables: Vec::new(),
+        bounds,
+        metadata,
+        report: None,
+    })
+}
+
+/// Assemble a reflex from one tree predicting every output
+pub fn build_multi_output_reflex(
+    tree: MultiOutputTree,
+    feature_count: usize,
+    bounds: OutputBounds,
+    metadata: ReflexMetadata,
+) -> io::Result<Reflex> {
+    let output_count = bounds.min.len();
+    check_shape(feature_count, output_count, &bounds, tree.nodes.iter())?;
+    if tree
+        .leaves
+        .iter()
+        .any(|values| values.len() != output_count)
+    {
+        return Err(invalid("need one leaf value per output".to_string()));
+    }
+    Ok(Reflex {
+        header: ReflexHeader::new(
+            ModelType::MultiOutputTree,
+            feature_count as u8,
+            output_count as u8,
+            unix_now(),
+            0,
+            0,
+            0,
+        ),
+        trees: Vec::new(),
+        ensembles: Vec::new(),
+        linear: Vec::new(),
+        multi_output: Some(tree),
+        tables: Vec::new(),
+        bounds,
+        metadata,
+        report: None,
+    })
+}
+
+/// Assemble a lookup-table reflex, one table per output
+pub fn build_table_reflex(
+    tables: Vec<LookupTable>,
+    feature_count: usize,
+    bounds: OutputBounds,
+    metadata: ReflexMetadata,
+) -> io::Result<Reflex> {
+    check_shape(feature_count, tables.len(), &bounds, std::iter::empty())?;
+    if tables.iter().any(|table| {
+        table.row_feature as usize >= feature_count || table.col_feature as usize >= feature_count
+    }) {
+        return Err(invalid(
+            "table reads a feature past feature_count".to_string(),
+        ));
+    }
+    Ok(Reflex {
+        header: ReflexHeader::new(
+            ModelType::LookupTable,
+            feature_count as u8,
+            tables.len() as u8,
+            unix_now(),
+            0,
+            0,
+            0,
+        ),
+        trees: Vec::new(),
+        ensembles: Vec::new(),
+        linear: Vec::new(),
+        multi_output: None,
+        tables,
+        bounds,
+        metadata,
+        report: None,
+    })
+}
+
+/// Check counts fit the header, bounds cover every output and splits stay within the features
+fn check_shape<'a>(
+    feature_count: usize,
+    output_count: usize,
+    bounds: &OutputBounds,
+    mut nodes: impl Iterator<Item = &'a TreeNode>,
+) -> io::Result<()> {
+    if feature_count > u8::MAX as usize || output_count > u8::MAX as usize {
+        return Err(invalid(format!(
+            "{} features / {} outputs exceed the header's u8 counts",
+            feature_count, output_count
+        )));
+    }
+    if bounds.min.len() != output_count || bounds.max.len() != output_count {
+        return Err(invalid("need one bound per output".to_string()));
+    }
+    if nodes.any(|node| !node.is_leaf() && node.feature_idx as usize >= feature_count) {
+        return Err(invalid(
+            "tree splits on a feature past feature_count".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Metadata for a reflex trained now by this crate
+pub fn training_metadata(
+    feature_schema: &str,
+    telemetry_hash: &str,
+    lambda: f32,
+    notes: &str,
+) -> ReflexMetadata {
+    ReflexMetadata {
+        created_at: iso8601(unix_now()),
+        trainer_commit: format!("trainer@{}", env!("CARGO_PKG_VERSION")),
+        feature_schema: feature_schema.to_string(),
+        telemetry_hash: telemetry_hash.to_string(),
+        lambda,
+        notes: notes.to_string(),
+        ..ReflexMetadata::default()
+    }
+}
+
+/// CRC32 of the feature names, in order, as hex, for the reflex's `schema_hash`
+///
+/// Catches a model paired with telemetry whose features were reordered or
+/// renamed under the same schema name.
+pub fn schema_hash(feature_names: &[&str]) -> String {
+    format!(
+        "{:08x}",
+        crc32fast::hash(feature_names.join(",").as_bytes())
+    )
+}
+
+/// Serialize `reflex` to `path`
+pub fn write_reflex(reflex: &Reflex, path: impl AsRef<Path>) -> io::Result<()> {
+    std::fs::write(path, reflex.to_bytes()?)
+}
+
+/// Seconds since the epoch, or `SOURCE_DATE_EPOCH` when set so reruns stamp the same time
+fn unix_now() -> u64 {
+    if let Some(epoch) = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.parse().ok())
+    {
+        return epoch;
+    }
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// UTC timestamp like `2025-10-06T12:00:00Z`
+fn iso8601(unix_secs: u64) -> String {
+    let days = (unix_secs / 86_400) as i64;
+    let secs = unix_secs % 86_400;
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CartTrainer, Dataset, ForestTrainer, RidgeTrainer};
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(iso8601(0), "1970-01-01T00:00:00Z");
+        assert_eq!(iso8601(1_728_216_000), "2024-10-06T12:00:00Z");
+        assert_eq!(iso8601(951_782_400), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_trained_tree_roundtrips_through_reflex() {
+        let features: Vec<Vec<f32>> = (0..200).map(|i| vec![i as f32 / 200.0, 0.5]).collect();
+        let targets = features.iter().map(|r| 2.0 + 60.0 * r[0]).collect();
+        let data = Dataset::new(features, targets).unwrap();
+        let tree = CartTrainer::new()
+            .with_max_depth(5)
+            .with_min_samples_leaf(5)
+            .fit(&data);
+
+        let reflex = build_reflex(
+            vec![tree.clone()],
+            2,
+            OutputBounds {
+                min: vec![1.0],
+                max: vec![64.0],
+            },
+            training_metadata("compute-v1", &data.fingerprint(), 0.0, "test"),
+        )
+        .unwrap();
+        let loaded = Reflex::from_bytes(&reflex.to_bytes().unwrap()).unwrap();
+        for row in &data.features {
+            assert_eq!(loaded.infer(row)[0], tree.predict(row).clamp(1.0, 64.0));
+        }
+        assert_eq!(loaded.metadata.feature_schema, "compute-v1");
+
+        let two_bounds = OutputBounds {
+            min: vec![1.0, 1.0],
+            max: vec![64.0, 64.0],
+        };
+        assert!(build_reflex(vec![tree], 2, two_bounds, loaded.metadata.clone()).is_err());
+    }
+
+    #[test]
+    fn test_multi_output_tree_roundtrips_through_reflex() {
+        let features: Vec<Vec<f32>> = (0..100).map(|i| vec![i as f32 / 100.0]).collect();
+        let targets = vec![
+            features.iter().map(|r| 4.0 + 40.0 * r[0]).collect(),
+            features.iter().map(|r| 50.0 + 4000.0 * r[0]).collect(),
+        ];
+        let tree = CartTrainer::new()
+            .with_min_samples_leaf(5)
+            .fit_multi(&features, &targets);
+        let bounds = OutputBounds {
+            min: vec![1.0, 50.0],
+            max: vec![64.0, 4000.0],
+        };
+        let metadata = training_metadata("v1", "", 0.0, "");
+        let reflex =
+            build_multi_output_reflex(tree.clone(), 1, bounds.clone(), metadata.clone()).unwrap();
+        let loaded = Reflex::from_bytes(&reflex.to_bytes().unwrap()).unwrap();
+        assert_eq!(loaded.header.output_count, 2);
+        for row in &features {
+            let expected = tree.eval(row);
+            assert_eq!(
+                loaded.infer(row),
+                vec![
+                    expected[0].clamp(1.0, 64.0),
+                    expected[1].clamp(50.0, 4000.0)
+                ]
+            );
+        }
+
+        let one_bound = OutputBounds {
+            min: vec![1.0],
+            max: vec![64.0],
+        };
+        assert!(build_multi_output_reflex(tree, 1, one_bound, metadata).is_err());
+    }
+
+    #[test]
+    fn test_forest_roundtrips_through_reflex() {
+        let features: Vec<Vec<f32>> = (0..100).map(|i| vec![i as f32 / 100.0]).collect();
+        let targets = features.iter().map(|r| 4.0 + 40.0 * r[0]).collect();
+        let data = Dataset::new(features, targets).unwrap();
+        let forest = ForestTrainer::new().with_n_trees(5).fit(&data);
+
+        let bounds = OutputBounds {
+            min: vec![1.0],
+            max: vec![64.0],
+        };
+        let reflex = build_ensemble_reflex(
+            vec![forest.clone()],
+            1,
+            bounds,
+            training_metadata("v1", "", 0.0, ""),
+        )
+        .unwrap();
+        let loaded = Reflex::from_bytes(&reflex.to_bytes().unwrap()).unwrap();
+        assert_eq!(loaded.ensembles[0].trees.len(), 5);
+        for row in &data.features {
+            assert_eq!(loaded.infer(row)[0], forest.eval(row).clamp(1.0, 64.0));
+        }
+    }
+
+    #[test]
+    fn test_ridge_roundtrips_through_reflex() {
+        let features: Vec<Vec<f32>> = (0..40).map(|i| vec![i as f32 / 40.0, 0.25]).collect();
+        let targets = features.iter().map(|r| 8.0 + 16.0 * r[0]).collect();
+        let data = Dataset::new(features, targets).unwrap();
+        let model = RidgeTrainer::new().with_lambda(0.01).fit(&data);
+
+        let bounds = OutputBounds {
+            min: vec![1.0],
+            max: vec![64.0],
+        };
+        let reflex = build_linear_reflex(
+            vec![model.clone()],
+            2,
+            bounds.clone(),
+            training_metadata("v1", "", 0.0, ""),
+        )
+        .unwrap();
+        let loaded = Reflex::from_bytes(&reflex.to_bytes().unwrap()).unwrap();
+        for row in &data.features {
+            assert_eq!(loaded.infer(row)[0], model.eval(row).clamp(1.0, 64.0));
+        }
+        assert!(build_linear_reflex(vec![model], 3, bounds, loaded.metadata.clone()).is_err());
+    }
+}
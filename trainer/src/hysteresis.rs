@@ -0,0 +1,116 @@
+//! Flap-resistant label post-processing
+//!
+//! The empirically optimal action is noisy: a sweep that measures N=9
+//! beating N=8 by a hair for one window and losing the next would teach
+//! the model to flap. [`LabelHysteresis`] keeps the current label until a
+//! different one holds for `min_run` consecutive windows, so only changes
+//! that last survive. Labels are a time-ordered sequence; a change that
+//! lasts is adopted at its first window, with no lag.
+
+use crate::Dataset;
+
+/// Hysteresis on a time-ordered label sequence
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelHysteresis {
+    pub min_run: usize, // windows a new label must hold to replace the current one
+    pub deadband: f32,  // labels this close to the current one don't count as a change
+}
+
+impl LabelHysteresis {
+    /// A change must last 2 windows; any difference counts
+    pub fn new() -> Self {
+        Self {
+            min_run: 2,
+            deadband: 0.0,
+        }
+    }
+
+    pub fn with_min_run(mut self, min_run: usize) -> Self {
+        assert!(min_run > 0, "a label holds for at least one window");
+        self.min_run = min_run;
+        self
+    }
+
+    pub fn with_deadband(mut self, deadband: f32) -> Self {
+        assert!(deadband >= 0.0, "deadband is a distance");
+        self.deadband = deadband;
+        self
+    }
+
+    /// `labels` with transient changes replaced by the label before them
+    ///
+    /// A run of `min_run` windows all away from the current label (by more
+    /// than the deadband) switches to the label at the run's start; runs
+    /// cut short by the end of the sequence don't.
+    pub fn smooth(&self, labels: &[f32]) -> Vec<f32> {
+        let Some(&first) = labels.first() else {
+            return Vec::new();
+        };
+        let mut held = first;
+        let mut smoothed = Vec::with_capacity(labels.len());
+        for (i, &label) in labels.iter().enumerate() {
+            let moved = |value: f32| (value - held).abs() > self.deadband;
+            if moved(label)
+                && labels.len() - i >= self.min_run
+                && labels[i..i + self.min_run]
+                    .iter()
+                    .all(|&value| moved(value))
+            {
+                held = label;
+            }
+            smoothed.push(held);
+        }
+        smoothed
+    }
+
+    /// `data` with its targets smoothed in row (time) order
+    pub fn smooth_dataset(&self, data: &Dataset) -> Dataset {
+        Dataset {
+            targets: self.smooth(&data.targets),
+            ..data.clone()
+        }
+    }
+}
+
+impl Default for LabelHysteresis {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How many times `labels` changes value from one window to the next
+pub fn label_flips(labels: &[f32]) -> usize {
+    labels.windows(2).filter(|pair| pair[0] != pair[1]).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transient_labels_are_held_out() {
+        let labels = [8.0, 8.0, 9.0, 8.0, 8.0, 12.0, 12.0, 12.0, 11.0, 12.0, 4.0];
+        let smoothed = LabelHysteresis::new().smooth(&labels);
+        // One-window blips vanish, the lasting move to 12 lands on time, and
+        // the trailing single window has nothing after it to confirm it
+        assert_eq!(
+            smoothed,
+            [8.0, 8.0, 8.0, 8.0, 8.0, 12.0, 12.0, 12.0, 12.0, 12.0, 12.0]
+        );
+        assert_eq!((label_flips(&labels), label_flips(&smoothed)), (6, 1));
+
+        // Within the deadband nothing counts as a change
+        let banded = LabelHysteresis::new()
+            .with_min_run(1)
+            .with_deadband(1.0)
+            .smooth(&labels);
+        assert_eq!(banded[2], 8.0);
+        assert_eq!(banded[8], 12.0);
+        assert_eq!(banded[10], 4.0);
+
+        assert_eq!(
+            LabelHysteresis::new().with_min_run(1).smooth(&labels),
+            labels
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Permutation feature importance and feature pruning
+//!
+//! A feature's importance is how much validation MAE rises when its column
+//! is shuffled across rows, breaking its link to the target while keeping
+//! its distribution. Features whose shuffle doesn't hurt can be dropped and
+//! the model retrained without them. Dropped features are masked to a
+//! constant rather than removed, so the retrained model still takes the
+//! runtime's full feature vector; the kept subset is recorded in the
+//! reflex's feature schema.
+
+use crate::{mean_absolute_error, Dataset};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use reflex_format::{FeatureImportance, ReflexMetadata, TrainingReport};
+
+/// Validation MAE and how much shuffling each feature raises it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Importance {
+    pub baseline_mae: f64,
+    pub mae_increase: Vec<f64>, // per feature, averaged over repeats
+}
+
+impl Importance {
+    /// Features whose shuffle raised MAE by more than `min_increase`
+    pub fn useful(&self, min_increase: f64) -> Vec<usize> {
+        (0..self.mae_increase.len())
+            .filter(|&f| self.mae_increase[f] > min_increase)
+            .collect()
+    }
+
+    /// (feature, MAE increase), most important first
+    pub fn ranked(&self) -> Vec<(usize, f64)> {
+        let mut ranked: Vec<(usize, f64)> = self.mae_increase.iter().copied().enumerate().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked
+    }
+
+    /// Record the ranking in a training report
+    ///
+    /// `names` labels features by index; features past its end are written as indices.
+    pub fn write_report(&self, report: &mut TrainingReport, names: &[&str]) {
+        report.feature_importance = self
+            .ranked()
+            .into_iter()
+            .map(|(f, mae_increase)| FeatureImportance {
+                feature: feature_name(names, f),
+                mae_increase,
+            })
+            .collect();
+    }
+}
+
+/// A model retrained on the features that mattered
+#[derive(Debug, Clone)]
+pub struct Pruned<M> {
+    pub model: M,
+    pub kept: Vec<usize>,
+    pub importance: Importance, // of the model trained on every feature
+    pub mae: f64,               // validation MAE of `model`
+}
+
+impl<M> Pruned<M> {
+    /// Append the kept features to the schema, e.g. `compute-v1[runq_len,worker_util]`
+    ///
+    /// `names` labels features by index; features past its end are written as indices.
+    pub fn write_schema(&self, metadata: &mut ReflexMetadata, names: &[&str]) {
+        let kept: Vec<String> = self.kept.iter().map(|&f| feature_name(names, f)).collect();
+        metadata.feature_schema = format!("{}[{}]", metadata.feature_schema, kept.join(","));
+    }
+}
+
+/// Permutation importance over a validation set
+#[derive(Debug, Clone)]
+pub struct PermutationImportance {
+    pub repeats: usize,    // shuffles averaged per feature
+    pub min_increase: f64, // MAE increase a feature needs to survive pruning
+    pub seed: u64,
+}
+
+impl PermutationImportance {
+    /// 5 shuffles per feature, pruning features whose shuffle doesn't raise MAE
+    pub fn new() -> Self {
+        Self {
+            repeats: 5,
+            min_increase: 0.0,
+            seed: 0,
+        }
+    }
+
+    pub fn with_repeats(mut self, repeats: usize) -> Self {
+        assert!(repeats > 0, "need at least one shuffle per feature");
+        self.repeats = repeats;
+        self
+    }
+
+    pub fn with_min_increase(mut self, min_increase: f64) -> Self {
+        self.min_increase = min_increase;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Score each feature of `valid` against `predict`
+    pub fn measure(&self, valid: &Dataset, predict: impl Fn(&[f32]) -> f32) -> Importance {
+        assert!(!valid.is_empty(), "cannot measure importance on no rows");
+        let baseline_mae = mean_absolute_error(valid, &predict);
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut row = vec![0.0; valid.feature_count()];
+        let mae_increase = (0..valid.feature_count())
+            .map(|feature| {
+                let mut column: Vec<f32> = valid.features.iter().map(|r| r[feature]).collect();
+                let mut total = 0.0;
+                for _ in 0..self.repeats {
+                    column.shuffle(&mut rng);
+                    let error: f64 = valid
+                        .features
+                        .iter()
+                        .zip(&valid.targets)
+                        .zip(&column)
+                        .map(|((features, &target), &shuffled)| {
+                            row.copy_from_slice(features);
+                            row[feature] = shuffled;
+                            (predict(&row) - target).abs() as f64
+                        })
+                        .sum();
+                    total += error / valid.len() as f64 - baseline_mae;
+                }
+                total / self.repeats as f64
+            })
+            .collect();
+        Importance {
+            baseline_mae,
+            mae_increase,
+        }
+    }
+
+    /// Fit on every feature, measure importance on `valid`, and refit on the useful ones
+    ///
+    /// `predict` evaluates a fitted model, e.g. `RegressionTree::predict` or
+    /// `TreeEnsemble::eval`. If every feature is useful the first model is
+    /// returned as is; if none is, the most important one is kept.
+    pub fn prune<M>(
+        &self,
+        train: &Dataset,
+        valid: &Dataset,
+        fit: impl Fn(&Dataset) -> M,
+        predict: impl Fn(&M, &[f32]) -> f32,
+    ) -> Pruned<M> {
+        let model = fit(train);
+        let importance = self.measure(valid, |row| predict(&model, row));
+        let mut kept = importance.useful(self.min_increase);
+        if kept.len() == train.feature_count() {
+            return Pruned {
+                model,
+                kept,
+                mae: importance.baseline_mae,
+                importance,
+            };
+        }
+        if kept.is_empty() {
+            kept.push(importance.ranked()[0].0);
+        }
+        let model = fit(&train.mask_features(&kept));
+        let mae = mean_absolute_error(&valid.mask_features(&kept), |row| predict(&model, row));
+        Pruned {
+            model,
+            kept,
+            importance,
+            mae,
+        }
+    }
+}
+
+impl Default for PermutationImportance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn feature_name(names: &[&str], feature: usize) -> String {
+    names
+        .get(feature)
+        .map_or(feature.to_string(), |name| name.to_string())
+}
+
+impl Dataset {
+    /// Copy with every feature not in `keep` set to 0, so no split or weight can use it
+    pub fn mask_features(&self, keep: &[usize]) -> Dataset {
+        let mut masked = self.clone();
+        for row in &mut masked.features {
+            for (feature, value) in row.iter_mut().enumerate() {
+                if !keep.contains(&feature) {
+                    *value = 0.0;
+                }
+            }
+        }
+        masked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{training_metadata, CartTrainer, RegressionTree};
+
+    /// Target is a step in feature 0; features 1 and 2 are noise
+    fn step_with_noise() -> Dataset {
+        let features: Vec<Vec<f32>> = (0..300)
+            .map(|i| {
+                vec![
+                    (i % 100) as f32 / 100.0,
+                    ((i * 37) % 101) as f32 / 101.0,
+                    ((i * 61) % 103) as f32 / 103.0,
+                ]
+            })
+            .collect();
+        let targets = features
+            .iter()
+            .map(|r| if r[0] < 0.5 { 4.0 } else { 20.0 })
+            .collect();
+        Dataset::new(features, targets).unwrap()
+    }
+
+    #[test]
+    fn test_prune_keeps_only_the_informative_feature() {
+        let data = step_with_noise();
+        let (train, valid) = data.time_holdout(0.3, 0);
+        let fit = |d: &Dataset| {
+            CartTrainer::new()
+                .with_max_depth(6)
+                .with_min_samples_leaf(2)
+                .fit(d)
+        };
+        let pruned = PermutationImportance::new().with_min_increase(0.05).prune(
+            &train,
+            &valid,
+            fit,
+            RegressionTree::predict,
+        );
+
+        assert_eq!(pruned.importance.ranked()[0].0, 0);
+        assert!(pruned.importance.mae_increase[0] > 4.0);
+        assert_eq!(pruned.kept, vec![0]);
+        assert!(pruned.mae <= pruned.importance.baseline_mae);
+        assert!(pruned
+            .model
+            .nodes
+            .iter()
+            .all(|node| node.is_leaf() || node.feature_idx == 0));
+
+        let mut metadata = training_metadata("compute-v1", "", 0.0, "");
+        pruned.write_schema(&mut metadata, &["runq_len", "arrival_rate"]);
+        assert_eq!(metadata.feature_schema, "compute-v1[runq_len]");
+    }
+}
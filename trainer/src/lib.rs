@@ -0,0 +1,277 @@
+//! Reflex Trainer
+//!
+//! Fits reflex models from telemetry feature matrices and writes them as
+//! `.reflex` files, so models can be trained in Rust rather than only
+//! consumed. Features are expected in the space the runtime feeds the
+//! model, i.e. already normalized.
+
+use std::io;
+
+mod active;
+mod boosting;
+mod bounds;
+mod budget;
+mod calibration;
+mod config;
+pub mod dataset;
+pub mod distill;
+mod export;
+mod forest;
+mod hysteresis;
+mod importance;
+mod mining;
+mod report;
+mod ridge;
+mod scaling;
+mod search;
+mod split;
+mod table;
+mod tree;
+mod warm;
+
+pub use active::{ActiveLearner, RegionScore};
+pub use boosting::BoostingTrainer;
+pub use bounds::{clamping, BoundsLearner};
+pub use budget::{prune_to_bytes, prune_weakest_link};
+pub use calibration::{calibrate, cell_gap, compare_cells, fit_isotonic, CellComparison};
+pub use config::{ModelKind, TrainConfig, Trained};
+pub use export::{
+    build_ensemble_reflex, build_linear_reflex, build_multi_output_reflex, build_reflex,
+    build_table_reflex, schema_hash, training_metadata, write_reflex,
+};
+pub use forest::ForestTrainer;
+pub use hysteresis::{label_flips, LabelHysteresis};
+pub use importance::{Importance, PermutationImportance, Pruned};
+pub use mining::{read_divergence_log, DivergenceRecord, HardExampleMiner};
+pub use report::{residual_summary, training_report};
+pub use ridge::RidgeTrainer;
+pub use scaling::{check_normalizer, fit_normalizer, Scaling, Standardizer};
+pub use search::{CvScore, HyperparamSearch, Hyperparams, SearchResult, SearchSpace};
+pub use split::{blocked_folds, forward_folds, Fold};
+pub use table::TableTrainer;
+pub use tree::{CartTrainer, Monotonicity, RegressionTree};
+pub use warm::{refit_leaves, warm_start, WarmStart};
+
+/// Feature matrix with one target per row
+///
+/// Rows are in time order; `timestamps_us` says when each was observed,
+/// or is empty when row order is the only clock.
+#[derive(Debug, Clone, Default)]
+pub struct Dataset {
+    pub features: Vec<Vec<f32>>, // one row per sample
+    pub targets: Vec<f32>,
+    pub timestamps_us: Vec<u64>,
+}
+
+impl Dataset {
+    pub fn new(features: Vec<Vec<f32>>, targets: Vec<f32>) -> io::Result<Self> {
+        if features.len() != targets.len() {
+            return Err(invalid(format!(
+                "{} feature rows but {} targets",
+                features.len(),
+                targets.len()
+            )));
+        }
+        let width = features.first().map_or(0, Vec::len);
+        if features.iter().any(|row| row.len() != width) {
+            return Err(invalid("feature rows differ in length".to_string()));
+        }
+        if features
+            .iter()
+            .flatten()
+            .chain(&targets)
+            .any(|v| !v.is_finite())
+        {
+            return Err(invalid("dataset contains non-finite values".to_string()));
+        }
+        Ok(Self {
+            features,
+            targets,
+            timestamps_us: Vec::new(),
+        })
+    }
+
+    /// Attach per-row observation times (non-decreasing, one per row)
+    pub fn with_timestamps(mut self, timestamps_us: Vec<u64>) -> io::Result<Self> {
+        if timestamps_us.len() != self.len() {
+            return Err(invalid(format!(
+                "{} timestamps for {} rows",
+                timestamps_us.len(),
+                self.len()
+            )));
+        }
+        if timestamps_us.windows(2).any(|w| w[1] < w[0]) {
+            return Err(invalid("timestamps go backwards".to_string()));
+        }
+        self.timestamps_us = timestamps_us;
+        Ok(self)
+    }
+
+    /// When `row` was observed: its timestamp, or its index without timestamps
+    pub fn time_of(&self, row: usize) -> u64 {
+        self.timestamps_us.get(row).copied().unwrap_or(row as u64)
+    }
+
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    pub fn feature_count(&self) -> usize {
+        self.features.first().map_or(0, Vec::len)
+    }
+
+    /// The given rows, in order (rows may repeat)
+    pub fn subset(&self, rows: &[usize]) -> Dataset {
+        Dataset {
+            features: rows.iter().map(|&r| self.features[r].clone()).collect(),
+            targets: rows.iter().map(|&r| self.targets[r]).collect(),
+            timestamps_us: if self.timestamps_us.is_empty() {
+                Vec::new()
+            } else {
+                rows.iter().map(|&r| self.timestamps_us[r]).collect()
+            },
+        }
+    }
+
+    /// CRC32 of the feature and target values, as hex, for the reflex's `telemetry_hash`
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = crc32fast::Hasher::new();
+        for (row, target) in self.features.iter().zip(&self.targets) {
+            for value in row {
+                hasher.update(&value.to_le_bytes());
+            }
+            hasher.update(&target.to_le_bytes());
+        }
+        format!("{:08x}", hasher.finalize())
+    }
+}
+
+/// Training loss
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Loss {
+    /// Squared error: predictions estimate the conditional mean
+    Squared,
+    /// Pinball loss at quantile τ: under-predicting costs τ per unit and
+    /// over-predicting 1 - τ, so τ = 0.9 trains a model that would rather
+    /// over-provision
+    Quantile(f32),
+}
+
+impl Loss {
+    /// Loss of one prediction
+    pub fn value(&self, target: f32, prediction: f32) -> f64 {
+        let residual = (target - prediction) as f64;
+        match *self {
+            Loss::Squared => residual * residual,
+            Loss::Quantile(tau) => {
+                if residual >= 0.0 {
+                    tau as f64 * residual
+                } else {
+                    (tau as f64 - 1.0) * residual
+                }
+            }
+        }
+    }
+
+    /// Negative gradient with respect to the prediction (up to a constant factor)
+    pub(crate) fn negative_gradient(&self, target: f32, prediction: f32) -> f32 {
+        match *self {
+            Loss::Squared => target - prediction,
+            Loss::Quantile(tau) => {
+                if target > prediction {
+                    tau
+                } else {
+                    tau - 1.0
+                }
+            }
+        }
+    }
+
+    /// Best constant prediction for `values`: their mean, or their τ-quantile
+    pub(crate) fn optimum(&self, values: &mut [f32]) -> f32 {
+        match *self {
+            Loss::Squared => {
+                (values.iter().map(|&v| v as f64).sum::<f64>() / values.len() as f64) as f32
+            }
+            Loss::Quantile(tau) => {
+                values.sort_by(f32::total_cmp);
+                let rank =
+                    ((tau as f64 * values.len() as f64).ceil() as usize).clamp(1, values.len());
+                values[rank - 1]
+            }
+        }
+    }
+
+    fn validate(&self) {
+        if let Loss::Quantile(tau) = *self {
+            assert!(tau > 0.0 && tau < 1.0, "quantile must be in (0, 1)");
+        }
+    }
+}
+
+/// Mean `loss` of `predict` over `data`
+pub fn mean_loss(data: &Dataset, loss: Loss, predict: impl Fn(&[f32]) -> f32) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = data
+        .features
+        .iter()
+        .zip(&data.targets)
+        .map(|(row, &target)| loss.value(target, predict(row)))
+        .sum();
+    total / data.len() as f64
+}
+
+/// Mean absolute error of `predict` over `data`
+pub fn mean_absolute_error(data: &Dataset, predict: impl Fn(&[f32]) -> f32) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = data
+        .features
+        .iter()
+        .zip(&data.targets)
+        .map(|(row, &target)| (predict(row) - target).abs() as f64)
+        .sum();
+    total / data.len() as f64
+}
+
+/// Mean squared change of `predict` between consecutive rows, the flapping the smoothness penalty targets
+pub fn mean_squared_change(data: &Dataset, predict: impl Fn(&[f32]) -> f32) -> f64 {
+    if data.len() < 2 {
+        return 0.0;
+    }
+    let predictions: Vec<f64> = data
+        .features
+        .iter()
+        .map(|row| predict(row) as f64)
+        .collect();
+    let total: f64 = predictions.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum();
+    total / (data.len() - 1) as f64
+}
+
+/// Coefficient of determination of `predict` over `data`
+pub fn r2_score(data: &Dataset, predict: impl Fn(&[f32]) -> f32) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let mean = data.targets.iter().map(|&t| t as f64).sum::<f64>() / data.len() as f64;
+    let (mut ss_res, mut ss_tot) = (0.0, 0.0);
+    for (row, &target) in data.features.iter().zip(&data.targets) {
+        ss_res += (target as f64 - predict(row) as f64).powi(2);
+        ss_tot += (target as f64 - mean).powi(2);
+    }
+    if ss_tot == 0.0 {
+        return if ss_res == 0.0 { 1.0 } else { 0.0 };
+    }
+    1.0 - ss_res / ss_tot
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,177 @@
+//! Hard-example mining from shadow-mode divergence logs
+//!
+//! In shadow mode a candidate reflex decides alongside the incumbent
+//! policy without actuating. Each decision is logged as a
+//! [`DivergenceRecord`]; the ones where the candidate was far from the
+//! best known answer (the empirical optimum when a sweep measured one,
+//! the incumbent's choice otherwise) are the candidate's blind spots.
+//! [`HardExampleMiner`] turns those into labeled rows and repeats them in
+//! the next training set so the retrained model can't average them away.
+
+use crate::Dataset;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use telemetry_compute::ComputeTelemetry;
+
+/// One shadow-mode decision of a candidate next to the incumbent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DivergenceRecord {
+    pub telemetry: ComputeTelemetry,
+    pub candidate: f32, // pool size the candidate would have chosen
+    pub incumbent: f32, // pool size actually applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub optimum: Option<f32>, // empirical optimum, when a sweep measured one
+}
+
+impl DivergenceRecord {
+    /// The best known answer: the empirical optimum, else the incumbent's choice
+    pub fn label(&self) -> f32 {
+        self.optimum.unwrap_or(self.incumbent)
+    }
+
+    /// How far the candidate was from [`DivergenceRecord::label`]
+    pub fn divergence(&self) -> f32 {
+        (self.candidate - self.label()).abs()
+    }
+}
+
+/// Picks divergent shadow decisions and oversamples them into a training set
+#[derive(Debug, Clone)]
+pub struct HardExampleMiner {
+    pub min_divergence: f32, // in workers; closer decisions aren't hard
+    pub repeats: usize,      // copies of each hard example added
+}
+
+impl HardExampleMiner {
+    /// Decisions off by 2+ workers, each added 3 times
+    pub fn new() -> Self {
+        Self {
+            min_divergence: 2.0,
+            repeats: 3,
+        }
+    }
+
+    pub fn with_min_divergence(mut self, min_divergence: f32) -> Self {
+        assert!(min_divergence >= 0.0, "divergence is a distance");
+        self.min_divergence = min_divergence;
+        self
+    }
+
+    pub fn with_repeats(mut self, repeats: usize) -> Self {
+        self.repeats = repeats;
+        self
+    }
+
+    /// The hard records as raw compute-v1 rows labeled with the best known answer
+    pub fn mine(&self, records: &[DivergenceRecord]) -> io::Result<Dataset> {
+        let hard: Vec<&DivergenceRecord> = records
+            .iter()
+            .filter(|record| record.divergence() >= self.min_divergence)
+            .collect();
+        Dataset::new(
+            hard.iter()
+                .map(|record| record.telemetry.to_features().to_vec())
+                .collect(),
+            hard.iter().map(|record| record.label()).collect(),
+        )
+    }
+
+    /// `train` followed by `repeats` copies of the hard records
+    ///
+    /// The appended rows break time order, so the result has no timestamps;
+    /// split off validation rows before oversampling.
+    pub fn oversample(&self, train: &Dataset, records: &[DivergenceRecord]) -> io::Result<Dataset> {
+        let hard = self.mine(records)?;
+        if !hard.is_empty() && !train.is_empty() && hard.feature_count() != train.feature_count() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "divergence records and training rows differ in width",
+            ));
+        }
+        let mut oversampled = Dataset {
+            timestamps_us: Vec::new(),
+            ..train.clone()
+        };
+        for _ in 0..self.repeats {
+            oversampled.features.extend(hard.features.iter().cloned());
+            oversampled.targets.extend(&hard.targets);
+        }
+        Ok(oversampled)
+    }
+}
+
+impl Default for HardExampleMiner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read divergence records from JSON Lines, skipping blank lines
+pub fn read_divergence_log(path: impl AsRef<Path>) -> io::Result<Vec<DivergenceRecord>> {
+    let reader = BufReader::new(std::fs::File::open(path)?);
+    let mut records = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 1, e))
+        })?);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(
+        runq_len: u32,
+        candidate: f32,
+        incumbent: f32,
+        optimum: Option<f32>,
+    ) -> DivergenceRecord {
+        DivergenceRecord {
+            telemetry: serde_json::from_value(serde_json::json!({
+                "timestamp_us": 0, "runq_len": runq_len, "arrival_rate": 0.0, "completion_rate": 0.0,
+                "task_time_p50_us": 0.0, "task_time_p95_us": 0.0, "worker_util": 0.0,
+                "ctx_switches_per_sec": 0.0, "task_size_mean": 0.0, "task_size_var": 0.0,
+                "idle_worker_count": 0
+            }))
+            .unwrap(),
+            candidate,
+            incumbent,
+            optimum,
+        }
+    }
+
+    #[test]
+    fn test_mine_and_oversample_divergent_decisions() {
+        let records = vec![
+            record(1, 4.0, 5.0, None),       // close to the incumbent
+            record(2, 4.0, 12.0, None),      // far from the incumbent
+            record(3, 4.0, 12.0, Some(4.0)), // the candidate matched the optimum
+            record(4, 8.0, 8.0, Some(16.0)), // both missed the optimum
+        ];
+        let hard = HardExampleMiner::new().mine(&records).unwrap();
+        assert_eq!(hard.targets, vec![12.0, 16.0]);
+        assert_eq!(hard.features[1][0], 4.0);
+
+        let train = Dataset::new(vec![vec![0.0; hard.feature_count()]], vec![1.0])
+            .unwrap()
+            .with_timestamps(vec![7])
+            .unwrap();
+        let oversampled = HardExampleMiner::new()
+            .with_repeats(2)
+            .oversample(&train, &records)
+            .unwrap();
+        assert_eq!(oversampled.targets, vec![1.0, 12.0, 16.0, 12.0, 16.0]);
+        assert!(oversampled.timestamps_us.is_empty());
+
+        let line = serde_json::to_string(&records[2]).unwrap();
+        let parsed: DivergenceRecord = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed.label(), 4.0);
+    }
+}
@@ -0,0 +1,119 @@
+//! Training reports embedded in `.reflex` files
+//!
+//! [`training_report`] starts a [`TrainingReport`] from the data a model
+//! was fit and validated on; [`SearchResult::write_report`](crate::SearchResult::write_report)
+//! and [`Importance::write_report`](crate::Importance::write_report) add
+//! CV scores and feature importance. Attach it with
+//! [`Reflex::with_report`](reflex_format::Reflex::with_report).
+
+use crate::Dataset;
+use reflex_format::{ResidualSummary, TrainingReport};
+
+/// Report with the dataset sizes and residuals of `predict` on `valid`
+///
+/// Residuals fall back to the training rows when `valid` is empty.
+pub fn training_report(
+    train: &Dataset,
+    valid: &Dataset,
+    predict: impl Fn(&[f32]) -> f32,
+) -> TrainingReport {
+    let scored = if valid.is_empty() { train } else { valid };
+    TrainingReport {
+        train_rows: train.len(),
+        validation_rows: valid.len(),
+        residuals: residual_summary(scored, predict),
+        ..TrainingReport::default()
+    }
+}
+
+/// Distribution of `target - predict(row)` over `data`, or `None` without rows
+pub fn residual_summary(
+    data: &Dataset,
+    predict: impl Fn(&[f32]) -> f32,
+) -> Option<ResidualSummary> {
+    if data.is_empty() {
+        return None;
+    }
+    let mut residuals: Vec<f32> = data
+        .features
+        .iter()
+        .zip(&data.targets)
+        .map(|(row, &target)| target - predict(row))
+        .collect();
+    let n = residuals.len() as f64;
+    let mean = residuals.iter().map(|&r| r as f64).sum::<f64>() / n;
+    let mae = residuals.iter().map(|&r| r.abs() as f64).sum::<f64>() / n;
+    residuals.sort_by(f32::total_cmp);
+    let quantile = |q: f64| residuals[((residuals.len() - 1) as f64 * q).round() as usize];
+    Some(ResidualSummary {
+        count: residuals.len(),
+        mean: mean as f32,
+        mae: mae as f32,
+        p5: quantile(0.05),
+        p25: quantile(0.25),
+        p50: quantile(0.5),
+        p75: quantile(0.75),
+        p95: quantile(0.95),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        build_reflex, training_metadata, CartTrainer, HyperparamSearch, PermutationImportance,
+        SearchSpace,
+    };
+    use reflex_format::{OutputBounds, Reflex};
+
+    #[test]
+    fn test_report_roundtrips_through_reflex() {
+        let features: Vec<Vec<f32>> = (0..200)
+            .map(|i| vec![(i % 50) as f32 / 50.0, ((i * 7) % 13) as f32 / 13.0])
+            .collect();
+        let targets = features
+            .iter()
+            .enumerate()
+            .map(|(i, r)| 4.0 + 20.0 * r[0] + (i % 3) as f32)
+            .collect();
+        let data = Dataset::new(features, targets).unwrap();
+        let (train, valid) = data.time_holdout(0.25, 0);
+
+        let space = SearchSpace {
+            max_depth: vec![1, 4],
+            min_samples_leaf: vec![5],
+            lambda: vec![0.0],
+        };
+        let search = HyperparamSearch::new(space).grid(&train, |p, d| {
+            let tree = CartTrainer::new().with_max_depth(p.max_depth).fit(d);
+            move |row: &[f32]| tree.predict(row)
+        });
+        let tree = CartTrainer::new()
+            .with_max_depth(search.best().params.max_depth)
+            .fit(&train);
+        let mut report = training_report(&train, &valid, |row| tree.predict(row));
+        search.write_report(&mut report);
+        PermutationImportance::new()
+            .measure(&valid, |row| tree.predict(row))
+            .write_report(&mut report, &["load", "noise"]);
+
+        let residuals = report.residuals.clone().unwrap();
+        assert_eq!((report.train_rows, residuals.count), (150, 50));
+        assert!(residuals.p5 <= residuals.p50 && residuals.p50 <= residuals.p95);
+        assert_eq!(report.cv_scores.len(), 2);
+        assert_eq!(report.feature_importance[0].feature, "load");
+
+        let bounds = OutputBounds {
+            min: vec![1.0],
+            max: vec![64.0],
+        };
+        let reflex = build_reflex(vec![tree], 2, bounds, training_metadata("v1", "", 0.0, ""))
+            .unwrap()
+            .with_report(report.clone());
+        let loaded = Reflex::from_bytes(&reflex.to_bytes().unwrap()).unwrap();
+        let loaded = loaded.report.unwrap();
+        assert_eq!(loaded.residuals, report.residuals);
+        assert_eq!(loaded.feature_importance, report.feature_importance);
+        assert_eq!(loaded.cv_scores[0].params, report.cv_scores[0].params);
+    }
+}
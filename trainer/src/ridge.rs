@@ -0,0 +1,142 @@
+//! Ridge regression
+//!
+//! Closed-form least squares with an L2 penalty on the weights: the
+//! features and target are centred, `(XᵀX + λI) w = Xᵀy` is solved by
+//! Cholesky, and the bias recovers the means, so the intercept itself is
+//! never shrunk.
+
+use crate::Dataset;
+use reflex_format::LinearModel;
+
+/// Closed-form ridge regression trainer
+#[derive(Debug, Clone)]
+pub struct RidgeTrainer {
+    pub lambda: f64,
+}
+
+impl RidgeTrainer {
+    /// λ = 1, mild shrinkage for features normalized to [0, 1]
+    pub fn new() -> Self {
+        Self { lambda: 1.0 }
+    }
+
+    pub fn with_lambda(mut self, lambda: f64) -> Self {
+        assert!(lambda >= 0.0, "ridge penalty must be non-negative");
+        self.lambda = lambda;
+        self
+    }
+
+    /// Fit one output
+    ///
+    /// With λ = 0 a feature that never varies gets weight zero rather
+    /// than making the system singular.
+    pub fn fit(&self, data: &Dataset) -> LinearModel {
+        assert!(!data.is_empty(), "cannot fit a linear model to no rows");
+        let d = data.feature_count();
+        let n = data.len() as f64;
+
+        let mut x_mean = vec![0.0f64; d];
+        for row in &data.features {
+            for (mean, &x) in x_mean.iter_mut().zip(row) {
+                *mean += x as f64 / n;
+            }
+        }
+        let y_mean = data.targets.iter().map(|&y| y as f64).sum::<f64>() / n;
+
+        // Normal equations on centred data
+        let mut gram = vec![vec![0.0f64; d]; d];
+        let mut xty = vec![0.0f64; d];
+        for (row, &y) in data.features.iter().zip(&data.targets) {
+            let centred: Vec<f64> = row
+                .iter()
+                .zip(&x_mean)
+                .map(|(&x, m)| x as f64 - m)
+                .collect();
+            for (i, &xi) in centred.iter().enumerate() {
+                xty[i] += xi * (y as f64 - y_mean);
+                for (j, &xj) in centred.iter().enumerate() {
+                    gram[i][j] += xi * xj;
+                }
+            }
+        }
+        for (i, row) in gram.iter_mut().enumerate() {
+            // Tiny jitter keeps constant features solvable when λ = 0
+            row[i] += self.lambda + 1e-9;
+        }
+
+        let weights = cholesky_solve(gram, xty);
+        let bias = y_mean - weights.iter().zip(&x_mean).map(|(w, m)| w * m).sum::<f64>();
+        LinearModel {
+            weights: weights.into_iter().map(|w| w as f32).collect(),
+            bias: bias as f32,
+        }
+    }
+}
+
+impl Default for RidgeTrainer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Solve `a x = b` for symmetric positive-definite `a`
+pub(crate) fn cholesky_solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Vec<f64> {
+    let d = b.len();
+    // Factor in place: lower triangle of `a` becomes L with a = L Lᵀ
+    for j in 0..d {
+        let diag = a[j][j] - (0..j).map(|k| a[j][k] * a[j][k]).sum::<f64>();
+        a[j][j] = diag.max(f64::MIN_POSITIVE).sqrt();
+        for i in j + 1..d {
+            let dot: f64 = (0..j).map(|k| a[i][k] * a[j][k]).sum();
+            a[i][j] = (a[i][j] - dot) / a[j][j];
+        }
+    }
+    // L z = b, then Lᵀ x = z
+    for i in 0..d {
+        let dot: f64 = (0..i).map(|k| a[i][k] * b[k]).sum();
+        b[i] = (b[i] - dot) / a[i][i];
+    }
+    for i in (0..d).rev() {
+        let dot: f64 = (i + 1..d).map(|k| a[k][i] * b[k]).sum();
+        b[i] = (b[i] - dot) / a[i][i];
+    }
+    b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plane_data() -> Dataset {
+        // y = 3 + 20 x0 - 8 x1, x2 constant
+        let features: Vec<Vec<f32>> = (0..50)
+            .map(|i| vec![i as f32 / 50.0, ((i * 7) % 50) as f32 / 50.0, 0.5])
+            .collect();
+        let targets = features
+            .iter()
+            .map(|r| 3.0 + 20.0 * r[0] - 8.0 * r[1])
+            .collect();
+        Dataset::new(features, targets).unwrap()
+    }
+
+    #[test]
+    fn test_unpenalized_fit_recovers_plane() {
+        let model = RidgeTrainer::new().with_lambda(0.0).fit(&plane_data());
+        assert!((model.weights[0] - 20.0).abs() < 1e-3);
+        assert!((model.weights[1] + 8.0).abs() < 1e-3);
+        assert!(model.weights[2].abs() < 1e-3);
+        assert!((model.bias - 3.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_penalty_shrinks_weights() {
+        let data = plane_data();
+        let loose = RidgeTrainer::new().with_lambda(0.1).fit(&data);
+        let tight = RidgeTrainer::new().with_lambda(100.0).fit(&data);
+        assert!(tight.weights[0].abs() < loose.weights[0].abs());
+        assert!(tight.weights[1].abs() < loose.weights[1].abs());
+        // The intercept isn't penalized: the prediction at the feature mean stays the target mean
+        let mean_row = [0.49, 0.49, 0.5];
+        assert!((tight.eval(&mean_row) - loose.eval(&mean_row)).abs() < 1.0);
+    }
+}
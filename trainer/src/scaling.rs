@@ -0,0 +1,242 @@
+//! Fitting and checking the input normalizer
+//!
+//! The runtime scales every feature as `(x - min) / (max - min)`. Min-max
+//! scaling fits `min` and `max` to the training rows; standard scaling
+//! fits their mean and standard deviation and stores them as
+//! `min = mean`, `max = mean + std`, which the same formula turns into a
+//! z-score. Either way the scaler is fit on the training split only, and
+//! [`check_normalizer`] refuses to pair a model with bounds that are
+//! non-finite or collapse a feature the model actually uses.
+
+use crate::Dataset;
+use reflex_format::{ModelType, Reflex};
+use serde::{Deserialize, Serialize};
+use std::io;
+use telemetry_compute::{ComputeTelemetry, Normalizer};
+
+const N: usize = ComputeTelemetry::FEATURE_COUNT;
+
+/// How features are scaled before the model sees them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scaling {
+    /// Training range to [0, 1]
+    #[default]
+    MinMax,
+    /// Training mean to 0, one standard deviation to 1
+    Standard,
+}
+
+/// Per-feature mean and standard deviation of raw compute-v1 rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standardizer {
+    pub mean: [f32; N],
+    pub std: [f32; N],
+}
+
+impl Standardizer {
+    pub fn fit(rows: &Dataset) -> io::Result<Self> {
+        check_width(rows)?;
+        let n = rows.len().max(1) as f64;
+        let mut mean = [0.0; N];
+        let mut std = [0.0; N];
+        for i in 0..N {
+            let m = rows.features.iter().map(|row| row[i] as f64).sum::<f64>() / n;
+            let var = rows
+                .features
+                .iter()
+                .map(|row| (row[i] as f64 - m).powi(2))
+                .sum::<f64>()
+                / n;
+            mean[i] = m as f32;
+            std[i] = var.sqrt() as f32;
+        }
+        Ok(Self { mean, std })
+    }
+
+    /// The runtime's min-max form: `(x - mean) / std` as `(x - min) / (max - min)`
+    pub fn to_normalizer(&self) -> Normalizer {
+        let mut max = [0.0; N];
+        for (i, value) in max.iter_mut().enumerate() {
+            *value = self.mean[i] + self.std[i];
+        }
+        Normalizer {
+            min: self.mean,
+            max,
+        }
+    }
+}
+
+/// Fit the normalizer to raw compute-v1 training rows
+///
+/// Pass the training split only: bounds fit on validation rows leak them
+/// into the model's inputs.
+pub fn fit_normalizer(train: &Dataset, scaling: Scaling) -> io::Result<Normalizer> {
+    check_width(train)?;
+    if train.is_empty() {
+        return Err(invalid("cannot fit a normalizer to no rows".to_string()));
+    }
+    match scaling {
+        Scaling::MinMax => {
+            let mut normalizer = Normalizer::new();
+            for row in &train.features {
+                normalizer.observe(row.as_slice().try_into().expect("width checked"));
+            }
+            Ok(normalizer)
+        }
+        Scaling::Standard => Ok(Standardizer::fit(train)?.to_normalizer()),
+    }
+}
+
+/// Check `normalizer` can feed `reflex`
+///
+/// Bounds must be finite with `min <= max` (an unfit `Normalizer::new()`
+/// fails), and every feature the model splits on or weights must have
+/// `max > min`: a collapsed feature always reads 0.5, so the model and
+/// normalizer weren't fit on the same rows. Constant features the model
+/// ignores are fine.
+pub fn check_normalizer(reflex: &Reflex, normalizer: &Normalizer) -> io::Result<()> {
+    if reflex.header.feature_count as usize != N {
+        return Err(invalid(format!(
+            "model takes {} features, the normalizer scales {}",
+            reflex.header.feature_count, N
+        )));
+    }
+    let names = ComputeTelemetry::feature_names();
+    for (name, (&min, &max)) in names.iter().zip(normalizer.min.iter().zip(&normalizer.max)) {
+        if !min.is_finite() || !max.is_finite() || min > max {
+            return Err(invalid(format!(
+                "normalizer bounds for {} are [{}, {}]",
+                name, min, max
+            )));
+        }
+    }
+    for feature in used_features(reflex) {
+        if normalizer.max[feature] <= normalizer.min[feature] {
+            return Err(invalid(format!(
+                "the model uses {} but the normalizer collapses it to a constant",
+                names[feature]
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Features any tree splits on, any linear model weights or any table reads
+fn used_features(reflex: &Reflex) -> Vec<usize> {
+    let mut used = vec![false; reflex.header.feature_count as usize];
+    match ModelType::try_from(reflex.header.model_type) {
+        Ok(ModelType::Linear) => {
+            for model in &reflex.linear {
+                for (feature, &weight) in model.weights.iter().enumerate() {
+                    used[feature] |= weight != 0.0;
+                }
+            }
+        }
+        Ok(ModelType::LookupTable) => {
+            for table in &reflex.tables {
+                used[table.row_feature as usize] = true;
+                used[table.col_feature as usize] = true;
+            }
+        }
+        _ => {
+            let trees = reflex
+                .trees
+                .iter()
+                .chain(reflex.ensembles.iter().flat_map(|ensemble| &ensemble.trees))
+                .chain(reflex.multi_output.iter().map(|tree| &tree.nodes));
+            for node in trees.flatten().filter(|node| !node.is_leaf()) {
+                used[node.feature_idx as usize] = true;
+            }
+        }
+    }
+    (0..used.len()).filter(|&f| used[f]).collect()
+}
+
+fn check_width(rows: &Dataset) -> io::Result<()> {
+    if !rows.is_empty() && rows.feature_count() != N {
+        return Err(invalid(format!(
+            "{} features per row, compute-v1 has {}",
+            rows.feature_count(),
+            N
+        )));
+    }
+    Ok(())
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build_reflex, training_metadata, CartTrainer};
+    use reflex_format::OutputBounds;
+
+    /// Feature 0 carries the target; the rest are constant
+    fn rows() -> Dataset {
+        let features: Vec<Vec<f32>> = (0..100)
+            .map(|i| {
+                let mut row = vec![3.0; N];
+                row[0] = i as f32;
+                row
+            })
+            .collect();
+        let targets = features
+            .iter()
+            .map(|r| 2.0 + (r[0] / 10.0).floor())
+            .collect();
+        Dataset::new(features, targets).unwrap()
+    }
+
+    #[test]
+    fn test_standard_scaling_is_a_z_score() {
+        let normalizer = fit_normalizer(&rows(), Scaling::Standard).unwrap();
+        let mut row = [3.0; N];
+        row[0] = 49.5; // the mean
+        assert_eq!(normalizer.normalize(&row)[0], 0.0);
+        row[0] = 49.5 + 28.866; // one standard deviation up
+        assert!((normalizer.normalize(&row)[0] - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_check_catches_mismatched_pairs() {
+        let data = rows();
+        let normalizer = fit_normalizer(&data, Scaling::MinMax).unwrap();
+        let scaled = Dataset {
+            features: data
+                .features
+                .iter()
+                .map(|row| {
+                    normalizer
+                        .normalize(row.as_slice().try_into().unwrap())
+                        .to_vec()
+                })
+                .collect(),
+            ..data.clone()
+        };
+        let tree = CartTrainer::new().with_min_samples_leaf(5).fit(&scaled);
+        let bounds = OutputBounds {
+            min: vec![1.0],
+            max: vec![64.0],
+        };
+        let reflex =
+            build_reflex(vec![tree], N, bounds, training_metadata("v1", "", 0.0, "")).unwrap();
+
+        // Feature 1 is constant but unused
+        assert!(check_normalizer(&reflex, &normalizer).is_ok());
+        assert!(check_normalizer(&reflex, &Normalizer::new()).is_err());
+
+        let mut collapsed = normalizer.clone();
+        collapsed.max[0] = collapsed.min[0];
+        let message = check_normalizer(&reflex, &collapsed)
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("runq_len"), "{}", message);
+
+        let mut infinite = normalizer;
+        infinite.max[4] = f32::INFINITY;
+        assert!(check_normalizer(&reflex, &infinite).is_err());
+    }
+}
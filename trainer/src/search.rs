@@ -0,0 +1,357 @@
+//! Hyperparameter search with k-fold cross-validation
+//!
+//! Candidates come from a grid over, or random draws from, a
+//! [`SearchSpace`]; each is scored by mean validation MAE across the same
+//! k folds, so scores are comparable between candidates. Folds are a
+//! seeded shuffle by default; [`HyperparamSearch::with_blocked_folds`]
+//! validates on contiguous time blocks instead.
+
+use crate::{blocked_folds, mean_absolute_error, Dataset};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use reflex_format::{CvEntry, ReflexMetadata, TrainingReport};
+use std::fmt;
+use std::time::Duration;
+
+/// One point in the search space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hyperparams {
+    pub max_depth: usize,
+    pub min_samples_leaf: usize,
+    pub lambda: f32,
+}
+
+impl fmt::Display for Hyperparams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "max_depth={} min_samples_leaf={} lambda={}",
+            self.max_depth, self.min_samples_leaf, self.lambda
+        )
+    }
+}
+
+/// Values to try for each hyperparameter
+#[derive(Debug, Clone)]
+pub struct SearchSpace {
+    pub max_depth: Vec<usize>,
+    pub min_samples_leaf: Vec<usize>,
+    pub lambda: Vec<f32>,
+}
+
+impl SearchSpace {
+    /// Every combination, depth-major
+    pub fn grid(&self) -> Vec<Hyperparams> {
+        let mut grid = Vec::new();
+        for &max_depth in &self.max_depth {
+            for &min_samples_leaf in &self.min_samples_leaf {
+                for &lambda in &self.lambda {
+                    grid.push(Hyperparams {
+                        max_depth,
+                        min_samples_leaf,
+                        lambda,
+                    });
+                }
+            }
+        }
+        grid
+    }
+
+    /// `n` distinct combinations drawn without replacement (all of them if the grid is smaller)
+    pub fn sample(&self, n: usize, seed: u64) -> Vec<Hyperparams> {
+        let mut grid = self.grid();
+        grid.shuffle(&mut StdRng::seed_from_u64(seed));
+        grid.truncate(n);
+        grid
+    }
+}
+
+impl Default for SearchSpace {
+    /// Around the forge defaults (depth 4, 20 per leaf)
+    fn default() -> Self {
+        Self {
+            max_depth: vec![2, 3, 4, 5, 6],
+            min_samples_leaf: vec![5, 10, 20, 40],
+            lambda: vec![0.0, 0.1, 1.0],
+        }
+    }
+}
+
+/// Cross-validated score for one candidate
+#[derive(Debug, Clone)]
+pub struct CvScore {
+    pub params: Hyperparams,
+    pub mean_mae: f64,
+    pub std_mae: f64, // across folds
+}
+
+/// Scores for every candidate tried, best first
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub folds: usize,
+    pub scores: Vec<CvScore>,
+}
+
+impl SearchResult {
+    pub fn best(&self) -> &CvScore {
+        &self.scores[0]
+    }
+
+    /// Record the winning configuration in a reflex's metadata
+    ///
+    /// `lambda` takes the best value; the full configuration and its CV
+    /// score are appended to the notes.
+    pub fn write_metadata(&self, metadata: &mut ReflexMetadata) {
+        let best = self.best();
+        metadata.lambda = best.params.lambda;
+        let summary = format!(
+            "cv: {} mae={:.4}±{:.4} ({}-fold, {} candidates)",
+            best.params,
+            best.mean_mae,
+            best.std_mae,
+            self.folds,
+            self.scores.len()
+        );
+        if metadata.notes.is_empty() {
+            metadata.notes = summary;
+        } else {
+            metadata.notes = format!("{}; {}", metadata.notes, summary);
+        }
+    }
+
+    /// Record every candidate's CV score in a training report
+    pub fn write_report(&self, report: &mut TrainingReport) {
+        report.cv_scores = self
+            .scores
+            .iter()
+            .map(|score| CvEntry {
+                params: score.params.to_string(),
+                mean_mae: score.mean_mae,
+                std_mae: score.std_mae,
+            })
+            .collect();
+    }
+}
+
+/// k-fold cross-validated search over a [`SearchSpace`]
+#[derive(Debug, Clone)]
+pub struct HyperparamSearch {
+    pub space: SearchSpace,
+    pub folds: usize,
+    pub seed: u64,
+    pub blocked_gap_us: Option<u64>, // time-blocked folds instead of shuffled ones
+}
+
+impl HyperparamSearch {
+    /// 5-fold search over `space`
+    pub fn new(space: SearchSpace) -> Self {
+        Self {
+            space,
+            folds: 5,
+            seed: 0,
+            blocked_gap_us: None,
+        }
+    }
+
+    pub fn with_folds(mut self, folds: usize) -> Self {
+        assert!(folds >= 2, "cross-validation needs at least two folds");
+        self.folds = folds;
+        self
+    }
+
+    /// Seed for the fold assignment and random sampling
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Validate on contiguous blocks of time, dropping training rows within `gap` of each block
+    ///
+    /// See [`blocked_folds`]; use this on telemetry, where shuffled folds leak
+    /// overlapping windows into validation.
+    pub fn with_blocked_folds(mut self, gap: Duration) -> Self {
+        self.blocked_gap_us = Some(gap.as_micros() as u64);
+        self
+    }
+
+    /// Score every combination in the space
+    ///
+    /// `fit` trains a model for one candidate and returns its predictor;
+    /// how `lambda` is used is up to the model being searched.
+    pub fn grid<M, F>(&self, data: &Dataset, fit: F) -> SearchResult
+    where
+        M: Fn(&[f32]) -> f32,
+        F: Fn(&Hyperparams, &Dataset) -> M,
+    {
+        self.evaluate(data, self.space.grid(), fit)
+    }
+
+    /// Score `n` random combinations from the space
+    pub fn random<M, F>(&self, data: &Dataset, n: usize, fit: F) -> SearchResult
+    where
+        M: Fn(&[f32]) -> f32,
+        F: Fn(&Hyperparams, &Dataset) -> M,
+    {
+        self.evaluate(data, self.space.sample(n, self.seed), fit)
+    }
+
+    fn evaluate<M, F>(&self, data: &Dataset, candidates: Vec<Hyperparams>, fit: F) -> SearchResult
+    where
+        M: Fn(&[f32]) -> f32,
+        F: Fn(&Hyperparams, &Dataset) -> M,
+    {
+        assert!(!candidates.is_empty(), "search space is empty");
+        assert!(data.len() >= self.folds, "fewer rows than folds");
+        let folds = self.fold_splits(data);
+        let mut scores: Vec<CvScore> = candidates
+            .into_iter()
+            .map(|params| {
+                let maes: Vec<f64> = folds
+                    .iter()
+                    .map(|(train, valid)| {
+                        let model = fit(&params, train);
+                        mean_absolute_error(valid, model)
+                    })
+                    .collect();
+                let mean_mae = maes.iter().sum::<f64>() / maes.len() as f64;
+                let variance =
+                    maes.iter().map(|m| (m - mean_mae).powi(2)).sum::<f64>() / maes.len() as f64;
+                CvScore {
+                    params,
+                    mean_mae,
+                    std_mae: variance.sqrt(),
+                }
+            })
+            .collect();
+        scores.sort_by(|a, b| a.mean_mae.total_cmp(&b.mean_mae));
+        SearchResult {
+            folds: self.folds,
+            scores,
+        }
+    }
+
+    /// (train, validation) pairs, each row validated exactly once
+    fn fold_splits(&self, data: &Dataset) -> Vec<(Dataset, Dataset)> {
+        if let Some(gap_us) = self.blocked_gap_us {
+            return blocked_folds(data, self.folds, gap_us)
+                .into_iter()
+                .map(|(train, valid)| (data.subset(&train), data.subset(&valid)))
+                .collect();
+        }
+        let mut rows: Vec<usize> = (0..data.len()).collect();
+        rows.shuffle(&mut StdRng::seed_from_u64(self.seed));
+        (0..self.folds)
+            .map(|fold| {
+                let (mut train, mut valid) = (Vec::new(), Vec::new());
+                for (i, &row) in rows.iter().enumerate() {
+                    if i % self.folds == fold {
+                        valid.push(row);
+                    } else {
+                        train.push(row);
+                    }
+                }
+                (data.subset(&train), data.subset(&valid))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{training_metadata, CartTrainer, RidgeTrainer};
+
+    fn noisy_step() -> Dataset {
+        let features: Vec<Vec<f32>> = (0..200)
+            .map(|i| vec![i as f32 / 200.0, ((i * 53) % 200) as f32 / 200.0])
+            .collect();
+        let targets = features
+            .iter()
+            .enumerate()
+            .map(|(i, r)| if r[0] < 0.5 { 8.0 } else { 24.0 } + ((i * 31) % 7) as f32 - 3.0)
+            .collect();
+        Dataset::new(features, targets).unwrap()
+    }
+
+    #[test]
+    fn test_grid_prefers_generalizing_trees() {
+        let space = SearchSpace {
+            max_depth: vec![1, 8],
+            min_samples_leaf: vec![1, 20],
+            lambda: vec![0.0],
+        };
+        let result = HyperparamSearch::new(space)
+            .with_folds(4)
+            .grid(&noisy_step(), |p, train| {
+                let tree = CartTrainer::new()
+                    .with_max_depth(p.max_depth)
+                    .with_min_samples_leaf(p.min_samples_leaf)
+                    .fit(train);
+                move |row: &[f32]| tree.predict(row)
+            });
+        assert_eq!(result.scores.len(), 4);
+        assert!(result
+            .scores
+            .windows(2)
+            .all(|w| w[0].mean_mae <= w[1].mean_mae));
+        // Deep trees with single-sample leaves memorize the noise
+        let worst = result.scores.last().unwrap();
+        assert_eq!(
+            (worst.params.max_depth, worst.params.min_samples_leaf),
+            (8, 1)
+        );
+    }
+
+    #[test]
+    fn test_random_search_writes_best_into_metadata() {
+        let space = SearchSpace {
+            max_depth: vec![0],
+            min_samples_leaf: vec![1],
+            lambda: vec![0.01, 1.0, 1000.0],
+        };
+        let search = HyperparamSearch::new(space).with_seed(3);
+        let result = search.random(&noisy_step(), 2, |p, train| {
+            let model = RidgeTrainer::new().with_lambda(p.lambda as f64).fit(train);
+            move |row: &[f32]| model.eval(row)
+        });
+        assert_eq!(result.scores.len(), 2);
+
+        let mut metadata = training_metadata("v1", "", 0.0, "step");
+        result.write_metadata(&mut metadata);
+        assert_eq!(metadata.lambda, result.best().params.lambda);
+        assert!(metadata.notes.starts_with("step; cv: max_depth=0"));
+        assert!(metadata.notes.contains("5-fold, 2 candidates"));
+    }
+
+    #[test]
+    fn test_blocked_folds_score_drift_honestly() {
+        // A slowly drifting target, with row index as the only feature: any
+        // row's neighbours pin it down, but the future isn't known
+        let features = (0..200).map(|i| vec![i as f32]).collect();
+        let targets = (0..200)
+            .map(|i| 16.0 + 8.0 * (i as f32 / 15.0).sin())
+            .collect();
+        let data = Dataset::new(features, targets)
+            .unwrap()
+            .with_timestamps((0..200).map(|i| i * 1_000_000).collect())
+            .unwrap();
+        let space = SearchSpace {
+            max_depth: vec![8],
+            min_samples_leaf: vec![1],
+            lambda: vec![0.0],
+        };
+        let fit = |p: &Hyperparams, train: &Dataset| {
+            let tree = CartTrainer::new()
+                .with_max_depth(p.max_depth)
+                .with_min_samples_leaf(p.min_samples_leaf)
+                .fit(train);
+            move |row: &[f32]| tree.predict(row)
+        };
+        let shuffled = HyperparamSearch::new(space.clone()).grid(&data, fit);
+        let blocked = HyperparamSearch::new(space)
+            .with_blocked_folds(Duration::from_secs(5))
+            .grid(&data, fit);
+        assert!(blocked.best().mean_mae > 4.0 * shuffled.best().mean_mae);
+    }
+}
@@ -0,0 +1,174 @@
+//! Time-based train/validation splits
+//!
+//! Adjacent telemetry windows overlap and are heavily autocorrelated, so a
+//! randomly held-out row usually has a near-copy in the training set and
+//! validation scores come out far too rosy. These splits hold out
+//! contiguous stretches of time instead, and purge training rows within a
+//! `gap` of the held-out stretch so overlapping windows can't leak across.
+//! Times come from [`Dataset::time_of`].
+
+use crate::Dataset;
+
+/// (train rows, validation rows) for one split
+pub type Fold = (Vec<usize>, Vec<usize>);
+
+impl Dataset {
+    /// Train on rows observed before `cutoff_us - gap_us`, validate on rows from `cutoff_us` on
+    pub fn split_at_time(&self, cutoff_us: u64, gap_us: u64) -> (Dataset, Dataset) {
+        let (train, valid): (Vec<usize>, Vec<usize>) = (0..self.len())
+            .filter(|&r| {
+                self.time_of(r) >= cutoff_us || self.time_of(r) < cutoff_us.saturating_sub(gap_us)
+            })
+            .partition(|&r| self.time_of(r) < cutoff_us);
+        (self.subset(&train), self.subset(&valid))
+    }
+
+    /// Hold out the latest `valid_fraction` of rows, purging `gap_us` before them
+    pub fn time_holdout(&self, valid_fraction: f64, gap_us: u64) -> (Dataset, Dataset) {
+        assert!(
+            (0.0..1.0).contains(&valid_fraction),
+            "validation fraction must be in [0, 1)"
+        );
+        let n_valid = (self.len() as f64 * valid_fraction) as usize;
+        let (train, valid) = latest_rows(self, n_valid, gap_us);
+        (self.subset(&train), self.subset(&valid))
+    }
+}
+
+/// Validate on the latest `n_valid` rows, train on the earlier ones outside `gap_us`
+pub(crate) fn latest_rows(data: &Dataset, n_valid: usize, gap_us: u64) -> Fold {
+    let order = time_order(data);
+    if n_valid == 0 {
+        return (order, Vec::new());
+    }
+    purged_split(data, &order, data.len() - n_valid..data.len(), gap_us)
+}
+
+/// `k` folds, each validating on one contiguous block of time
+///
+/// Every row is validated once; training rows come from both sides of the
+/// block, minus those within `gap_us` of it.
+pub fn blocked_folds(data: &Dataset, k: usize, gap_us: u64) -> Vec<Fold> {
+    assert!(
+        k >= 2 && data.len() >= k,
+        "need at least two folds and a row per fold"
+    );
+    let order = time_order(data);
+    (0..k)
+        .map(|fold| purged_split(data, &order, block(data.len(), k, fold), gap_us))
+        .collect()
+}
+
+/// `k` forward-chaining folds: fold `i` trains on the first `i + 1` of
+/// `k + 1` time blocks and validates on the next
+///
+/// Training never sees the future, as in deployment; rows within `gap_us`
+/// before the validation block are purged.
+pub fn forward_folds(data: &Dataset, k: usize, gap_us: u64) -> Vec<Fold> {
+    assert!(
+        k >= 1 && data.len() > k,
+        "need a fold and more rows than folds"
+    );
+    let order = time_order(data);
+    (0..k)
+        .map(|fold| {
+            let valid_block = block(data.len(), k + 1, fold + 1);
+            let valid_start = data.time_of(order[valid_block.start]);
+            let valid = order[valid_block.clone()].to_vec();
+            let train = order[..valid_block.start]
+                .iter()
+                .copied()
+                .filter(|&r| data.time_of(r) < valid_start.saturating_sub(gap_us))
+                .collect();
+            (train, valid)
+        })
+        .collect()
+}
+
+/// Rows sorted by time (stable, so row order breaks ties)
+fn time_order(data: &Dataset) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.sort_by_key(|&r| data.time_of(r));
+    order
+}
+
+/// Position range of block `i` of `k` near-equal blocks over `n` rows
+fn block(n: usize, k: usize, i: usize) -> std::ops::Range<usize> {
+    (n * i / k)..(n * (i + 1) / k)
+}
+
+/// Validate on `order[valid]`, train on the rest outside `gap_us` of its time span
+fn purged_split(
+    data: &Dataset,
+    order: &[usize],
+    valid: std::ops::Range<usize>,
+    gap_us: u64,
+) -> Fold {
+    let start = data.time_of(order[valid.start]);
+    let end = data.time_of(order[valid.end - 1]);
+    let train = order[..valid.start]
+        .iter()
+        .chain(&order[valid.end..])
+        .copied()
+        .filter(|&r| {
+            let t = data.time_of(r);
+            t < start.saturating_sub(gap_us) || t > end.saturating_add(gap_us)
+        })
+        .collect();
+    (train, order[valid].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 20 rows, one every 100ms
+    fn series() -> Dataset {
+        let features = (0..20).map(|i| vec![i as f32]).collect();
+        let targets = (0..20).map(|i| i as f32).collect();
+        Dataset::new(features, targets)
+            .unwrap()
+            .with_timestamps((0..20).map(|i| i * 100_000).collect())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_holdout_and_cutoff_purge_the_gap() {
+        let data = series();
+        let (train, valid) = data.time_holdout(0.25, 150_000);
+        assert_eq!(
+            valid.timestamps_us,
+            vec![1_500_000, 1_600_000, 1_700_000, 1_800_000, 1_900_000]
+        );
+        assert_eq!(train.timestamps_us.last(), Some(&1_300_000)); // 1.4 s is within the gap
+
+        let (train, valid) = data.split_at_time(1_000_000, 0);
+        assert_eq!((train.len(), valid.len()), (10, 10));
+    }
+
+    #[test]
+    fn test_blocked_folds_validate_every_row_once() {
+        let data = series();
+        let folds = blocked_folds(&data, 4, 100_000);
+        let mut validated: Vec<usize> = folds.iter().flat_map(|(_, valid)| valid.clone()).collect();
+        validated.sort();
+        assert_eq!(validated, (0..20).collect::<Vec<_>>());
+        // Fold 1 holds out rows 5..10 and purges one row either side
+        let (train, valid) = &folds[1];
+        assert_eq!(valid, &vec![5, 6, 7, 8, 9]);
+        assert!(!train.contains(&4) && !train.contains(&10));
+        assert_eq!(train.len(), 13);
+    }
+
+    #[test]
+    fn test_forward_folds_never_train_on_the_future() {
+        let data = series();
+        let folds = forward_folds(&data, 3, 0);
+        assert_eq!(folds.len(), 3);
+        for (train, valid) in &folds {
+            assert!(train.iter().all(|&t| valid.iter().all(|&v| t < v)));
+        }
+        assert_eq!(folds[0], ((0..5).collect(), (5..10).collect()));
+        assert_eq!(folds[2].0.len(), 15);
+    }
+}
@@ -0,0 +1,209 @@
+//! Two-feature lookup tables
+//!
+//! A reviewer can't read a boosted ensemble, but they can read a 4×4
+//! table. [`TableTrainer`] picks the two features that matter most, cuts
+//! each at quantiles of its training values, and fills every cell with
+//! the loss's best constant for the rows in it, giving a
+//! [`LookupTable`] reflex that prints as exactly what it does.
+
+use crate::{Dataset, Loss};
+use reflex_format::LookupTable;
+
+/// Fits a [`LookupTable`] over the two most important features
+#[derive(Debug, Clone)]
+pub struct TableTrainer {
+    pub bins: usize, // per feature
+    pub loss: Loss,
+    pub features: Option<(usize, usize)>, // (row, column); picked from the data if unset
+}
+
+impl TableTrainer {
+    /// 4×4 cells under squared loss, features picked from the data
+    pub fn new() -> Self {
+        Self {
+            bins: 4,
+            loss: Loss::Squared,
+            features: None,
+        }
+    }
+
+    pub fn with_bins(mut self, bins: usize) -> Self {
+        assert!(bins > 0, "a table needs at least one bin");
+        self.bins = bins;
+        self
+    }
+
+    pub fn with_loss(mut self, loss: Loss) -> Self {
+        self.loss = loss;
+        self
+    }
+
+    /// Use these features for rows and columns instead of ranking them
+    pub fn with_features(mut self, row: usize, col: usize) -> Self {
+        assert_ne!(row, col, "a table needs two different features");
+        self.features = Some((row, col));
+        self
+    }
+
+    /// Features by how much binning on each alone cuts training loss, most first
+    pub fn rank_features(&self, data: &Dataset) -> Vec<usize> {
+        let baseline = self.cell_loss(data, &(0..data.len()).collect::<Vec<_>>());
+        let mut gains: Vec<(usize, f64)> = (0..data.feature_count())
+            .map(|feature| {
+                let edges = quantile_edges(data, feature, self.bins);
+                let mut cells = vec![Vec::new(); edges.len() + 1];
+                for (r, row) in data.features.iter().enumerate() {
+                    cells[bin(&edges, row[feature])].push(r);
+                }
+                let loss: f64 = cells.iter().map(|rows| self.cell_loss(data, rows)).sum();
+                (feature, baseline - loss)
+            })
+            .collect();
+        gains.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        gains.into_iter().map(|(feature, _)| feature).collect()
+    }
+
+    /// Fit the table for one output
+    ///
+    /// Empty cells take their row's value, or the overall value if the
+    /// whole row is empty.
+    pub fn fit(&self, data: &Dataset) -> LookupTable {
+        assert!(!data.is_empty(), "cannot fit a table to no rows");
+        assert!(data.feature_count() >= 2, "a table needs two features");
+        let (row_feature, col_feature) = self.features.unwrap_or_else(|| {
+            let ranked = self.rank_features(data);
+            (ranked[0], ranked[1])
+        });
+        let row_edges = quantile_edges(data, row_feature, self.bins);
+        let col_edges = quantile_edges(data, col_feature, self.bins);
+
+        let mut cells = vec![vec![Vec::new(); col_edges.len() + 1]; row_edges.len() + 1];
+        for (r, row) in data.features.iter().enumerate() {
+            cells[bin(&row_edges, row[row_feature])][bin(&col_edges, row[col_feature])].push(r);
+        }
+        let overall = self.cell_value(data, &(0..data.len()).collect::<Vec<_>>());
+        let values = cells
+            .iter()
+            .map(|row_cells| {
+                let row_rows: Vec<usize> = row_cells.concat();
+                let fallback = if row_rows.is_empty() {
+                    overall
+                } else {
+                    self.cell_value(data, &row_rows)
+                };
+                row_cells
+                    .iter()
+                    .map(|rows| {
+                        if rows.is_empty() {
+                            fallback
+                        } else {
+                            self.cell_value(data, rows)
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        LookupTable {
+            row_feature: row_feature as u8,
+            col_feature: col_feature as u8,
+            row_edges,
+            col_edges,
+            values,
+        }
+    }
+
+    fn cell_value(&self, data: &Dataset, rows: &[usize]) -> f32 {
+        let mut targets: Vec<f32> = rows.iter().map(|&r| data.targets[r]).collect();
+        self.loss.optimum(&mut targets)
+    }
+
+    fn cell_loss(&self, data: &Dataset, rows: &[usize]) -> f64 {
+        if rows.is_empty() {
+            return 0.0;
+        }
+        let value = self.cell_value(data, rows);
+        rows.iter()
+            .map(|&r| self.loss.value(data.targets[r], value))
+            .sum()
+    }
+}
+
+impl Default for TableTrainer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Up to `bins - 1` distinct cut points splitting `feature` into roughly equal-count bins
+///
+/// Each edge is the midpoint between the values either side of the cut,
+/// so training rows never sit on an edge.
+fn quantile_edges(data: &Dataset, feature: usize, bins: usize) -> Vec<f32> {
+    let mut values: Vec<f32> = data.features.iter().map(|row| row[feature]).collect();
+    values.sort_by(f32::total_cmp);
+    let mut edges: Vec<f32> = Vec::new();
+    for k in 1..bins {
+        // A cut inside a run of equal values moves to the run's end
+        let mut cut = (k * values.len() / bins).max(1);
+        while cut < values.len() && values[cut - 1] == values[cut] {
+            cut += 1;
+        }
+        if cut >= values.len() {
+            break;
+        }
+        let edge = values[cut - 1] + (values[cut] - values[cut - 1]) / 2.0;
+        if edges.last().is_none_or(|&last| edge > last) {
+            edges.push(edge);
+        }
+    }
+    edges
+}
+
+/// Bin index as `LookupTable::eval` computes it
+fn bin(edges: &[f32], value: f32) -> usize {
+    edges.partition_point(|&edge| value > edge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mean_absolute_error;
+
+    /// Target is a 2×2 pattern over features 2 and 0; feature 1 is noise
+    fn grid() -> Dataset {
+        let features: Vec<Vec<f32>> = (0..400)
+            .map(|i| {
+                vec![
+                    (i % 20) as f32 / 20.0,
+                    ((i * 37) % 100) as f32 / 100.0,
+                    (i / 20) as f32 / 20.0,
+                ]
+            })
+            .collect();
+        let targets = features
+            .iter()
+            .map(|r| 4.0 + 8.0 * (r[2] >= 0.5) as u8 as f32 + 2.0 * (r[0] >= 0.5) as u8 as f32)
+            .collect();
+        Dataset::new(features, targets).unwrap()
+    }
+
+    #[test]
+    fn test_table_picks_the_informative_features() {
+        let data = grid();
+        let trainer = TableTrainer::new();
+        assert_eq!(trainer.rank_features(&data)[..2], [2, 0]);
+
+        let table = trainer.fit(&data);
+        assert_eq!((table.row_feature, table.col_feature), (2, 0));
+        assert_eq!(table.values.len(), 4);
+        assert_eq!(mean_absolute_error(&data, |row| table.eval(row)), 0.0);
+        assert_eq!(table.eval(&[0.9, 0.0, 0.1]), 6.0);
+
+        let pinned = TableTrainer::new()
+            .with_bins(2)
+            .with_features(1, 2)
+            .fit(&data);
+        assert_eq!((pinned.row_edges.len(), pinned.col_edges.len()), (1, 1));
+        assert_eq!(pinned.row_feature, 1);
+    }
+}
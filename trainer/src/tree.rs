@@ -0,0 +1,813 @@
+//! CART regression trees
+//!
+//! Greedy top-down induction: each node takes the split with the largest
+//! reduction in squared error, until the depth limit, the leaf-size limit
+//! or a node with nothing left to gain.
+//!
+//! With a smoothness penalty λ the objective becomes
+//! `Σ (y_t - f(x_t))² + λ Σ (f(x_t) - f(x_{t-1}))²` over temporally
+//! adjacent rows (consecutive row indices): a split is charged for the
+//! adjacent pairs it separates, and the leaf values are refit jointly so
+//! leaves the trajectory hops between are pulled together. This is the
+//! decision-flapping cost the reflex metadata's `lambda` records.
+//!
+//! Splits are always chosen by squared error; under [`Loss::Quantile`]
+//! each leaf then predicts the τ-quantile of its targets instead of their
+//! mean (the joint smoothed refit only applies to squared loss).
+//!
+//! Monotonic constraints follow the bounded-leaf scheme: a split on a
+//! constrained feature is rejected if its sides point the wrong way, and
+//! otherwise caps every leaf below it on one side of the midpoint between
+//! the two sides' values, so no leaf further down can undo the ordering.
+//!
+//! [`CartTrainer::fit_multi`] grows one tree for several outputs at once:
+//! a split is scored by the squared-error reduction summed over outputs,
+//! each divided by its variance so an output measured in microseconds
+//! can't outvote one measured in packets, and every leaf holds a value per
+//! output. Outputs that come from the same leaf can't disagree the way
+//! separately trained trees do.
+
+use crate::ridge::cholesky_solve;
+use crate::{Dataset, Loss};
+use reflex_format::{MultiOutputTree, TreeNode};
+
+/// Regression tree in the `.reflex` node layout (preorder, root at 0)
+#[derive(Debug, Clone)]
+pub struct RegressionTree {
+    pub nodes: Vec<TreeNode>,
+}
+
+impl RegressionTree {
+    /// Evaluate the tree on one feature row, as `Reflex::infer` does before clamping
+    pub fn predict(&self, features: &[f32]) -> f32 {
+        self.nodes[self.leaf_index(features)].threshold
+    }
+
+    /// Index of the leaf node `features` lands in
+    pub fn leaf_index(&self, features: &[f32]) -> usize {
+        let mut node_idx = 0;
+        loop {
+            let node = &self.nodes[node_idx];
+            if node.is_leaf() {
+                return node_idx;
+            }
+            node_idx = if features[node.feature_idx as usize] <= node.threshold {
+                node.left as usize
+            } else {
+                node.right as usize
+            };
+        }
+    }
+
+    /// Longest root-to-leaf path, in splits
+    pub fn depth(&self) -> usize {
+        fn depth_from(nodes: &[TreeNode], idx: usize) -> usize {
+            let node = &nodes[idx];
+            if node.is_leaf() {
+                return 0;
+            }
+            1 + depth_from(nodes, node.left as usize).max(depth_from(nodes, node.right as usize))
+        }
+        depth_from(&self.nodes, 0)
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.nodes.iter().filter(|n| n.is_leaf()).count()
+    }
+}
+
+/// Direction a feature's effect on the output is constrained to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Monotonicity {
+    Increasing,
+    Decreasing,
+}
+
+/// Greedy regression-tree trainer (variance reduction)
+#[derive(Debug, Clone)]
+pub struct CartTrainer {
+    pub max_depth: usize,
+    pub min_samples_leaf: usize,
+    pub smoothness: f32, // λ on squared output changes between adjacent rows
+    pub loss: Loss,
+    pub monotone: Vec<(usize, Monotonicity)>, // (feature index, direction) per constrained feature
+}
+
+/// Inputs and output of one tree being grown
+struct Growth<'a> {
+    features: &'a [Vec<f32>],
+    split_targets: &'a [f32],
+    leaf_targets: &'a [f32],
+    nodes: Vec<TreeNode>,
+    ranges: Vec<(f32, f32)>, // allowed leaf values per node, from monotone splits above it
+}
+
+/// Inputs and output of one multi-output tree being grown
+struct MultiGrowth<'a> {
+    features: &'a [Vec<f32>],
+    targets: &'a [Vec<f32>], // one column per output
+    scaled: Vec<Vec<f32>>,   // columns divided by their standard deviation
+    nodes: Vec<TreeNode>,
+    leaves: Vec<Vec<f32>>,
+}
+
+/// Split chosen for a node
+struct Split {
+    feature: usize,
+    threshold: f32,
+    gain: f64, // reduction in summed squared error
+}
+
+impl CartTrainer {
+    /// Deepest tree whose node indices fit the format's `u16` child links
+    pub const MAX_DEPTH_LIMIT: usize = 15;
+
+    /// Depth 4 and 20 samples per leaf, as the forge trainers use
+    pub fn new() -> Self {
+        Self {
+            max_depth: 4,
+            min_samples_leaf: 20,
+            smoothness: 0.0,
+            loss: Loss::Squared,
+            monotone: Vec::new(),
+        }
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        assert!(
+            max_depth <= Self::MAX_DEPTH_LIMIT,
+            "tree depth limited to {}",
+            Self::MAX_DEPTH_LIMIT
+        );
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_min_samples_leaf(mut self, min_samples_leaf: usize) -> Self {
+        assert!(min_samples_leaf > 0, "leaves need at least one sample");
+        self.min_samples_leaf = min_samples_leaf;
+        self
+    }
+
+    /// Penalize output changes between adjacent rows by `lambda`
+    pub fn with_smoothness(mut self, lambda: f32) -> Self {
+        assert!(lambda >= 0.0, "smoothness penalty must be non-negative");
+        self.smoothness = lambda;
+        self
+    }
+
+    /// Loss the leaf values minimize
+    pub fn with_loss(mut self, loss: Loss) -> Self {
+        loss.validate();
+        self.loss = loss;
+        self
+    }
+
+    /// Constrain the output to move only in `direction` as `feature` grows
+    ///
+    /// e.g. worker count non-decreasing in run-queue length.
+    pub fn with_monotone(mut self, feature: usize, direction: Monotonicity) -> Self {
+        self.monotone.retain(|&(f, _)| f != feature);
+        self.monotone.push((feature, direction));
+        self
+    }
+
+    fn constraint(&self, feature: usize) -> Option<Monotonicity> {
+        self.monotone
+            .iter()
+            .find(|&&(f, _)| f == feature)
+            .map(|&(_, direction)| direction)
+    }
+
+    /// Fit a tree to every row of `data`
+    pub fn fit(&self, data: &Dataset) -> RegressionTree {
+        let rows: Vec<usize> = (0..data.len()).collect();
+        self.fit_rows(&data.features, &data.targets, &rows)
+    }
+
+    /// Fit a tree to `targets` over the given rows of `features`
+    ///
+    /// Rows may repeat (bootstrap samples); `targets` is indexed like
+    /// `features`, so residuals can stand in for the dataset's targets.
+    pub fn fit_rows(
+        &self,
+        features: &[Vec<f32>],
+        targets: &[f32],
+        rows: &[usize],
+    ) -> RegressionTree {
+        self.fit_rows_split_on(features, targets, targets, rows)
+    }
+
+    /// Choose splits by `split_targets` but set leaves from `leaf_targets`
+    ///
+    /// Boosting splits on the loss gradient and takes leaf values from the residuals.
+    pub(crate) fn fit_rows_split_on(
+        &self,
+        features: &[Vec<f32>],
+        split_targets: &[f32],
+        leaf_targets: &[f32],
+        rows: &[usize],
+    ) -> RegressionTree {
+        assert!(!rows.is_empty(), "cannot fit a tree to no rows");
+        let mut growth = Growth {
+            features,
+            split_targets,
+            leaf_targets,
+            nodes: Vec::new(),
+            ranges: Vec::new(),
+        };
+        let mut rows = rows.to_vec();
+        self.grow(
+            &mut growth,
+            &mut rows,
+            0,
+            (f32::NEG_INFINITY, f32::INFINITY),
+        );
+        let Growth { nodes, ranges, .. } = growth;
+        let mut tree = RegressionTree { nodes };
+        if self.smoothness > 0.0 && self.loss == Loss::Squared {
+            self.refit_leaves(&mut tree, features, leaf_targets, &rows);
+            for (node, &(low, high)) in tree.nodes.iter_mut().zip(&ranges) {
+                if node.is_leaf() {
+                    *node = TreeNode::leaf(node.threshold.clamp(low, high));
+                }
+            }
+        }
+        tree
+    }
+
+    /// Fit one tree to several target columns, indexed like `features`
+    ///
+    /// Leaves hold the loss's optimum for every output. Smoothness and
+    /// monotone constraints apply to single-output trees only.
+    pub fn fit_multi(&self, features: &[Vec<f32>], targets: &[Vec<f32>]) -> MultiOutputTree {
+        assert!(!features.is_empty(), "cannot fit a tree to no rows");
+        assert!(!targets.is_empty(), "need at least one output");
+        assert!(
+            targets.iter().all(|column| column.len() == features.len()),
+            "need one target per row in every output"
+        );
+        let scaled = targets
+            .iter()
+            .map(|column| {
+                let n = column.len() as f64;
+                let mean = column.iter().map(|&y| y as f64).sum::<f64>() / n;
+                let var = column
+                    .iter()
+                    .map(|&y| (y as f64 - mean).powi(2))
+                    .sum::<f64>()
+                    / n;
+                // A constant output has nothing to split on
+                let scale = if var > 0.0 { 1.0 / var.sqrt() } else { 0.0 };
+                column.iter().map(|&y| (y as f64 * scale) as f32).collect()
+            })
+            .collect();
+        let mut growth = MultiGrowth {
+            features,
+            targets,
+            scaled,
+            nodes: Vec::new(),
+            leaves: Vec::new(),
+        };
+        let mut rows: Vec<usize> = (0..features.len()).collect();
+        self.grow_multi(&mut growth, &mut rows, 0);
+        MultiOutputTree {
+            nodes: growth.nodes,
+            leaves: growth.leaves,
+        }
+    }
+
+    /// Append the multi-output subtree for `rows` in preorder, returning its root index
+    fn grow_multi(&self, growth: &mut MultiGrowth, rows: &mut [usize], depth: usize) -> u16 {
+        let idx = growth.nodes.len() as u16;
+        let split = if depth < self.max_depth {
+            self.best_multi_split(growth.features, &growth.scaled, rows)
+        } else {
+            None
+        };
+        let Some(split) = split else {
+            let unbounded = (f32::NEG_INFINITY, f32::INFINITY);
+            let values = growth
+                .targets
+                .iter()
+                .map(|column| self.leaf_value(column, rows, unbounded))
+                .collect();
+            growth.nodes.push(TreeNode {
+                left: growth.leaves.len() as u16,
+                ..TreeNode::leaf(0.0)
+            });
+            growth.leaves.push(values);
+            return idx;
+        };
+
+        growth.nodes.push(TreeNode::leaf(0.0)); // placeholder until the children are placed
+        let features = growth.features;
+        let (left_rows, right_rows) =
+            partition(rows, |r| features[r][split.feature] <= split.threshold);
+        let left = self.grow_multi(growth, left_rows, depth + 1);
+        let right = self.grow_multi(growth, right_rows, depth + 1);
+        growth.nodes[idx as usize] =
+            TreeNode::split(split.feature as u8, split.threshold, left, right);
+        idx
+    }
+
+    /// Best split of `rows` by squared error summed over the `targets` columns
+    fn best_multi_split(
+        &self,
+        features: &[Vec<f32>],
+        targets: &[Vec<f32>],
+        rows: &[usize],
+    ) -> Option<Split> {
+        let n = rows.len();
+        if n < 2 * self.min_samples_leaf {
+            return None;
+        }
+        let sum_over = |rows: &[usize], column: &[f32]| -> f64 {
+            rows.iter().map(|&r| column[r] as f64).sum()
+        };
+        let total_sums: Vec<f64> = targets
+            .iter()
+            .map(|column| sum_over(rows, column))
+            .collect();
+        let total_sq: f64 = targets
+            .iter()
+            .map(|column| {
+                rows.iter()
+                    .map(|&r| (column[r] as f64).powi(2))
+                    .sum::<f64>()
+            })
+            .sum();
+        let sse = |sq: f64, sums: &mut dyn Iterator<Item = f64>, count: usize| {
+            sq - sums.map(|sum| sum * sum).sum::<f64>() / count as f64
+        };
+        let parent_sse = sse(total_sq, &mut total_sums.iter().copied(), n);
+
+        let mut best: Option<Split> = None;
+        let mut best_gain = 1e-12;
+        let mut sorted = rows.to_vec();
+        let mut left_sums = vec![0.0f64; targets.len()];
+        let feature_count = features[rows[0]].len();
+        #[allow(clippy::needless_range_loop)] // `feature` indexes each row, not `features`
+        for feature in 0..feature_count {
+            sorted.sort_by(|&a, &b| features[a][feature].total_cmp(&features[b][feature]));
+            left_sums.iter_mut().for_each(|sum| *sum = 0.0);
+            let mut left_sq = 0.0;
+            for (i, pair) in sorted.windows(2).enumerate() {
+                let r = pair[0];
+                for (sum, column) in left_sums.iter_mut().zip(targets) {
+                    *sum += column[r] as f64;
+                    left_sq += (column[r] as f64).powi(2);
+                }
+                let left_n = i + 1;
+                let (low, high) = (features[r][feature], features[pair[1]][feature]);
+                if left_n < self.min_samples_leaf
+                    || n - left_n < self.min_samples_leaf
+                    || low == high
+                {
+                    continue;
+                }
+                let left_sse = sse(left_sq, &mut left_sums.iter().copied(), left_n);
+                let mut right_sums = total_sums.iter().zip(&left_sums).map(|(t, l)| t - l);
+                let right_sse = sse(total_sq - left_sq, &mut right_sums, n - left_n);
+                let gain = parent_sse - left_sse - right_sse;
+                if gain > best_gain {
+                    best_gain = gain;
+                    best = Some(Split {
+                        feature,
+                        threshold: midpoint(low, high),
+                        gain,
+                    });
+                }
+            }
+        }
+        best
+    }
+
+    /// Jointly re-solve the leaf values for the smoothed objective, holding the splits fixed
+    ///
+    /// Setting the gradient to zero gives `(D + λL) v = s`: `D` holds each
+    /// leaf's row count, `s` its target sum, and `L` is the Laplacian of
+    /// adjacent-row transitions between leaves.
+    fn refit_leaves(
+        &self,
+        tree: &mut RegressionTree,
+        features: &[Vec<f32>],
+        targets: &[f32],
+        rows: &[usize],
+    ) {
+        let leaves: Vec<usize> = (0..tree.nodes.len())
+            .filter(|&i| tree.nodes[i].is_leaf())
+            .collect();
+        let leaf_of = |row: usize| {
+            leaves
+                .binary_search(&tree.leaf_index(&features[row]))
+                .unwrap()
+        };
+
+        let k = leaves.len();
+        let lambda = self.smoothness as f64;
+        let mut system = vec![vec![0.0f64; k]; k];
+        let mut sums = vec![0.0f64; k];
+        for &r in rows {
+            let leaf = leaf_of(r);
+            system[leaf][leaf] += 1.0;
+            sums[leaf] += targets[r] as f64;
+        }
+        for (a, b) in adjacent_pairs(rows, features.len()) {
+            let (p, q) = (leaf_of(a), leaf_of(b));
+            if p != q {
+                system[p][p] += lambda;
+                system[q][q] += lambda;
+                system[p][q] -= lambda;
+                system[q][p] -= lambda;
+            }
+        }
+        let values = cholesky_solve(system, sums);
+        for (&node, value) in leaves.iter().zip(values) {
+            tree.nodes[node] = TreeNode::leaf(value as f32);
+        }
+    }
+
+    /// Append the subtree for `rows` to the growth in preorder, returning its root index
+    ///
+    /// Leaf values are kept within `range`.
+    fn grow(
+        &self,
+        growth: &mut Growth,
+        rows: &mut [usize],
+        depth: usize,
+        range: (f32, f32),
+    ) -> u16 {
+        let idx = growth.nodes.len() as u16;
+        growth.ranges.push(range);
+        let split = if depth < self.max_depth {
+            self.best_split(growth.features, growth.split_targets, rows)
+        } else {
+            None
+        };
+        let Some(split) = split else {
+            growth.nodes.push(TreeNode::leaf(self.leaf_value(
+                growth.leaf_targets,
+                rows,
+                range,
+            )));
+            return idx;
+        };
+
+        growth.nodes.push(TreeNode::leaf(0.0)); // placeholder until the children are placed
+        let features = growth.features;
+        let (left_rows, right_rows) =
+            partition(rows, |r| features[r][split.feature] <= split.threshold);
+        let (left_range, right_range) = match self.constraint(split.feature) {
+            None => (range, range),
+            Some(direction) => {
+                let left_value = self.leaf_value(growth.leaf_targets, left_rows, range);
+                let right_value = self.leaf_value(growth.leaf_targets, right_rows, range);
+                let mid = (left_value + right_value) / 2.0;
+                match direction {
+                    Monotonicity::Increasing => ((range.0, mid), (mid, range.1)),
+                    Monotonicity::Decreasing => ((mid, range.1), (range.0, mid)),
+                }
+            }
+        };
+        let left = self.grow(growth, left_rows, depth + 1, left_range);
+        let right = self.grow(growth, right_rows, depth + 1, right_range);
+        growth.nodes[idx as usize] =
+            TreeNode::split(split.feature as u8, split.threshold, left, right);
+        idx
+    }
+
+    /// The loss's best constant for `rows`, within `range`
+    fn leaf_value(&self, targets: &[f32], rows: &[usize], range: (f32, f32)) -> f32 {
+        let mut values: Vec<f32> = rows.iter().map(|&r| targets[r]).collect();
+        self.loss.optimum(&mut values).clamp(range.0, range.1)
+    }
+
+    /// Best split of `rows` leaving at least `min_samples_leaf` on each side, if any reduces error
+    fn best_split(&self, features: &[Vec<f32>], targets: &[f32], rows: &[usize]) -> Option<Split> {
+        let n = rows.len();
+        if n < 2 * self.min_samples_leaf {
+            return None;
+        }
+        let total_sum: f64 = rows.iter().map(|&r| targets[r] as f64).sum();
+        let total_sq: f64 = rows.iter().map(|&r| (targets[r] as f64).powi(2)).sum();
+        let parent_sse = total_sq - total_sum * total_sum / n as f64;
+
+        let pairs = if self.smoothness > 0.0 {
+            adjacent_pairs(rows, features.len())
+        } else {
+            Vec::new()
+        };
+        let mut best: Option<Split> = None;
+        let mut sorted: Vec<(f32, f64)> = Vec::with_capacity(n);
+        let (mut pair_lows, mut pair_highs) = (Vec::new(), Vec::new());
+        let feature_count = features[rows[0]].len();
+        #[allow(clippy::needless_range_loop)] // `feature` indexes each row, not `features`
+        for feature in 0..feature_count {
+            sorted.clear();
+            sorted.extend(
+                rows.iter()
+                    .map(|&r| (features[r][feature], targets[r] as f64)),
+            );
+            sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+            pair_lows.clear();
+            pair_highs.clear();
+            for &(a, b) in &pairs {
+                let (x, y) = (features[a][feature], features[b][feature]);
+                pair_lows.push(x.min(y));
+                pair_highs.push(x.max(y));
+            }
+            pair_lows.sort_by(f32::total_cmp);
+            pair_highs.sort_by(f32::total_cmp);
+
+            let min_gain = best.as_ref().map_or(1e-12, |b| b.gain);
+            let crossings = Crossings {
+                lows: &pair_lows,
+                highs: &pair_highs,
+            };
+            let direction = self.constraint(feature);
+            if let Some((threshold, gain)) =
+                self.scan(&sorted, crossings, direction, parent_sse, min_gain)
+            {
+                best = Some(Split {
+                    feature,
+                    threshold,
+                    gain,
+                });
+            }
+        }
+        best
+    }
+
+    /// Best threshold over `sorted` (value, target) pairs gaining more than `min_gain`
+    ///
+    /// With smoothing on, each adjacent pair a threshold separates costs
+    /// `λ (left mean - right mean)²`, the change it would add between them.
+    /// Under a monotone `direction`, thresholds whose side means point the
+    /// other way are skipped.
+    fn scan(
+        &self,
+        sorted: &[(f32, f64)],
+        crossings: Crossings,
+        direction: Option<Monotonicity>,
+        parent_sse: f64,
+        min_gain: f64,
+    ) -> Option<(f32, f64)> {
+        let n = sorted.len();
+        let total_sum: f64 = sorted.iter().map(|(_, y)| y).sum();
+        let total_sq: f64 = sorted.iter().map(|(_, y)| y * y).sum();
+        let mut best = None;
+        let mut best_gain = min_gain;
+        let (mut left_sum, mut left_sq) = (0.0, 0.0);
+        let (mut lows_below, mut highs_below) = (0, 0);
+        for (i, pair) in sorted.windows(2).enumerate() {
+            let (low, y) = pair[0];
+            let high = pair[1].0;
+            left_sum += y;
+            left_sq += y * y;
+            let left_n = i + 1;
+            if left_n < self.min_samples_leaf || n - left_n < self.min_samples_leaf || low == high {
+                continue;
+            }
+            let right_sum = total_sum - left_sum;
+            let mean_gap = left_sum / left_n as f64 - right_sum / (n - left_n) as f64;
+            let wrong_way = match direction {
+                Some(Monotonicity::Increasing) => mean_gap > 0.0,
+                Some(Monotonicity::Decreasing) => mean_gap < 0.0,
+                None => false,
+            };
+            if wrong_way {
+                continue;
+            }
+            let left_sse = left_sq - left_sum * left_sum / left_n as f64;
+            let right_sse = (total_sq - left_sq) - right_sum * right_sum / (n - left_n) as f64;
+            let mut gain = parent_sse - left_sse - right_sse;
+            if !crossings.lows.is_empty() {
+                // Pairs with low <= threshold < high straddle the split
+                lows_below += crossings.lows[lows_below..].partition_point(|&x| x <= low);
+                highs_below += crossings.highs[highs_below..].partition_point(|&x| x <= low);
+                gain -= self.smoothness as f64
+                    * (lows_below - highs_below) as f64
+                    * mean_gap
+                    * mean_gap;
+            }
+            if gain > best_gain {
+                best_gain = gain;
+                best = Some((midpoint(low, high), gain));
+            }
+        }
+        best
+    }
+}
+
+impl Default for CartTrainer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sorted per-pair feature extremes of the adjacent pairs in a node
+#[derive(Clone, Copy)]
+struct Crossings<'a> {
+    lows: &'a [f32],
+    highs: &'a [f32],
+}
+
+/// Temporally adjacent `(t - 1, t)` row pairs with both rows among `rows`
+fn adjacent_pairs(rows: &[usize], row_count: usize) -> Vec<(usize, usize)> {
+    let mut present = vec![false; row_count];
+    for &r in rows {
+        present[r] = true;
+    }
+    (1..row_count)
+        .filter(|&t| present[t - 1] && present[t])
+        .map(|t| (t - 1, t))
+        .collect()
+}
+
+/// Threshold between two adjacent distinct values that sends `low` left and `high` right
+fn midpoint(low: f32, high: f32) -> f32 {
+    let mid = low + (high - low) / 2.0;
+    if mid < high {
+        mid
+    } else {
+        low
+    }
+}
+
+/// Reorder `rows` so rows matching `goes_left` come first, and split there
+fn partition(
+    rows: &mut [usize],
+    goes_left: impl Fn(usize) -> bool,
+) -> (&mut [usize], &mut [usize]) {
+    let mut boundary = 0;
+    for i in 0..rows.len() {
+        if goes_left(rows[i]) {
+            rows.swap(i, boundary);
+            boundary += 1;
+        }
+    }
+    rows.split_at_mut(boundary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step_data() -> Dataset {
+        // Target steps from 4 to 16 at x0 = 0.5; x1 is noise
+        let features: Vec<Vec<f32>> = (0..100)
+            .map(|i| vec![i as f32 / 100.0, ((i * 37) % 100) as f32 / 100.0])
+            .collect();
+        let targets = features
+            .iter()
+            .map(|r| if r[0] < 0.5 { 4.0 } else { 16.0 })
+            .collect();
+        Dataset::new(features, targets).unwrap()
+    }
+
+    #[test]
+    fn test_finds_step_split() {
+        let tree = CartTrainer::new()
+            .with_min_samples_leaf(5)
+            .fit(&step_data());
+        assert_eq!(tree.nodes[0].feature_idx, 0);
+        assert!(tree.nodes[0].threshold > 0.49 && tree.nodes[0].threshold < 0.5);
+        // Both halves are pure, so nothing further to gain
+        assert_eq!(tree.leaf_count(), 2);
+        assert_eq!(tree.predict(&[0.2, 0.9]), 4.0);
+        assert_eq!(tree.predict(&[0.8, 0.1]), 16.0);
+    }
+
+    #[test]
+    fn test_multi_output_leaves_share_splits() {
+        // Output 0 steps on x0 in workers, output 1 on x1 in microseconds
+        let data = step_data();
+        let targets = vec![
+            data.targets.clone(),
+            data.features
+                .iter()
+                .map(|r| if r[1] < 0.3 { 100.0 } else { 5000.0 })
+                .collect(),
+        ];
+        let tree = CartTrainer::new()
+            .with_max_depth(2)
+            .with_min_samples_leaf(5)
+            .fit_multi(&data.features, &targets);
+
+        assert_eq!(tree.leaves.len(), 4);
+        assert!(tree.leaves.iter().all(|values| values.len() == 2));
+        assert_eq!(tree.eval(&[0.2, 0.1]), [4.0, 100.0]);
+        assert_eq!(tree.eval(&[0.2, 0.9]), [4.0, 5000.0]);
+        assert_eq!(tree.eval(&[0.8, 0.1]), [16.0, 100.0]);
+        assert_eq!(tree.eval(&[0.8, 0.9]), [16.0, 5000.0]);
+    }
+
+    #[test]
+    fn test_respects_depth_and_leaf_limits() {
+        let features: Vec<Vec<f32>> = (0..64).map(|i| vec![i as f32]).collect();
+        let targets: Vec<f32> = (0..64).map(|i| (i * i) as f32).collect();
+        let data = Dataset::new(features, targets).unwrap();
+
+        let tree = CartTrainer::new()
+            .with_max_depth(3)
+            .with_min_samples_leaf(1)
+            .fit(&data);
+        assert_eq!(tree.depth(), 3);
+        assert_eq!(tree.leaf_count(), 8);
+
+        let tree = CartTrainer::new()
+            .with_max_depth(10)
+            .with_min_samples_leaf(20)
+            .fit(&data);
+        assert!(tree.leaf_count() <= 3);
+    }
+
+    #[test]
+    fn test_smoothness_damps_flapping() {
+        // Load creeps up with jitter around the 0.5 step, so an unpenalized
+        // tree flips its output back and forth near the boundary
+        let features: Vec<Vec<f32>> = (0..200)
+            .map(|t| vec![t as f32 / 200.0 + if t % 2 == 0 { 0.03 } else { -0.03 }])
+            .collect();
+        let targets = features
+            .iter()
+            .map(|r| if r[0] < 0.5 { 8.0 } else { 24.0 })
+            .collect();
+        let data = Dataset::new(features, targets).unwrap();
+
+        let trainer = CartTrainer::new()
+            .with_max_depth(2)
+            .with_min_samples_leaf(5);
+        let sharp = trainer.fit(&data);
+        let smooth = trainer.clone().with_smoothness(2.0).fit(&data);
+
+        let flapping =
+            |tree: &RegressionTree| crate::mean_squared_change(&data, |row| tree.predict(row));
+        assert!(flapping(&smooth) < flapping(&sharp) / 2.0);
+        // Still tracks the step away from the boundary
+        assert!(smooth.predict(&[0.1]) < 12.0 && smooth.predict(&[0.9]) > 20.0);
+    }
+
+    #[test]
+    fn test_quantile_leaves_lean_high() {
+        // Targets 1..=100 with no usable feature: one leaf
+        let features: Vec<Vec<f32>> = (0..100).map(|_| vec![0.0]).collect();
+        let targets: Vec<f32> = (1..=100).map(|t| t as f32).collect();
+        let data = Dataset::new(features, targets).unwrap();
+
+        let mean = CartTrainer::new().fit(&data);
+        let p90 = CartTrainer::new().with_loss(Loss::Quantile(0.9)).fit(&data);
+        assert_eq!(mean.predict(&[0.0]), 50.5);
+        assert_eq!(p90.predict(&[0.0]), 90.0);
+        let pinball = |tree: &RegressionTree| {
+            crate::mean_loss(&data, Loss::Quantile(0.9), |row| tree.predict(row))
+        };
+        assert!(pinball(&p90) < pinball(&mean));
+    }
+
+    #[test]
+    fn test_monotone_constraint_holds_everywhere() {
+        // Workers rise with queue length (x0) but with a noisy dip in the middle
+        let features: Vec<Vec<f32>> = (0..200)
+            .map(|i| vec![i as f32 / 200.0, ((i * 71) % 200) as f32 / 200.0])
+            .collect();
+        let targets = features
+            .iter()
+            .map(|r| {
+                let dip = if (0.4..0.6).contains(&r[0]) {
+                    -12.0
+                } else {
+                    0.0
+                };
+                4.0 + 20.0 * r[0] + dip + 4.0 * r[1]
+            })
+            .collect();
+        let data = Dataset::new(features, targets).unwrap();
+
+        let non_decreasing = |tree: &RegressionTree| {
+            (0..=20).all(|other| {
+                let x1 = other as f32 / 20.0;
+                (0..100).all(|i| {
+                    tree.predict(&[i as f32 / 100.0, x1])
+                        <= tree.predict(&[(i + 1) as f32 / 100.0, x1])
+                })
+            })
+        };
+        let trainer = CartTrainer::new()
+            .with_max_depth(5)
+            .with_min_samples_leaf(5);
+        assert!(!non_decreasing(&trainer.fit(&data)));
+
+        let constrained = trainer.with_monotone(0, Monotonicity::Increasing);
+        assert!(non_decreasing(&constrained.fit(&data)));
+        assert!(non_decreasing(
+            &constrained.clone().with_smoothness(1.0).fit(&data)
+        ));
+        // Decreasing in x1 fights the data, so the tree mostly ignores it
+        let tree = constrained
+            .with_monotone(1, Monotonicity::Decreasing)
+            .fit(&data);
+        assert!(tree.predict(&[0.9, 0.0]) >= tree.predict(&[0.9, 1.0]));
+    }
+}
@@ -0,0 +1,254 @@
+//! Warm-start retraining from an existing reflex
+//!
+//! Nightly refreshes don't need a cold fit. [`refit_leaves`] keeps every
+//! split and moves the leaf values toward new data; boosted ensembles can
+//! instead grow more rounds with [`BoostingTrainer::fit_more`].
+//! [`warm_start`] applies either to a reflex and returns a child whose
+//! metadata records its parent in a [`Lineage`]. The child keeps the
+//! parent's bounds and normalizer, so new rows must be normalized the
+//! way the parent's were.
+
+use crate::{
+    build_ensemble_reflex, build_reflex, training_metadata, BoostingTrainer, Dataset, Loss,
+    RegressionTree,
+};
+use reflex_format::{Lineage, ModelType, Reflex, ReflexMetadata, TreeEnsemble, TreeNode};
+use std::io;
+
+/// How to retrain from a parent reflex
+#[derive(Debug, Clone)]
+pub enum WarmStart {
+    /// Keep every split and refit the leaf values under this loss
+    RefitLeaves(Loss),
+    /// Keep every tree and boost more rounds (boosted ensembles only)
+    AppendRounds(BoostingTrainer),
+}
+
+impl WarmStart {
+    /// Name recorded in the child's lineage
+    pub fn method(&self) -> &'static str {
+        match self {
+            WarmStart::RefitLeaves(_) => "refit_leaves",
+            WarmStart::AppendRounds(_) => "append_rounds",
+        }
+    }
+}
+
+/// Copy of `ensemble` with leaf values refit to `data`, splits unchanged
+///
+/// Each tree takes an equal share of the remaining error: a leaf moves by
+/// the loss's optimum over the residuals of the rows reaching it, divided
+/// by `scale × trees`. For a single tree that is an exact refit, and for a
+/// forest each tree moves as if refit on its own. Leaves no row reaches
+/// keep their values.
+pub fn refit_leaves(ensemble: &TreeEnsemble, data: &Dataset, loss: Loss) -> TreeEnsemble {
+    assert!(!data.is_empty(), "cannot refit on no rows");
+    let residuals: Vec<f32> = data
+        .features
+        .iter()
+        .zip(&data.targets)
+        .map(|(row, &target)| target - ensemble.eval(row))
+        .collect();
+    let share = ensemble.scale * ensemble.trees.len() as f32;
+    let mut refit = ensemble.clone();
+    for tree in &mut refit.trees {
+        let mut by_leaf = vec![Vec::new(); tree.len()];
+        for (row, &residual) in data.features.iter().zip(&residuals) {
+            by_leaf[leaf_of(tree, row)].push(residual);
+        }
+        for (node, mut residuals) in tree.iter_mut().zip(by_leaf) {
+            if !residuals.is_empty() {
+                node.threshold += loss.optimum(&mut residuals) / share;
+            }
+        }
+    }
+    refit
+}
+
+/// Retrain `parent` on `data`, returning a child reflex with lineage metadata
+///
+/// Supports single-output tree and ensemble reflexes; `data` is in the
+/// parent's normalized feature space.
+pub fn warm_start(parent: &Reflex, data: &Dataset, method: &WarmStart) -> io::Result<Reflex> {
+    let feature_count = parent.header.feature_count as usize;
+    if parent.header.output_count != 1 {
+        return Err(unsupported("warm start needs a single-output reflex"));
+    }
+    if data.feature_count() != feature_count {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} features per row, the parent takes {}",
+                data.feature_count(),
+                feature_count
+            ),
+        ));
+    }
+    if data.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "no rows to retrain on",
+        ));
+    }
+
+    let metadata = child_metadata(parent, data, method)?;
+    let bounds = parent.bounds.clone();
+    match (ModelType::try_from(parent.header.model_type), method) {
+        (Ok(ModelType::DecisionTree), WarmStart::RefitLeaves(loss)) => {
+            let single = TreeEnsemble {
+                base: 0.0,
+                scale: 1.0,
+                trees: parent.trees.clone(),
+            };
+            let nodes = refit_leaves(&single, data, *loss).trees.remove(0);
+            build_reflex(
+                vec![RegressionTree { nodes }],
+                feature_count,
+                bounds,
+                metadata,
+            )
+        }
+        (Ok(ModelType::Ensemble), WarmStart::RefitLeaves(loss)) => {
+            let ensemble = refit_leaves(&parent.ensembles[0], data, *loss);
+            build_ensemble_reflex(vec![ensemble], feature_count, bounds, metadata)
+        }
+        (Ok(ModelType::Ensemble), WarmStart::AppendRounds(trainer)) => {
+            let ensemble = trainer.fit_more(data, parent.ensembles[0].clone());
+            build_ensemble_reflex(vec![ensemble], feature_count, bounds, metadata)
+        }
+        (Ok(ModelType::DecisionTree), WarmStart::AppendRounds(_)) => {
+            Err(unsupported("appending rounds needs an ensemble reflex"))
+        }
+        _ => Err(unsupported("warm start needs a tree or ensemble reflex")),
+    }
+}
+
+/// The parent's metadata, restamped for `data` and pointing back at the parent
+fn child_metadata(
+    parent: &Reflex,
+    data: &Dataset,
+    method: &WarmStart,
+) -> io::Result<ReflexMetadata> {
+    let stamped = training_metadata(
+        &parent.metadata.feature_schema,
+        &data.fingerprint(),
+        parent.metadata.lambda,
+        &parent.metadata.notes,
+    );
+    let generation = parent
+        .metadata
+        .lineage
+        .as_ref()
+        .map_or(1, |lineage| lineage.generation + 1);
+    Ok(ReflexMetadata {
+        created_at: stamped.created_at,
+        trainer_commit: stamped.trainer_commit,
+        telemetry_hash: stamped.telemetry_hash,
+        lineage: Some(Lineage {
+            parent_checksum: parent.checksum()?,
+            parent_created_at: parent.metadata.created_at.clone(),
+            generation,
+            method: method.method().to_string(),
+        }),
+        calibration: Vec::new(), // fitted to the parent's outputs, not the child's
+        seed: match method {
+            WarmStart::AppendRounds(trainer) => Some(trainer.seed),
+            WarmStart::RefitLeaves(_) => None, // draws nothing at random
+        },
+        ..parent.metadata.clone()
+    })
+}
+
+/// Index of the leaf `row` lands in
+fn leaf_of(tree: &[TreeNode], row: &[f32]) -> usize {
+    let mut idx = 0;
+    while !tree[idx].is_leaf() {
+        let node = &tree[idx];
+        idx = if row[node.feature_idx as usize] <= node.threshold {
+            node.left as usize
+        } else {
+            node.right as usize
+        };
+    }
+    idx
+}
+
+fn unsupported(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CartTrainer, ForestTrainer};
+    use reflex_format::OutputBounds;
+
+    /// Targets rise with feature 0; `shift` moves the whole curve up
+    fn ramp(shift: f32) -> Dataset {
+        let features: Vec<Vec<f32>> = (0..400).map(|i| vec![(i % 100) as f32 / 100.0]).collect();
+        let targets = features.iter().map(|r| 4.0 + 20.0 * r[0] + shift).collect();
+        Dataset::new(features, targets).unwrap()
+    }
+
+    fn bounds() -> OutputBounds {
+        OutputBounds {
+            min: vec![1.0],
+            max: vec![64.0],
+        }
+    }
+
+    fn mae(reflex: &Reflex, data: &Dataset) -> f64 {
+        crate::mean_absolute_error(data, |row| reflex.infer(row)[0])
+    }
+
+    #[test]
+    fn test_refit_leaves_tracks_shifted_targets() {
+        let (old, new) = (ramp(0.0), ramp(6.0));
+        let tree = CartTrainer::new().with_max_depth(4).fit(&old);
+        let metadata = training_metadata("v1", &old.fingerprint(), 0.0, "");
+        let parent = build_reflex(vec![tree], 1, bounds(), metadata).unwrap();
+
+        let child = warm_start(&parent, &new, &WarmStart::RefitLeaves(Loss::Squared)).unwrap();
+        assert!(mae(&parent, &new) > 5.0);
+        assert!(mae(&child, &new) < 1.0);
+        assert_eq!(child.trees[0].len(), parent.trees[0].len());
+
+        let forest = ForestTrainer::new().with_n_trees(8).fit(&old);
+        let metadata = training_metadata("v1", &old.fingerprint(), 0.0, "");
+        let parent = build_ensemble_reflex(vec![forest], 1, bounds(), metadata).unwrap();
+        let child = warm_start(&parent, &new, &WarmStart::RefitLeaves(Loss::Squared)).unwrap();
+        assert!(mae(&child, &new) < mae(&parent, &new) / 4.0);
+    }
+
+    #[test]
+    fn test_append_rounds_and_lineage() {
+        let (old, new) = (ramp(0.0), ramp(6.0));
+        let trainer = BoostingTrainer::new()
+            .with_max_rounds(40)
+            .with_learning_rate(0.3);
+        let metadata = training_metadata("v1", &old.fingerprint(), 0.0, "nightly");
+        let parent = build_ensemble_reflex(vec![trainer.fit(&old)], 1, bounds(), metadata).unwrap();
+
+        let append = WarmStart::AppendRounds(trainer);
+        let child = warm_start(&parent, &new, &append).unwrap();
+        assert!(child.ensembles[0].trees.len() > parent.ensembles[0].trees.len());
+        assert_eq!(
+            child.ensembles[0].trees[..parent.ensembles[0].trees.len()],
+            parent.ensembles[0].trees[..]
+        );
+        assert!(mae(&child, &new) < mae(&parent, &new) / 4.0);
+
+        let lineage = child.metadata.lineage.clone().unwrap();
+        assert_eq!(lineage.parent_checksum, parent.checksum().unwrap());
+        assert_eq!(
+            (lineage.generation, lineage.method.as_str()),
+            (1, "append_rounds")
+        );
+        assert_eq!(child.metadata.telemetry_hash, new.fingerprint());
+        assert_eq!(child.metadata.notes, "nightly");
+
+        let loaded = Reflex::from_bytes(&child.to_bytes().unwrap()).unwrap();
+        let grandchild = warm_start(&loaded, &new, &WarmStart::RefitLeaves(Loss::Squared)).unwrap();
+        assert_eq!(grandchild.metadata.lineage.unwrap().generation, 2);
+    }
+}